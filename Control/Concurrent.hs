@@ -0,0 +1,29 @@
+module Control.Concurrent where
+
+import Prelude
+
+data ThreadId = ThreadId Int
+    deriving(Eq)
+
+instance Show ThreadId where
+    show t = case t of
+        ThreadId n -> "ThreadId " ++ show n
+
+forkIO :: IO () -> IO ThreadId
+forkIO action = primForkIO action >>= \n -> return (ThreadId n)
+
+myThreadId :: IO ThreadId
+myThreadId = primMyThreadId >>= \n -> return (ThreadId n)
+
+throwTo :: Exception e => ThreadId -> e -> IO ()
+throwTo t e = case t of
+    ThreadId n -> primThrowTo n (toException e)
+
+killThread :: ThreadId -> IO ()
+killThread t = throwTo t ThreadKilled
+
+yield :: IO ()
+yield = primYield
+
+threadDelay :: Int -> IO ()
+threadDelay micros = primThreadDelay micros
@@ -0,0 +1,36 @@
+module Control.Concurrent.Chan where
+
+import Prelude
+import Control.Concurrent.MVar
+
+data ChItem a = ChItem a (MVar (ChItem a))
+
+data Chan a = Chan (MVar (MVar (ChItem a))) (MVar (MVar (ChItem a)))
+
+newChan :: IO (Chan a)
+newChan = do
+    hole <- newEmptyMVar
+    readVar <- newMVar hole
+    writeVar <- newMVar hole
+    return (Chan readVar writeVar)
+
+writeChan :: Chan a -> a -> IO ()
+writeChan chan x = case chan of
+    Chan readVar writeVar -> do
+        newHole <- newEmptyMVar
+        oldHole <- takeMVar writeVar
+        putMVar oldHole (ChItem x newHole)
+        putMVar writeVar newHole
+
+readChan :: Chan a -> IO a
+readChan chan = case chan of
+    Chan readVar writeVar -> do
+        readEnd <- takeMVar readVar
+        item <- readMVar readEnd
+        case item of
+            ChItem x newReadEnd -> putMVar readVar newReadEnd >> return x
+
+writeList2Chan :: Chan a -> [a] -> IO ()
+writeList2Chan chan xs = case xs of
+    y:ys -> writeChan chan y >> writeList2Chan chan ys
+    [] -> return ()
@@ -0,0 +1,42 @@
+module Control.Concurrent.MVar where
+
+import Prelude
+
+newEmptyMVar :: IO (MVar a)
+newEmptyMVar = primNewEmptyMVar
+
+newMVar :: a -> IO (MVar a)
+newMVar x = newEmptyMVar >>= \m -> putMVar m x >> return m
+
+takeMVar :: MVar a -> IO a
+takeMVar m = primTakeMVar m
+
+putMVar :: MVar a -> a -> IO ()
+putMVar m x = primPutMVar m x
+
+readMVar :: MVar a -> IO a
+readMVar m = primReadMVar m
+
+tryTakeMVar :: MVar a -> IO (Maybe a)
+tryTakeMVar m = primTryTakeMVar m
+
+tryPutMVar :: MVar a -> a -> IO Bool
+tryPutMVar m x = primTryPutMVar m x
+
+swapMVar :: MVar a -> a -> IO a
+swapMVar m new = takeMVar m >>= \old -> putMVar m new >> return old
+
+withMVar :: MVar a -> (a -> IO b) -> IO b
+withMVar m f = takeMVar m >>= \x ->
+    primCatch (f x) (\e -> putMVar m x >> primThrow e) >>= \result ->
+    putMVar m x >> return result
+
+modifyMVar_ :: MVar a -> (a -> IO a) -> IO ()
+modifyMVar_ m f = takeMVar m >>= \x ->
+    primCatch (f x) (\e -> putMVar m x >> primThrow e) >>= \y ->
+    putMVar m y
+
+modifyMVar :: MVar a -> (a -> IO (a, b)) -> IO b
+modifyMVar m f = takeMVar m >>= \x ->
+    primCatch (f x) (\e -> putMVar m x >> primThrow e) >>= \p -> case p of
+        (y, result) -> putMVar m y >> return result
@@ -0,0 +1,69 @@
+module Control.Concurrent.STM where
+
+import Prelude
+
+data STM a = STM (IO a)
+
+unsafeIOToSTM :: IO a -> STM a
+unsafeIOToSTM io = STM io
+
+unsafeSTMToIO :: STM a -> IO a
+unsafeSTMToIO m = case m of
+    STM io -> io
+
+instance Functor STM where
+    fmap f m = STM (fmap f (unsafeSTMToIO m))
+
+instance Applicative STM where
+    pure x = STM (return x)
+    (<*>) f x = STM (unsafeSTMToIO f <*> unsafeSTMToIO x)
+
+instance Monad STM where
+    (>>=) m f = STM (unsafeSTMToIO m >>= \x -> unsafeSTMToIO (f x))
+
+instance Alternative STM where
+    empty = retry
+    (<|>) a b = orElse a b
+
+instance MonadPlus STM where
+    mzero = retry
+    mplus a b = orElse a b
+
+atomically :: STM a -> IO a
+atomically m = primAtomically (unsafeSTMToIO m)
+
+retry :: STM a
+retry = STM primRetry
+
+orElse :: STM a -> STM a -> STM a
+orElse a b = STM (primOrElse (unsafeSTMToIO a) (unsafeSTMToIO b))
+
+check :: Bool -> STM ()
+check b = if b then return () else retry
+
+throwSTM :: Exception e => e -> STM a
+throwSTM e = STM (throw e)
+
+newTVar :: a -> STM (TVar a)
+newTVar x = STM (primNewTVar x)
+
+newTVarIO :: a -> IO (TVar a)
+newTVarIO x = primNewTVar x
+
+readTVar :: TVar a -> STM a
+readTVar v = STM (primReadTVar v)
+
+readTVarIO :: TVar a -> IO a
+readTVarIO v = primReadTVar v
+
+writeTVar :: TVar a -> a -> STM ()
+writeTVar v x = STM (primWriteTVar v x)
+
+modifyTVar :: TVar a -> (a -> a) -> STM ()
+modifyTVar v f = readTVar v >>= \x -> writeTVar v (f x)
+
+modifyTVar' :: TVar a -> (a -> a) -> STM ()
+modifyTVar' v f = readTVar v >>= \x -> let y = f x in y `seq` writeTVar v y
+
+swapTVar :: TVar a -> a -> STM a
+swapTVar v new = readTVar v >>= \old -> writeTVar v new >> return old
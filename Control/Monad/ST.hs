@@ -0,0 +1,28 @@
+module Control.Monad.ST where
+
+import Prelude
+
+data ST s a = ST (IO a)
+
+unsafeIOToST :: IO a -> ST s a
+unsafeIOToST io = ST io
+
+unsafeSTToIO :: ST s a -> IO a
+unsafeSTToIO m = case m of
+    ST io -> io
+
+instance Functor (ST s) where
+    fmap f m = ST (fmap f (unsafeSTToIO m))
+
+instance Applicative (ST s) where
+    pure x = ST (return x)
+    (<*>) f x = ST (unsafeSTToIO f <*> unsafeSTToIO x)
+
+instance Monad (ST s) where
+    (>>=) m f = ST (unsafeSTToIO m >>= \x -> unsafeSTToIO (f x))
+
+runST :: ST s a -> a
+runST m = primUnsafePerformIO (unsafeSTToIO m)
+
+stToIO :: ST RealWorld a -> IO a
+stToIO m = unsafeSTToIO m
@@ -0,0 +1,47 @@
+module Data.Array.Base where
+
+import Prelude
+
+data IOArray i e = IOArray Int Int (MutableArray e)
+
+data IOUArray i e = IOUArray Int Int MutableIntArray
+
+rangeSize :: (Int, Int) -> Int
+rangeSize bounds = case bounds of
+    (lo, hi) -> if hi < lo then 0 else hi - lo + 1
+
+index :: (Int, Int) -> Int -> Int
+index bounds i = case bounds of
+    (lo, hi) -> if (i < lo) || (i > hi)
+        then error ("array index " ++ show i ++ " out of range " ++ show bounds)
+        else i - lo
+
+newIOArray :: (Int, Int) -> e -> IO (IOArray Int e)
+newIOArray bounds x = primNewArray (rangeSize bounds) x >>= \arr -> return (IOArray (fst bounds) (snd bounds) arr)
+
+boundsIOArray :: IOArray Int e -> (Int, Int)
+boundsIOArray arr = case arr of
+    IOArray lo hi _ -> (lo, hi)
+
+readIOArray :: IOArray Int e -> Int -> IO e
+readIOArray arr i = case arr of
+    IOArray lo hi elems -> primReadArray elems (index (lo, hi) i)
+
+writeIOArray :: IOArray Int e -> Int -> e -> IO ()
+writeIOArray arr i x = case arr of
+    IOArray lo hi elems -> primWriteArray elems (index (lo, hi) i) x
+
+newIOUArray :: (Int, Int) -> Int -> IO (IOUArray Int Int)
+newIOUArray bounds x = primNewIntArray (rangeSize bounds) x >>= \arr -> return (IOUArray (fst bounds) (snd bounds) arr)
+
+boundsIOUArray :: IOUArray Int Int -> (Int, Int)
+boundsIOUArray arr = case arr of
+    IOUArray lo hi _ -> (lo, hi)
+
+readIOUArray :: IOUArray Int Int -> Int -> IO Int
+readIOUArray arr i = case arr of
+    IOUArray lo hi elems -> primReadIntArray elems (index (lo, hi) i)
+
+writeIOUArray :: IOUArray Int Int -> Int -> Int -> IO ()
+writeIOUArray arr i x = case arr of
+    IOUArray lo hi elems -> primWriteIntArray elems (index (lo, hi) i) x
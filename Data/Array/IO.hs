@@ -0,0 +1,70 @@
+module Data.Array.IO where
+
+import Prelude
+import Data.Array.Base
+
+newArray :: (Int, Int) -> e -> IO (IOArray Int e)
+newArray bounds x = newIOArray bounds x
+
+newListArray :: (Int, Int) -> [e] -> IO (IOArray Int e)
+newListArray bounds xs = newArray bounds (error "undefined array element") >>= \arr ->
+    writeElems arr (fst bounds) xs >> return arr
+
+readArray :: IOArray Int e -> Int -> IO e
+readArray arr i = readIOArray arr i
+
+writeArray :: IOArray Int e -> Int -> e -> IO ()
+writeArray arr i x = writeIOArray arr i x
+
+modifyArray :: IOArray Int e -> Int -> (e -> e) -> IO ()
+modifyArray arr i f = readArray arr i >>= \x -> writeArray arr i (f x)
+
+modifyArray' :: IOArray Int e -> Int -> (e -> e) -> IO ()
+modifyArray' arr i f = readArray arr i >>= \x -> let y = f x in y `seq` writeArray arr i y
+
+getBounds :: IOArray Int e -> IO (Int, Int)
+getBounds arr = return (boundsIOArray arr)
+
+getElems :: IOArray Int e -> IO [e]
+getElems arr = readElems arr (fst (boundsIOArray arr))
+
+writeElems :: IOArray Int e -> Int -> [e] -> IO ()
+writeElems arr i xs = case xs of
+    [] -> return ()
+    y:ys -> writeArray arr i y >> writeElems arr (i + 1) ys
+
+readElems :: IOArray Int e -> Int -> IO [e]
+readElems arr i = if i > snd (boundsIOArray arr)
+    then return []
+    else readArray arr i >>= \x -> readElems arr (i + 1) >>= \xs -> return (x : xs)
+
+newUArray :: (Int, Int) -> Int -> IO (IOUArray Int Int)
+newUArray bounds x = newIOUArray bounds x
+
+newListUArray :: (Int, Int) -> [Int] -> IO (IOUArray Int Int)
+newListUArray bounds xs = newUArray bounds 0 >>= \arr -> writeUElems arr (fst bounds) xs >> return arr
+
+readUArray :: IOUArray Int Int -> Int -> IO Int
+readUArray arr i = readIOUArray arr i
+
+writeUArray :: IOUArray Int Int -> Int -> Int -> IO ()
+writeUArray arr i x = writeIOUArray arr i x
+
+modifyUArray :: IOUArray Int Int -> Int -> (Int -> Int) -> IO ()
+modifyUArray arr i f = readUArray arr i >>= \x -> writeUArray arr i (f x)
+
+getUBounds :: IOUArray Int Int -> IO (Int, Int)
+getUBounds arr = return (boundsIOUArray arr)
+
+getUElems :: IOUArray Int Int -> IO [Int]
+getUElems arr = readUElems arr (fst (boundsIOUArray arr))
+
+writeUElems :: IOUArray Int Int -> Int -> [Int] -> IO ()
+writeUElems arr i xs = case xs of
+    [] -> return ()
+    y:ys -> writeUArray arr i y >> writeUElems arr (i + 1) ys
+
+readUElems :: IOUArray Int Int -> Int -> IO [Int]
+readUElems arr i = if i > snd (boundsIOUArray arr)
+    then return []
+    else readUArray arr i >>= \x -> readUElems arr (i + 1) >>= \xs -> return (x : xs)
@@ -0,0 +1,83 @@
+module Data.Array.ST where
+
+import Prelude
+import Control.Monad.ST
+import Data.Array.Base
+
+data STArray s i e = STArray (IOArray i e)
+
+data STUArray s i e = STUArray (IOUArray i e)
+
+unSTArray :: STArray s Int e -> IOArray Int e
+unSTArray arr = case arr of
+    STArray a -> a
+
+unSTUArray :: STUArray s Int Int -> IOUArray Int Int
+unSTUArray arr = case arr of
+    STUArray a -> a
+
+newArray :: (Int, Int) -> e -> ST s (STArray s Int e)
+newArray bounds x = unsafeIOToST (newIOArray bounds x >>= \arr -> return (STArray arr))
+
+newListArray :: (Int, Int) -> [e] -> ST s (STArray s Int e)
+newListArray bounds xs = newArray bounds (error "undefined array element") >>= \arr ->
+    writeElems arr (fst bounds) xs >> return arr
+
+readArray :: STArray s Int e -> Int -> ST s e
+readArray arr i = unsafeIOToST (readIOArray (unSTArray arr) i)
+
+writeArray :: STArray s Int e -> Int -> e -> ST s ()
+writeArray arr i x = unsafeIOToST (writeIOArray (unSTArray arr) i x)
+
+modifyArray :: STArray s Int e -> Int -> (e -> e) -> ST s ()
+modifyArray arr i f = readArray arr i >>= \x -> writeArray arr i (f x)
+
+modifyArray' :: STArray s Int e -> Int -> (e -> e) -> ST s ()
+modifyArray' arr i f = readArray arr i >>= \x -> let y = f x in y `seq` writeArray arr i y
+
+getBounds :: STArray s Int e -> ST s (Int, Int)
+getBounds arr = return (boundsIOArray (unSTArray arr))
+
+getElems :: STArray s Int e -> ST s [e]
+getElems arr = readElems arr (fst (boundsIOArray (unSTArray arr)))
+
+writeElems :: STArray s Int e -> Int -> [e] -> ST s ()
+writeElems arr i xs = case xs of
+    [] -> return ()
+    y:ys -> writeArray arr i y >> writeElems arr (i + 1) ys
+
+readElems :: STArray s Int e -> Int -> ST s [e]
+readElems arr i = if i > snd (boundsIOArray (unSTArray arr))
+    then return []
+    else readArray arr i >>= \x -> readElems arr (i + 1) >>= \xs -> return (x : xs)
+
+newUArray :: (Int, Int) -> Int -> ST s (STUArray s Int Int)
+newUArray bounds x = unsafeIOToST (newIOUArray bounds x >>= \arr -> return (STUArray arr))
+
+newListUArray :: (Int, Int) -> [Int] -> ST s (STUArray s Int Int)
+newListUArray bounds xs = newUArray bounds 0 >>= \arr -> writeUElems arr (fst bounds) xs >> return arr
+
+readUArray :: STUArray s Int Int -> Int -> ST s Int
+readUArray arr i = unsafeIOToST (readIOUArray (unSTUArray arr) i)
+
+writeUArray :: STUArray s Int Int -> Int -> Int -> ST s ()
+writeUArray arr i x = unsafeIOToST (writeIOUArray (unSTUArray arr) i x)
+
+modifyUArray :: STUArray s Int Int -> Int -> (Int -> Int) -> ST s ()
+modifyUArray arr i f = readUArray arr i >>= \x -> writeUArray arr i (f x)
+
+getUBounds :: STUArray s Int Int -> ST s (Int, Int)
+getUBounds arr = return (boundsIOUArray (unSTUArray arr))
+
+getUElems :: STUArray s Int Int -> ST s [Int]
+getUElems arr = readUElems arr (fst (boundsIOUArray (unSTUArray arr)))
+
+writeUElems :: STUArray s Int Int -> Int -> [Int] -> ST s ()
+writeUElems arr i xs = case xs of
+    [] -> return ()
+    y:ys -> writeUArray arr i y >> writeUElems arr (i + 1) ys
+
+readUElems :: STUArray s Int Int -> Int -> ST s [Int]
+readUElems arr i = if i > snd (boundsIOUArray (unSTUArray arr))
+    then return []
+    else readUArray arr i >>= \x -> readUElems arr (i + 1) >>= \xs -> return (x : xs)
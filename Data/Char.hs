@@ -0,0 +1,67 @@
+module Data.Char where
+
+import Prelude
+
+ord :: Char -> Int
+ord c = primCharToInt c
+
+chr :: Int -> Char
+chr n = primIntToChar n
+
+isSpace :: Char -> Bool
+isSpace c = primCharIsSpace c
+
+isAlpha :: Char -> Bool
+isAlpha c = primCharIsAlpha c
+
+isLetter :: Char -> Bool
+isLetter c = primCharIsAlpha c
+
+isAlphaNum :: Char -> Bool
+isAlphaNum c = primCharIsAlphaNum c
+
+isUpper :: Char -> Bool
+isUpper c = primCharIsUpper c
+
+isLower :: Char -> Bool
+isLower c = primCharIsLower c
+
+isPunctuation :: Char -> Bool
+isPunctuation c = primCharIsPunctuation c
+
+isControl :: Char -> Bool
+isControl c = primCharIsControl c
+
+isPrint :: Char -> Bool
+isPrint c = not (primCharIsControl c)
+
+isAscii :: Char -> Bool
+isAscii c = ord c < 128
+
+isDigit :: Char -> Bool
+isDigit c = (ord c >= 48) && (ord c <= 57)
+
+isOctDigit :: Char -> Bool
+isOctDigit c = (ord c >= 48) && (ord c <= 55)
+
+isHexDigit :: Char -> Bool
+isHexDigit c = isDigit c || ((ord c >= 65) && (ord c <= 70)) || ((ord c >= 97) && (ord c <= 102))
+
+toUpper :: Char -> Char
+toUpper c = primCharToUpper c
+
+toLower :: Char -> Char
+toLower c = primCharToLower c
+
+digitToInt :: Char -> Int
+digitToInt c
+    | isDigit c = ord c - 48
+    | (ord c >= 97) && (ord c <= 102) = ord c - 87
+    | (ord c >= 65) && (ord c <= 70) = ord c - 55
+    | otherwise = error ("Char.digitToInt: not a digit " ++ [c])
+
+intToDigit :: Int -> Char
+intToDigit n
+    | (n >= 0) && (n <= 9) = chr (n + 48)
+    | (n >= 10) && (n <= 15) = chr (n + 87)
+    | otherwise = error "Char.intToDigit: not a digit"
@@ -0,0 +1,38 @@
+module Data.Either where
+
+import Prelude
+
+isLeft :: Either a b -> Bool
+isLeft e = case e of
+    Left _ -> True
+    Right _ -> False
+
+isRight :: Either a b -> Bool
+isRight e = not (isLeft e)
+
+fromLeft :: a -> Either a b -> a
+fromLeft def e = case e of
+    Left x -> x
+    Right _ -> def
+
+fromRight :: b -> Either a b -> b
+fromRight def e = case e of
+    Left _ -> def
+    Right x -> x
+
+lefts :: [Either a b] -> [a]
+lefts es = case es of
+    e:rest -> case e of
+        Left x -> x : lefts rest
+        Right _ -> lefts rest
+    [] -> []
+
+rights :: [Either a b] -> [b]
+rights es = case es of
+    e:rest -> case e of
+        Left _ -> rights rest
+        Right x -> x : rights rest
+    [] -> []
+
+partitionEithers :: [Either a b] -> ([a], [b])
+partitionEithers es = (lefts es, rights es)
@@ -0,0 +1,22 @@
+module Data.IORef where
+
+import Prelude
+
+newIORef :: a -> IO (IORef a)
+newIORef x = primNewIORef x
+
+readIORef :: IORef a -> IO a
+readIORef ref = primReadIORef ref
+
+writeIORef :: IORef a -> a -> IO ()
+writeIORef ref x = primWriteIORef ref x
+
+modifyIORef :: IORef a -> (a -> a) -> IO ()
+modifyIORef ref f = readIORef ref >>= \x -> writeIORef ref (f x)
+
+modifyIORef' :: IORef a -> (a -> a) -> IO ()
+modifyIORef' ref f = readIORef ref >>= \x -> let y = f x in y `seq` writeIORef ref y
+
+atomicModifyIORef :: IORef a -> (a -> (a, b)) -> IO b
+atomicModifyIORef ref f = readIORef ref >>= \x -> case f x of
+    (y, result) -> writeIORef ref y >> return result
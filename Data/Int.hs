@@ -0,0 +1,100 @@
+module Data.Int where
+
+import Prelude
+
+newtype Int8 = I8 Int
+newtype Int16 = I16 Int
+newtype Int32 = I32 Int
+newtype Int64 = I64 Int
+
+instance Eq Int8 where
+    (==) (I8 x) (I8 y) = primIntEQ x y
+
+instance Ord Int8 where
+    compare (I8 x) (I8 y) = compare x y
+
+instance Num Int8 where
+    (+) (I8 x) (I8 y) = I8 (primNarrowInt 8 (x + y))
+    (-) (I8 x) (I8 y) = I8 (primNarrowInt 8 (x - y))
+    (*) (I8 x) (I8 y) = I8 (primNarrowInt 8 (x * y))
+    fromInteger n = I8 (primNarrowInt 8 (primIntegerToInt n))
+
+instance Real Int8 where
+    toRational (I8 x) = toRational x
+
+instance Integral Int8 where
+    div (I8 x) (I8 y) = I8 (primNarrowInt 8 (div x y))
+    rem (I8 x) (I8 y) = I8 (rem x y)
+    toInteger (I8 x) = primIntToInteger x
+
+instance Show Int8 where
+    showsPrec p (I8 x) = showsPrec p x
+
+instance Eq Int16 where
+    (==) (I16 x) (I16 y) = primIntEQ x y
+
+instance Ord Int16 where
+    compare (I16 x) (I16 y) = compare x y
+
+instance Num Int16 where
+    (+) (I16 x) (I16 y) = I16 (primNarrowInt 16 (x + y))
+    (-) (I16 x) (I16 y) = I16 (primNarrowInt 16 (x - y))
+    (*) (I16 x) (I16 y) = I16 (primNarrowInt 16 (x * y))
+    fromInteger n = I16 (primNarrowInt 16 (primIntegerToInt n))
+
+instance Real Int16 where
+    toRational (I16 x) = toRational x
+
+instance Integral Int16 where
+    div (I16 x) (I16 y) = I16 (primNarrowInt 16 (div x y))
+    rem (I16 x) (I16 y) = I16 (rem x y)
+    toInteger (I16 x) = primIntToInteger x
+
+instance Show Int16 where
+    showsPrec p (I16 x) = showsPrec p x
+
+instance Eq Int32 where
+    (==) (I32 x) (I32 y) = primIntEQ x y
+
+instance Ord Int32 where
+    compare (I32 x) (I32 y) = compare x y
+
+instance Num Int32 where
+    (+) (I32 x) (I32 y) = I32 (primNarrowInt 32 (x + y))
+    (-) (I32 x) (I32 y) = I32 (primNarrowInt 32 (x - y))
+    (*) (I32 x) (I32 y) = I32 (primNarrowInt 32 (x * y))
+    fromInteger n = I32 (primNarrowInt 32 (primIntegerToInt n))
+
+instance Real Int32 where
+    toRational (I32 x) = toRational x
+
+instance Integral Int32 where
+    div (I32 x) (I32 y) = I32 (primNarrowInt 32 (div x y))
+    rem (I32 x) (I32 y) = I32 (rem x y)
+    toInteger (I32 x) = primIntToInteger x
+
+instance Show Int32 where
+    showsPrec p (I32 x) = showsPrec p x
+
+instance Eq Int64 where
+    (==) (I64 x) (I64 y) = primIntEQ x y
+
+instance Ord Int64 where
+    compare (I64 x) (I64 y) = compare x y
+
+instance Num Int64 where
+    (+) (I64 x) (I64 y) = I64 (x + y)
+    (-) (I64 x) (I64 y) = I64 (x - y)
+    (*) (I64 x) (I64 y) = I64 (x * y)
+    fromInteger n = I64 (primIntegerToInt n)
+
+instance Real Int64 where
+    toRational (I64 x) = toRational x
+
+instance Integral Int64 where
+    div (I64 x) (I64 y) = I64 (div x y)
+    rem (I64 x) (I64 y) = I64 (rem x y)
+    toInteger (I64 x) = primIntToInteger x
+
+instance Show Int64 where
+    showsPrec p (I64 x) = showsPrec p x
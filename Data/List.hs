@@ -0,0 +1,276 @@
+module Data.List where
+
+import Prelude
+import Data.Char
+
+zip :: [a] -> [b] -> [(a, b)]
+zip xs ys = zipWith (\x y -> (x, y)) xs ys
+
+zip3 :: [a] -> [b] -> [c] -> [(a, b, c)]
+zip3 xs ys zs = zipWith3 (\x y z -> (x, y, z)) xs ys zs
+
+zipWith :: (a -> b -> c) -> [a] -> [b] -> [c]
+zipWith f xs ys = case xs of
+    x:xs2 -> case ys of
+        y:ys2 -> f x y : zipWith f xs2 ys2
+        [] -> []
+    [] -> []
+
+zipWith3 :: (a -> b -> c -> d) -> [a] -> [b] -> [c] -> [d]
+zipWith3 f xs ys zs = case xs of
+    x:xs2 -> case ys of
+        y:ys2 -> case zs of
+            z:zs2 -> f x y z : zipWith3 f xs2 ys2 zs2
+            [] -> []
+        [] -> []
+    [] -> []
+
+unzip :: [(a, b)] -> ([a], [b])
+unzip ps = (map fst ps, map snd ps)
+
+unzip3 :: [(a, b, c)] -> ([a], [b], [c])
+unzip3 ts = (map first3 ts, map second3 ts, map third3 ts)
+
+first3 :: (a, b, c) -> a
+first3 t = case t of
+    (x, _, _) -> x
+
+second3 :: (a, b, c) -> b
+second3 t = case t of
+    (_, y, _) -> y
+
+third3 :: (a, b, c) -> c
+third3 t = case t of
+    (_, _, z) -> z
+
+lookup :: Eq a => a -> [(a, b)] -> Maybe b
+lookup key ps = case ps of
+    p:rest -> case p of
+        (k, v) -> if key == k then Just v else lookup key rest
+    [] -> Nothing
+
+take :: Int -> [a] -> [a]
+take n xs = if n <= 0 then [] else case xs of
+    y:ys -> y : take (n - 1) ys
+    [] -> []
+
+drop :: Int -> [a] -> [a]
+drop n xs = if n <= 0 then xs else case xs of
+    _:ys -> drop (n - 1) ys
+    [] -> []
+
+splitAt :: Int -> [a] -> ([a], [a])
+splitAt n xs = (take n xs, drop n xs)
+
+takeWhile :: (a -> Bool) -> [a] -> [a]
+takeWhile p xs = case xs of
+    y:ys -> if p y then y : takeWhile p ys else []
+    [] -> []
+
+dropWhile :: (a -> Bool) -> [a] -> [a]
+dropWhile p xs = case xs of
+    y:ys -> if p y then dropWhile p ys else xs
+    [] -> []
+
+span :: (a -> Bool) -> [a] -> ([a], [a])
+span p xs = (takeWhile p xs, dropWhile p xs)
+
+break :: (a -> Bool) -> [a] -> ([a], [a])
+break p xs = span (\x -> not (p x)) xs
+
+repeat :: a -> [a]
+repeat x = let xs = x : xs in xs
+
+replicate :: Int -> a -> [a]
+replicate n x = take n (repeat x)
+
+iterate :: (a -> a) -> a -> [a]
+iterate f x = x : iterate f (f x)
+
+cycle :: [a] -> [a]
+cycle xs = case xs of
+    [] -> error "cycle called on empty list"
+    _ -> let ys = xs ++ ys in ys
+
+lines :: [Char] -> [[Char]]
+lines s = case s of
+    [] -> []
+    _ -> case break (\c -> c == '\n') s of
+        (l, rest) -> l : lines (drop 1 rest)
+
+unlines :: [[Char]] -> [Char]
+unlines ls = concatMap (\l -> l ++ "\n") ls
+
+words :: [Char] -> [[Char]]
+words s = case dropWhile isSpace s of
+    [] -> []
+    s2 -> case break isSpace s2 of
+        (w, rest) -> w : words rest
+
+unwords :: [[Char]] -> [Char]
+unwords ws = intercalate " " ws
+
+intersperse :: a -> [a] -> [a]
+intersperse sep xs = case xs of
+    y:ys -> y : prependToAll sep ys
+    [] -> []
+
+prependToAll :: a -> [a] -> [a]
+prependToAll sep xs = case xs of
+    y:ys -> sep : y : prependToAll sep ys
+    [] -> []
+
+intercalate :: [a] -> [[a]] -> [a]
+intercalate sep xss = concat (intersperse sep xss)
+
+transpose :: [[a]] -> [[a]]
+transpose xss = case xss of
+    [] -> []
+    ys:yss -> case ys of
+        [] -> transpose yss
+        z:zs -> (z : concatMap (take 1) yss) : transpose (zs : map (drop 1) yss)
+
+isPrefixOf :: Eq a => [a] -> [a] -> Bool
+isPrefixOf xs ys = case xs of
+    [] -> True
+    x:xs2 -> case ys of
+        y:ys2 -> (x == y) && isPrefixOf xs2 ys2
+        [] -> False
+
+isSuffixOf :: Eq a => [a] -> [a] -> Bool
+isSuffixOf xs ys = isPrefixOf (reverse xs) (reverse ys)
+
+isInfixOf :: Eq a => [a] -> [a] -> Bool
+isInfixOf needle haystack = any (isPrefixOf needle) (tails haystack)
+
+tails :: [a] -> [[a]]
+tails xs = case xs of
+    _:ys -> xs : tails ys
+    [] -> [[]]
+
+inits :: [a] -> [[a]]
+inits xs = case xs of
+    y:ys -> [] : map (\zs -> y : zs) (inits ys)
+    [] -> [[]]
+
+nub :: Eq a => [a] -> [a]
+nub xs = nubBy (==) xs
+
+nubBy :: (a -> a -> Bool) -> [a] -> [a]
+nubBy eq xs = case xs of
+    y:ys -> y : nubBy eq (filter (\z -> not (eq y z)) ys)
+    [] -> []
+
+delete :: Eq a => a -> [a] -> [a]
+delete x ys = deleteBy (==) x ys
+
+deleteBy :: (a -> a -> Bool) -> a -> [a] -> [a]
+deleteBy eq x ys = case ys of
+    y:rest -> if eq x y then rest else y : deleteBy eq x rest
+    [] -> []
+
+union :: Eq a => [a] -> [a] -> [a]
+union xs ys = xs ++ foldl (\acc x -> delete x acc) (nub ys) xs
+
+intersect :: Eq a => [a] -> [a] -> [a]
+intersect xs ys = filter (\x -> elem x ys) xs
+
+insert :: Ord a => a -> [a] -> [a]
+insert x ys = insertBy compare x ys
+
+insertBy :: (a -> a -> Ordering) -> a -> [a] -> [a]
+insertBy cmp x ys = case ys of
+    y:rest -> case cmp x y of
+        GT -> y : insertBy cmp x rest
+        _ -> x : ys
+    [] -> [x]
+
+sort :: Ord a => [a] -> [a]
+sort xs = sortBy compare xs
+
+sortOn :: Ord b => (a -> b) -> [a] -> [a]
+sortOn f xs = sortBy (\x y -> compare (f x) (f y)) xs
+
+sortBy :: (a -> a -> Ordering) -> [a] -> [a]
+sortBy cmp xs = mergeAll cmp (map (\x -> [x]) xs)
+
+mergeAll :: (a -> a -> Ordering) -> [[a]] -> [a]
+mergeAll cmp xss = case xss of
+    [] -> []
+    ys:rest -> case rest of
+        [] -> ys
+        _ -> mergeAll cmp (mergePairs cmp xss)
+
+mergePairs :: (a -> a -> Ordering) -> [[a]] -> [[a]]
+mergePairs cmp xss = case xss of
+    ys:rest -> case rest of
+        zs:rest2 -> merge cmp ys zs : mergePairs cmp rest2
+        [] -> [ys]
+    [] -> []
+
+merge :: (a -> a -> Ordering) -> [a] -> [a] -> [a]
+merge cmp xs ys = case xs of
+    [] -> ys
+    x:xs2 -> case ys of
+        [] -> xs
+        y:ys2 -> case cmp x y of
+            GT -> y : merge cmp xs ys2
+            _ -> x : merge cmp xs2 ys
+
+group :: Eq a => [a] -> [[a]]
+group xs = groupBy (==) xs
+
+groupBy :: (a -> a -> Bool) -> [a] -> [[a]]
+groupBy eq xs = case xs of
+    y:ys -> case span (eq y) ys of
+        (same, rest) -> (y : same) : groupBy eq rest
+    [] -> []
+
+partition :: (a -> Bool) -> [a] -> ([a], [a])
+partition p xs = (filter p xs, filter (\x -> not (p x)) xs)
+
+find :: (a -> Bool) -> [a] -> Maybe a
+find p xs = case xs of
+    y:ys -> if p y then Just y else find p ys
+    [] -> Nothing
+
+findIndex :: (a -> Bool) -> [a] -> Maybe Int
+findIndex p xs = findIndexFrom 0 p xs
+
+findIndexFrom :: Int -> (a -> Bool) -> [a] -> Maybe Int
+findIndexFrom i p xs = case xs of
+    y:ys -> if p y then Just i else findIndexFrom (i + 1) p ys
+    [] -> Nothing
+
+elemIndex :: Eq a => a -> [a] -> Maybe Int
+elemIndex x xs = findIndex (\y -> x == y) xs
+
+foldl1 :: (a -> a -> a) -> [a] -> a
+foldl1 f xs = case xs of
+    y:ys -> foldl f y ys
+    [] -> error "foldl1 called on empty list"
+
+foldr1 :: (a -> a -> a) -> [a] -> a
+foldr1 f xs = case xs of
+    y:ys -> case ys of
+        [] -> y
+        _ -> f y (foldr1 f ys)
+    [] -> error "foldr1 called on empty list"
+
+scanl :: (b -> a -> b) -> b -> [a] -> [b]
+scanl f z xs = case xs of
+    y:ys -> z : scanl f (f z y) ys
+    [] -> [z]
+
+scanr :: (a -> b -> b) -> b -> [a] -> [b]
+scanr f z xs = case xs of
+    y:ys -> let qs = scanr f z ys in f y (head qs) : qs
+    [] -> [z]
+
+maximumBy :: (a -> a -> Ordering) -> [a] -> a
+maximumBy cmp xs = foldl1 larger xs
+    where larger x y = if cmp x y == GT then x else y
+
+minimumBy :: (a -> a -> Ordering) -> [a] -> a
+minimumBy cmp xs = foldl1 smaller xs
+    where smaller x y = if cmp x y == GT then y else x
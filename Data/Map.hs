@@ -0,0 +1,344 @@
+module Data.Map where
+
+import Prelude hiding (map, filter, foldr, foldl, null, toList, empty)
+
+data Map k a = Tip | Bin Int k a (Map k a) (Map k a)
+
+instance (Eq k, Eq a) => Eq (Map k a) where
+    (==) m1 m2 = (size m1 == size m2) && (keys m1 == keys m2) && (elems m1 == elems m2)
+
+instance (Ord k, Ord a) => Ord (Map k a) where
+    compare m1 m2 = compareAssocs (toAscList m1) (toAscList m2)
+
+instance (Show k, Show a) => Show (Map k a) where
+    showsPrec p m = showParen (p > 10) (showString "fromList " . shows (toAscList m))
+
+instance Functor (Map k) where
+    fmap f m = map f m
+
+instance Foldable (Map k) where
+    foldr f z m = foldrWithKey (\_ x acc -> f x acc) z m
+    foldl f z m = foldlWithKey (\acc _ x -> f acc x) z m
+    toList m = elems m
+    null m = size m == 0
+    length m = size m
+
+compareAssocs :: (Ord k, Ord a) => [(k, a)] -> [(k, a)] -> Ordering
+compareAssocs xs ys = case xs of
+    [] -> case ys of
+        [] -> EQ
+        _ -> LT
+    p:ps -> case ys of
+        [] -> GT
+        q:qs -> case p of
+            (kx, x) -> case q of
+                (ky, y) -> case compare kx ky of
+                    EQ -> case compare x y of
+                        EQ -> compareAssocs ps qs
+                        c -> c
+                    c -> c
+
+empty :: Map k a
+empty = Tip
+
+singleton :: k -> a -> Map k a
+singleton k x = Bin 1 k x Tip Tip
+
+size :: Map k a -> Int
+size m = case m of
+    Tip -> 0
+    Bin n _ _ _ _ -> n
+
+null :: Map k a -> Bool
+null m = case m of
+    Tip -> True
+    Bin _ _ _ _ _ -> False
+
+lookup :: Ord k => k -> Map k a -> Maybe a
+lookup k m = case m of
+    Tip -> Nothing
+    Bin _ kx x l r -> case compare k kx of
+        LT -> lookup k l
+        GT -> lookup k r
+        EQ -> Just x
+
+member :: Ord k => k -> Map k a -> Bool
+member k m = case lookup k m of
+    Just _ -> True
+    Nothing -> False
+
+notMember :: Ord k => k -> Map k a -> Bool
+notMember k m = not (member k m)
+
+findWithDefault :: Ord k => a -> k -> Map k a -> a
+findWithDefault def k m = case lookup k m of
+    Just x -> x
+    Nothing -> def
+
+insert :: Ord k => k -> a -> Map k a -> Map k a
+insert k x m = insertWith const k x m
+
+insertWith :: Ord k => (a -> a -> a) -> k -> a -> Map k a -> Map k a
+insertWith f k x m = case m of
+    Tip -> singleton k x
+    Bin n ky y l r -> case compare k ky of
+        LT -> balance ky y (insertWith f k x l) r
+        GT -> balance ky y l (insertWith f k x r)
+        EQ -> Bin n k (f x y) l r
+
+delete :: Ord k => k -> Map k a -> Map k a
+delete k m = case m of
+    Tip -> Tip
+    Bin _ kx x l r -> case compare k kx of
+        LT -> balance kx x (delete k l) r
+        GT -> balance kx x l (delete k r)
+        EQ -> glue l r
+
+adjust :: Ord k => (a -> a) -> k -> Map k a -> Map k a
+adjust f k m = case m of
+    Tip -> Tip
+    Bin n kx x l r -> case compare k kx of
+        LT -> Bin n kx x (adjust f k l) r
+        GT -> Bin n kx x l (adjust f k r)
+        EQ -> Bin n kx (f x) l r
+
+alter :: Ord k => (Maybe a -> Maybe a) -> k -> Map k a -> Map k a
+alter f k m = case m of
+    Tip -> case f Nothing of
+        Just x -> singleton k x
+        Nothing -> Tip
+    Bin n kx x l r -> case compare k kx of
+        LT -> balance kx x (alter f k l) r
+        GT -> balance kx x l (alter f k r)
+        EQ -> case f (Just x) of
+            Just y -> Bin n kx y l r
+            Nothing -> glue l r
+
+update :: Ord k => (a -> Maybe a) -> k -> Map k a -> Map k a
+update f k m = alter (\x -> maybe Nothing f x) k m
+
+union :: Ord k => Map k a -> Map k a -> Map k a
+union m1 m2 = unionWith const m1 m2
+
+unionWith :: Ord k => (a -> a -> a) -> Map k a -> Map k a -> Map k a
+unionWith f m1 m2 = foldlWithKey (\m k x -> insertWith (\new old -> f old new) k x m) m1 m2
+
+unions :: Ord k => [Map k a] -> Map k a
+unions ms = foldl' union empty ms
+
+difference :: Ord k => Map k a -> Map k b -> Map k a
+difference m1 m2 = filterWithKey (\k _ -> notMember k m2) m1
+
+intersection :: Ord k => Map k a -> Map k b -> Map k a
+intersection m1 m2 = filterWithKey (\k _ -> member k m2) m1
+
+filter :: (a -> Bool) -> Map k a -> Map k a
+filter p m = filterWithKey (\_ x -> p x) m
+
+filterWithKey :: (k -> a -> Bool) -> Map k a -> Map k a
+filterWithKey p m = case m of
+    Tip -> Tip
+    Bin _ kx x l r -> case p kx x of
+        True -> link kx x (filterWithKey p l) (filterWithKey p r)
+        False -> merge (filterWithKey p l) (filterWithKey p r)
+
+map :: (a -> b) -> Map k a -> Map k b
+map f m = mapWithKey (\_ x -> f x) m
+
+mapWithKey :: (k -> a -> b) -> Map k a -> Map k b
+mapWithKey f m = case m of
+    Tip -> Tip
+    Bin n k x l r -> Bin n k (f k x) (mapWithKey f l) (mapWithKey f r)
+
+foldr :: (a -> b -> b) -> b -> Map k a -> b
+foldr f z m = foldrWithKey (\_ x acc -> f x acc) z m
+
+foldl :: (b -> a -> b) -> b -> Map k a -> b
+foldl f z m = foldlWithKey (\acc _ x -> f acc x) z m
+
+foldrWithKey :: (k -> a -> b -> b) -> b -> Map k a -> b
+foldrWithKey f z m = case m of
+    Tip -> z
+    Bin _ k x l r -> foldrWithKey f (f k x (foldrWithKey f z r)) l
+
+foldlWithKey :: (b -> k -> a -> b) -> b -> Map k a -> b
+foldlWithKey f z m = case m of
+    Tip -> z
+    Bin _ k x l r -> foldlWithKey f (f (foldlWithKey f z l) k x) r
+
+elems :: Map k a -> [a]
+elems m = foldrWithKey (\_ x xs -> x : xs) [] m
+
+keys :: Map k a -> [k]
+keys m = foldrWithKey (\k _ ks -> k : ks) [] m
+
+assocs :: Map k a -> [(k, a)]
+assocs m = toAscList m
+
+toList :: Map k a -> [(k, a)]
+toList m = toAscList m
+
+toAscList :: Map k a -> [(k, a)]
+toAscList m = foldrWithKey (\k x xs -> (k, x) : xs) [] m
+
+fromList :: Ord k => [(k, a)] -> Map k a
+fromList xs = foldl' (\m p -> insert (fst p) (snd p) m) empty xs
+
+fromListWith :: Ord k => (a -> a -> a) -> [(k, a)] -> Map k a
+fromListWith f xs = foldl' (\m p -> insertWith f (fst p) (snd p) m) empty xs
+
+findMin :: Map k a -> (k, a)
+findMin m = case m of
+    Bin _ k x l _ -> case l of
+        Tip -> (k, x)
+        Bin _ _ _ _ _ -> findMin l
+    Tip -> error "Map.findMin: empty map has no minimal element"
+
+findMax :: Map k a -> (k, a)
+findMax m = case m of
+    Bin _ k x _ r -> case r of
+        Tip -> (k, x)
+        Bin _ _ _ _ _ -> findMax r
+    Tip -> error "Map.findMax: empty map has no maximal element"
+
+deleteMin :: Map k a -> Map k a
+deleteMin m = case m of
+    Tip -> Tip
+    Bin _ k x l r -> case l of
+        Tip -> r
+        Bin _ _ _ _ _ -> balance k x (deleteMin l) r
+
+deleteMax :: Map k a -> Map k a
+deleteMax m = case m of
+    Tip -> Tip
+    Bin _ k x l r -> case r of
+        Tip -> l
+        Bin _ _ _ _ _ -> balance k x l (deleteMax r)
+
+valid :: Ord k => Map k a -> Bool
+valid m = balanced m && validSize m && ordered (keys m)
+
+balanced :: Map k a -> Bool
+balanced m = case m of
+    Tip -> True
+    Bin _ _ _ l r -> (((size l + size r) <= 1) || ((size l <= delta * size r) && (size r <= delta * size l)))
+        && balanced l && balanced r
+
+validSize :: Map k a -> Bool
+validSize m = case m of
+    Tip -> True
+    Bin n _ _ l r -> (n == size l + size r + 1) && validSize l && validSize r
+
+ordered :: Ord a => [a] -> Bool
+ordered xs = case xs of
+    [] -> True
+    x:ys -> case ys of
+        [] -> True
+        y:_ -> (x < y) && ordered ys
+
+delta :: Int
+delta = 3
+
+ratio :: Int
+ratio = 2
+
+bin :: k -> a -> Map k a -> Map k a -> Map k a
+bin k x l r = Bin (size l + size r + 1) k x l r
+
+balance :: k -> a -> Map k a -> Map k a -> Map k a
+balance k x l r =
+    if (size l + size r) <= 1 then bin k x l r
+    else if size r > delta * size l then rotateL k x l r
+    else if size l > delta * size r then rotateR k x l r
+    else bin k x l r
+
+rotateL :: k -> a -> Map k a -> Map k a -> Map k a
+rotateL k x l r = case r of
+    Bin _ _ _ ly ry -> if size ly < ratio * size ry then singleL k x l r else doubleL k x l r
+    Tip -> error "Map.rotateL: empty tree"
+
+rotateR :: k -> a -> Map k a -> Map k a -> Map k a
+rotateR k x l r = case l of
+    Bin _ _ _ ly ry -> if size ry < ratio * size ly then singleR k x l r else doubleR k x l r
+    Tip -> error "Map.rotateR: empty tree"
+
+singleL :: k -> a -> Map k a -> Map k a -> Map k a
+singleL k1 x1 t1 r = case r of
+    Bin _ k2 x2 t2 t3 -> bin k2 x2 (bin k1 x1 t1 t2) t3
+    Tip -> error "Map.singleL: empty tree"
+
+singleR :: k -> a -> Map k a -> Map k a -> Map k a
+singleR k1 x1 l t3 = case l of
+    Bin _ k2 x2 t1 t2 -> bin k2 x2 t1 (bin k1 x1 t2 t3)
+    Tip -> error "Map.singleR: empty tree"
+
+doubleL :: k -> a -> Map k a -> Map k a -> Map k a
+doubleL k1 x1 t1 r = case r of
+    Bin _ k2 x2 m t4 -> case m of
+        Bin _ k3 x3 t2 t3 -> bin k3 x3 (bin k1 x1 t1 t2) (bin k2 x2 t3 t4)
+        Tip -> error "Map.doubleL: empty tree"
+    Tip -> error "Map.doubleL: empty tree"
+
+doubleR :: k -> a -> Map k a -> Map k a -> Map k a
+doubleR k1 x1 l t4 = case l of
+    Bin _ k2 x2 t1 m -> case m of
+        Bin _ k3 x3 t2 t3 -> bin k3 x3 (bin k2 x2 t1 t2) (bin k1 x1 t3 t4)
+        Tip -> error "Map.doubleR: empty tree"
+    Tip -> error "Map.doubleR: empty tree"
+
+link :: k -> a -> Map k a -> Map k a -> Map k a
+link kx x l r = case l of
+    Tip -> insertMin kx x r
+    Bin sl ky y ly ry -> case r of
+        Tip -> insertMax kx x l
+        Bin sr kz z lz rz ->
+            if delta * sl < sr then balance kz z (link kx x l lz) rz
+            else if delta * sr < sl then balance ky y ly (link kx x ry r)
+            else bin kx x l r
+
+insertMin :: k -> a -> Map k a -> Map k a
+insertMin kx x m = case m of
+    Tip -> singleton kx x
+    Bin _ ky y l r -> balance ky y (insertMin kx x l) r
+
+insertMax :: k -> a -> Map k a -> Map k a
+insertMax kx x m = case m of
+    Tip -> singleton kx x
+    Bin _ ky y l r -> balance ky y l (insertMax kx x r)
+
+merge :: Map k a -> Map k a -> Map k a
+merge l r = case l of
+    Tip -> r
+    Bin sl kx x lx rx -> case r of
+        Tip -> l
+        Bin sr ky y ly ry ->
+            if delta * sl < sr then balance ky y (merge l ly) ry
+            else if delta * sr < sl then balance kx x lx (merge rx r)
+            else glue l r
+
+glue :: Map k a -> Map k a -> Map k a
+glue l r = case l of
+    Tip -> r
+    Bin _ _ _ _ _ -> case r of
+        Tip -> l
+        Bin _ _ _ _ _ -> if size l > size r
+            then case deleteFindMax l of
+                (km, xm, l2) -> balance km xm l2 r
+            else case deleteFindMin r of
+                (km, xm, r2) -> balance km xm l r2
+
+deleteFindMin :: Map k a -> (k, a, Map k a)
+deleteFindMin m = case m of
+    Bin _ k x l r -> case l of
+        Tip -> (k, x, r)
+        Bin _ _ _ _ _ -> case deleteFindMin l of
+            (km, xm, l2) -> (km, xm, balance k x l2 r)
+    Tip -> error "Map.deleteFindMin: can not return the minimal element of an empty map"
+
+deleteFindMax :: Map k a -> (k, a, Map k a)
+deleteFindMax m = case m of
+    Bin _ k x l r -> case r of
+        Tip -> (k, x, l)
+        Bin _ _ _ _ _ -> case deleteFindMax r of
+            (km, xm, r2) -> (km, xm, balance k x l r2)
+    Tip -> error "Map.deleteFindMax: can not return the maximal element of an empty map"
@@ -0,0 +1,45 @@
+module Data.Maybe where
+
+import Prelude
+
+isJust :: Maybe a -> Bool
+isJust x = case x of
+    Just _ -> True
+    Nothing -> False
+
+isNothing :: Maybe a -> Bool
+isNothing x = not (isJust x)
+
+fromJust :: Maybe a -> a
+fromJust x = case x of
+    Just y -> y
+    Nothing -> error "Maybe.fromJust: Nothing"
+
+fromMaybe :: a -> Maybe a -> a
+fromMaybe def x = case x of
+    Just y -> y
+    Nothing -> def
+
+listToMaybe :: [a] -> Maybe a
+listToMaybe xs = case xs of
+    y:_ -> Just y
+    [] -> Nothing
+
+maybeToList :: Maybe a -> [a]
+maybeToList x = case x of
+    Just y -> [y]
+    Nothing -> []
+
+catMaybes :: [Maybe a] -> [a]
+catMaybes xs = case xs of
+    y:ys -> case y of
+        Just z -> z : catMaybes ys
+        Nothing -> catMaybes ys
+    [] -> []
+
+mapMaybe :: (a -> Maybe b) -> [a] -> [b]
+mapMaybe f xs = case xs of
+    y:ys -> case f y of
+        Just z -> z : mapMaybe f ys
+        Nothing -> mapMaybe f ys
+    [] -> []
@@ -0,0 +1,14 @@
+module Data.Ratio where
+
+import Prelude
+
+infixl 7 %
+
+(%) :: Integral a => a -> a -> Ratio a
+(%) x y = reduce x y
+
+numerator :: Ratio a -> a
+numerator (MkRatio x _) = x
+
+denominator :: Ratio a -> a
+denominator (MkRatio _ y) = y
@@ -0,0 +1,23 @@
+module Data.STRef where
+
+import Prelude
+import Control.Monad.ST
+
+data STRef s a = STRef (IORef a)
+
+newSTRef :: a -> ST s (STRef s a)
+newSTRef x = unsafeIOToST (primNewIORef x >>= \ref -> return (STRef ref))
+
+readSTRef :: STRef s a -> ST s a
+readSTRef ref = case ref of
+    STRef r -> unsafeIOToST (primReadIORef r)
+
+writeSTRef :: STRef s a -> a -> ST s ()
+writeSTRef ref x = case ref of
+    STRef r -> unsafeIOToST (primWriteIORef r x)
+
+modifySTRef :: STRef s a -> (a -> a) -> ST s ()
+modifySTRef ref f = readSTRef ref >>= \x -> writeSTRef ref (f x)
+
+modifySTRef' :: STRef s a -> (a -> a) -> ST s ()
+modifySTRef' ref f = readSTRef ref >>= \x -> let y = f x in y `seq` writeSTRef ref y
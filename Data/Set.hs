@@ -0,0 +1,273 @@
+module Data.Set where
+
+import Prelude hiding (map, filter, foldr, foldl, null, toList, empty)
+
+data Set a = Tip | Bin Int a (Set a) (Set a)
+
+instance Eq a => Eq (Set a) where
+    (==) s1 s2 = (size s1 == size s2) && (toAscList s1 == toAscList s2)
+
+instance Ord a => Ord (Set a) where
+    compare s1 s2 = compare (toAscList s1) (toAscList s2)
+
+instance Show a => Show (Set a) where
+    showsPrec p s = showParen (p > 10) (showString "fromList " . shows (toAscList s))
+
+instance Foldable Set where
+    foldr f z s = foldr f z s
+    foldl f z s = foldl f z s
+    toList s = toAscList s
+    null s = size s == 0
+    length s = size s
+    elem x s = elemSet x s
+
+elemSet :: Eq a => a -> Set a -> Bool
+elemSet x s = case s of
+    Tip -> False
+    Bin _ y l r -> (x == y) || elemSet x l || elemSet x r
+
+empty :: Set a
+empty = Tip
+
+singleton :: a -> Set a
+singleton x = Bin 1 x Tip Tip
+
+size :: Set a -> Int
+size s = case s of
+    Tip -> 0
+    Bin n _ _ _ -> n
+
+null :: Set a -> Bool
+null s = case s of
+    Tip -> True
+    Bin _ _ _ _ -> False
+
+member :: Ord a => a -> Set a -> Bool
+member x s = case s of
+    Tip -> False
+    Bin _ y l r -> case compare x y of
+        LT -> member x l
+        GT -> member x r
+        EQ -> True
+
+notMember :: Ord a => a -> Set a -> Bool
+notMember x s = not (member x s)
+
+insert :: Ord a => a -> Set a -> Set a
+insert x s = case s of
+    Tip -> singleton x
+    Bin n y l r -> case compare x y of
+        LT -> balance y (insert x l) r
+        GT -> balance y l (insert x r)
+        EQ -> Bin n x l r
+
+delete :: Ord a => a -> Set a -> Set a
+delete x s = case s of
+    Tip -> Tip
+    Bin _ y l r -> case compare x y of
+        LT -> balance y (delete x l) r
+        GT -> balance y l (delete x r)
+        EQ -> glue l r
+
+union :: Ord a => Set a -> Set a -> Set a
+union s1 s2 = foldl (\s x -> if member x s then s else insert x s) s1 s2
+
+unions :: Ord a => [Set a] -> Set a
+unions ss = foldl' union empty ss
+
+difference :: Ord a => Set a -> Set a -> Set a
+difference s1 s2 = filter (\x -> notMember x s2) s1
+
+intersection :: Ord a => Set a -> Set a -> Set a
+intersection s1 s2 = filter (\x -> member x s2) s1
+
+isSubsetOf :: Ord a => Set a -> Set a -> Bool
+isSubsetOf s1 s2 = (size s1 <= size s2) && all (\x -> member x s2) (toAscList s1)
+
+filter :: (a -> Bool) -> Set a -> Set a
+filter p s = case s of
+    Tip -> Tip
+    Bin _ x l r -> case p x of
+        True -> link x (filter p l) (filter p r)
+        False -> merge (filter p l) (filter p r)
+
+map :: Ord b => (a -> b) -> Set a -> Set b
+map f s = fromList (fmap f (toAscList s))
+
+foldr :: (a -> b -> b) -> b -> Set a -> b
+foldr f z s = case s of
+    Tip -> z
+    Bin _ x l r -> foldr f (f x (foldr f z r)) l
+
+foldl :: (b -> a -> b) -> b -> Set a -> b
+foldl f z s = case s of
+    Tip -> z
+    Bin _ x l r -> foldl f (f (foldl f z l) x) r
+
+elems :: Set a -> [a]
+elems s = toAscList s
+
+toList :: Set a -> [a]
+toList s = toAscList s
+
+toAscList :: Set a -> [a]
+toAscList s = foldr (\x xs -> x : xs) [] s
+
+fromList :: Ord a => [a] -> Set a
+fromList xs = foldl' (\s x -> insert x s) empty xs
+
+findMin :: Set a -> a
+findMin s = case s of
+    Bin _ x l _ -> case l of
+        Tip -> x
+        Bin _ _ _ _ -> findMin l
+    Tip -> error "Set.findMin: empty set has no minimal element"
+
+findMax :: Set a -> a
+findMax s = case s of
+    Bin _ x _ r -> case r of
+        Tip -> x
+        Bin _ _ _ _ -> findMax r
+    Tip -> error "Set.findMax: empty set has no maximal element"
+
+deleteMin :: Set a -> Set a
+deleteMin s = case s of
+    Tip -> Tip
+    Bin _ x l r -> case l of
+        Tip -> r
+        Bin _ _ _ _ -> balance x (deleteMin l) r
+
+deleteMax :: Set a -> Set a
+deleteMax s = case s of
+    Tip -> Tip
+    Bin _ x l r -> case r of
+        Tip -> l
+        Bin _ _ _ _ -> balance x l (deleteMax r)
+
+valid :: Ord a => Set a -> Bool
+valid s = balanced s && validSize s && ordered (toAscList s)
+
+balanced :: Set a -> Bool
+balanced s = case s of
+    Tip -> True
+    Bin _ _ l r -> (((size l + size r) <= 1) || ((size l <= delta * size r) && (size r <= delta * size l)))
+        && balanced l && balanced r
+
+validSize :: Set a -> Bool
+validSize s = case s of
+    Tip -> True
+    Bin n _ l r -> (n == size l + size r + 1) && validSize l && validSize r
+
+ordered :: Ord a => [a] -> Bool
+ordered xs = case xs of
+    [] -> True
+    x:ys -> case ys of
+        [] -> True
+        y:_ -> (x < y) && ordered ys
+
+delta :: Int
+delta = 3
+
+ratio :: Int
+ratio = 2
+
+bin :: a -> Set a -> Set a -> Set a
+bin x l r = Bin (size l + size r + 1) x l r
+
+balance :: a -> Set a -> Set a -> Set a
+balance x l r =
+    if (size l + size r) <= 1 then bin x l r
+    else if size r > delta * size l then rotateL x l r
+    else if size l > delta * size r then rotateR x l r
+    else bin x l r
+
+rotateL :: a -> Set a -> Set a -> Set a
+rotateL x l r = case r of
+    Bin _ _ ly ry -> if size ly < ratio * size ry then singleL x l r else doubleL x l r
+    Tip -> error "Set.rotateL: empty tree"
+
+rotateR :: a -> Set a -> Set a -> Set a
+rotateR x l r = case l of
+    Bin _ _ ly ry -> if size ry < ratio * size ly then singleR x l r else doubleR x l r
+    Tip -> error "Set.rotateR: empty tree"
+
+singleL :: a -> Set a -> Set a -> Set a
+singleL x1 t1 r = case r of
+    Bin _ x2 t2 t3 -> bin x2 (bin x1 t1 t2) t3
+    Tip -> error "Set.singleL: empty tree"
+
+singleR :: a -> Set a -> Set a -> Set a
+singleR x1 l t3 = case l of
+    Bin _ x2 t1 t2 -> bin x2 t1 (bin x1 t2 t3)
+    Tip -> error "Set.singleR: empty tree"
+
+doubleL :: a -> Set a -> Set a -> Set a
+doubleL x1 t1 r = case r of
+    Bin _ x2 m t4 -> case m of
+        Bin _ x3 t2 t3 -> bin x3 (bin x1 t1 t2) (bin x2 t3 t4)
+        Tip -> error "Set.doubleL: empty tree"
+    Tip -> error "Set.doubleL: empty tree"
+
+doubleR :: a -> Set a -> Set a -> Set a
+doubleR x1 l t4 = case l of
+    Bin _ x2 t1 m -> case m of
+        Bin _ x3 t2 t3 -> bin x3 (bin x2 t1 t2) (bin x1 t3 t4)
+        Tip -> error "Set.doubleR: empty tree"
+    Tip -> error "Set.doubleR: empty tree"
+
+link :: a -> Set a -> Set a -> Set a
+link x l r = case l of
+    Tip -> insertMin x r
+    Bin sl y ly ry -> case r of
+        Tip -> insertMax x l
+        Bin sr z lz rz ->
+            if delta * sl < sr then balance z (link x l lz) rz
+            else if delta * sr < sl then balance y ly (link x ry r)
+            else bin x l r
+
+insertMin :: a -> Set a -> Set a
+insertMin x s = case s of
+    Tip -> singleton x
+    Bin _ y l r -> balance y (insertMin x l) r
+
+insertMax :: a -> Set a -> Set a
+insertMax x s = case s of
+    Tip -> singleton x
+    Bin _ y l r -> balance y l (insertMax x r)
+
+merge :: Set a -> Set a -> Set a
+merge l r = case l of
+    Tip -> r
+    Bin sl x lx rx -> case r of
+        Tip -> l
+        Bin sr y ly ry ->
+            if delta * sl < sr then balance y (merge l ly) ry
+            else if delta * sr < sl then balance x lx (merge rx r)
+            else glue l r
+
+glue :: Set a -> Set a -> Set a
+glue l r = case l of
+    Tip -> r
+    Bin _ _ _ _ -> case r of
+        Tip -> l
+        Bin _ _ _ _ -> if size l > size r
+            then case deleteFindMax l of
+                (m, l2) -> balance m l2 r
+            else case deleteFindMin r of
+                (m, r2) -> balance m l r2
+
+deleteFindMin :: Set a -> (a, Set a)
+deleteFindMin s = case s of
+    Bin _ x l r -> case l of
+        Tip -> (x, r)
+        Bin _ _ _ _ -> case deleteFindMin l of
+            (m, l2) -> (m, balance x l2 r)
+    Tip -> error "Set.deleteFindMin: can not return the minimal element of an empty set"
+
+deleteFindMax :: Set a -> (a, Set a)
+deleteFindMax s = case s of
+    Bin _ x l r -> case r of
+        Tip -> (x, l)
+        Bin _ _ _ _ -> case deleteFindMax r of
+            (m, r2) -> (m, balance x l r2)
+    Tip -> error "Set.deleteFindMax: can not return the maximal element of an empty set"
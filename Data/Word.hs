@@ -0,0 +1,100 @@
+module Data.Word where
+
+import Prelude
+
+newtype Word8 = W8 Int
+newtype Word16 = W16 Int
+newtype Word32 = W32 Int
+newtype Word64 = W64 Int
+
+instance Eq Word8 where
+    (==) (W8 x) (W8 y) = primIntEQ x y
+
+instance Ord Word8 where
+    compare (W8 x) (W8 y) = compare x y
+
+instance Num Word8 where
+    (+) (W8 x) (W8 y) = W8 (primNarrowWord 8 (x + y))
+    (-) (W8 x) (W8 y) = W8 (primNarrowWord 8 (x - y))
+    (*) (W8 x) (W8 y) = W8 (primNarrowWord 8 (x * y))
+    fromInteger n = W8 (primNarrowWord 8 (primIntegerToInt n))
+
+instance Real Word8 where
+    toRational (W8 x) = toRational x
+
+instance Integral Word8 where
+    div (W8 x) (W8 y) = W8 (div x y)
+    rem (W8 x) (W8 y) = W8 (rem x y)
+    toInteger (W8 x) = primIntToInteger x
+
+instance Show Word8 where
+    show (W8 x) = show x
+
+instance Eq Word16 where
+    (==) (W16 x) (W16 y) = primIntEQ x y
+
+instance Ord Word16 where
+    compare (W16 x) (W16 y) = compare x y
+
+instance Num Word16 where
+    (+) (W16 x) (W16 y) = W16 (primNarrowWord 16 (x + y))
+    (-) (W16 x) (W16 y) = W16 (primNarrowWord 16 (x - y))
+    (*) (W16 x) (W16 y) = W16 (primNarrowWord 16 (x * y))
+    fromInteger n = W16 (primNarrowWord 16 (primIntegerToInt n))
+
+instance Real Word16 where
+    toRational (W16 x) = toRational x
+
+instance Integral Word16 where
+    div (W16 x) (W16 y) = W16 (div x y)
+    rem (W16 x) (W16 y) = W16 (rem x y)
+    toInteger (W16 x) = primIntToInteger x
+
+instance Show Word16 where
+    show (W16 x) = show x
+
+instance Eq Word32 where
+    (==) (W32 x) (W32 y) = primIntEQ x y
+
+instance Ord Word32 where
+    compare (W32 x) (W32 y) = compare x y
+
+instance Num Word32 where
+    (+) (W32 x) (W32 y) = W32 (primNarrowWord 32 (x + y))
+    (-) (W32 x) (W32 y) = W32 (primNarrowWord 32 (x - y))
+    (*) (W32 x) (W32 y) = W32 (primNarrowWord 32 (x * y))
+    fromInteger n = W32 (primNarrowWord 32 (primIntegerToInt n))
+
+instance Real Word32 where
+    toRational (W32 x) = toRational x
+
+instance Integral Word32 where
+    div (W32 x) (W32 y) = W32 (div x y)
+    rem (W32 x) (W32 y) = W32 (rem x y)
+    toInteger (W32 x) = primIntToInteger x
+
+instance Show Word32 where
+    show (W32 x) = show x
+
+instance Eq Word64 where
+    (==) (W64 x) (W64 y) = primIntEQ x y
+
+instance Ord Word64 where
+    compare (W64 x) (W64 y) = primWordCompare x y
+
+instance Num Word64 where
+    (+) (W64 x) (W64 y) = W64 (x + y)
+    (-) (W64 x) (W64 y) = W64 (x - y)
+    (*) (W64 x) (W64 y) = W64 (x * y)
+    fromInteger n = W64 (primIntegerToInt n)
+
+instance Real Word64 where
+    toRational (W64 x) = MkRatio (primWordToInteger x) 1
+
+instance Integral Word64 where
+    div (W64 x) (W64 y) = W64 (primWordQuot x y)
+    rem (W64 x) (W64 y) = W64 (primWordRem x y)
+    toInteger (W64 x) = primWordToInteger x
+
+instance Show Word64 where
+    show (W64 x) = primShowInteger (primWordToInteger x)
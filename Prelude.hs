@@ -61,6 +61,23 @@ instance Eq Double where
     (==) x y = primDoubleEQ x y
     (/=) x y = not (x == y)
 
+instance Eq Integer where
+    (==) x y = case primIntegerCompare x y of
+        EQ -> True
+        _ -> False
+
+instance Eq Word where
+    (==) (W x) (W y) = primIntEQ x y
+
+instance Eq Float where
+    (==) (F x) (F y) = primDoubleEQ x y
+
+instance Eq Char where
+    (==) x y = primIntEQ (primCharToInt x) (primCharToInt y)
+
+instance Eq () where
+    (==) x y = True
+
 instance Eq a => Eq [a] where
     (==) (x:xs) (y:ys) = (x == y) && (xs == ys)
     (==) [] [] = True
@@ -68,6 +85,10 @@ instance Eq a => Eq [a] where
 
     (/=) xs ys = not (xs == ys)
 
+newtype Word = W Int
+
+newtype Float = F Double
+
 infixl 6 +, -
 infixl 7 *
 
@@ -75,42 +96,282 @@ class Num a where
     (+) :: a -> a -> a
     (-) :: a -> a -> a
     (*) :: a -> a -> a
-    fromInteger :: Int -> a
+    fromInteger :: Integer -> a
 
 instance Num Int where
     (+) x y = primIntAdd x y
     (-) x y = primIntSubtract x y
     (*) x y = primIntMultiply x y
-    fromInteger x = x
+    fromInteger x = primIntegerToInt x
 
 instance Num Double where
     (+) x y = primDoubleAdd x y
     (-) x y = primDoubleSubtract x y
     (*) x y = primDoubleMultiply x y
-    fromInteger x = primIntToDouble x
+    fromInteger x = primIntegerToDouble x
+
+instance Num Integer where
+    (+) x y = primIntegerAdd x y
+    (-) x y = primIntegerSubtract x y
+    (*) x y = primIntegerMultiply x y
+    fromInteger x = x
+
+instance Num Word where
+    (+) (W x) (W y) = W (primIntAdd x y)
+    (-) (W x) (W y) = W (primIntSubtract x y)
+    (*) (W x) (W y) = W (primIntMultiply x y)
+    fromInteger x = W (primIntegerToInt x)
+
+instance Num Float where
+    (+) (F x) (F y) = F (primDoubleToFloat (primDoubleAdd x y))
+    (-) (F x) (F y) = F (primDoubleToFloat (primDoubleSubtract x y))
+    (*) (F x) (F y) = F (primDoubleToFloat (primDoubleMultiply x y))
+    fromInteger x = F (primDoubleToFloat (primIntegerToDouble x))
+
+data Ratio a = MkRatio a a
+
+type Rational = Ratio Integer
+
+class (Num a, Ord a) => Real a where
+    toRational :: a -> Rational
+
+instance Real Int where
+    toRational x = MkRatio (primIntToInteger x) 1
+
+instance Real Integer where
+    toRational x = MkRatio x 1
+
+instance Real Word where
+    toRational (W x) = MkRatio (primWordToInteger x) 1
+
+instance Real Double where
+    toRational x = decodedToRational (primDecodeDouble x)
+
+instance Real Float where
+    toRational (F x) = decodedToRational (primDecodeFloat x)
+
+decodedToRational :: (Integer, Int) -> Rational
+decodedToRational d = case d of
+    (m, e) -> if e < 0 then reduce m (shiftInteger 1 (0 - e)) else MkRatio (shiftInteger m e) 1
+
+shiftInteger :: Integer -> Int -> Integer
+shiftInteger x n = if n == 0 then x else shiftInteger (x * 2) (n - 1)
 
 infixl 7 /
 
-class Fractional a where
+class Num a => Fractional a where
     (/) :: a -> a -> a
-    fromRational :: Double -> a
+    fromRational :: Rational -> a
 
 instance Fractional Double where
     (/) x y = primDoubleDivide x y
-    fromRational x = x
+    fromRational (MkRatio n d) = primRationalToDouble n d
+
+instance Fractional Float where
+    (/) (F x) (F y) = F (primDoubleToFloat (primDoubleDivide x y))
+    fromRational (MkRatio n d) = F (primDoubleToFloat (primRationalToDouble n d))
 
 infixl 7 `div`, `rem`
 
-class Integral a where
+class Real a => Integral a where
     div :: a -> a -> a
     rem :: a -> a -> a
-    toInteger :: a -> Int
+    toInteger :: a -> Integer
 
 instance Integral Int where
     div x y = primIntDivide x y
     rem x y = primIntRemainder x y
+    toInteger x = primIntToInteger x
+
+instance Integral Integer where
+    div x y = primIntegerDiv x y
+    rem x y = primIntegerRem x y
     toInteger x = x
 
+instance Integral Word where
+    div (W x) (W y) = W (primWordQuot x y)
+    rem (W x) (W y) = W (primWordRem x y)
+    toInteger (W x) = primWordToInteger x
+
+fromIntegral :: (Integral a, Num b) => a -> b
+fromIntegral x = fromInteger (toInteger x)
+
+realToFrac :: (Real a, Fractional b) => a -> b
+realToFrac x = fromRational (toRational x)
+
+infixr 8 ^
+(^) :: (Num a, Integral b) => a -> b -> a
+(^) x n = if n < 0 then error "Negative exponent" else power x n 1
+
+power :: (Num a, Integral b) => a -> b -> a -> a
+power x n acc =
+    if n == 0
+        then acc
+        else if rem n 2 == 0
+            then power (x * x) (div n 2) acc
+            else power (x * x) (div n 2) (acc * x)
+
+gcd :: Integral a => a -> a -> a
+gcd x y = gcdPositive (absIntegral x) (absIntegral y)
+
+gcdPositive :: Integral a => a -> a -> a
+gcdPositive x y = if y == 0 then x else gcdPositive y (rem x y)
+
+absIntegral :: Integral a => a -> a
+absIntegral x = if x < 0 then 0 - x else x
+
+reduce :: Integral a => a -> a -> Ratio a
+reduce x y = if y == 0
+    then error "Ratio has zero denominator"
+    else if y < 0
+        then reduce (0 - x) (0 - y)
+        else let d = gcd x y in MkRatio (div x d) (div y d)
+
+instance Integral a => Eq (Ratio a) where
+    (==) (MkRatio a b) (MkRatio c d) = (a == c) && (b == d)
+
+instance Integral a => Ord (Ratio a) where
+    compare (MkRatio a b) (MkRatio c d) = compare (a * d) (c * b)
+
+instance Integral a => Num (Ratio a) where
+    (+) (MkRatio a b) (MkRatio c d) = reduce (a * d + c * b) (b * d)
+    (-) (MkRatio a b) (MkRatio c d) = reduce (a * d - c * b) (b * d)
+    (*) (MkRatio a b) (MkRatio c d) = reduce (a * c) (b * d)
+    fromInteger n = MkRatio (fromInteger n) 1
+
+instance Integral a => Real (Ratio a) where
+    toRational (MkRatio a b) = MkRatio (toInteger a) (toInteger b)
+
+instance Integral a => Fractional (Ratio a) where
+    (/) (MkRatio a b) (MkRatio c d) = reduce (a * d) (b * c)
+    fromRational (MkRatio a b) = MkRatio (fromInteger a) (fromInteger b)
+
+instance Show a => Show (Ratio a) where
+    showsPrec p (MkRatio a b) = showParen (p > 7) (showsPrec 8 a . showString " % " . showsPrec 8 b)
+
+infixr 8 **
+
+class Fractional a => Floating a where
+    pi :: a
+    exp :: a -> a
+    log :: a -> a
+    sqrt :: a -> a
+    (**) :: a -> a -> a
+    logBase :: a -> a -> a
+    sin :: a -> a
+    cos :: a -> a
+    tan :: a -> a
+    asin :: a -> a
+    acos :: a -> a
+    atan :: a -> a
+    sinh :: a -> a
+    cosh :: a -> a
+    tanh :: a -> a
+    (**) x y = exp (log x * y)
+    logBase x y = log y / log x
+    tan x = sin x / cos x
+    tanh x = sinh x / cosh x
+
+instance Floating Double where
+    pi = 3.141592653589793
+    exp x = primDoubleExp x
+    log x = primDoubleLog x
+    sqrt x = primDoubleSqrt x
+    (**) x y = primDoublePow x y
+    sin x = primDoubleSin x
+    cos x = primDoubleCos x
+    tan x = primDoubleTan x
+    asin x = primDoubleAsin x
+    acos x = primDoubleAcos x
+    atan x = primDoubleAtan x
+    sinh x = primDoubleSinh x
+    cosh x = primDoubleCosh x
+    tanh x = primDoubleTanh x
+
+instance Floating Float where
+    pi = F (primDoubleToFloat 3.141592653589793)
+    exp (F x) = F (primDoubleToFloat (primDoubleExp x))
+    log (F x) = F (primDoubleToFloat (primDoubleLog x))
+    sqrt (F x) = F (primDoubleToFloat (primDoubleSqrt x))
+    (**) (F x) (F y) = F (primDoubleToFloat (primDoublePow x y))
+    sin (F x) = F (primDoubleToFloat (primDoubleSin x))
+    cos (F x) = F (primDoubleToFloat (primDoubleCos x))
+    tan (F x) = F (primDoubleToFloat (primDoubleTan x))
+    asin (F x) = F (primDoubleToFloat (primDoubleAsin x))
+    acos (F x) = F (primDoubleToFloat (primDoubleAcos x))
+    atan (F x) = F (primDoubleToFloat (primDoubleAtan x))
+    sinh (F x) = F (primDoubleToFloat (primDoubleSinh x))
+    cosh (F x) = F (primDoubleToFloat (primDoubleCosh x))
+    tanh (F x) = F (primDoubleToFloat (primDoubleTanh x))
+
+class (Real a, Fractional a) => RealFrac a where
+    properFraction :: Integral b => a -> (b, a)
+    truncate :: Integral b => a -> b
+    round :: Integral b => a -> b
+    ceiling :: Integral b => a -> b
+    floor :: Integral b => a -> b
+    truncate x = fst (properFraction x)
+
+instance Integral a => RealFrac (Ratio a) where
+    properFraction (MkRatio a b) = (fromIntegral (div (a - rem a b) b), MkRatio (rem a b) b)
+    floor x = case properFraction x of
+        (n, r) -> if r < 0 then n - 1 else n
+    ceiling x = case properFraction x of
+        (n, r) -> if r > 0 then n + 1 else n
+    round x = case properFraction x of
+        (n, r) -> let
+                m = if r < 0 then n - 1 else n + 1
+                half = if r < 0 then 0 - r else r
+            in case compare half (MkRatio 1 2) of
+                LT -> n
+                EQ -> if rem n 2 == 0 then n else m
+                GT -> m
+
+instance RealFrac Double where
+    properFraction x = (fromInteger (primDoubleToInteger x), primDoubleSubtract x (primDoubleTruncate x))
+    truncate x = fromInteger (primDoubleToInteger x)
+    round x = fromInteger (primDoubleToInteger (primDoubleRound x))
+    ceiling x = fromInteger (primDoubleToInteger (primDoubleCeiling x))
+    floor x = fromInteger (primDoubleToInteger (primDoubleFloor x))
+
+instance RealFrac Float where
+    properFraction (F x) = (fromInteger (primDoubleToInteger x), F (primDoubleSubtract x (primDoubleTruncate x)))
+    truncate (F x) = truncate x
+    round (F x) = round x
+    ceiling (F x) = ceiling x
+    floor (F x) = floor x
+
+class (RealFrac a, Floating a) => RealFloat a where
+    floatRadix :: a -> Integer
+    floatDigits :: a -> Int
+    isNaN :: a -> Bool
+    isInfinite :: a -> Bool
+    isNegativeZero :: a -> Bool
+    isIEEE :: a -> Bool
+    atan2 :: a -> a -> a
+    decodeFloat :: a -> (Integer, Int)
+    encodeFloat :: Integer -> Int -> a
+    floatRadix x = 2
+    isIEEE x = True
+
+instance RealFloat Double where
+    floatDigits x = 53
+    isNaN x = primDoubleIsNaN x
+    isInfinite x = primDoubleIsInfinite x
+    isNegativeZero x = primDoubleIsNegativeZero x
+    atan2 y x = primDoubleAtan2 y x
+    decodeFloat x = primDecodeDouble x
+    encodeFloat m e = primEncodeDouble m e
+
+instance RealFloat Float where
+    floatDigits x = 24
+    isNaN (F x) = primDoubleIsNaN x
+    isInfinite (F x) = primDoubleIsInfinite x
+    isNegativeZero (F x) = primDoubleIsNegativeZero x
+    atan2 (F y) (F x) = F (primDoubleToFloat (primDoubleAtan2 y x))
+    decodeFloat (F x) = primDecodeFloat x
+    encodeFloat m e = F (primDoubleToFloat (primEncodeDouble m e))
+
 data Ordering = LT | EQ | GT
     deriving(Eq, Ord)
 
@@ -211,6 +472,21 @@ instance Ord Double where
         True -> x
         False -> y
 
+instance Ord Integer where
+    compare x y = primIntegerCompare x y
+
+instance Ord Word where
+    compare (W x) (W y) = primWordCompare x y
+
+instance Ord Float where
+    compare (F x) (F y) = compare x y
+
+instance Ord Char where
+    compare x y = compare (primCharToInt x) (primCharToInt y)
+
+instance Ord () where
+    compare x y = EQ
+
 instance Ord Bool where
     compare False True = LT
     compare True False = GT
@@ -246,38 +522,261 @@ instance Ord a => Ord [a] where
     compare [] (_:_) = LT
     compare [] [] = EQ
 
+infixl 4 <$>, <$, <*>, *>, <*
+
 class Functor f where
     fmap :: (a -> b) -> f a -> f b
+    (<$) :: a -> f b -> f a
+    (<$) x m = fmap (const x) m
 
-instance Functor Maybe where
-    fmap f x = case x of
-        Just y -> Just (f y)
-        Nothing -> Nothing
+(<$>) :: Functor f => (a -> b) -> f a -> f b
+(<$>) f x = fmap f x
 
-instance Functor [] where
-    fmap = map
+class Functor f => Applicative f where
+    pure :: a -> f a
+    (<*>) :: f (a -> b) -> f a -> f b
+    (*>) :: f a -> f b -> f b
+    (<*) :: f a -> f b -> f a
+    (*>) x y = fmap (const id) x <*> y
+    (<*) x y = fmap const x <*> y
+
+liftA2 :: Applicative f => (a -> b -> c) -> f a -> f b -> f c
+liftA2 f x y = fmap f x <*> y
 
 infixl 1 >>, >>=
 
-class Monad m where
+class Applicative m => Monad m where
     (>>=) :: m a -> (a -> m b) -> m b
+    (>>) :: m a -> m b -> m b
     return :: a -> m a
+    (>>) x y = x >>= \_ -> y
+    return x = pure x
+
+class Monad m => MonadFail m where
     fail :: [Char] -> m a
 
-(>>) :: Monad m => m a -> m b -> m b
-(>>) x y = x >>= \_ -> y
+infixl 3 <|>
+
+class Applicative f => Alternative f where
+    empty :: f a
+    (<|>) :: f a -> f a -> f a
+
+class (Alternative m, Monad m) => MonadPlus m where
+    mzero :: m a
+    mplus :: m a -> m a -> m a
+    mzero = empty
+    mplus x y = x <|> y
+
+instance Functor Maybe where
+    fmap f x = case x of
+        Just y -> Just (f y)
+        Nothing -> Nothing
+
+instance Applicative Maybe where
+    pure x = Just x
+    (<*>) f x = case f of
+        Just g -> fmap g x
+        Nothing -> Nothing
 
 instance Monad Maybe where
     (>>=) x f = case x of
         Just y -> f y
         Nothing -> Nothing
-    return x = Just x
-    fail x = error x
+
+instance MonadFail Maybe where
+    fail x = Nothing
+
+instance Alternative Maybe where
+    empty = Nothing
+    (<|>) x y = case x of
+        Just _ -> x
+        Nothing -> y
+
+instance MonadPlus Maybe where
+    mzero = Nothing
+    mplus x y = x <|> y
+
+instance Functor [] where
+    fmap = map
+
+instance Applicative [] where
+    pure x = [x]
+    (<*>) fs xs = concat (map (\f -> map f xs) fs)
 
 instance Monad [] where
     (>>=) xs f = concat (map f xs)
-    return x = [x]
-    fail x = error x
+
+instance MonadFail [] where
+    fail x = []
+
+instance Alternative [] where
+    empty = []
+    (<|>) xs ys = xs ++ ys
+
+instance MonadPlus [] where
+    mzero = []
+    mplus xs ys = xs ++ ys
+
+instance Functor (Either e) where
+    fmap f x = case x of
+        Left e -> Left e
+        Right y -> Right (f y)
+
+instance Applicative (Either e) where
+    pure x = Right x
+    (<*>) f x = case f of
+        Left e -> Left e
+        Right g -> fmap g x
+
+instance Monad (Either e) where
+    (>>=) x f = case x of
+        Left e -> Left e
+        Right y -> f y
+
+infixr 6 <>
+
+class Semigroup a where
+    (<>) :: a -> a -> a
+
+class Semigroup a => Monoid a where
+    mempty :: a
+    mappend :: a -> a -> a
+    mconcat :: [a] -> a
+    mappend x y = x <> y
+    mconcat xs = case xs of
+        y:ys -> mappend y (mconcat ys)
+        [] -> mempty
+
+instance Semigroup [a] where
+    (<>) xs ys = xs ++ ys
+
+instance Monoid [a] where
+    mempty = []
+
+instance Semigroup Ordering where
+    (<>) x y = case x of
+        EQ -> y
+        _ -> x
+
+instance Monoid Ordering where
+    mempty = EQ
+
+instance Semigroup () where
+    (<>) x y = ()
+
+instance Monoid () where
+    mempty = ()
+
+instance Semigroup a => Semigroup (Maybe a) where
+    (<>) x y = case x of
+        Just a -> case y of
+            Just b -> Just (a <> b)
+            Nothing -> x
+        Nothing -> y
+
+instance Semigroup a => Monoid (Maybe a) where
+    mempty = Nothing
+
+class Foldable t where
+    foldr :: (a -> b -> b) -> b -> t a -> b
+    foldMap :: Monoid m => (a -> m) -> t a -> m
+    foldl :: (b -> a -> b) -> b -> t a -> b
+    foldl' :: (b -> a -> b) -> b -> t a -> b
+    toList :: t a -> [a]
+    null :: t a -> Bool
+    length :: t a -> Int
+    elem :: Eq a => a -> t a -> Bool
+    maximum :: Ord a => t a -> a
+    minimum :: Ord a => t a -> a
+    sum :: Num a => t a -> a
+    product :: Num a => t a -> a
+    foldMap f xs = foldr (\x acc -> mappend (f x) acc) mempty xs
+    foldl f z xs = foldl f z (toList xs)
+    foldl' f z xs = foldl' f z (toList xs)
+    toList xs = foldr (\x acc -> x : acc) [] xs
+    null xs = foldr (\_ _ -> False) True xs
+    length xs = foldl' (\n _ -> n + 1) 0 xs
+    elem x xs = foldr (\y acc -> x == y || acc) False xs
+    maximum xs = maximum (toList xs)
+    minimum xs = minimum (toList xs)
+    sum xs = foldl' (+) 0 xs
+    product xs = foldl' (*) 1 xs
+
+instance Foldable [] where
+    foldr f z xs = case xs of
+        y:ys -> f y (foldr f z ys)
+        [] -> z
+    foldMap f xs = case xs of
+        y:ys -> mappend (f y) (foldMap f ys)
+        [] -> mempty
+    foldl f z xs = case xs of
+        y:ys -> foldl f (f z y) ys
+        [] -> z
+    foldl' f z xs = case xs of
+        y:ys -> let z2 = f z y in z2 `seq` foldl' f z2 ys
+        [] -> z
+    toList xs = xs
+    null xs = case xs of
+        _:_ -> False
+        [] -> True
+    length xs = foldl' (\n _ -> n + 1) 0 xs
+    elem x xs = case xs of
+        y:ys -> x == y || elem x ys
+        [] -> False
+    maximum xs = case xs of
+        y:ys -> foldl' max y ys
+        [] -> error "maximum called on empty structure"
+    minimum xs = case xs of
+        y:ys -> foldl' min y ys
+        [] -> error "minimum called on empty structure"
+    sum xs = foldl' (+) 0 xs
+    product xs = foldl' (*) 1 xs
+
+instance Foldable Maybe where
+    foldr f z x = case x of
+        Just y -> f y z
+        Nothing -> z
+
+instance Foldable (Either e) where
+    foldr f z x = case x of
+        Left _ -> z
+        Right y -> f y z
+
+instance Foldable ((,) a) where
+    foldr f z x = case x of
+        (_, y) -> f y z
+
+instance Functor ((,) a) where
+    fmap f x = case x of
+        (y, z) -> (y, f z)
+
+class (Functor t, Foldable t) => Traversable t where
+    traverse :: Applicative f => (a -> f b) -> t a -> f (t b)
+    sequenceA :: Applicative f => t (f a) -> f (t a)
+    mapM :: Monad m => (a -> m b) -> t a -> m (t b)
+    sequence :: Monad m => t (m a) -> m (t a)
+    sequenceA xs = traverse id xs
+    mapM f xs = traverse f xs
+    sequence xs = mapM id xs
+
+instance Traversable [] where
+    traverse f xs = case xs of
+        y:ys -> liftA2 (\z zs -> z : zs) (f y) (traverse f ys)
+        [] -> pure []
+
+instance Traversable Maybe where
+    traverse f x = case x of
+        Just y -> fmap Just (f y)
+        Nothing -> pure Nothing
+
+instance Traversable (Either e) where
+    traverse f x = case x of
+        Left e -> pure (Left e)
+        Right y -> fmap Right (f y)
+
+instance Traversable ((,) a) where
+    traverse f x = case x of
+        (y, z) -> fmap (\w -> (y, w)) (f z)
 
 class Enum a where
     succ :: a -> a
@@ -302,6 +801,21 @@ instance Enum Int where
         True -> start : enumFromThenTo (start + step) step stop
         False -> []
 
+instance Enum Integer where
+    succ x = x + 1
+    pred x = x - 1
+    enumFrom x =
+        let
+            xs = x : enumFrom (x + 1)
+        in xs
+    enumFromThen n step = n : enumFromThen (n + step) step
+    enumFromTo start stop = case start <= stop of
+        True -> start : enumFromTo (start + 1) stop
+        False -> []
+    enumFromThenTo start step stop = case start <= stop of
+        True -> start : enumFromThenTo (start + step) step stop
+        False -> []
+
 instance Enum Double where
     succ x = x + 1
     pred x = x - 1
@@ -358,10 +872,6 @@ map :: (a -> b) -> [a] -> [b]
 map f (y:ys) = f y : map f ys
 map f [] = []
 
-foldl :: (b -> a -> b) -> b -> [a] -> b
-foldl f x (y:ys) = foldl f (f x y) ys
-foldl f x [] = x
-
 undefined :: a
 undefined = error "undefined"
 
@@ -389,11 +899,6 @@ init xs = case xs of
         [] -> []
     [] -> error "init called on empty list"
 
-sum :: Num a => [a] -> a
-sum xs = case xs of
-    y:ys -> y + sum ys
-    [] -> 0
-
 infixl 9 !!
 
 (!!) :: [a] -> Int -> a
@@ -425,46 +930,783 @@ filter p xs = case xs of
         False -> filter p ys
     [] -> []
 
-null :: [a] -> Bool
-null xs = case xs of
-    y:ys -> False
-    [] -> True
+concat :: Foldable t => t [a] -> [a]
+concat xs = foldr (\ys acc -> ys ++ acc) [] xs
 
-length :: [a] -> Int
-length xs = case xs of
-    _:ys -> 1 + length ys
-    [] -> 0
+concatMap :: Foldable t => (a -> [b]) -> t a -> [b]
+concatMap f xs = foldr (\x acc -> f x ++ acc) [] xs
 
-concat :: [[a]] -> [a]
-concat xs = case xs of
-    y:ys -> y ++ concat ys
-    [] -> []
+and :: Foldable t => t Bool -> Bool
+and xs = foldr (&&) True xs
+
+or :: Foldable t => t Bool -> Bool
+or xs = foldr (||) False xs
+
+any :: Foldable t => (a -> Bool) -> t a -> Bool
+any p xs = foldr (\x acc -> p x || acc) False xs
+
+all :: Foldable t => (a -> Bool) -> t a -> Bool
+all p xs = foldr (\x acc -> p x && acc) True xs
+
+notElem :: (Foldable t, Eq a) => a -> t a -> Bool
+notElem x xs = not (elem x xs)
+
+mapM_ :: (Foldable t, Monad m) => (a -> m b) -> t a -> m ()
+mapM_ f xs = foldr (\x acc -> f x >> acc) (return ()) xs
 
+sequence_ :: (Foldable t, Monad m) => t (m a) -> m ()
+sequence_ xs = foldr (\x acc -> x >> acc) (return ()) xs
+
+
+type ShowS = [Char] -> [Char]
 
 class Show a where
+    showsPrec :: Int -> a -> ShowS
     show :: a -> [Char]
+    showList :: [a] -> ShowS
+    showsPrec _ x s = show x ++ s
+    show x = showsPrec 0 x ""
+    showList xs s = showListWith shows xs s
+
+shows :: Show a => a -> ShowS
+shows x = showsPrec 0 x
+
+showChar :: Char -> ShowS
+showChar c s = c : s
+
+showString :: [Char] -> ShowS
+showString str s = str ++ s
+
+showParen :: Bool -> ShowS -> ShowS
+showParen b p = if b then showChar '(' . p . showChar ')' else p
+
+showListWith :: (a -> ShowS) -> [a] -> ShowS
+showListWith showx xs s = case xs of
+    [] -> "[]" ++ s
+    y:ys -> '[' : showx y (showListTail showx ys s)
+
+showListTail :: (a -> ShowS) -> [a] -> ShowS
+showListTail showx xs s = case xs of
+    [] -> ']' : s
+    y:ys -> ',' : showx y (showListTail showx ys s)
+
+showSignedString :: Int -> [Char] -> ShowS
+showSignedString p str = showParen ((p > 6) && isNegativeString str) (showString str)
+
+isNegativeString :: [Char] -> Bool
+isNegativeString str = case str of
+    c:_ -> c == '-'
+    [] -> False
+
+instance Show Int where
+    showsPrec p n = showSignedString p (if n < 0 then '-' : showDigits (0 - n) else showDigits n)
+
+instance Show Integer where
+    showsPrec p n = showSignedString p (primShowInteger n)
+
+instance Show Word where
+    showsPrec _ n = showString (primShowInteger (toInteger n))
+
+instance Show Double where
+    showsPrec p x = showSignedString p (primShowDouble x)
+
+instance Show Float where
+    showsPrec p (F x) = showSignedString p (primShowFloat x)
+
+showDigits :: Int -> [Char]
+showDigits n = if n < 10 then [digit n] else showDigits (n `div` 10) ++ [digit (n `rem` 10)]
+    where digit d = "0123456789" !! d
+
+instance Show Char where
+    showsPrec _ c s = if c == '\'' then "'\\''" ++ s else '\'' : showLitChar c ('\'' : s)
+    showList cs s = '"' : showLitString cs ('"' : s)
+
+showLitString :: [Char] -> ShowS
+showLitString cs s = case cs of
+    [] -> s
+    c:rest -> if c == '"'
+        then '\\' : '"' : showLitString rest s
+        else showLitChar c (protectEscape c rest (showLitString rest s))
+
+protectEscape :: Char -> [Char] -> ShowS
+protectEscape c rest s = case rest of
+    [] -> s
+    next:_ -> if ((primCharToInt c > 127) && isDecimalDigit next) || ((primCharToInt c == 14) && (next == 'H'))
+        then '\\' : '&' : s
+        else s
+
+isDecimalDigit :: Char -> Bool
+isDecimalDigit c = (primCharToInt c >= 48) && (primCharToInt c <= 57)
+
+showLitChar :: Char -> ShowS
+showLitChar c s =
+    let code = primCharToInt c
+    in if code > 127 then '\\' : (showDigits code ++ s)
+    else if code == 127 then "\\DEL" ++ s
+    else if c == '\\' then "\\\\" ++ s
+    else if code >= 32 then c : s
+    else if code == 7 then "\\a" ++ s
+    else if code == 8 then "\\b" ++ s
+    else if code == 9 then "\\t" ++ s
+    else if code == 10 then "\\n" ++ s
+    else if code == 11 then "\\v" ++ s
+    else if code == 12 then "\\f" ++ s
+    else if code == 13 then "\\r" ++ s
+    else '\\' : ((asciiControlNames !! code) ++ s)
+
+asciiControlNames :: [[Char]]
+asciiControlNames = ["NUL", "SOH", "STX", "ETX", "EOT", "ENQ", "ACK", "BEL", "BS", "HT", "LF", "VT", "FF", "CR", "SO", "SI",
+    "DLE", "DC1", "DC2", "DC3", "DC4", "NAK", "SYN", "ETB", "CAN", "EM", "SUB", "ESC", "FS", "GS", "RS", "US"]
+
+instance Show a => Show [a] where
+    showsPrec _ xs = showList xs
 
 instance Show Bool where
     show x = case x of
         True -> "True"
         False -> "False"
 
-instance (Show a, Show b) => Show (a, b) where
+instance Show Ordering where
     show x = case x of
-        (y, z) -> "(" ++ show y ++ ", " ++ show z ++ ")"
+        LT -> "LT"
+        EQ -> "EQ"
+        GT -> "GT"
+
+instance Show () where
+    show x = "()"
+
+instance (Show a, Show b) => Show (a, b) where
+    showsPrec _ x = case x of
+        (a, b) -> showChar '(' . shows a . showChar ',' . shows b . showChar ')'
+
+instance (Show a, Show b, Show c) => Show (a, b, c) where
+    showsPrec _ x = case x of
+        (a, b, c) -> showChar '(' . shows a . showChar ',' . shows b . showChar ',' . shows c . showChar ')'
+
+instance (Show a, Show b, Show c, Show d) => Show (a, b, c, d) where
+    showsPrec _ x = case x of
+        (a, b, c, d) -> showChar '(' . shows a . showChar ',' . shows b . showChar ',' . shows c . showChar ','
+            . shows d . showChar ')'
+
+instance (Show a, Show b, Show c, Show d, Show e) => Show (a, b, c, d, e) where
+    showsPrec _ x = case x of
+        (a, b, c, d, e) -> showChar '(' . shows a . showChar ',' . shows b . showChar ',' . shows c . showChar ','
+            . shows d . showChar ',' . shows e . showChar ')'
+
+instance (Show a, Show b, Show c, Show d, Show e, Show f) => Show (a, b, c, d, e, f) where
+    showsPrec _ x = case x of
+        (a, b, c, d, e, f) -> showChar '(' . shows a . showChar ',' . shows b . showChar ',' . shows c . showChar ','
+            . shows d . showChar ',' . shows e . showChar ',' . shows f . showChar ')'
+
+instance (Show a, Show b, Show c, Show d, Show e, Show f, Show g) => Show (a, b, c, d, e, f, g) where
+    showsPrec _ x = case x of
+        (a, b, c, d, e, f, g) -> showChar '(' . shows a . showChar ',' . shows b . showChar ',' . shows c . showChar ','
+            . shows d . showChar ',' . shows e . showChar ',' . shows f . showChar ',' . shows g . showChar ')'
 
 instance Show a => Show (Maybe a) where
-    show x = case x of
-        Just y -> "Just (" ++ show y ++ ")"
-        Nothing -> "Nothing"
+    showsPrec p x = case x of
+        Just y -> showParen (p > 10) (showString "Just " . showsPrec 11 y)
+        Nothing -> showString "Nothing"
+
+instance (Show a, Show b) => Show (Either a b) where
+    showsPrec p x = case x of
+        Left y -> showParen (p > 10) (showString "Left " . showsPrec 11 y)
+        Right y -> showParen (p > 10) (showString "Right " . showsPrec 11 y)
+
+type ReadS a = [Char] -> [(a, [Char])]
+
+class Read a where
+    readsPrec :: Int -> ReadS a
+    readList :: ReadS [a]
+    readList s = readListWith (readsPrec 0) s
+
+reads :: Read a => ReadS a
+reads s = readsPrec 0 s
+
+read :: Read a => [Char] -> a
+read s = case readComplete reads s of
+    x:rest -> if null rest then x else error "Prelude.read: ambiguous parse"
+    [] -> error "Prelude.read: no parse"
+
+readComplete :: ReadS a -> [Char] -> [a]
+readComplete reader s = map fst (filter (\r -> null (dropSpace (snd r))) (reader s))
+
+readParen :: Bool -> ReadS a -> ReadS a
+readParen b g s = if b then readMandatoryParen g s else readOptionalParen g s
+
+readOptionalParen :: ReadS a -> ReadS a
+readOptionalParen g s = g s ++ readMandatoryParen g s
+
+readMandatoryParen :: ReadS a -> ReadS a
+readMandatoryParen g s = readsThenToken (readsSkip "(" (readOptionalParen g)) ")" s
+
+readsReturn :: a -> ReadS a
+readsReturn x s = [(x, s)]
+
+readsThen :: ReadS a -> (a -> ReadS b) -> ReadS b
+readsThen reader f s = concatMap (\r -> f (fst r) (snd r)) (reader s)
+
+readsApply :: ReadS (a -> b) -> ReadS a -> ReadS b
+readsApply readf readx s = concatMap (\f -> map (\x -> (fst f (fst x), snd x)) (readx (snd f))) (readf s)
+
+readsSkip :: [Char] -> ReadS a -> ReadS a
+readsSkip token reader s = concatMap reader (expectToken token s)
+
+readsThenToken :: ReadS a -> [Char] -> ReadS a
+readsThenToken reader token s = concatMap (\r -> map (\rest -> (fst r, rest)) (expectToken token (snd r))) (reader s)
+
+expectToken :: [Char] -> [Char] -> [[Char]]
+expectToken token s = map snd (filter (\l -> fst l == token) (lex s))
+
+readListWith :: ReadS a -> ReadS [a]
+readListWith readx s = readParen False (readsSkip "[" (readListElements readx)) s
+
+readListElements :: ReadS a -> ReadS [a]
+readListElements readx s = readsSkip "]" (readsReturn []) s ++ readNonEmptyList readx s
+
+readNonEmptyList :: ReadS a -> ReadS [a]
+readNonEmptyList readx s = readsApply (readsApply (readsReturn (\x xs -> x : xs)) readx) (readListRest readx) s
+
+readListRest :: ReadS a -> ReadS [a]
+readListRest readx s = readsSkip "]" (readsReturn []) s ++ readsSkip "," (readNonEmptyList readx) s
+
+readsTupleOpen :: Read a => (a -> b) -> ReadS b
+readsTupleOpen f s = readsApply (readsReturn f) (readsSkip "(" reads) s
+
+readsTupleField :: Read a => ReadS (a -> b) -> ReadS b
+readsTupleField readf s = readsApply readf (readsSkip "," reads) s
+
+readSigned :: Num a => ReadS a -> ReadS a
+readSigned readPositive s = readParen False (readSignedNumber readPositive) s
+
+readSignedNumber :: Num a => ReadS a -> ReadS a
+readSignedNumber readPositive s = readNumberToken readPositive s
+    ++ map (\x -> (0 - fst x, snd x)) (readsSkip "-" (readNumberToken readPositive) s)
+
+readNumberToken :: ReadS a -> ReadS a
+readNumberToken readPositive s = concatMap (\t -> map (\x -> (fst x, snd t)) (filter (\x -> null (snd x)) (readPositive (fst t)))) (lex s)
+
+readDecimal :: Num a => ReadS a
+readDecimal s = case spanChars isDecimalDigit s of
+    (digits, rest) -> if null digits then [] else [(digitsToNum digits, rest)]
+
+digitsToNum :: Num a => [Char] -> a
+digitsToNum digits = foldl (\n d -> n * 10 + fromInteger (primIntToInteger (primCharToInt d - 48))) 0 digits
+
+readFloating :: Fractional a => ReadS a
+readFloating s =
+    if s == "Infinity" then [(1 / 0, "")]
+    else if s == "NaN" then [(0 / 0, "")]
+    else case spanChars isDecimalDigit s of
+        (whole, rest) -> if null whole then [] else readFloatingFraction whole rest
+
+readFloatingFraction :: Fractional a => [Char] -> ReadS a
+readFloatingFraction whole s = case s of
+    c:cs -> if c == '.' then readFloatingDigits whole cs else readFloatingExponent whole 0 s
+    [] -> [(makeFloating whole 0, [])]
+
+readFloatingDigits :: Fractional a => [Char] -> ReadS a
+readFloatingDigits whole s = case spanChars isDecimalDigit s of
+    (fraction, rest) -> readFloatingExponent (whole ++ fraction) (length fraction) rest
+
+readFloatingExponent :: Fractional a => [Char] -> Int -> ReadS a
+readFloatingExponent digits scale s = case s of
+    c:cs -> if (c == 'e') || (c == 'E')
+        then map (\e -> (makeFloating digits (fst e - scale), snd e)) (readExponent cs)
+        else [(makeFloating digits (0 - scale), s)]
+    [] -> [(makeFloating digits (0 - scale), s)]
+
+readExponent :: ReadS Int
+readExponent s = case s of
+    c:cs -> if c == '-' then map (\e -> (0 - fst e, snd e)) (readDecimal cs)
+        else if c == '+' then readDecimal cs
+        else readDecimal s
+    [] -> []
+
+makeFloating :: Fractional a => [Char] -> Int -> a
+makeFloating digits e = if e < 0
+    then fromRational (MkRatio (digitsToNum digits) (10 ^ (0 - e)))
+    else fromRational (MkRatio (digitsToNum digits * 10 ^ e) 1)
+
+lex :: ReadS [Char]
+lex s = lexToken (dropSpace s)
+
+dropSpace :: [Char] -> [Char]
+dropSpace s = case s of
+    c:cs -> if primCharIsSpace c then dropSpace cs else s
+    [] -> []
+
+lexToken :: ReadS [Char]
+lexToken s = case s of
+    [] -> [("", "")]
+    c:cs ->
+        if c == '\'' then lexCharLiteral cs
+        else if c == '"' then map (consFst '"') (lexStringLiteral cs)
+        else if isSpecialChar c then [([c], cs)]
+        else if isIdentifierStart c then [spanChars isIdentifierChar s]
+        else if isDecimalDigit c then lexNumber s
+        else if isSymbolChar c then [spanChars isSymbolChar s]
+        else []
+
+isSpecialChar :: Char -> Bool
+isSpecialChar c = elem c "()[],;`{}"
+
+isSymbolChar :: Char -> Bool
+isSymbolChar c = elem c "!@#$%&*+./<=>?\\^|:-~"
+
+isIdentifierStart :: Char -> Bool
+isIdentifierStart c = primCharIsAlpha c || (c == '_')
+
+isIdentifierChar :: Char -> Bool
+isIdentifierChar c = primCharIsAlphaNum c || (c == '_') || (c == '\'')
+
+spanChars :: (Char -> Bool) -> [Char] -> ([Char], [Char])
+spanChars p s = case s of
+    c:cs -> if p c then consFst c (spanChars p cs) else ([], s)
+    [] -> ([], [])
+
+consFst :: a -> ([a], b) -> ([a], b)
+consFst x p = case p of
+    (xs, y) -> (x : xs, y)
+
+startsWithDigit :: [Char] -> Bool
+startsWithDigit s = case s of
+    c:_ -> isDecimalDigit c
+    [] -> False
+
+lexNumber :: ReadS [Char]
+lexNumber s = case spanChars isDecimalDigit s of
+    (whole, rest) -> lexFraction whole rest
+
+lexFraction :: [Char] -> ReadS [Char]
+lexFraction whole s = case s of
+    c:cs -> if (c == '.') && startsWithDigit cs then lexFractionDigits whole cs else lexExponent whole s
+    [] -> [(whole, [])]
+
+lexFractionDigits :: [Char] -> ReadS [Char]
+lexFractionDigits whole s = case spanChars isDecimalDigit s of
+    (fraction, rest) -> lexExponent (whole ++ "." ++ fraction) rest
+
+lexExponent :: [Char] -> ReadS [Char]
+lexExponent number s = case s of
+    c:cs -> if (c == 'e') || (c == 'E') then lexExponentSign number c cs else [(number, s)]
+    [] -> [(number, s)]
+
+lexExponentSign :: [Char] -> Char -> ReadS [Char]
+lexExponentSign number e s = case s of
+    c:cs ->
+        if ((c == '-') || (c == '+')) && startsWithDigit cs then lexExponentDigits (number ++ [e, c]) cs
+        else if isDecimalDigit c then lexExponentDigits (number ++ [e]) s
+        else [(number, e : s)]
+    [] -> [(number, [e])]
+
+lexExponentDigits :: [Char] -> ReadS [Char]
+lexExponentDigits number s = case spanChars isDecimalDigit s of
+    (digits, rest) -> [(number ++ digits, rest)]
+
+lexCharLiteral :: ReadS [Char]
+lexCharLiteral s = case s of
+    c:cs -> if c == '\\' then lexEscapedCharLiteral cs else lexCloseQuote ['\'', c] cs
+    [] -> []
+
+lexEscapedCharLiteral :: ReadS [Char]
+lexEscapedCharLiteral s = case s of
+    c:cs -> case spanChars (\x -> not (x == '\'')) cs of
+        (body, rest) -> lexCloseQuote ('\'' : '\\' : c : body) rest
+    [] -> []
+
+lexCloseQuote :: [Char] -> ReadS [Char]
+lexCloseQuote token s = case s of
+    c:cs -> if c == '\'' then [(token ++ "'", cs)] else []
+    [] -> []
+
+lexStringLiteral :: ReadS [Char]
+lexStringLiteral s = case s of
+    c:cs ->
+        if c == '"' then [("\"", cs)]
+        else if c == '\\' then lexEscapedStringLiteral cs
+        else map (consFst c) (lexStringLiteral cs)
+    [] -> []
+
+lexEscapedStringLiteral :: ReadS [Char]
+lexEscapedStringLiteral s = case s of
+    c:cs -> map (\r -> consFst '\\' (consFst c r)) (lexStringLiteral cs)
+    [] -> []
+
+readLitChar :: ReadS Char
+readLitChar s = case s of
+    c:cs -> if c == '\\' then readEscape cs else [(c, cs)]
+    [] -> []
+
+readEscape :: ReadS Char
+readEscape s = case s of
+    c:cs -> if isDecimalDigit c then readNumericEscape s else readCharEscape c cs s
+    [] -> []
+
+readNumericEscape :: ReadS Char
+readNumericEscape s = case spanChars isDecimalDigit s of
+    (digits, rest) -> [(primIntToChar (digitsToNum digits), rest)]
+
+readCharEscape :: Char -> [Char] -> ReadS Char
+readCharEscape c cs s = case filter (\e -> fst e == c) simpleEscapes of
+    e:_ -> [(primIntToChar (snd e), cs)]
+    [] -> readAsciiEscape 0 asciiControlNames s
+
+simpleEscapes :: [(Char, Int)]
+simpleEscapes = [('a', 7), ('b', 8), ('t', 9), ('n', 10), ('v', 11), ('f', 12), ('r', 13), ('\\', 92), ('"', 34), ('\'', 39)]
+
+readAsciiEscape :: Int -> [[Char]] -> ReadS Char
+readAsciiEscape code names s = case names of
+    [] -> map (\rest -> (primIntToChar 127, rest)) (stripToken "DEL" s)
+    name:others -> case stripToken name s of
+        rest:_ -> [(primIntToChar code, rest)]
+        [] -> readAsciiEscape (code + 1) others s
+
+stripToken :: [Char] -> [Char] -> [[Char]]
+stripToken prefix s = case prefix of
+    [] -> [s]
+    p:ps -> case s of
+        c:cs -> if p == c then stripToken ps cs else []
+        [] -> []
+
+instance Read Int where
+    readsPrec _ s = readSigned readDecimal s
+
+instance Read Integer where
+    readsPrec _ s = readSigned readDecimal s
+
+instance Read Word where
+    readsPrec _ s = readSigned readDecimal s
+
+instance Read Double where
+    readsPrec _ s = readSigned readFloating s
+
+instance Read Float where
+    readsPrec _ s = readSigned readFloating s
+
+instance Read Char where
+    readsPrec _ s = readParen False readCharLiteral s
+    readList s = readParen False readStringLiteral s ++ readListWith (readsPrec 0) s
+
+readCharLiteral :: ReadS Char
+readCharLiteral s = readsThen lex readCharLexeme s
+
+readCharLexeme :: [Char] -> ReadS Char
+readCharLexeme lexeme rest = case lexeme of
+    q:body -> if q == '\'' then map (\c -> (fst c, rest)) (filter (\c -> snd c == "'") (readLitChar body)) else []
+    [] -> []
+
+readStringLiteral :: ReadS [Char]
+readStringLiteral s = readsThen lex readStringLexeme s
+
+readStringLexeme :: [Char] -> ReadS [Char]
+readStringLexeme lexeme rest = case lexeme of
+    q:body -> if q == '"' then map (\str -> (str, rest)) (readStringBody body) else []
+    [] -> []
+
+readStringBody :: [Char] -> [[Char]]
+readStringBody s = case s of
+    c:cs -> if c == '"' then (if null cs then [""] else []) else readStringChar s
+    [] -> []
+
+readStringChar :: [Char] -> [[Char]]
+readStringChar s = case stripToken "\\&" s of
+    rest:_ -> readStringBody rest
+    [] -> concatMap (\r -> map (\str -> fst r : str) (readStringBody (snd r))) (readLitChar s)
+
+instance Read a => Read [a] where
+    readsPrec _ s = readList s
+
+instance Read Bool where
+    readsPrec _ s = readParen False (readsSkip "True" (readsReturn True)) s
+        ++ readParen False (readsSkip "False" (readsReturn False)) s
+
+instance Read Ordering where
+    readsPrec _ s = readParen False (readsSkip "LT" (readsReturn LT)) s
+        ++ readParen False (readsSkip "EQ" (readsReturn EQ)) s
+        ++ readParen False (readsSkip "GT" (readsReturn GT)) s
+
+instance Read () where
+    readsPrec _ s = readParen False (readsSkip "(" (readsSkip ")" (readsReturn ()))) s
+
+instance (Read a, Read b) => Read (a, b) where
+    readsPrec _ s = readParen False (readsThenToken (readsTupleField (readsTupleOpen (\a b -> (a, b)))) ")") s
+
+instance (Read a, Read b, Read c) => Read (a, b, c) where
+    readsPrec _ s = readParen False (readsThenToken (readsTupleField (readsTupleField
+        (readsTupleOpen (\a b c -> (a, b, c))))) ")") s
+
+instance (Read a, Read b, Read c, Read d) => Read (a, b, c, d) where
+    readsPrec _ s = readParen False (readsThenToken (readsTupleField (readsTupleField (readsTupleField
+        (readsTupleOpen (\a b c d -> (a, b, c, d)))))) ")") s
+
+instance (Read a, Read b, Read c, Read d, Read e) => Read (a, b, c, d, e) where
+    readsPrec _ s = readParen False (readsThenToken (readsTupleField (readsTupleField (readsTupleField (readsTupleField
+        (readsTupleOpen (\a b c d e -> (a, b, c, d, e))))))) ")") s
+
+instance (Read a, Read b, Read c, Read d, Read e, Read f) => Read (a, b, c, d, e, f) where
+    readsPrec _ s = readParen False (readsThenToken (readsTupleField (readsTupleField (readsTupleField (readsTupleField
+        (readsTupleField (readsTupleOpen (\a b c d e f -> (a, b, c, d, e, f)))))))) ")") s
+
+instance (Read a, Read b, Read c, Read d, Read e, Read f, Read g) => Read (a, b, c, d, e, f, g) where
+    readsPrec _ s = readParen False (readsThenToken (readsTupleField (readsTupleField (readsTupleField (readsTupleField
+        (readsTupleField (readsTupleField (readsTupleOpen (\a b c d e f g -> (a, b, c, d, e, f, g))))))))) ")") s
+
+instance Read a => Read (Maybe a) where
+    readsPrec p s = readParen False (readsSkip "Nothing" (readsReturn Nothing)) s
+        ++ readParen (p > 10) (readsApply (readsSkip "Just" (readsReturn (\x -> Just x))) (readsPrec 11)) s
+
+instance (Read a, Read b) => Read (Either a b) where
+    readsPrec p s = readParen (p > 10) (readsApply (readsSkip "Left" (readsReturn (\x -> Left x))) (readsPrec 11)) s
+        ++ readParen (p > 10) (readsApply (readsSkip "Right" (readsReturn (\x -> Right x))) (readsPrec 11)) s
 
 data RealWorld = RealWorld
 
 data IO a = IO
 
+instance Functor IO where
+    fmap f x = x >>= \y -> return (f y)
+
+instance Applicative IO where
+    pure x = io_return x
+    (<*>) f x = f >>= \g -> x >>= \y -> return (g y)
+
 instance Monad IO where
     (>>=) x f = io_bind x f
-    return = io_return
-    fail x = error x
 
+instance MonadFail IO where
+    fail x = ioError (userError x)
+
+data SomeException = SomeErrorCall ErrorCall | SomeArithException ArithException | SomePatternMatchFail PatternMatchFail
+    | SomeAsyncException AsyncException | SomeNonTermination NonTermination | SomeIOException IOException
+    | SomeExitCode ExitCode | SomeBlockedIndefinitelyOnMVar BlockedIndefinitelyOnMVar
+    | SomeBlockedIndefinitelyOnSTM BlockedIndefinitelyOnSTM
+
+data ErrorCall = ErrorCall [Char]
+
+data ArithException = Overflow | DivideByZero
+
+data PatternMatchFail = PatternMatchFail [Char]
+
+data AsyncException = StackOverflow | HeapOverflow | ThreadKilled | UserInterrupt
+
+data NonTermination = NonTermination [Char]
+
+data IOException = IOError IOErrorType [Char]
+
+data ExitCode = ExitSuccess | ExitFailure Int
+    deriving(Eq)
+
+data BlockedIndefinitelyOnMVar = BlockedIndefinitelyOnMVar
+
+data BlockedIndefinitelyOnSTM = BlockedIndefinitelyOnSTM
+
+data IOErrorType = AlreadyExists | NoSuchThing | ResourceBusy | ResourceExhausted | EOF
+    | IllegalOperation | PermissionDenied | UserError | OtherError
+    deriving(Eq)
+
+class Exception e where
+    toException :: e -> SomeException
+    fromException :: SomeException -> Maybe e
+
+instance Exception SomeException where
+    toException e = e
+    fromException e = Just e
+
+instance Exception ErrorCall where
+    toException e = SomeErrorCall e
+    fromException e = case e of
+        SomeErrorCall x -> Just x
+        _ -> Nothing
+
+instance Exception ArithException where
+    toException e = SomeArithException e
+    fromException e = case e of
+        SomeArithException x -> Just x
+        _ -> Nothing
+
+instance Exception PatternMatchFail where
+    toException e = SomePatternMatchFail e
+    fromException e = case e of
+        SomePatternMatchFail x -> Just x
+        _ -> Nothing
+
+instance Exception AsyncException where
+    toException e = SomeAsyncException e
+    fromException e = case e of
+        SomeAsyncException x -> Just x
+        _ -> Nothing
+
+instance Exception NonTermination where
+    toException e = SomeNonTermination e
+    fromException e = case e of
+        SomeNonTermination x -> Just x
+        _ -> Nothing
+
+instance Exception IOException where
+    toException e = SomeIOException e
+    fromException e = case e of
+        SomeIOException x -> Just x
+        _ -> Nothing
+
+instance Exception ExitCode where
+    toException e = SomeExitCode e
+    fromException e = case e of
+        SomeExitCode x -> Just x
+        _ -> Nothing
+
+instance Exception BlockedIndefinitelyOnMVar where
+    toException e = SomeBlockedIndefinitelyOnMVar e
+    fromException e = case e of
+        SomeBlockedIndefinitelyOnMVar x -> Just x
+        _ -> Nothing
+
+instance Exception BlockedIndefinitelyOnSTM where
+    toException e = SomeBlockedIndefinitelyOnSTM e
+    fromException e = case e of
+        SomeBlockedIndefinitelyOnSTM x -> Just x
+        _ -> Nothing
+
+instance Show SomeException where
+    show e = case e of
+        SomeErrorCall x -> show x
+        SomeArithException x -> show x
+        SomePatternMatchFail x -> show x
+        SomeAsyncException x -> show x
+        SomeNonTermination x -> show x
+        SomeIOException x -> show x
+        SomeExitCode x -> show x
+        SomeBlockedIndefinitelyOnMVar x -> show x
+        SomeBlockedIndefinitelyOnSTM x -> show x
+
+instance Show ErrorCall where
+    show e = case e of
+        ErrorCall msg -> msg
+
+instance Show ArithException where
+    show e = case e of
+        Overflow -> "arithmetic overflow"
+        DivideByZero -> "divide by zero"
+
+instance Show PatternMatchFail where
+    show e = case e of
+        PatternMatchFail msg -> msg
+
+instance Show AsyncException where
+    show e = case e of
+        StackOverflow -> "stack overflow"
+        HeapOverflow -> "heap overflow"
+        ThreadKilled -> "thread killed"
+        UserInterrupt -> "user interrupt"
+
+instance Show NonTermination where
+    show e = case e of
+        NonTermination name -> "<<loop>> in " ++ name
+
+instance Show IOException where
+    show e = case e of
+        IOError typ msg -> if typ == UserError then "user error (" ++ msg ++ ")" else msg
+
+instance Show ExitCode where
+    show e = case e of
+        ExitSuccess -> "ExitSuccess"
+        ExitFailure code -> "ExitFailure " ++ showsPrec 11 code ""
+
+instance Show BlockedIndefinitelyOnMVar where
+    show e = "thread blocked indefinitely in an MVar operation"
+
+instance Show BlockedIndefinitelyOnSTM where
+    show e = "thread blocked indefinitely in an STM transaction"
+
+throw :: Exception e => e -> a
+throw e = primThrow (toException e)
+
+catch :: Exception e => IO a -> (e -> IO a) -> IO a
+catch action handler = primCatch action (catchHandler handler)
+
+catchHandler :: Exception e => (e -> IO a) -> SomeException -> IO a
+catchHandler handler e = case fromException e of
+    Just x -> handler x
+    Nothing -> primThrow e
+
+bracket :: IO a -> (a -> IO b) -> (a -> IO c) -> IO c
+bracket acquire release use = do
+    resource <- acquire
+    result <- primCatch (use resource) (\e -> release resource >> primThrow e)
+    release resource
+    return result
+
+ioError :: IOException -> IO a
+ioError e = throw e
+
+userError :: [Char] -> IOException
+userError msg = IOError UserError msg
+
+ioeGetErrorType :: IOException -> IOErrorType
+ioeGetErrorType (IOError typ msg) = typ
+
+isAlreadyExistsError :: IOException -> Bool
+isAlreadyExistsError e = ioeGetErrorType e == AlreadyExists
+
+isDoesNotExistError :: IOException -> Bool
+isDoesNotExistError e = ioeGetErrorType e == NoSuchThing
+
+isEOFError :: IOException -> Bool
+isEOFError e = ioeGetErrorType e == EOF
+
+isIllegalOperation :: IOException -> Bool
+isIllegalOperation e = ioeGetErrorType e == IllegalOperation
+
+isPermissionError :: IOException -> Bool
+isPermissionError e = ioeGetErrorType e == PermissionDenied
+
+isUserError :: IOException -> Bool
+isUserError e = ioeGetErrorType e == UserError
+
+data Handle = Handle Int
+
+data IOMode = ReadMode | WriteMode | AppendMode | ReadWriteMode
+
+stdin :: Handle
+stdin = Handle 0
+
+stdout :: Handle
+stdout = Handle 1
+
+stderr :: Handle
+stderr = Handle 2
+
+hPutStrLn :: Handle -> [Char] -> IO ()
+hPutStrLn h s = hPutStr h (s ++ "\n")
+
+putStr :: [Char] -> IO ()
+putStr s = hPutStr stdout s
+
+putStrLn :: [Char] -> IO ()
+putStrLn s = hPutStrLn stdout s
+
+print :: Show a => a -> IO ()
+print x = putStrLn (show x)
+
+getLine :: IO [Char]
+getLine = hGetLine stdin
+
+getContents :: IO [Char]
+getContents = hGetContents stdin
+
+interact :: ([Char] -> [Char]) -> IO ()
+interact f = do
+    s <- getContents
+    putStr (f s)
+
+withFile :: [Char] -> IOMode -> (Handle -> IO a) -> IO a
+withFile path mode use = bracket (openFile path mode) hClose use
+
+readFile :: [Char] -> IO [Char]
+readFile path = withFile path ReadMode hGetContents
+
+writeFile :: [Char] -> [Char] -> IO ()
+writeFile path s = withFile path WriteMode (\h -> hPutStr h s)
 
+appendFile :: [Char] -> [Char] -> IO ()
+appendFile path s = withFile path AppendMode (\h -> hPutStr h s)
@@ -0,0 +1,12 @@
+module System.Environment where
+
+import Prelude
+
+getArgs :: IO [[Char]]
+getArgs = primGetArgs
+
+getProgName :: IO [Char]
+getProgName = primGetProgName
+
+lookupEnv :: [Char] -> IO (Maybe [Char])
+lookupEnv name = primLookupEnv name
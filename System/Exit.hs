@@ -0,0 +1,16 @@
+module System.Exit where
+
+import Prelude
+
+exitWith :: ExitCode -> IO a
+exitWith code = case code of
+    ExitFailure n -> if n == 0
+        then ioError (IOError IllegalOperation "exitWith: invalid argument (ExitFailure 0)")
+        else throw code
+    ExitSuccess -> throw code
+
+exitSuccess :: IO a
+exitSuccess = exitWith ExitSuccess
+
+exitFailure :: IO a
+exitFailure = exitWith (ExitFailure 1)
@@ -0,0 +1,13 @@
+module Text.Read where
+
+import Prelude
+
+readMaybe :: Read a => [Char] -> Maybe a
+readMaybe s = case readComplete reads s of
+    x:rest -> if null rest then Just x else Nothing
+    [] -> Nothing
+
+readEither :: Read a => [Char] -> Either [Char] a
+readEither s = case readComplete reads s of
+    x:rest -> if null rest then Right x else Left "Prelude.read: ambiguous parse"
+    [] -> Left "Prelude.read: no parse"
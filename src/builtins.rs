@@ -9,13 +9,134 @@ pub fn builtins() -> Vec<(&'static str, Type<Name>)> {
     let var2 = Type::Generic(TypeVariable { id: intern("b"), kind: Kind::Star, age: 0 } );
     vec![("error", function_type_(list_type(char_type()), var.clone())),
       ("seq", function_type_(var.clone(), function_type_(var2.clone(), var2.clone()))),
-      ("readFile", function_type_(list_type(char_type()), io(list_type(char_type())))),
       ("io_bind", function_type_(io(var.clone()),
                   function_type_(function_type_(var.clone(), io(var2.clone())),
                                  io(var2.clone())))),
       ("io_return", function_type_(var.clone(), io(var.clone()))),
-      ("putStrLn", function_type_(list_type(char_type()), io(unit()))),
-      ("#compare_tags", function_type_(var.clone(), function_type_(var.clone(), Type::new_op(name("Ordering"), Vec::new())))),
+      ("#compare_tags", function_type_(var.clone(), function_type_(var.clone(), ordering()))),
+      ("primThrow", function_type_(some_exception(), var.clone())),
+      ("primCatch", function_type_(io(var.clone()),
+                    function_type_(function_type_(some_exception(), io(var.clone())),
+                                   io(var.clone())))),
+      ("evaluate", function_type_(var.clone(), io(var.clone()))),
+      ("#pattern_match_fail", function_type_(list_type(char_type()), var.clone())),
+      ("openFile", function_type_(list_type(char_type()), function_type_(io_mode(), io(handle())))),
+      ("hClose", function_type_(handle(), io(unit()))),
+      ("hFlush", function_type_(handle(), io(unit()))),
+      ("hPutStr", function_type_(handle(), function_type_(list_type(char_type()), io(unit())))),
+      ("hGetLine", function_type_(handle(), io(list_type(char_type())))),
+      ("hGetContents", function_type_(handle(), io(list_type(char_type())))),
+      ("hIsEOF", function_type_(handle(), io(bool_type()))),
+      ("primGetArgs", io(list_type(list_type(char_type())))),
+      ("primGetProgName", io(list_type(char_type()))),
+      ("primLookupEnv", function_type_(list_type(char_type()), io(maybe(list_type(char_type()))))),
+      ("primNewIORef", function_type_(var.clone(), io(io_ref(var.clone())))),
+      ("primReadIORef", function_type_(io_ref(var.clone()), io(var.clone()))),
+      ("primWriteIORef", function_type_(io_ref(var.clone()), function_type_(var.clone(), io(unit())))),
+      ("primNewArray", function_type_(int_type(), function_type_(var.clone(), io(mutable_array(var.clone()))))),
+      ("primReadArray", function_type_(mutable_array(var.clone()), function_type_(int_type(), io(var.clone())))),
+      ("primWriteArray", function_type_(mutable_array(var.clone()),
+                         function_type_(int_type(), function_type_(var.clone(), io(unit()))))),
+      ("primNewIntArray", function_type_(int_type(), function_type_(int_type(), io(mutable_int_array())))),
+      ("primReadIntArray", function_type_(mutable_int_array(), function_type_(int_type(), io(int_type())))),
+      ("primWriteIntArray", function_type_(mutable_int_array(),
+                            function_type_(int_type(), function_type_(int_type(), io(unit()))))),
+      ("primUnsafePerformIO", function_type_(io(var.clone()), var.clone())),
+      ("primForkIO", function_type_(io(unit()), io(int_type()))),
+      ("primYield", io(unit())),
+      ("primThreadDelay", function_type_(int_type(), io(unit()))),
+      ("primMyThreadId", io(int_type())),
+      ("primThrowTo", function_type_(int_type(), function_type_(some_exception(), io(unit())))),
+      ("primNewEmptyMVar", io(mvar(var.clone()))),
+      ("primTakeMVar", function_type_(mvar(var.clone()), io(var.clone()))),
+      ("primPutMVar", function_type_(mvar(var.clone()), function_type_(var.clone(), io(unit())))),
+      ("primReadMVar", function_type_(mvar(var.clone()), io(var.clone()))),
+      ("primTryTakeMVar", function_type_(mvar(var.clone()), io(maybe(var.clone())))),
+      ("primTryPutMVar", function_type_(mvar(var.clone()), function_type_(var.clone(), io(bool_type())))),
+      ("primNewTVar", function_type_(var.clone(), io(tvar(var.clone())))),
+      ("primReadTVar", function_type_(tvar(var.clone()), io(var.clone()))),
+      ("primWriteTVar", function_type_(tvar(var.clone()), function_type_(var.clone(), io(unit())))),
+      ("primRetry", io(var.clone())),
+      ("primAtomically", function_type_(io(var.clone()), io(var.clone()))),
+      ("primOrElse", function_type_(io(var.clone()), function_type_(io(var.clone()), io(var.clone())))),
+      ("primIntToInteger", function_type_(int_type(), integer_type())),
+      ("primIntegerToInt", function_type_(integer_type(), int_type())),
+      ("primIntegerToDouble", function_type_(integer_type(), double_type())),
+      ("primDoubleToInteger", function_type_(double_type(), integer_type())),
+      ("primIntegerAdd", integer_binop()),
+      ("primIntegerSubtract", integer_binop()),
+      ("primIntegerMultiply", integer_binop()),
+      ("primIntegerQuot", integer_binop()),
+      ("primIntegerRem", integer_binop()),
+      ("primIntegerDiv", integer_binop()),
+      ("primIntegerMod", integer_binop()),
+      ("primIntegerCompare", function_type_(integer_type(), function_type_(integer_type(), ordering()))),
+      ("primShowInteger", function_type_(integer_type(), list_type(char_type()))),
+      ("primNarrowInt", int_binop()),
+      ("primNarrowWord", int_binop()),
+      ("primWordCompare", function_type_(int_type(), function_type_(int_type(), ordering()))),
+      ("primWordQuot", int_binop()),
+      ("primWordRem", int_binop()),
+      ("primWordToInteger", function_type_(int_type(), integer_type())),
+      ("primShowDouble", function_type_(double_type(), list_type(char_type()))),
+      ("primShowFloat", function_type_(double_type(), list_type(char_type()))),
+      ("primRationalToDouble", function_type_(integer_type(), function_type_(integer_type(), double_type()))),
+      ("primDecodeDouble", function_type_(double_type(), decoded())),
+      ("primDecodeFloat", function_type_(double_type(), decoded())),
+      ("primEncodeDouble", function_type_(integer_type(), function_type_(int_type(), double_type()))),
     ]
 }
 
+
+fn ordering() -> Type<Name> {
+    Type::new_op(name("Ordering"), Vec::new())
+}
+
+fn int_binop() -> Type<Name> {
+    function_type_(int_type(), function_type_(int_type(), int_type()))
+}
+
+fn integer_binop() -> Type<Name> {
+    function_type_(integer_type(), function_type_(integer_type(), integer_type()))
+}
+
+///The `(Integer, Int)` mantissa and exponent returned by `decodeFloat`
+fn decoded() -> Type<Name> {
+    Type::new_op(name("(,)"), vec![integer_type(), int_type()])
+}
+
+fn some_exception() -> Type<Name> {
+    Type::new_op(name("SomeException"), Vec::new())
+}
+
+fn handle() -> Type<Name> {
+    Type::new_op(name("Handle"), Vec::new())
+}
+
+fn maybe(typ: Type<Name>) -> Type<Name> {
+    Type::new_op(name("Maybe"), vec![typ])
+}
+
+fn io_ref(typ: Type<Name>) -> Type<Name> {
+    Type::new_op(name("IORef"), vec![typ])
+}
+
+fn mvar(typ: Type<Name>) -> Type<Name> {
+    Type::new_op(name("MVar"), vec![typ])
+}
+
+fn tvar(typ: Type<Name>) -> Type<Name> {
+    Type::new_op(name("TVar"), vec![typ])
+}
+
+fn mutable_array(typ: Type<Name>) -> Type<Name> {
+    Type::new_op(name("MutableArray"), vec![typ])
+}
+
+fn mutable_int_array() -> Type<Name> {
+    Type::new_op(name("MutableIntArray"), Vec::new())
+}
+
+fn io_mode() -> Type<Name> {
+    Type::new_op(name("IOMode"), Vec::new())
+}
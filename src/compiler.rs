@@ -2,15 +2,19 @@ use interner::*;
 use core::*;
 use core::Expr::*;
 use types::{qualified, extract_applied_type};
-use typecheck::{Types, DataTypes, TypeEnvironment, find_specialized_instances};
+use typecheck::{Types, DataTypes, TypeEnvironment, TypecheckOptions, find_specialized_instances};
 use scoped_map::ScopedMap;
 use std::borrow::ToOwned;
+use std::cell::RefCell;
+use std::collections::HashMap;
 
-use core::translate::{translate_module, translate_modules};
+use core::translate::{translate_expr, translate_module, translate_modules};
 use lambda_lift::do_lambda_lift;
 use renamer::rename_module;
 use renamer::typ::*;
 use builtins::builtins;
+use integer::Integer;
+use vm::VMError;
 
 use self::Instruction::*;
 
@@ -38,6 +42,46 @@ pub enum Instruction {
     DoubleGE,
     IntToDouble,
     DoubleToInt,
+    DoubleSqrt,
+    DoubleExp,
+    DoubleLog,
+    DoubleSin,
+    DoubleCos,
+    DoubleTan,
+    DoubleAsin,
+    DoubleAcos,
+    DoubleAtan,
+    DoubleSinh,
+    DoubleCosh,
+    DoubleTanh,
+    DoublePow,
+    DoubleAtan2,
+    ///Rounds a `Double` towards zero, leaving it as a `Double`
+    DoubleTruncate,
+    ///Rounds a `Double` to the nearest integer, choosing the even integer if it is halfway between two
+    DoubleRound,
+    DoubleFloor,
+    DoubleCeiling,
+    DoubleIsNaN,
+    DoubleIsInfinite,
+    DoubleIsNegativeZero,
+    ///Rounds a `Double` to the precision of a `Float`
+    DoubleToFloat,
+    ///Converts a `Char` to its code point
+    CharToInt,
+    ///Converts a code point to a `Char`, throwing an `ErrorCall` if it is not a valid code point
+    IntToChar,
+    CharIsSpace,
+    CharIsAlpha,
+    CharIsAlphaNum,
+    CharIsUpper,
+    CharIsLower,
+    CharIsPunctuation,
+    CharIsControl,
+    CharToUpper,
+    CharToLower,
+    ///Compares an `Int` with a number of any of the builtin number types
+    NumberEQ,
     Push(usize),
     PushGlobal(usize),
     PushInt(isize),
@@ -54,12 +98,18 @@ pub enum Instruction {
     CaseJump(usize),
     Jump(usize),
     JumpFalse(usize),
+    PatternMatchFail,
     PushDictionary(usize),
     PushDictionaryMember(usize),
     PushBuiltin(usize),
     MkapDictionary,
+    ///Applies a class method to the dictionary of its own constraints, merging it with the instance dictionary
+    ///the method may already be applied to so the method receives a single dictionary
+    MkapMethodDictionary,
     ConstructDictionary(usize),
-    PushDictionaryRange(usize, usize)
+    PushDictionaryRange(usize, usize),
+    Alloc(usize),
+    Rewrite(usize)
 }
 #[derive(Debug)]
 enum Var<'a> {
@@ -76,6 +126,37 @@ enum Var<'a> {
 static UNARY_PRIMITIVES: &'static [(&'static str, Instruction)] = &[
     ("primIntToDouble", IntToDouble),
     ("primDoubleToInt", DoubleToInt),
+    ("primDoubleSqrt", DoubleSqrt),
+    ("primDoubleExp", DoubleExp),
+    ("primDoubleLog", DoubleLog),
+    ("primDoubleSin", DoubleSin),
+    ("primDoubleCos", DoubleCos),
+    ("primDoubleTan", DoubleTan),
+    ("primDoubleAsin", DoubleAsin),
+    ("primDoubleAcos", DoubleAcos),
+    ("primDoubleAtan", DoubleAtan),
+    ("primDoubleSinh", DoubleSinh),
+    ("primDoubleCosh", DoubleCosh),
+    ("primDoubleTanh", DoubleTanh),
+    ("primDoubleTruncate", DoubleTruncate),
+    ("primDoubleRound", DoubleRound),
+    ("primDoubleFloor", DoubleFloor),
+    ("primDoubleCeiling", DoubleCeiling),
+    ("primDoubleIsNaN", DoubleIsNaN),
+    ("primDoubleIsInfinite", DoubleIsInfinite),
+    ("primDoubleIsNegativeZero", DoubleIsNegativeZero),
+    ("primDoubleToFloat", DoubleToFloat),
+    ("primCharToInt", CharToInt),
+    ("primIntToChar", IntToChar),
+    ("primCharIsSpace", CharIsSpace),
+    ("primCharIsAlpha", CharIsAlpha),
+    ("primCharIsAlphaNum", CharIsAlphaNum),
+    ("primCharIsUpper", CharIsUpper),
+    ("primCharIsLower", CharIsLower),
+    ("primCharIsPunctuation", CharIsPunctuation),
+    ("primCharIsControl", CharIsControl),
+    ("primCharToUpper", CharToUpper),
+    ("primCharToLower", CharToLower),
 ];
 
 static BINARY_PRIMITIVES: &'static [(&'static str, Instruction)] = &[
@@ -99,6 +180,8 @@ static BINARY_PRIMITIVES: &'static [(&'static str, Instruction)] = &[
     ("primDoubleLE", DoubleLE),
     ("primDoubleGT", DoubleGT),
     ("primDoubleGE", DoubleGE),
+    ("primDoublePow", DoublePow),
+    ("primDoubleAtan2", DoubleAtan2),
 ];
 
 
@@ -358,7 +441,12 @@ pub struct Compiler<'a> {
     pub assemblies: Vec<&'a Assembly>,
     module: Option<&'a Module<Id>>,
     variables: ScopedMap<Name, Var<'a>>,
-    context: Vec<Constraint<Name>>
+    context: Vec<Constraint<Name>>,
+    ///Caches the number of functions in the dictionary of each class since finding it walks
+    ///through every super class
+    dictionary_sizes: RefCell<HashMap<Name, usize>>,
+    ///Caches the offset of a super class's functions in the dictionary of a class
+    super_class_offsets: RefCell<HashMap<(Name, Name), Option<usize>>>
 }
 
 
@@ -378,7 +466,9 @@ impl <'a> Compiler<'a> {
             stack_size : 0, assemblies: Vec::new(),
             module: None,
             variables: variables,
-            context: Vec::new()
+            context: Vec::new(),
+            dictionary_sizes: RefCell::new(HashMap::new()),
+            super_class_offsets: RefCell::new(HashMap::new())
         }
     }
     
@@ -496,6 +586,22 @@ impl <'a> Compiler<'a> {
         })
     }
 
+    ///Returns false for type variables which are not bound by the context of the current binding,
+    ///which is the type given to variables in nested patterns
+    fn is_known_type(&self, typ: &Type<Name>) -> bool {
+        match *typ {
+            Type::Variable(ref var) => self.context.iter().any(|constraint| constraint.variables[0] == *var),
+            _ => true
+        }
+    }
+
+    fn is_newtype(&self, identifier: Name) -> bool {
+        match self.find(identifier) {
+            Some(Var::Newtype) => true,
+            _ => false
+        }
+    }
+
     fn find_constructor(&self, identifier : Name) -> Option<(u16, u16)> {
         self.module.and_then(|module| find_constructor(module, identifier))
         .or_else(|| {
@@ -541,6 +647,19 @@ impl <'a> Compiler<'a> {
         })
     }
 
+    fn find_instance(&self, classname: Name, typ: &Type<Name>) -> Option<(&[Constraint<Name>], &Type<Name>)> {
+        self.module.and_then(|m| m.find_instance(classname, typ))
+            .or_else(|| {
+            for types in self.assemblies.iter() {
+                match types.find_instance(classname, typ) {
+                    Some(result) => return Some(result),
+                    None => ()
+                }
+            }
+            None
+        })
+    }
+
     fn new_stack_var(&mut self, identifier : Name) {
         self.variables.insert(identifier, Var::Stack(self.stack_size));
         self.stack_size += 1;
@@ -565,38 +684,50 @@ impl <'a> Compiler<'a> {
             }
             &Literal(ref literal) => {
                 match &literal.value {
-                    &Integral(i) => {
+                    &Integral(ref i) => {
                         if literal.typ == int_type() {
-                            instructions.push(PushInt(i));
+                            instructions.push(PushInt(i.to_isize_wrapping()));
                         }
                         else if literal.typ == double_type() {
-                            instructions.push(PushFloat(i as f64));
+                            instructions.push(PushFloat(i.to_f64()));
+                        }
+                        else if literal.typ == integer_type() {
+                            let integer = integer_literal(i);
+                            self.compile(&integer, instructions, strict);
                         }
                         else {
                             let from_integer = Identifier(Id {
                                 name: Name { name: intern("fromInteger"), uid: 0 }, 
-                                typ: qualified(vec![], function_type_(int_type(), literal.typ.clone())),
+                                typ: qualified(vec![], function_type_(integer_type(), literal.typ.clone())),
                             });
-                            let number = Literal(LiteralData { typ: int_type(), value: Integral(i) });
-                            let apply = Apply(box from_integer, box number);
+                            let apply = Apply(box from_integer, box integer_literal(i));
                             self.compile(&apply, instructions, strict);
                         }
                     }
-                    &Fractional(f) => {
+                    &Fractional(ref r) => {
                         if literal.typ == double_type() {
-                            instructions.push(PushFloat(f));
+                            instructions.push(PushFloat(r.to_f64()));
                         }
                         else {
-                            let from_rational = Identifier(Id {
-                                name: Name { name: intern("fromRational"), uid: 0 }, 
-                                typ: qualified(vec![], function_type_(double_type(), literal.typ.clone())),
-                            });
-                            let number = Literal(LiteralData {
-                                typ: double_type(),
-                                value: Fractional(f)
+                            //The literal is turned into an exact Rational which is then
+                            //converted with `fromRational` unless it is already a Rational
+                            let make_ratio = Identifier(Id {
+                                name: Name { name: intern("MkRatio"), uid: 0 },
+                                typ: qualified(vec![], function_type_(integer_type(), function_type_(integer_type(), rational_type()))),
                             });
-                            let apply = Apply(box from_rational, box number);
-                            self.compile(&apply, instructions, strict);
+                            let ratio = Apply(box Apply(box make_ratio, box integer_literal(r.numerator())),
+                                              box integer_literal(r.denominator()));
+                            if literal.typ == rational_type() {
+                                self.compile(&ratio, instructions, strict);
+                            }
+                            else {
+                                let from_rational = Identifier(Id {
+                                    name: Name { name: intern("fromRational"), uid: 0 }, 
+                                    typ: qualified(vec![], function_type_(rational_type(), literal.typ.clone())),
+                                });
+                                let apply = Apply(box from_rational, box ratio);
+                                self.compile(&apply, instructions, strict);
+                            }
                         }
                     }
                     &String(ref s) => {
@@ -612,6 +743,23 @@ impl <'a> Compiler<'a> {
             &Apply(..) => {
                 self.compile_apply(expr, ArgList::Nil, instructions, strict);
             }
+            &Let(ref bindings, ref body) if is_recursive(bindings) => {
+                self.scope(&mut |this| {
+                    //Allocate a node for each binding so that the bindings can refer to each other
+                    //and then overwrite each node with its binding once it has been constructed
+                    let first = this.stack_size;
+                    for bind in bindings.iter() {
+                        this.new_stack_var(bind.name.name.clone());
+                    }
+                    instructions.push(Alloc(bindings.len()));
+                    for (i, bind) in bindings.iter().enumerate() {
+                        this.compile(&bind.expression, instructions, false);
+                        instructions.push(Rewrite(first + i));
+                    }
+                    this.compile(&**body, instructions, strict);
+                    instructions.push(Slide(bindings.len()));
+                });
+            }
             &Let(ref bindings, ref body) => {
                 self.scope(&mut |this| {
                     for bind in bindings.iter() {
@@ -627,6 +775,7 @@ impl <'a> Compiler<'a> {
                 });
             }
             &Case(ref body, ref alternatives) => {
+                let body_type = body.get_type();
                 self.compile(&**body, instructions, true);
                 self.stack_size += 1;
                 //Dummy variable for the case expression
@@ -639,7 +788,7 @@ impl <'a> Compiler<'a> {
                         let pattern_start = instructions.len() as isize;
                         let mut branches = Vec::new();
                         let i = this.stack_size - 1;
-                        let stack_increase = this.compile_pattern(&alt.pattern, &mut branches, instructions, i);
+                        let stack_increase = this.compile_pattern(&alt.pattern, body_type, &mut branches, instructions, i);
                         let pattern_end = instructions.len() as isize;
                         this.compile(&alt.expression, instructions, strict);
                         instructions.push(Slide(stack_increase));
@@ -661,6 +810,11 @@ impl <'a> Compiler<'a> {
                         }
                     });
                 }
+                //If the last alternative can fail there may be values which no alternative matches
+                match alternatives.last().map(|alt| &alt.pattern) {
+                    Some(&Pattern::Identifier(..)) | Some(&Pattern::WildCard) => (),
+                    _ => instructions.push(PatternMatchFail)
+                }
                 for branch in end_branches.iter() {
                     instructions[*branch] = Jump(instructions.len());
                 }
@@ -795,7 +949,20 @@ impl <'a> Compiler<'a> {
                 }
             }
             None => {
-                self.compile_with_constraints(name, actual_type, function_type, constraints, instructions)
+                //Constraints on the method itself are not part of the class dictionary
+                //so they are passed in a separate dictionary to the function in the class dictionary
+                let (class_constraints, method_constraints): (Vec<_>, Vec<_>) = constraints.iter()
+                    .cloned()
+                    .partition(|constraint| constraint.variables[0] == *var);
+                if method_constraints.len() == 0 {
+                    self.compile_with_constraints(name, actual_type, function_type, constraints, instructions)
+                }
+                else {
+                    let dictionary_key = find_specialized_instances(function_type, actual_type, &*method_constraints);
+                    self.push_dictionary(&*method_constraints, &*dictionary_key, instructions);
+                    self.compile_with_constraints(name, actual_type, function_type, &*class_constraints, instructions);
+                    instructions.push(MkapMethodDictionary);
+                }
             }
         }
     }
@@ -805,12 +972,10 @@ impl <'a> Compiler<'a> {
         match self.find(Name { name: intern("$dict"), uid: 0}) {
             Some(Var::Stack(_)) => {
                 //Push dictionary or member of dictionary
-                match self.push_dictionary_member(constraints, name) {
+                let dictionary_key = find_specialized_instances(function_type, actual_type, constraints);
+                match self.push_dictionary_member(&*dictionary_key, name) {
                     Some(index) => instructions.push(PushDictionaryMember(index)),
-                    None => {
-                        let dictionary_key = find_specialized_instances(function_type, actual_type, constraints);
-                        self.push_dictionary(constraints, &*dictionary_key, instructions);
-                    }
+                    None => self.push_dictionary(constraints, &*dictionary_key, instructions)
                 }
             }
             _ => {
@@ -824,10 +989,12 @@ impl <'a> Compiler<'a> {
     
     fn push_dictionary(&mut self, context: &[Constraint<Name>], constraints: &[(Name, Type<Name>)], instructions: &mut Vec<Instruction>) {
         debug!("Push dictionary {:?} ==> {:?}", context, constraints);
-        for &(ref class, ref typ) in constraints.iter() {
+        //ConstructDictionary takes the dictionary on the top of the stack first
+        //so the dictionary for the first constraint is pushed last
+        for &(ref class, ref typ) in constraints.iter().rev() {
             self.fold_dictionary(*class, typ, instructions);
-            instructions.push(ConstructDictionary(constraints.len()));
         }
+        instructions.push(ConstructDictionary(constraints.len()));
     }
     
     //Writes instructions which pushes a dictionary for the type to the top of the stack
@@ -839,65 +1006,130 @@ impl <'a> Compiler<'a> {
                 let index = self.find_dictionary_index(&[(class.clone(), typ.clone())]);
                 instructions.push(PushDictionary(index));
             }
+            //Only instances with a context, such as `Eq a => Eq [a]`, need the dictionaries of the type arguments
+            Type::Application(..) if self.find_instance(class, typ).map_or(false, |(constraints, _)| constraints.is_empty()) => {
+                debug!("Unconstrained instance for {:?}", typ);
+                let index = self.find_dictionary_index(&[(class.clone(), typ.clone())]);
+                instructions.push(PushDictionary(index));
+            }
             Type::Application(ref lhs, ref rhs) => {
                 debug!("App for ({:?} {:?})", lhs, rhs);
-                //For function in functions
-                // Mkap function fold_dictionary(rhs)
-                self.fold_dictionary(class, &**lhs, instructions);
-                self.fold_dictionary(class, &**rhs, instructions);
+                let (constraints, instance_type) = self.find_instance(class, typ)
+                    .map(|(constraints, instance_type)| (constraints.to_owned(), instance_type.clone()))
+                    .unwrap_or_else(|| panic!("Compiler error: No instance {:?} for {:?}", class, typ));
+                //Push the functions of the instance and then apply them to the dictionary for the
+                //context of the instance, built from the types which the instance is used at
+                let index = self.find_dictionary_index(&[(class.clone(), typ.clone())]);
+                instructions.push(PushDictionary(index));
+                for constraint in constraints.iter().rev() {
+                    let arg = bound_type(&instance_type, typ, &constraint.variables[0])
+                        .unwrap_or_else(|| panic!("Compiler error: {:?} is not bound in {:?}", constraint, typ));
+                    self.fold_dictionary(constraint.class, arg, instructions);
+                }
+                instructions.push(ConstructDictionary(constraints.len()));
                 instructions.push(MkapDictionary);
             }
             Type::Variable(ref var) => {
-                //This variable must appear in the context
-                let mut has_constraint = false;
+                //This variable must appear in the context, either with the class itself
+                //or with a class which has it as a super class
                 let mut index = 0;
+                let mut found = None;
                 for constraint in self.context.iter() {
-                    if constraint.variables[0] == *var && constraint.class == class {
-                        has_constraint = true;
-                        break
+                    if constraint.variables[0] == *var {
+                        found = self.super_class_offset(constraint.class, class).map(|offset| index + offset);
+                        if found.is_some() {
+                            break
+                        }
                     }
-                    let (_, _, decls) = self.find_class(constraint.class).unwrap();
-                    index += decls.len();
-                }
-                if has_constraint {
-                    //Found the variable in the constraints
-                    let num_class_functions = self.find_class(class)
-                        .map(|(_, _, decls)| decls.len())
-                        .unwrap();
-                    debug!("Use previous dict for {:?} at {:?}..{:?}", var, index, num_class_functions);
-                    instructions.push(PushDictionaryRange(index, num_class_functions));
+                    index += self.dictionary_size(constraint.class);
                 }
-                else {
-                    debug!("No dict for {:?}", var);
+                match found {
+                    Some(index) => {
+                        //Found the variable in the constraints
+                        let num_class_functions = self.dictionary_size(class);
+                        debug!("Use previous dict for {:?} at {:?}..{:?}", var, index, num_class_functions);
+                        instructions.push(PushDictionaryRange(index, num_class_functions));
+                    }
+                    None => {
+                        debug!("No dict for {:?}", var);
+                    }
                 }
             }
             _ => panic!("Did not expect generic")
         }
     }
 
-    ///Lookup which index in the instance dictionary that holds the function called 'name'
-    fn push_dictionary_member(&self, constraints: &[Constraint<Name>], name: Name) -> Option<usize> {
-        if constraints.len() == 0 {
-            panic!("Attempted to push dictionary member '{:?}' with no constraints", name)
-        }
-        let mut ii = 0;
-        for c in constraints.iter() {
-            let result = self.walk_classes(c.class, &mut |declarations| -> Option<usize> {
+    ///Lookup which index in the dictionary of the current binding that holds the function called 'name'
+    ///Returns None if the function is not used at one of the type variables in the context
+    fn push_dictionary_member(&self, constraints: &[(Name, Type<Name>)], name: Name) -> Option<usize> {
+        let var = match constraints.first() {
+            Some(&(_, Type::Variable(ref var))) => var,
+            _ => return None
+        };
+        let mut offset = 0;
+        for constraint in self.context.iter() {
+            let mut ii = 0;
+            let mut found = None;
+            self.walk_classes(constraint.class, &mut |declarations| -> Option<()> {
                 for decl in declarations.iter() {
-                    if decl.name == name {
-                        return Some(ii)
+                    if decl.name == name && found.is_none() {
+                        found = Some(ii);
                     }
                     ii += 1;
                 }
                 None
             });
-            if result.is_some() {
-                return result;
+            match found {
+                Some(index) if constraint.variables[0] == *var => return Some(offset + index),
+                _ => ()
             }
+            offset += ii;
         }
         None
     }
 
+    ///Returns the offset of the functions of 'super_class' in the dictionary for 'class'
+    ///or None if 'super_class' is not 'class' or one of its super classes
+    fn super_class_offset(&self, class: Name, super_class: Name) -> Option<usize> {
+        if class == super_class {
+            return Some(0);
+        }
+        match self.super_class_offsets.borrow().get(&(class, super_class)) {
+            Some(offset) => return *offset,
+            None => ()
+        }
+        let (constraints, _, _) = self.find_class(class)
+            .expect("Compiler error: Expected class");
+        let mut offset = 0;
+        let mut result = None;
+        for constraint in constraints.iter() {
+            match self.super_class_offset(constraint.class, super_class) {
+                Some(index) => {
+                    result = Some(offset + index);
+                    break
+                }
+                None => offset += self.dictionary_size(constraint.class)
+            }
+        }
+        self.super_class_offsets.borrow_mut().insert((class, super_class), result);
+        result
+    }
+
+    ///Returns the number of functions in the dictionary for 'class', including its super classes
+    fn dictionary_size(&self, class: Name) -> usize {
+        match self.dictionary_sizes.borrow().get(&class) {
+            Some(size) => return *size,
+            None => ()
+        }
+        let mut size = 0;
+        self.walk_classes(class, &mut |declarations| -> Option<()> {
+            size += declarations.len();
+            None
+        });
+        self.dictionary_sizes.borrow_mut().insert(class, size);
+        size
+    }
+
     ///Walks through the class and all of its super classes, calling 'f' on each of them
     ///Returning Some(..) from the function quits and returns that value
     fn walk_classes<T>(&self, class: Name, f: &mut FnMut(&[TypeDeclaration<Name>]) -> Option<T>) -> Option<T> {
@@ -962,9 +1194,14 @@ impl <'a> Compiler<'a> {
     ///Compiles a pattern.
     ///An index to the Jump instruction which is taken when the match fails is stored in the branches vector
     ///These instructions will need to be updated later with the correct jump location.
-    fn compile_pattern(&mut self, pattern: &Pattern<Id>, branches: &mut Vec<usize>, instructions: &mut Vec<Instruction>, stack_size: usize) -> usize {
+    fn compile_pattern(&mut self, pattern: &Pattern<Id>, typ: &Type<Name>, branches: &mut Vec<usize>, instructions: &mut Vec<Instruction>, stack_size: usize) -> usize {
         debug!("Pattern {:?} at {:?}", pattern, stack_size);
         match pattern {
+            &Pattern::Constructor(ref name, ref patterns) if self.is_newtype(name.name) => {
+                //Newtypes have the same representation as the type they wrap so matching always succeeds
+                self.new_var_at(patterns[0].name.clone(), stack_size);
+                0
+            }
             &Pattern::Constructor(ref name, ref patterns) => {
                 instructions.push(Push(stack_size));
                 match self.find_constructor(name.name) {
@@ -983,14 +1220,38 @@ impl <'a> Compiler<'a> {
                 }
                 patterns.len()
             }
-            &Pattern::Number(number) => {
+            &Pattern::Number(ref number) if *typ == int_type() => {
                 instructions.push(Push(stack_size));
                 instructions.push(Eval);
-                instructions.push(PushInt(number));
+                instructions.push(PushInt(number.to_isize_wrapping()));
                 instructions.push(IntEQ);
                 instructions.push(JumpFalse(0));
                 0
             }
+            &Pattern::Number(ref number) if !self.is_known_type(typ) => {
+                //Nested patterns do not know the type they match on so the comparison
+                //is done on whichever builtin number type the value turns out to have
+                instructions.push(Push(stack_size));
+                instructions.push(Eval);
+                instructions.push(PushInt(number.to_isize_wrapping()));
+                instructions.push(NumberEQ);
+                instructions.push(JumpFalse(0));
+                0
+            }
+            &Pattern::Number(ref number) => {
+                //Other types compare the value against the literal with (==) from their Eq instance
+                let scrutinee = Name { name: intern("#scrutinee"), uid: 0 };
+                self.new_var_at(scrutinee, stack_size);
+                let eq = Identifier(Id {
+                    name: Name { name: intern("=="), uid: 0 },
+                    typ: qualified(vec![], function_type_(typ.clone(), function_type_(typ.clone(), bool_type()))),
+                });
+                let literal = Literal(LiteralData { typ: typ.clone(), value: Integral(number.clone()) });
+                let value = Identifier(Id { name: scrutinee, typ: qualified(vec![], typ.clone()) });
+                self.compile(&Apply(box Apply(box eq, box value), box literal), instructions, true);
+                instructions.push(JumpFalse(0));
+                0
+            }
             &Pattern::Identifier(ref ident) => {
                 self.new_var_at(ident.name.clone(), stack_size);
                 0
@@ -1002,7 +1263,55 @@ impl <'a> Compiler<'a> {
     }
 }
 
+///Constructs an expression which creates `value` as an `Integer`.
+///Values which do not fit in an `Int` are built from several `Int` literals
+fn integer_literal(value: &Integer) -> Expr<Id> {
+    fn builtin(name: &str, typ: Type<Name>) -> Expr<Id> {
+        Identifier(Id { name: Name { name: intern(name), uid: 0 }, typ: qualified(vec![], typ) })
+    }
+    fn apply(func: Expr<Id>, args: Vec<Expr<Id>>) -> Expr<Id> {
+        args.into_iter().fold(func, |func, arg| Apply(box func, box arg))
+    }
+    let from_int = |i: isize| {
+        let int = Literal(LiteralData { typ: int_type(), value: Integral(Integer::from_isize(i)) });
+        apply(builtin("primIntToInteger", function_type_(int_type(), integer_type())), vec![int])
+    };
+    match value.to_isize() {
+        Some(i) => from_int(i),
+        None => {
+            let binop = function_type_(integer_type(), function_type_(integer_type(), integer_type()));
+            let base = 1 << 62;
+            let (high, low) = value.div_mod(&Integer::from_isize(base)).unwrap();
+            let shifted = apply(builtin("primIntegerMultiply", binop.clone()), vec![integer_literal(&high), from_int(base)]);
+            apply(builtin("primIntegerAdd", binop), vec![shifted, from_int(low.to_isize_wrapping())])
+        }
+    }
+}
+
 ///Attempts to find the actual type of the for the variable which has a constraint
+///Returns true if any of the bindings refer to a name bound in the same let expression
+fn is_recursive(bindings: &[Binding<Id>]) -> bool {
+    use core::ref_::{Visitor, walk_expr};
+    struct References<'a> { bindings: &'a [Binding<Id>], found: bool }
+    impl <'a> Visitor<Id> for References<'a> {
+        fn visit_expr(&mut self, expr: &Expr<Id>) {
+            match *expr {
+                Identifier(ref id) => {
+                    if self.bindings.iter().any(|bind| bind.name.name == id.name) {
+                        self.found = true;
+                    }
+                }
+                _ => walk_expr(self, expr)
+            }
+        }
+    }
+    let mut visitor = References { bindings: bindings, found: false };
+    for bind in bindings.iter() {
+        visitor.visit_binding(bind);
+    }
+    visitor.found
+}
+
 fn try_find_instance_type<'a>(class_var: &TypeVariable, class_type: &Type<Name>, actual_type: &'a Type<Name>) -> Option<&'a str> {
     match (class_type, actual_type) {
         (&Type::Variable(ref var), _) if var == class_var => {
@@ -1024,6 +1333,18 @@ fn try_find_instance_type<'a>(class_var: &TypeVariable, class_type: &Type<Name>,
     }
 }
 
+///Returns the part of 'actual_type' which the variable 'var' in 'instance_type' is bound to
+fn bound_type<'a>(instance_type: &Type<Name>, actual_type: &'a Type<Name>, var: &TypeVariable) -> Option<&'a Type<Name>> {
+    match (instance_type, actual_type) {
+        (&Type::Variable(ref v), _) | (&Type::Generic(ref v), _) if v.id == var.id => Some(actual_type),
+        (&Type::Application(ref lhs1, ref rhs1), &Type::Application(ref lhs2, ref rhs2)) => {
+            bound_type(&**lhs1, &**lhs2, var)
+                .or_else(|| bound_type(&**rhs1, &**rhs2, var))
+        }
+        _ => None
+    }
+}
+
 #[allow(dead_code)]
 pub fn compile(contents: &str) -> Result<Assembly, ::std::string::String> {
     let mut type_env = TypeEnvironment::new();
@@ -1032,10 +1353,13 @@ pub fn compile(contents: &str) -> Result<Assembly, ::std::string::String> {
 #[allow(dead_code)]
 pub fn compile_with_type_env<'a>(type_env: &mut TypeEnvironment<'a>, assemblies: &[&'a Assembly], contents: &str) -> Result<Assembly, ::std::string::String> {
     use parser::Parser;
+    use infix::PrecedenceVisitor;
+    use module::MutVisitor;
 
     let mut parser = Parser::new(contents.chars()); 
-    let module = try!(parser.module().map_err(|e| format!("{:?}", e)));
+    let module = try!(parser.module().map_err(|e| format!("{}", e)));
     let mut module = try!(rename_module(module).map_err(|e| format!("{}", e)));
+    PrecedenceVisitor::new().visit_module(&mut module);
     for assem in assemblies.iter() {
         type_env.add_types(*assem);
     }
@@ -1048,21 +1372,31 @@ pub fn compile_with_type_env<'a>(type_env: &mut TypeEnvironment<'a>, assemblies:
     Ok(compiler.compile_module(&core_module))
 }
 
-pub fn compile_string(module: &str) -> Result<Vec<Assembly>, ::std::string::String> {
-    use typecheck::typecheck_string;
-    let modules = try!(typecheck_string(module));
+pub fn compile_string(module: &str) -> Result<Vec<Assembly>, VMError> {
+    compile_string_with(module, TypecheckOptions::default())
+}
+
+///Compiles a module and its imported modules with the bindings generalized as specified by `options`
+pub fn compile_string_with(module: &str, options: TypecheckOptions) -> Result<Vec<Assembly>, VMError> {
+    use typecheck::typecheck_string_with;
+    let modules = try!(typecheck_string_with(module, options));
     compile_module_(modules)
 }
 
 ///Takes a module name and does everything needed up to and including compiling the module
 ///and its imported modules
-pub fn compile_module(module: &str) -> Result<Vec<Assembly>, ::std::string::String> {
-    use typecheck::typecheck_module;
-    let modules = try!(typecheck_module(module));
+pub fn compile_module(module: &str) -> Result<Vec<Assembly>, VMError> {
+    compile_module_with(module, TypecheckOptions::default())
+}
+
+///Compiles a module and its imported modules, loaded from files, with the bindings generalized as specified by `options`
+pub fn compile_module_with(module: &str, options: TypecheckOptions) -> Result<Vec<Assembly>, VMError> {
+    use typecheck::typecheck_module_with;
+    let modules = try!(typecheck_module_with(module, options));
     compile_module_(modules)
 }
 
-fn compile_module_(modules: Vec<::module::Module<Name>>) -> Result<Vec<Assembly>, ::std::string::String> {
+fn compile_module_(modules: Vec<::module::Module<Name>>) -> Result<Vec<Assembly>, VMError> {
     use compiler::Compiler;
     let core_modules: Vec<Module<Id<Name>>> = translate_modules(modules)
         .into_iter()
@@ -1082,6 +1416,35 @@ fn compile_module_(modules: Vec<::module::Module<Name>>) -> Result<Vec<Assembly>
     Ok(assemblies)
 }
 
+///Compiles `expr` into an assembly where it is bound to `main`.
+///The expression can refer to the globals of `assemblies` and its bindings are generalized as specified by `options`
+pub fn compile_expr<'a>(assemblies: &[&'a Assembly], mut expr: ::module::TypedExpr<Name>, options: TypecheckOptions) -> Result<Assembly, VMError> {
+    let mut type_env = TypeEnvironment::new();
+    for &assembly in assemblies.iter() {
+        type_env.add_types(assembly as &DataTypes);
+    }
+    type_env.set_extended_defaults(true);
+    type_env.set_options(options);
+    try!(type_env.typecheck_expr(&mut expr));
+    let temp_module = Module::from_expr(translate_expr(expr));
+    let m = do_lambda_lift(temp_module);
+
+    let mut compiler = Compiler::new();
+    for &assembly in assemblies.iter() {
+        compiler.assemblies.push(assembly);
+    }
+    Ok(compiler.compile_module(&m))
+}
+
+///Compiles `show expr` the same way as `compile_expr`.
+///Returns `None` if the type of `expr` does not have a `Show` instance
+pub fn compile_show_expr<'a>(assemblies: &[&'a Assembly], expr: ::module::TypedExpr<Name>, options: TypecheckOptions) -> Option<Assembly> {
+    use module::Expr::{Apply, Identifier};
+    let span = expr.span;
+    let show = ::module::TypedExpr::with_span(Identifier(Name { name: intern("show"), uid: 0 }), span);
+    compile_expr(assemblies, ::module::TypedExpr::with_span(Apply(box show, box expr), span), options).ok()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -1163,8 +1526,8 @@ r"main = case [primIntAdd 1 0] of
 
 
     assert_eq!(assembly.super_combinators[0].instructions, vec![Pack(0, 0), PushInt(0), PushInt(1), Add, Pack(1, 2),
-        Push(0), CaseJump(1), Jump(14), Split(2), Push(1), Eval, Slide(2), Jump(22), Pop(2),
-        Push(0), CaseJump(0), Jump(22), Split(0), PushInt(2), Slide(0), Jump(22), Pop(0), Slide(1), Eval, Update(0), Unwind]);
+        Push(0), CaseJump(1), Jump(14), Split(2), Push(1), Eval, Slide(2), Jump(23), Pop(2),
+        Push(0), CaseJump(0), Jump(22), Split(0), PushInt(2), Slide(0), Jump(23), Pop(0), PatternMatchFail, Slide(1), Eval, Update(0), Unwind]);
 }
 
 #[test]
@@ -1216,6 +1579,38 @@ fn compile_prelude() {
     assert_eq!(sc.instructions, vec![PushInt(0), PushInt(2), Add, PushGlobal(id_index), Mkap, Eval, Update(0), Unwind]);
 }
 
+#[test]
+fn compile_foldable_known_instance() {
+    //Calling a `Foldable` method on a known container calls the instance function directly, without a dictionary
+    let mut type_env = TypeEnvironment::new();
+    let mut contents = ::std::string::String::new();
+    File::open("Prelude.hs").and_then(|mut f| f.read_to_string(&mut contents)).unwrap();
+    let prelude = compile_with_type_env(&mut type_env, &[], &contents).unwrap();
+
+    let assembly = compile_with_type_env(&mut type_env, &[&prelude], r"main = length [primIntAdd 1 0]").unwrap();
+
+    let sc = &assembly.super_combinators[0];
+    let length_index = prelude.super_combinators.iter().position(|sc| sc.name.name == intern("#[]length")).unwrap();
+    assert_eq!(sc.instructions, vec![Pack(0, 0), PushInt(0), PushInt(1), Add, Pack(1, 2), PushGlobal(length_index), Mkap, Eval, Update(0), Unwind]);
+}
+
+#[test]
+fn compile_expr_with_options() {
+    use parser::Parser;
+    use renamer::rename_expr;
+    use typecheck::TypecheckOptions;
+    let mut type_env = TypeEnvironment::new();
+    let mut contents = ::std::string::String::new();
+    File::open("Prelude.hs").and_then(|mut f| f.read_to_string(&mut contents)).unwrap();
+    let prelude = compile_with_type_env(&mut type_env, &[], &contents).unwrap();
+
+    let expr = Parser::new(r"let f x = x in (f True, f 'a')".chars()).complete_expression().unwrap();
+    let expr = rename_expr(expr).unwrap();
+    assert!(super::compile_expr(&[&prelude], expr.clone(), TypecheckOptions::default()).is_ok());
+    let options = TypecheckOptions { mono_local_binds: true, .. TypecheckOptions::default() };
+    assert!(super::compile_expr(&[&prelude], expr, options).is_err());
+}
+
 #[test]
 fn generics_do_not_propagate() {
     //Test that the type of 'i' does not get overwritten by the use inside the let binding
@@ -1225,9 +1620,9 @@ fn generics_do_not_propagate() {
     compile(
 r"
 class Num a where
-    fromInteger :: Int -> a
+    fromInteger :: Integer -> a
 instance Num Int where
-    fromInteger x = x
+    fromInteger x = primIntegerToInt x
 class Integral a where
     rem :: a -> a -> a
 
@@ -1269,12 +1664,15 @@ fn bench_prelude(b: &mut Bencher) {
     use core::translate::translate_module;
     use renamer::tests::rename_module;
     use parser::Parser;
+    use infix::PrecedenceVisitor;
+    use module::MutVisitor;
 
     let path = &Path::new("Prelude.hs");
     let mut contents = ::std::string::String::new();
     File::open(path).and_then(|mut f| f.read_to_string(&mut contents)).unwrap();
     let mut parser = Parser::new(contents.chars());
     let mut module = rename_module(parser.module().unwrap());
+    PrecedenceVisitor::new().visit_module(&mut module);
     let mut type_env = TypeEnvironment::new();
     type_env.typecheck_module_(&mut module);
     let core_module = do_lambda_lift(translate_module(module));
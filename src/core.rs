@@ -5,6 +5,7 @@ pub use module::{Constructor, DataDefinition, TypeDeclaration, Newtype};
 pub use module::LiteralData::{Integral, Fractional, String, Char};
 use typecheck::TcType;
 use module;
+use integer::Integer;
 use interner::*;
 pub use renamer::Name;
 
@@ -65,7 +66,7 @@ pub struct Alternative<Ident> {
 pub enum Pattern<Ident> {
     Constructor(Ident, Vec<Ident>),
     Identifier(Ident),
-    Number(isize),
+    Number(Integer),
     WildCard
 }
 
@@ -226,6 +227,12 @@ pub mod ref_ {
     }
 
     pub fn walk_module<V: Visitor<Ident>, Ident>(visitor: &mut V, module: &Module<Ident>) {
+        for bind in module.instances.iter().flat_map(|i| i.bindings.iter()) {
+            visitor.visit_binding(bind);
+        }
+        for bind in module.classes.iter().flat_map(|c| c.bindings.iter()) {
+            visitor.visit_binding(bind);
+        }
         for bind in module.bindings.iter() {
             visitor.visit_binding(bind);
         }
@@ -285,6 +292,12 @@ pub mod mutable {
     }
 
     pub fn walk_module<Ident, V: Visitor<Ident>>(visitor: &mut V, module: &mut Module<Ident>) {
+        for bind in module.instances.iter_mut().flat_map(|i| i.bindings.iter_mut()) {
+            visitor.visit_binding(bind);
+        }
+        for bind in module.classes.iter_mut().flat_map(|c| c.bindings.iter_mut()) {
+            visitor.visit_binding(bind);
+        }
         for bind in module.bindings.iter_mut() {
             visitor.visit_binding(bind);
         }
@@ -347,6 +360,20 @@ pub mod result {
     }
 
     pub fn walk_module<V: Visitor<Ident>, Ident>(visitor: &mut V, mut module: Module<Ident>) -> Module<Ident> {
+        for instance in module.instances.iter_mut() {
+            let mut bindings = vec![];
+            ::std::mem::swap(&mut instance.bindings, &mut bindings);
+            instance.bindings = bindings.into_iter()
+                .map(|bind| visitor.visit_binding(bind))
+                .collect();
+        }
+        for class in module.classes.iter_mut() {
+            let mut bindings = vec![];
+            ::std::mem::swap(&mut class.bindings, &mut bindings);
+            class.bindings = bindings.into_iter()
+                .map(|bind| visitor.visit_binding(bind))
+                .collect();
+        }
         let mut bindings = vec![];
         ::std::mem::swap(&mut module.bindings, &mut bindings);
         module.bindings = bindings.into_iter()
@@ -404,6 +431,7 @@ pub mod translate {
     use renamer::NameSupply;
     use renamer::typ::*;
     use deriving::*;
+    use lexer::Located;
     use std::collections::HashMap;
 
     struct Translator<'a> {
@@ -447,6 +475,8 @@ pub mod translate {
             classes,
             instances,
             data_definitions,
+            type_synonyms : _type_synonyms,
+            defaults : _defaults,
             fixity_declarations : _fixity_declarations
         } = module;
 
@@ -458,7 +488,8 @@ pub mod translate {
                 name,
                 variable,
                 declarations,
-                bindings
+                bindings,
+                span: _
             } = class;
             Class {
                 constraints: constraints,
@@ -474,7 +505,8 @@ pub mod translate {
                 classname,
                 typ,
                 constraints,
-                bindings
+                bindings,
+                span: _
             } = instance;
             let bs: Vec<Binding<Id<Name>>> = translator.translate_bindings(bindings).into_iter().collect();
             new_instances.push(Instance {
@@ -486,7 +518,7 @@ pub mod translate {
         }
         let bs: Vec<Binding<Id<Name>>> = translator.translate_bindings(bindings).into_iter().collect();
         for data in data_definitions.iter() {
-            generate_deriving(&mut new_instances, data);
+            generate_deriving(&mut new_instances, data, &mut translator.name_supply);
         }
         for instance in new_instances.iter_mut() {
             let (class_var, class_decls) = (translator.functions_in_class)(instance.classname);
@@ -507,25 +539,31 @@ pub mod translate {
 
     ///Creates stub functions for each undeclared function in the instance
     fn create_default_stubs(class_var: &TypeVariable, class_decls: &[TypeDeclaration<Name>], instance: &Instance<Id<Name>>) -> Vec<Binding<Id<Name>>> {
+        let typ_name = module::extract_applied_type(&instance.typ).ctor().name.name;
         class_decls.iter()
-            .filter(|decl| instance.bindings.iter().find(|bind| bind.name.as_ref().ends_with(decl.name.as_ref())).is_none())
+            .filter(|decl| {
+                let instance_fn_name = module::encode_binding_identifier(typ_name, decl.name.name);
+                instance.bindings.iter().find(|bind| bind.name.name.name == instance_fn_name).is_none()
+            })
             .map(|decl| {
                 debug!("Create default function for {} ({}) {}", instance.classname, instance.typ, decl.name);
                 //The stub functions will naturally have the same type as the function in the class but with the variable replaced
                 //with the instance's type
                 let mut typ = decl.typ.clone();
+                rename_captured_variables(&mut typ, class_var, &instance.typ);
                 ::typecheck::replace_var(&mut typ.value, class_var, &instance.typ);
                 {
                     let context = ::std::mem::replace(&mut typ.constraints, Vec::new());
-                    //Remove all constraints which refer to the class's variable
-                    let vec_context: Vec<Constraint<Name>> = context.into_iter()
-                        .filter(|c| c.variables[0] != *class_var)
+                    //Remove all constraints which refer to the class's variable and add the instance's context
+                    //in front of them, the same layout as the functions defined in the instance
+                    let vec_context: Vec<Constraint<Name>> = instance.constraints.iter()
+                        .cloned()
+                        .chain(context.into_iter().filter(|c| c.variables[0] != *class_var))
                         .collect();
                     typ.constraints = vec_context;
                 }
                 let Qualified { value: typ, constraints } = typ;
                 let default_name = module::encode_binding_identifier(instance.classname.name, decl.name.name);
-                let typ_name = module::extract_applied_type(&instance.typ).ctor().name.name;
                 let instance_fn_name = module::encode_binding_identifier(typ_name, decl.name.name);
 
                 //Example stub for undeclared (/=)
@@ -537,6 +575,42 @@ pub mod translate {
             })
             .collect()
     }
+    ///Renames the variables of a method's type which also appear in the instance's type,
+    ///for instance `a` in `sum :: Num a => t a -> a` for `instance Foldable ((,) a)`,
+    ///so that they are not captured when the class variable is replaced by the instance's type
+    fn rename_captured_variables(typ: &mut Qualified<TcType, Name>, class_var: &TypeVariable, instance_type: &TcType) {
+        fn variables(typ: &TcType, vars: &mut Vec<TypeVariable>) {
+            match *typ {
+                Type::Variable(ref var) => vars.push(var.clone()),
+                Type::Application(ref lhs, ref rhs) => {
+                    variables(&**lhs, vars);
+                    variables(&**rhs, vars);
+                }
+                _ => ()
+            }
+        }
+        let mut instance_vars = Vec::new();
+        variables(instance_type, &mut instance_vars);
+        let mut method_vars = Vec::new();
+        variables(&typ.value, &mut method_vars);
+        for var in method_vars.iter() {
+            if var == class_var || !instance_vars.iter().any(|v| v.id == var.id) {
+                continue
+            }
+            let mut new_var = var.clone();
+            while instance_vars.iter().chain(method_vars.iter()).any(|v| v.id == new_var.id) {
+                new_var.id = intern(&format!("{}'", new_var.id));
+            }
+            ::typecheck::replace_var(&mut typ.value, var, &Type::Variable(new_var.clone()));
+            for constraint in typ.constraints.iter_mut() {
+                for constraint_var in constraint.variables.iter_mut() {
+                    if constraint_var == var {
+                        *constraint_var = new_var.clone();
+                    }
+                }
+            }
+        }
+    }
 impl <'a> Translator<'a> {
     fn translate_match(&mut self, matches: module::Match<Name>) -> Expr<Id<Name>> {
         match matches {
@@ -557,7 +631,7 @@ impl <'a> Translator<'a> {
             match expr {
                 module::Expr::Lambda(arg, body) => {
                     //TODO need to make unique names for the lambdas created here
-                    let argname = match arg {
+                    let argname = match arg.node {
                         module::Pattern::Identifier(arg) => arg,
                         module::Pattern::WildCard => Name { name: intern("_"), uid: usize::max_value() },
                         _ => panic!("Core translation of pattern matches in lambdas are not implemented")
@@ -590,7 +664,7 @@ impl <'a> Translator<'a> {
             }
             module::Expr::Literal(l) => Literal(LiteralData { typ: typ, value: l }),
             module::Expr::Lambda(arg, body) => {
-                match arg {
+                match arg.node {
                     module::Pattern::Identifier(arg) => Lambda(Id::new(arg, typ, vec![]), box self.translate_expr_rest(*body)),
                     module::Pattern::WildCard => Lambda(Id::new(Name { name: intern("_"), uid: usize::max_value() }, typ, vec![]), box self.translate_expr_rest(*body)),
                     _ => panic!("Core translation of pattern matches in lambdas are not implemented")
@@ -649,18 +723,21 @@ impl <'a> Translator<'a> {
     ///    let ok p = do {stmts}
 	///        ok _ = fail "..."
 	///    in e >>= ok
+    ///The `fail` alternative is only added if matching `p` can fail, `fail` is a member of `MonadFail`
     fn do_bind_translate(&mut self, pattern: module::Pattern<Name>, expr: Expr<Id<Name>>, result: Expr<Id<Name>>) -> Expr<Id<Name>> {
 
         let m_a = expr.get_type().clone();
         let a = m_a.appr().clone();
         let m_b = result.get_type().clone();
                 debug!("m_a {}", m_a);
-        let c = match *m_a.appl() {
-            Type::Variable(ref var) => vec![Constraint { class: Name { name: intern("Monad"), uid: 0 }, variables: vec![var.clone()] }],
+        let monad_constraint = |class: &str| match *m_a.appl() {
+            Type::Variable(ref var) => vec![Constraint { class: Name { name: intern(class), uid: 0 }, variables: vec![var.clone()] }],
             _ => vec![]
         };
+        let c = monad_constraint("Monad");
+        let fail_constraint = monad_constraint("MonadFail");
         let arg2_type = function_type_(a.clone(), m_b.clone());
-        let bind_typ = function_type_(m_a, function_type_(arg2_type.clone(), m_b.clone()));
+        let bind_typ = function_type_(m_a.clone(), function_type_(arg2_type.clone(), m_b.clone()));
         let bind_ident = Identifier(Id::new(Name { name: intern(">>="), uid: 0}, bind_typ, c.clone()));
 
         //Create ok binding
@@ -670,10 +747,13 @@ impl <'a> Translator<'a> {
             c.clone()
         );//TODO unique id
         let var = Id::new(self.name_supply.from_str("p"), function_type_(a, m_b.clone()), c.clone());//Constraints for a
-        let fail_ident = Identifier(Id::new(Name { name: intern("fail"), uid: 0 }, function_type_(list_type(char_type()), m_b), c));
-        let func = Lambda(var.clone(), box Case(box Identifier(var), 
-            vec![Alternative { pattern: self.translate_pattern(pattern), expression: result }
-            , Alternative { pattern: Pattern::WildCard, expression: Apply(box fail_ident, box string("Unmatched pattern in let")) } ]));
+        let can_fail = !pattern.is_failure_free();
+        let mut alternatives = vec![Alternative { pattern: self.translate_pattern(pattern), expression: result }];
+        if can_fail {
+            let fail_ident = Identifier(Id::new(Name { name: intern("fail"), uid: 0 }, function_type_(list_type(char_type()), m_b), fail_constraint));
+            alternatives.push(Alternative { pattern: Pattern::WildCard, expression: Apply(box fail_ident, box string("Pattern match failure in do expression")) });
+        }
+        let func = Lambda(var.clone(), box Case(box Identifier(var), alternatives));
         let bind = Binding { name: func_ident.clone(), expression: func };
         
         Let(vec![bind], box apply(bind_ident, (vec![expr, Identifier(func_ident)]).into_iter()))
@@ -701,7 +781,7 @@ impl <'a> Translator<'a> {
                 let index = result.len();
                 let mut name = id.name.name.to_string();
                 let base_length = name.len();
-                result.push((id, Pattern::Number(0)));//Dummy
+                result.push((id, Pattern::Number(Integer::zero())));//Dummy
                 for (i, p) in patterns.iter_mut().enumerate() {
                     let x = match *p {
                         module::Pattern::Constructor(..) | module::Pattern::Number(..) => {
@@ -736,10 +816,10 @@ impl <'a> Translator<'a> {
     }
     ///Translates a pattern list of patterns into a list of patterns which are not nested.
     ///The first argument of each tuple is the identifier that is expected to be passed to the case.
-    fn unwrap_patterns(&mut self, uid: usize, arg_ids: &[Id<Name>], arguments: &[module::Pattern<Name>]) -> Vec<(Id<Name>, Pattern<Id<Name>>)> {
+    fn unwrap_patterns(&mut self, uid: usize, arg_ids: &[Id<Name>], arguments: &[Located<module::Pattern<Name>>]) -> Vec<(Id<Name>, Pattern<Id<Name>>)> {
         let mut result = Vec::new();
         for (p, id) in arguments.iter().zip(arg_ids.iter()) {
-            self.unwrap_pattern(uid, id.clone(), p.clone(), &mut result);
+            self.unwrap_pattern(uid, id.clone(), p.node.clone(), &mut result);
         }
         result
     }
@@ -753,7 +833,7 @@ impl <'a> Translator<'a> {
         let uid = self.name_supply.next_id();
         for module::Alternative { pattern, matches, where_bindings } in alts.into_iter() {
             let bindings = where_bindings.map_or(Vec::new(), |bs| self.translate_bindings(bs));
-            vec.push((self.unwrap_patterns(uid, dummy_var, &[pattern.node]), bindings, matches));
+            vec.push((self.unwrap_patterns(uid, dummy_var, &[pattern]), bindings, matches));
         }
         let mut x = self.translate_equations_(vec);
         match x {
@@ -776,10 +856,11 @@ impl <'a> Translator<'a> {
                 arguments,
                 matches,
                 typ: module::Qualified { constraints, value: typ, },
-                where_bindings
+                where_bindings,
+                span: _
             } = bindings.pop().unwrap();
             let arg_iterator = arguments.into_iter().map(|p| {
-                match p {
+                match p.node {
                     module::Pattern::Identifier(n) => n,
                     module::Pattern::WildCard => Name { name: intern("_"), uid: usize::max_value() },
                     _ => panic!("simple_binding fail")
@@ -801,18 +882,27 @@ impl <'a> Translator<'a> {
         }
         //Generate new names for each of the arguments (since it is likely that not all arguments have a name)
         let mut arg_ids = Vec::new();
+        //The same arguments but with the type of the argument rather than the type of the lambda
+        //binding it, so that matches on the arguments know the type they match on
+        let mut matched_ids = Vec::new();
         let name;
         {
             let binding0 = &bindings[0];
             name = Id::new(binding0.name.clone(), binding0.typ.value.clone(), binding0.typ.constraints.clone());
             let mut typ = &binding0.typ.value;
             for _ in 0..binding0.arguments.len() {
-                arg_ids.push(Id::new(self.name_supply.from_str("arg"), typ.clone(), vec![]));
-                typ = match *typ {
-                    Type::Application(_, ref next) => &**next,
-                    _ => typ//We dont actually have a function type which we need, so we are likely in a unittest
-                            //just reuse the same type so we do not crash
+                let id = Id::new(self.name_supply.from_str("arg"), typ.clone(), vec![]);
+                let (arg_type, next) = match *typ {
+                    Type::Application(ref lhs, ref next) => match **lhs {
+                        Type::Application(_, ref arg) => (&**arg, &**next),
+                        _ => (typ, typ)
+                    },
+                    _ => (typ, typ)//We dont actually have a function type which we need, so we are likely in a unittest
+                                   //just reuse the same type so we do not crash
                 };
+                matched_ids.push(Id::new(id.name.clone(), arg_type.clone(), vec![]));
+                arg_ids.push(id);
+                typ = next;
             }
         }
         //First we flatten all the patterns that occur in each equation
@@ -826,7 +916,7 @@ impl <'a> Translator<'a> {
                 ..
             } = bind;
             let where_bindings_binds = where_bindings.map_or(Vec::new(), |bs| self.translate_bindings(bs));
-            (self.unwrap_patterns(uid, arg_ids.as_ref(), &*arguments), where_bindings_binds, matches)
+            (self.unwrap_patterns(uid, matched_ids.as_ref(), &*arguments), where_bindings_binds, matches)
         }).collect();
         let mut expr = self.translate_equations_(equations);
         expr = make_lambda(arg_ids.into_iter(), expr);
@@ -1066,7 +1156,7 @@ impl <'a> Translator<'a> {
     ///Tests that the binding has no patterns for its arguments
     fn simple_binding(binding: &module::Binding<Name>) -> bool {
         binding.arguments.iter().all(|arg| {
-            match *arg {
+            match arg.node {
                 module::Pattern::WildCard | module::Pattern::Identifier(..) => true,
                 _ => false
             }
@@ -1116,10 +1206,10 @@ impl <'a> Translator<'a> {
     fn string(s: &str) -> Expr<Id<Name>> {
         Literal(LiteralData { typ: list_type(char_type()), value: String(intern(s)) })
     }
-    ///Creates an expression which reports an unmatched guard error when executed
+    ///Creates an expression which throws a `PatternMatchFail` exception when executed
     fn unmatched_guard() -> Expr<Id<Name>> {
-        let error_ident = Identifier(Id::new(Name { name: intern("error"), uid: 0 }, function_type_(list_type(char_type()), Type::new_var(intern("a"))), vec![]));
-        Apply(box error_ident, box string("Unmatched guard"))
+        let error_ident = Identifier(Id::new(Name { name: intern("#pattern_match_fail"), uid: 0 }, function_type_(list_type(char_type()), Type::new_var(intern("a"))), vec![]));
+        Apply(box error_ident, box string("non-exhaustive guards"))
     }
 
 }
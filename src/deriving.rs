@@ -4,16 +4,18 @@ use core::Expr::*;
 use renamer::{name, NameSupply};
 use renamer::typ::*;
 use interner::{intern, InternedStr};
+use integer::Integer;
+use types::try_get_function;
 
-pub fn generate_deriving(instances: &mut Vec<Instance<Id<Name>>>, data: &DataDefinition<Name>) {
-    let mut gen = DerivingGen { name_supply: NameSupply::new() };
+pub fn generate_deriving(instances: &mut Vec<Instance<Id<Name>>>, data: &DataDefinition<Name>, name_supply: &mut NameSupply) {
+    let mut gen = DerivingGen { name_supply: name_supply };
     for deriving in data.deriving.iter() {
         match deriving.as_ref() {
             "Eq" => {
                 let mut bindings = Vec::new();
                 bindings.push(gen.generate_eq(data));
                 instances.push(Instance {
-                    constraints: Vec::new(),
+                    constraints: make_constraints(Vec::new(), intern("Eq"), &data.typ.value),
                     typ: data.typ.value.clone(),
                     classname: Name { name: intern("Eq"), uid: 0 },
                     bindings: bindings
@@ -25,21 +27,31 @@ pub fn generate_deriving(instances: &mut Vec<Instance<Id<Name>>>, data: &DataDef
                 debug!("Generated Ord {:?} ->>\n{:?}", data.typ, b);
                 bindings.push(b);
                 instances.push(Instance {
-                    constraints: Vec::new(),
+                    constraints: make_constraints(Vec::new(), intern("Ord"), &data.typ.value),
                     typ: data.typ.value.clone(),
                     classname: Name { name: intern("Ord"), uid: 0 },
                     bindings: bindings
                 });
             }
+            "Read" => {
+                let mut bindings = Vec::new();
+                bindings.push(gen.generate_read(data));
+                instances.push(Instance {
+                    constraints: make_constraints(Vec::new(), intern("Read"), &data.typ.value),
+                    typ: data.typ.value.clone(),
+                    classname: Name { name: intern("Read"), uid: 0 },
+                    bindings: bindings
+                });
+            }
             x => panic!("Cannot generate instance for class {:?}", x)
         }
     }
 }
 
-struct DerivingGen {
-    name_supply: NameSupply
+struct DerivingGen<'a> {
+    name_supply: &'a mut NameSupply
 }
-impl DerivingGen {
+impl <'a> DerivingGen<'a> {
     fn generate_eq(&mut self, data: &DataDefinition<Name>) -> Binding<Id<Name>> {
         self.make_binop("Eq", "==", data, &mut |this, id_l, id_r| {
             let alts = this.match_same_constructors(data, &id_r, &mut |this, l, r| this.eq_fields(l, r));
@@ -88,6 +100,85 @@ impl DerivingGen {
         }
     }
 
+    ///Generates `readsPrec` which tries each constructor in turn, reading the constructor's name
+    ///followed by its fields at precedence 11 in the same way as `showsPrec` prints them
+    fn generate_read(&mut self, data: &DataDefinition<Name>) -> Binding<Id<Name>> {
+        let reads_data = reads_type(data.typ.value.clone());
+        let mut id_prec = Id::new(self.name_supply.anonymous(), int_type(), data.typ.constraints.clone());
+        let mut id_input = Id::new(self.name_supply.anonymous(), string_type(), data.typ.constraints.clone());
+        let expr = {
+            let read_paren = id("readParen", function_type_(bool_type(), function_type_(reads_data.clone(), reads_data.clone())));
+            let mut alternatives = data.constructors.iter().map(|constructor| {
+                //Constructors without fields never need parentheses
+                let needs_paren = if constructor.arity == 0 {
+                    Identifier(id("False", bool_type()))
+                }
+                else {
+                    bool_binop(">", Identifier(id_prec.clone()), int_literal(10))
+                };
+                let reader = self.read_constructor(constructor);
+                apply(apply(apply(Identifier(read_paren.clone()), needs_paren), reader), Identifier(id_input.clone()))
+            });
+            let first = alternatives.next().unwrap();
+            alternatives.fold(first, |acc, alt| {
+                let typ = acc.get_type().clone();
+                binop("++", acc, alt, typ)
+            })
+        };
+        id_input.typ.value = reads_data.clone();
+        id_prec.typ.value = function_type_(int_type(), reads_data);
+        let lambda_expr = Lambda(id_prec, box Lambda(id_input, box expr));
+        let data_name = extract_applied_type(&data.typ.value).ctor().name;
+        let name = encode_binding_identifier(data_name.name, intern("readsPrec"));
+        let constraints = make_constraints(Vec::new(), intern("Read"), &data.typ.value);
+        Binding {
+            name: Id::new(Name { name: name, uid: 0 }, lambda_expr.get_type().clone(), constraints),
+            expression: lambda_expr
+        }
+    }
+
+    ///Creates a reader for 'constructor' which reads its name and then applies it to each field
+    ///as it is read with `readsPrec 11`
+    fn read_constructor(&mut self, constructor: &Constructor<Name>) -> Expr<Id<Name>> {
+        let ctor_type = &constructor.typ.value;
+        let ctor = self.constructor_function(constructor);
+        let reads_return = id("readsReturn", function_type_(ctor_type.clone(), reads_type(ctor_type.clone())));
+        let reads_skip = id("readsSkip", function_type_(string_type(), function_type_(reads_type(ctor_type.clone()), reads_type(ctor_type.clone()))));
+        let ctor_name = Literal(LiteralData { typ: string_type(), value: String(constructor.name.name) });
+        let mut reader = apply(apply(Identifier(reads_skip), ctor_name), apply(Identifier(reads_return), ctor));
+        let mut typ = ctor_type;
+        while let Some((field, rest)) = try_get_function(typ) {
+            let reads_prec = id("readsPrec", function_type_(int_type(), reads_type(field.clone())));
+            let reads_apply = id("readsApply", function_type_(reads_type(typ.clone()),
+                function_type_(reads_type(field.clone()), reads_type(rest.clone()))));
+            reader = apply(apply(Identifier(reads_apply), reader), apply(Identifier(reads_prec), int_literal(11)));
+            typ = rest;
+        }
+        reader
+    }
+
+    ///Returns 'constructor' as a function value. Constructors can only be compiled when they are
+    ///applied to all of their fields so a constructor with fields is wrapped in a lambda
+    fn constructor_function(&mut self, constructor: &Constructor<Name>) -> Expr<Id<Name>> {
+        let ctor_type = &constructor.typ.value;
+        let ctor = Identifier(Id::new(constructor.name, ctor_type.clone(), constructor.typ.constraints.clone()));
+        if constructor.arity == 0 {
+            return ctor
+        }
+        let args: Vec<Id<Name>> = ArgIterator { typ: ctor_type }
+            .map(|arg| Id::new(self.name_supply.anonymous(), arg.clone(), Vec::new()))
+            .collect();
+        let mut body = args.iter().fold(ctor, |f, arg| apply(f, Identifier(arg.clone())));
+        let mut typ = body.get_type().clone();
+        for arg in args.into_iter().rev() {
+            //The argument of a lambda has the type of the entire lambda
+            typ = function_type_(arg.typ.value.clone(), typ);
+            body = Lambda(Id::new(arg.name, typ.clone(), Vec::new()), box body);
+        }
+        let id = Id::new(self.name_supply.from_str("#lambda"), typ, Vec::new());
+        Let(vec![Binding { name: id.clone(), expression: body }], box Identifier(id))
+    }
+
     ///Creates a binary function binding with the name 'funcname' which is a function in an instance for 'data'
     ///This function takes two parameters of the type of 'data'
     fn make_binop(&mut self, class: &str, funcname: &str, data: &DataDefinition<Name>, func: &mut FnMut(&mut DerivingGen, Id<Name>, Id<Name>) -> Expr<Id<Name>>) -> Binding<Id<Name>> {
@@ -101,16 +192,6 @@ impl DerivingGen {
         let lambda_expr = Lambda(id_l, box Lambda(id_r, box expr));//TODO types
         let data_name = extract_applied_type(&data.typ.value).ctor().name;
         let name = encode_binding_identifier(data_name.name, intern(funcname));
-        //Create a constraint for each type parameter
-        fn make_constraints(mut result: Vec<Constraint<Name>>, class: InternedStr, typ: &Type<Name>) -> Vec<Constraint<Name>> {
-            match typ {
-                &Type::Application(ref f, ref param) => {
-                    result.push(Constraint { class: Name { name: class, uid: 0 }, variables: vec![param.var().clone()] });
-                    make_constraints(result, class, &**f)
-                }
-                _ => result
-            }
-        }
         let constraints = make_constraints(Vec::new(), intern(class), &data.typ.value);
         Binding {
             name: Id::new(Name { name: name, uid: 0 }, lambda_expr.get_type().clone(), constraints),
@@ -155,6 +236,34 @@ impl DerivingGen {
     }
 }
 
+///Create a constraint for each type parameter
+fn make_constraints(mut result: Vec<Constraint<Name>>, class: InternedStr, typ: &Type<Name>) -> Vec<Constraint<Name>> {
+    match typ {
+        &Type::Application(ref f, ref param) => {
+            result.push(Constraint { class: Name { name: class, uid: 0 }, variables: vec![param.var().clone()] });
+            make_constraints(result, class, &**f)
+        }
+        _ => result
+    }
+}
+
+///Returns the type `ReadS a` for 'typ'
+fn reads_type(typ: Type<Name>) -> Type<Name> {
+    let result = Type::new_op(name("(,)"), vec![typ, string_type()]);
+    function_type_(string_type(), list_type(result))
+}
+
+fn string_type() -> Type<Name> {
+    list_type(char_type())
+}
+
+fn int_literal(i: isize) -> Expr<Id<Name>> {
+    Literal(LiteralData { typ: int_type(), value: Integral(Integer::from_isize(i)) })
+}
+
+fn apply(func: Expr<Id<Name>>, arg: Expr<Id<Name>>) -> Expr<Id<Name>> {
+    Apply(box func, box arg)
+}
 
 fn id(s: &str, typ: Type<Name>) -> Id<Name> {
     Id::new(Name {name: intern(s), uid: 0 }, typ, Vec::new())
@@ -186,7 +295,6 @@ struct ArgIterator<'a> {
 impl <'a> Iterator for ArgIterator<'a> {
     type Item = &'a Type<Name>;
     fn next(&mut self) -> Option<&'a Type<Name>> {
-        use types::try_get_function;
         match try_get_function(self.typ) {
             Some((arg, rest)) => {
                 self.typ = rest;
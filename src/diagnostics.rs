@@ -0,0 +1,454 @@
+use std::fmt;
+use std::env;
+use std::io;
+use std::io::Write;
+use lexer::{Location, Span};
+use source::{file_name, source_line};
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Severity {
+    Error,
+    Warning
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning")
+        }
+    }
+}
+
+///A message attached to a span of the source.
+///Primary labels point at the cause of the diagnostic, secondary labels give context
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool
+}
+
+///A suggested fix for a diagnostic.
+///The `replacement` text either replaces the text covered by `span` or, if `insert` is true,
+///is inserted directly after it
+#[derive(Clone, Debug)]
+pub struct Suggestion {
+    pub span: Span,
+    pub message: String,
+    pub replacement: String,
+    pub insert: bool
+}
+
+///The format which diagnostics are reported in
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ErrorFormat {
+    ///Rendered with source snippets for a human to read
+    Human,
+    ///One JSON object per line, for tools such as editors
+    Json
+}
+
+impl ErrorFormat {
+    pub fn from_str(s: &str) -> Option<ErrorFormat> {
+        match s {
+            "human" => Some(ErrorFormat::Human),
+            "json" => Some(ErrorFormat::Json),
+            _ => None
+        }
+    }
+}
+
+///A diagnostic which can be reported to the user.
+///Each kind of diagnostic has a stable error `code` which does not change between versions.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+    pub suggestions: Vec<Suggestion>
+}
+
+impl Diagnostic {
+    pub fn error(code: &'static str, message: String) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            code: code,
+            message: message,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+            suggestions: Vec::new()
+        }
+    }
+
+    pub fn with_label(mut self, span: Span, message: String) -> Diagnostic {
+        self.labels.push(Label { span: span, message: message, primary: true });
+        self
+    }
+
+    pub fn with_secondary_label(mut self, span: Span, message: String) -> Diagnostic {
+        self.labels.push(Label { span: span, message: message, primary: false });
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Diagnostic {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn with_help(mut self, help: String) -> Diagnostic {
+        self.help.push(help);
+        self
+    }
+
+    ///Suggests inserting `text` directly after `span`
+    pub fn with_insertion(mut self, span: Span, message: String, text: String) -> Diagnostic {
+        self.suggestions.push(Suggestion { span: span, message: message, replacement: text, insert: true });
+        self
+    }
+
+    ///Returns the span of the first primary label
+    pub fn primary_span(&self) -> Option<Span> {
+        self.labels.iter()
+            .find(|label| label.primary && label.span.start != Location::eof())
+            .map(|label| label.span)
+    }
+
+    ///Renders the diagnostic along with the source lines it refers to.
+    ///If `colour` is true ANSI escape codes are used to highlight the output.
+    pub fn render(&self, colour: bool) -> String {
+        let style = Style { colour: colour };
+        let severity_colour = match self.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW
+        };
+        let mut out = String::new();
+        out.push_str(&style.paint(severity_colour, &format!("{}[{}]", self.severity, self.code)));
+        out.push_str(&style.paint(BOLD, &format!(": {}", self.message)));
+        out.push('\n');
+
+        let labels: Vec<&Label> = self.labels.iter()
+            .filter(|label| label.span.start != Location::eof())
+            .collect();
+        let gutter = labels.iter()
+            .map(|label| (label.span.start.row + 1).to_string().len())
+            .max()
+            .unwrap_or(0);
+        let blank = format!("{} |", repeat(' ', gutter));
+
+        let mut previous_file = None;
+        for label in labels.iter() {
+            if previous_file != Some(label.span.file) {
+                let arrow = if previous_file.is_none() { "-->" } else { ":::" };
+                out.push_str(&format!("{}{} {}\n", repeat(' ', gutter), style.paint(BLUE, arrow), label.span));
+                out.push_str(&style.paint(BLUE, &blank));
+                out.push('\n');
+                previous_file = Some(label.span.file);
+            }
+            let Span { file, start, end } = label.span;
+            let line = match source_line(file, start.row) {
+                Some(line) => line.replace('\t', " "),
+                None => {
+                    out.push_str(&format!("{} {}\n", style.paint(BLUE, &blank), label.message));
+                    continue
+                }
+            };
+            let line_len = line.chars().count() as isize;
+            let first = if start.column < 1 { 1 } else { start.column };
+            //Spans which cover multiple lines are underlined up to the end of their first line
+            let last = if end.row != start.row || end.column > line_len { line_len } else { end.column };
+            let width = if last < first { 1 } else { (last - first + 1) as usize };
+            let (underline, label_colour) = if label.primary { ('^', severity_colour) } else { ('-', BLUE) };
+
+            out.push_str(&style.paint(BLUE, &format!("{:>width$} |", start.row + 1, width = gutter)));
+            out.push_str(&format!(" {}\n", line));
+            out.push_str(&style.paint(BLUE, &blank));
+            out.push_str(&format!(" {}", repeat(' ', (first - 1) as usize)));
+            out.push_str(&style.paint(label_colour, &format!("{} {}", repeat(underline, width), label.message)));
+            out.push('\n');
+        }
+        for note in self.notes.iter() {
+            out.push_str(&format!("{} {} {}\n", repeat(' ', gutter), style.paint(BOLD, "= note:"), note));
+        }
+        for help in self.help.iter() {
+            out.push_str(&format!("{} {} {}\n", repeat(' ', gutter), style.paint(BOLD, "= help:"), help));
+        }
+        for suggestion in self.suggestions.iter() {
+            out.push_str(&format!("{} {} {}\n", repeat(' ', gutter), style.paint(BOLD, "= help:"), suggestion.message));
+        }
+        out
+    }
+
+    ///Renders the diagnostic as a single line JSON object.
+    ///Lines and columns start at 1 and spans include their last column.
+    ///Spans which have no location in the source are written as null
+    pub fn to_json(&self) -> String {
+        let primary = self.primary_span();
+        let labels: Vec<String> = self.labels.iter()
+            .map(|label| format!("{{\"file\":{},\"span\":{},\"message\":{},\"primary\":{}}}",
+                json_file(Some(label.span)), json_span(Some(label.span)), json_string(&label.message), label.primary))
+            .collect();
+        let suggestions: Vec<String> = self.suggestions.iter()
+            .map(|suggestion| format!("{{\"file\":{},\"span\":{},\"message\":{},\"replacement\":{},\"kind\":{}}}",
+                json_file(Some(suggestion.span)), json_span(Some(suggestion.span)), json_string(&suggestion.message),
+                json_string(&suggestion.replacement), json_string(if suggestion.insert { "insert" } else { "replace" })))
+            .collect();
+        format!("{{\"severity\":{},\"code\":{},\"message\":{},\"file\":{},\"span\":{},\"labels\":[{}],\"notes\":{},\"help\":{},\"suggestions\":[{}]}}",
+            json_string(&self.severity.to_string()),
+            json_string(self.code),
+            json_string(&self.message),
+            json_file(primary),
+            json_span(primary),
+            labels.join(","),
+            json_strings(&self.notes),
+            json_strings(&self.help),
+            suggestions.join(","))
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+///Trait for errors which can be converted into diagnostics
+pub trait ToDiagnostics {
+    fn to_diagnostics(&self) -> Vec<Diagnostic>;
+}
+
+///Writes all diagnostics of `errors`, without colour, into the formatter
+pub fn write_diagnostics<T: ToDiagnostics>(f: &mut fmt::Formatter, errors: &T) -> fmt::Result {
+    for diagnostic in errors.to_diagnostics().iter() {
+        try!(write!(f, "{}", diagnostic));
+    }
+    Ok(())
+}
+
+///Writes all diagnostics of `errors` in the JSON format, one object per line
+pub fn write_json<W: Write, T: ToDiagnostics>(out: &mut W, errors: &T) -> io::Result<()> {
+    for diagnostic in errors.to_diagnostics().iter() {
+        try!(writeln!(out, "{}", diagnostic.to_json()));
+    }
+    Ok(())
+}
+
+///Reports the diagnostics of `errors` on stderr, using colours if stderr is a terminal
+pub fn report<T: ToDiagnostics>(errors: &T) {
+    report_as(ErrorFormat::Human, errors)
+}
+
+///Reports the diagnostics of `errors` on stderr in the given format
+pub fn report_as<T: ToDiagnostics>(format: ErrorFormat, errors: &T) {
+    let stderr = io::stderr();
+    let mut stderr = stderr.lock();
+    match format {
+        ErrorFormat::Human => {
+            let colour = use_colour();
+            for diagnostic in errors.to_diagnostics().iter() {
+                let _ = write!(stderr, "{}", diagnostic.render(colour));
+            }
+        }
+        ErrorFormat::Json => { let _ = write_json(&mut stderr, errors); }
+    }
+}
+
+///Returns true if diagnostics written to stderr should use colours
+pub fn use_colour() -> bool {
+    env::var_os("NO_COLOR").is_none()
+        && env::var("TERM").map(|term| term != "dumb").unwrap_or(true)
+        && stderr_is_tty()
+}
+
+#[cfg(unix)]
+fn stderr_is_tty() -> bool {
+    extern "C" {
+        fn isatty(fd: i32) -> i32;
+    }
+    unsafe { isatty(2) != 0 }
+}
+
+#[cfg(not(unix))]
+fn stderr_is_tty() -> bool {
+    false
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_strings(strings: &[String]) -> String {
+    let strings: Vec<String> = strings.iter()
+        .map(|s| json_string(s))
+        .collect();
+    format!("[{}]", strings.join(","))
+}
+
+fn json_file(span: Option<Span>) -> String {
+    match span {
+        Some(span) if span.start != Location::eof() => json_string(&file_name(span.file)),
+        _ => "null".to_string()
+    }
+}
+
+fn json_span(span: Option<Span>) -> String {
+    match span {
+        Some(Span { start, end, .. }) if start != Location::eof() => {
+            let end = if end == Location::eof() { start } else { end };
+            format!("{{\"start\":{{\"line\":{},\"column\":{}}},\"end\":{{\"line\":{},\"column\":{}}}}}",
+                start.row + 1, start.column, end.row + 1, end.column)
+        }
+        _ => "null".to_string()
+    }
+}
+
+static RED: &'static str = "\x1b[1;31m";
+static YELLOW: &'static str = "\x1b[1;33m";
+static BLUE: &'static str = "\x1b[1;34m";
+static BOLD: &'static str = "\x1b[1m";
+static RESET: &'static str = "\x1b[0m";
+
+struct Style {
+    colour: bool
+}
+
+impl Style {
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.colour {
+            format!("{}{}{}", code, text, RESET)
+        }
+        else {
+            text.to_string()
+        }
+    }
+}
+
+fn repeat(c: char, n: usize) -> String {
+    ::std::iter::repeat(c).take(n).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::path::{Path, PathBuf};
+    use lexer::{Location, Span};
+    use source::add_file;
+    use vm::{VMError, compile_file, execute_main_module};
+    use diagnostics::{Diagnostic, write_json};
+
+    fn location(row: isize, column: isize) -> Location {
+        Location { row: row, column: column, absolute: -1 }
+    }
+
+    #[test]
+    fn render_snippet() {
+        let file = add_file("Render.hs", "main = 1\ntest = primIntAdd 1 []\n");
+        let diagnostic = Diagnostic::error("E9999", "Test message".to_string())
+            .with_label(Span::new(file, location(1, 21), location(1, 22)), "found here".to_string())
+            .with_secondary_label(Span::new(file, location(0, 1), location(0, 4)), "context".to_string())
+            .with_note("a note".to_string())
+            .with_help("some help".to_string());
+        let expected =
+r"error[E9999]: Test message
+ --> Render.hs:2:21-22
+  |
+2 | test = primIntAdd 1 []
+  |                     ^^ found here
+1 | main = 1
+  | ---- context
+  = note: a note
+  = help: some help
+";
+        assert_eq!(diagnostic.render(false), expected);
+    }
+
+    #[test]
+    fn render_colour() {
+        let diagnostic = Diagnostic::error("E9999", "Test message".to_string());
+        assert_eq!(diagnostic.render(true), "\x1b[1;31merror[E9999]\x1b[0m\x1b[1m: Test message\x1b[0m\n");
+    }
+
+    #[test]
+    fn json_escapes() {
+        let file = add_file("Json.hs", "main = 1\n");
+        let diagnostic = Diagnostic::error("E9999", "a \"quoted\"\tmessage\n".to_string())
+            .with_label(Span::new(file, location(0, 1), location(0, 4)), "label".to_string())
+            .with_insertion(Span::new(file, location(0, 6), location(0, 6)), "insert".to_string(), ")".to_string());
+        let expected = concat!(
+            r#"{"severity":"error","code":"E9999","message":"a \"quoted\"\tmessage\n","#,
+            r#""file":"Json.hs","span":{"start":{"line":1,"column":1},"end":{"line":1,"column":4}},"#,
+            r#""labels":[{"file":"Json.hs","span":{"start":{"line":1,"column":1},"end":{"line":1,"column":4}},"message":"label","primary":true}],"#,
+            r#""notes":[],"help":[],"#,
+            r#""suggestions":[{"file":"Json.hs","span":{"start":{"line":1,"column":6},"end":{"line":1,"column":6}},"message":"insert","replacement":")","kind":"insert"}]}"#);
+        assert_eq!(diagnostic.to_json(), expected);
+    }
+
+    ///Compares the JSON diagnostics of the error which `run` returns for each file in `directory` with the
+    ///.json file next to it. Setting UPDATE_GOLDEN regenerates the expected output
+    fn check_json_golden<F>(directory: &str, run: F)
+        where F: Fn(&Path) -> VMError {
+        let mut inputs: Vec<PathBuf> = fs::read_dir(directory).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().map_or(false, |ext| ext == "hs"))
+            .collect();
+        inputs.sort();
+        assert!(inputs.len() != 0);
+        for input in inputs.iter() {
+            let err = run(input);
+            let mut actual = Vec::new();
+            write_json(&mut actual, &err).unwrap();
+            let actual = String::from_utf8(actual).unwrap();
+            let golden = input.with_extension("json");
+            if env::var_os("UPDATE_GOLDEN").is_some() {
+                File::create(&golden).and_then(|mut f| f.write_all(actual.as_bytes())).unwrap();
+            }
+            else {
+                let mut expected = String::new();
+                File::open(&golden).and_then(|mut f| f.read_to_string(&mut expected))
+                    .unwrap_or_else(|err| panic!("{}: {}", golden.display(), err));
+                assert!(actual == expected, "{}\nExpected:\n{}\nActual:\n{}", input.display(), expected, actual);
+            }
+        }
+    }
+
+    ///Compiles each file in tests/diagnostics and compares its errors with the golden file
+    #[test]
+    fn json_golden() {
+        check_json_golden("tests/diagnostics", |input| match compile_file(input.to_str().unwrap()) {
+            Ok(_) => panic!("Expected {} to fail to compile", input.display()),
+            Err(err) => err
+        });
+    }
+
+    ///Runs the `main` function of each program in tests/diagnostics/runtime and compares the uncaught
+    ///exception with the golden file
+    #[test]
+    fn json_runtime_golden() {
+        check_json_golden("tests/diagnostics/runtime", |input| match execute_main_module(input.with_extension("").to_str().unwrap()) {
+            Ok(result) => panic!("Expected {} to fail at runtime, got {:?}", input.display(), result),
+            Err(err) => err
+        });
+    }
+}
@@ -7,15 +7,17 @@ pub struct PrecedenceVisitor { precedence: HashMap<Name, (isize, Assoc)>  }
 
 impl MutVisitor<Name> for PrecedenceVisitor {
     fn visit_expr(&mut self, expr: &mut TypedExpr<Name>) {
-        walk_expr_mut(self, expr);
         match expr.expr {
             Expr::OpApply(..) => {
+                //The whole chain of operators must be rewritten before its operands are visited,
+                //otherwise the rewritten right operand would be seen as a single operand
                 let mut temp = TypedExpr::new(Expr::Identifier(Name { uid: usize::max_value(), name: intern("") }));
                 ::std::mem::swap(&mut temp, expr);
                 temp = self.rewrite(box temp);
                 ::std::mem::swap(&mut temp, expr);
+                self.visit_operands(expr);
             }
-            _ => ()
+            _ => walk_expr_mut(self, expr)
         }
     }
     fn visit_module(&mut self, module: &mut Module<Name>) {
@@ -35,6 +37,17 @@ impl PrecedenceVisitor {
         PrecedenceVisitor { precedence: map }
     }
 
+    ///Visits the operands of an operator chain which has already been rewritten
+    fn visit_operands(&mut self, expr: &mut TypedExpr<Name>) {
+        match expr.expr {
+            Expr::OpApply(ref mut lhs, _, ref mut rhs) => {
+                self.visit_operands(&mut **lhs);
+                self.visit_operands(&mut **rhs);
+            }
+            _ => self.visit_expr(expr)
+        }
+    }
+
     fn get_precedence(&self, name: &Name) -> (isize, Assoc) {
         self.precedence.get(name)
             .map(|x| *x)
@@ -50,14 +63,14 @@ impl PrecedenceVisitor {
             let op = op_stack.pop().unwrap();
             let rhs = expr_stack.pop().unwrap();
             let lhs = expr_stack.pop().unwrap();
-            let loc = lhs.location;
-            expr_stack.push(box TypedExpr::with_location(Expr::OpApply(lhs, op, rhs), loc));
+            let span = lhs.span.to(rhs.span);
+            expr_stack.push(box TypedExpr::with_span(Expr::OpApply(lhs, op, rhs), span));
         }
         let mut expr_stack = Vec::new();
         let mut op_stack = Vec::new();
         loop {
             //FIXME should destructure instead of clone
-            let TypedExpr { typ, location, expr } = (*input).clone();
+            let TypedExpr { typ, span, expr } = (*input).clone();
             match expr {
                 Expr::OpApply(l, op, r) => {
                     expr_stack.push(l);
@@ -96,12 +109,13 @@ impl PrecedenceVisitor {
                     }
                 }
                 rhs => {
-                    let mut result = TypedExpr { typ: typ, location: location, expr: rhs };
+                    let mut result = TypedExpr { typ: typ, span: span, expr: rhs };
                     while op_stack.len() != 0 {
                         assert!(expr_stack.len() >= 1);
                         let lhs = expr_stack.pop().unwrap();
                         let op = op_stack.pop().unwrap();
-                        result = TypedExpr::with_location(Expr::OpApply(lhs, op, box result), location);
+                        let span = lhs.span.to(result.span);
+                        result = TypedExpr::with_span(Expr::OpApply(lhs, op, box result), span);
                     }
                     return result;
                 }
@@ -151,6 +165,44 @@ test = 3 * 4 * (5 - 6)").unwrap();
             intern("*"),
             paren(op_apply(number(5), intern("-"), number(6)))))));
     }
+    #[test]
+    fn operator_precedence_long_chain()
+    {
+        let m = parse_string(
+r"import Prelude
+test = 1 + 2 * 3 + 4 * 5 + 6").unwrap();
+        let mut modules = rename_modules(m);
+        let mut v = PrecedenceVisitor::new();
+        for module in modules.iter_mut() {
+            v.visit_module(module);
+        }
+        assert_eq!(modules.last().unwrap().bindings[0].matches, Match::Simple(rename_expr(op_apply(
+            op_apply(
+                op_apply(number(1), intern("+"), op_apply(number(2), intern("*"), number(3))),
+                intern("+"),
+                op_apply(number(4), intern("*"), number(5))),
+            intern("+"),
+            number(6)))));
+    }
+
+    #[test]
+    fn operator_precedence_class_default()
+    {
+        let m = parse_string(
+r"import Prelude
+class Test a where
+    test :: a -> Int
+    test _ = 3 * 4 - 5 * 6").unwrap();
+        let mut modules = rename_modules(m);
+        let mut v = PrecedenceVisitor::new();
+        for module in modules.iter_mut() {
+            v.visit_module(module);
+        }
+        assert_eq!(modules.last().unwrap().classes[0].bindings[0].matches, Match::Simple(rename_expr(op_apply(
+            op_apply(number(3), intern("*"), number(4)),
+            intern("-"),
+            op_apply(number(5), intern("*"), number(6))))));
+    }
 
     #[test]
     fn rewrite_operators() {
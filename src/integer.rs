@@ -0,0 +1,514 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Sub, Mul, Neg};
+use std::str::FromStr;
+
+///An arbitrary precision integer which is used to implement Haskell's `Integer` type
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Integer {
+    negative: bool,
+    ///The magnitude of the integer in base 2^32, least significant digit first.
+    ///Never has any trailing zeroes so that zero is represented by an empty vector
+    digits: Vec<u32>
+}
+
+const BASE: u64 = 1 << 32;
+///The largest power of 10 which fits in a digit, used when converting to and from strings
+const DECIMAL_BASE: u32 = 1_000_000_000;
+const DECIMAL_DIGITS: usize = 9;
+
+impl Integer {
+    pub fn zero() -> Integer {
+        Integer { negative: false, digits: Vec::new() }
+    }
+
+    pub fn from_isize(value: isize) -> Integer {
+        let mut result = Integer::from_u64((value as i64).wrapping_abs() as u64);
+        result.negative = value < 0;
+        result
+    }
+
+    ///Creates an integer from the bits of `value` interpreted as an unsigned number
+    pub fn from_unsigned(value: isize) -> Integer {
+        Integer::from_u64(value as u64)
+    }
+
+    fn from_u64(value: u64) -> Integer {
+        Integer::from_digits(false, vec![value as u32, (value >> 32) as u32])
+    }
+
+    fn from_digits(negative: bool, mut digits: Vec<u32>) -> Integer {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Integer { negative: negative && digits.len() != 0, digits: digits }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.len() == 0
+    }
+
+    ///Returns the value if it fits in an `isize`
+    pub fn to_isize(&self) -> Option<isize> {
+        let value = self.to_isize_wrapping();
+        if *self == Integer::from_isize(value) { Some(value) } else { None }
+    }
+
+    ///Returns the lowest 64 bits of the two's complement representation,
+    ///the conversion done by `fromInteger` for the fixed size integer types
+    pub fn to_isize_wrapping(&self) -> isize {
+        let low = self.digits.get(0).cloned().unwrap_or(0) as u64;
+        let high = self.digits.get(1).cloned().unwrap_or(0) as u64;
+        let magnitude = low | (high << 32);
+        (if self.negative { magnitude.wrapping_neg() } else { magnitude }) as isize
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        Integer::ratio_to_f64(self, &Integer::from_isize(1))
+    }
+
+    ///Converts `numerator / denominator` to the nearest double
+    pub fn ratio_to_f64(numerator: &Integer, denominator: &Integer) -> f64 {
+        if denominator.is_zero() {
+            return if numerator.is_zero() { ::std::f64::NAN }
+                   else if numerator.negative { ::std::f64::NEG_INFINITY }
+                   else { ::std::f64::INFINITY };
+        }
+        if numerator.is_zero() {
+            return 0.;
+        }
+        //Scale the fraction so that the quotient has 62 or 63 bits which leaves enough bits below
+        //the 53 of the mantissa for the conversion from u64 to round correctly
+        let shift = 62 + denominator.bit_length() as isize - numerator.bit_length() as isize;
+        let (n, d) = if shift >= 0 {
+            (numerator.abs().shift_left(shift as usize), denominator.abs())
+        }
+        else {
+            (numerator.abs(), denominator.abs().shift_left((-shift) as usize))
+        };
+        let (quotient, remainder) = divide(&n.digits, &d.digits);
+        let mut bits = Integer::from_digits(false, quotient).to_isize_wrapping() as u64;
+        if remainder.iter().any(|&digit| digit != 0) {
+            //Sticky bit so that halfway cases are not rounded as if they were exact
+            bits |= 1;
+        }
+        let mut value = bits as f64;
+        let mut exponent = -shift;
+        while exponent > 1000 {
+            value *= 2f64.powi(1000);
+            exponent -= 1000;
+        }
+        while exponent < -1000 {
+            value *= 2f64.powi(-1000);
+            exponent += 1000;
+        }
+        value *= 2f64.powi(exponent as i32);
+        if numerator.negative != denominator.negative { -value } else { value }
+    }
+
+    ///Converts a double to an integer, truncating any fraction.
+    ///Returns `None` for infinite and NaN values
+    pub fn from_f64(value: f64) -> Option<Integer> {
+        if !value.is_finite() {
+            return None;
+        }
+        let mut magnitude = value.abs().trunc();
+        let mut digits = Vec::new();
+        while magnitude >= 1. {
+            digits.push((magnitude % BASE as f64) as u32);
+            magnitude = (magnitude / BASE as f64).trunc();
+        }
+        Some(Integer::from_digits(value < 0., digits))
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(&self) -> Integer {
+        Integer { negative: false, digits: self.digits.clone() }
+    }
+
+    ///The number of bits needed to represent the magnitude
+    pub fn bit_length(&self) -> usize {
+        match self.digits.last() {
+            Some(&high) => self.digits.len() * 32 - high.leading_zeros() as usize,
+            None => 0
+        }
+    }
+
+    ///Multiplies the integer by `2^bits`
+    pub fn shift_left(&self, bits: usize) -> Integer {
+        let mut digits = vec![0; bits / 32];
+        digits.extend(shift_left(&self.digits, (bits % 32) as u32));
+        Integer::from_digits(self.negative, digits)
+    }
+
+    ///The greatest common divisor which is always non-negative
+    pub fn gcd(&self, other: &Integer) -> Integer {
+        let mut a = self.abs();
+        let mut b = other.abs();
+        while !b.is_zero() {
+            let (_, remainder) = a.quot_rem(&b).unwrap();
+            a = b;
+            b = remainder;
+        }
+        a
+    }
+
+    ///Division truncated towards zero, returns `None` if `divisor` is zero
+    pub fn quot_rem(&self, divisor: &Integer) -> Option<(Integer, Integer)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = divide(&self.digits, &divisor.digits);
+        Some((Integer::from_digits(self.negative != divisor.negative, quotient),
+              Integer::from_digits(self.negative, remainder)))
+    }
+
+    ///Division truncated towards negative infinity, returns `None` if `divisor` is zero
+    pub fn div_mod(&self, divisor: &Integer) -> Option<(Integer, Integer)> {
+        self.quot_rem(divisor).map(|(quotient, remainder)| {
+            if !remainder.is_zero() && remainder.negative != divisor.negative {
+                (&quotient - &Integer::from_isize(1), &remainder + divisor)
+            }
+            else {
+                (quotient, remainder)
+            }
+        })
+    }
+}
+
+fn compare_digits(l: &[u32], r: &[u32]) -> Ordering {
+    l.len().cmp(&r.len())
+        .then_with(|| l.iter().rev().cmp(r.iter().rev()))
+}
+
+fn add_digits(l: &[u32], r: &[u32]) -> Vec<u32> {
+    let (long, short) = if l.len() >= r.len() { (l, r) } else { (r, l) };
+    let mut result = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0;
+    for (i, &digit) in long.iter().enumerate() {
+        let sum = digit as u64 + short.get(i).cloned().unwrap_or(0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    result.push(carry as u32);
+    result
+}
+
+///Subtracts `r` from `l`, `l` must be larger than or equal to `r`
+fn sub_digits(l: &[u32], r: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(l.len());
+    let mut borrow = 0;
+    for (i, &digit) in l.iter().enumerate() {
+        let difference = digit as i64 - r.get(i).cloned().unwrap_or(0) as i64 - borrow;
+        result.push(difference as u32);
+        borrow = if difference < 0 { 1 } else { 0 };
+    }
+    result
+}
+
+fn mul_digits(l: &[u32], r: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; l.len() + r.len()];
+    for (i, &x) in l.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in r.iter().enumerate() {
+            let product = x as u64 * y as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + r.len()] = carry as u32;
+    }
+    result
+}
+
+///Divides `dividend` by a single digit, returning the quotient and remainder
+fn divide_digit(dividend: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder = 0u64;
+    for i in (0..dividend.len()).rev() {
+        let current = (remainder << 32) | dividend[i] as u64;
+        quotient[i] = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    (quotient, remainder as u32)
+}
+
+///Long division of two magnitudes (Knuth's algorithm D), `divisor` must not be zero
+fn divide(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if compare_digits(dividend, divisor) == Ordering::Less {
+        return (Vec::new(), dividend.to_vec());
+    }
+    if divisor.len() == 1 {
+        let (quotient, remainder) = divide_digit(dividend, divisor[0]);
+        return (quotient, vec![remainder]);
+    }
+    //Normalize so that the most significant digit of the divisor has its highest bit set
+    let n = divisor.len();
+    let m = dividend.len() - n;
+    let shift = divisor[n - 1].leading_zeros();
+    let v = shift_left(divisor, shift);
+    let mut u = shift_left(dividend, shift);
+    u.resize(dividend.len() + 1, 0);
+
+    let mut quotient = vec![0u32; m + 1];
+    for j in (0..(m + 1)).rev() {
+        let numerator = ((u[j + n] as u64) << 32) | u[j + n - 1] as u64;
+        let mut estimate = numerator / v[n - 1] as u64;
+        let mut rest = numerator % v[n - 1] as u64;
+        while estimate >= BASE || estimate * v[n - 2] as u64 > ((rest << 32) | u[j + n - 2] as u64) {
+            estimate -= 1;
+            rest += v[n - 1] as u64;
+            if rest >= BASE {
+                break;
+            }
+        }
+        //Multiply and subtract the estimate times the divisor from the current part of the dividend
+        let mut borrow = 0i64;
+        for i in 0..n {
+            let product = estimate * v[i] as u64;
+            let t = u[i + j] as i64 - borrow - (product & 0xFFFF_FFFF) as i64;
+            u[i + j] = t as u32;
+            borrow = (product >> 32) as i64 - (t >> 32);
+        }
+        let t = u[j + n] as i64 - borrow;
+        u[j + n] = t as u32;
+        if t < 0 {
+            //The estimate was one too large so add the divisor back
+            estimate -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                u[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+        quotient[j] = estimate as u32;
+    }
+    u.truncate(n);
+    (quotient, shift_right(&u, shift))
+}
+
+fn shift_left(digits: &[u32], shift: u32) -> Vec<u32> {
+    if shift == 0 {
+        return digits.to_vec();
+    }
+    let mut result = Vec::with_capacity(digits.len() + 1);
+    let mut carry = 0;
+    for &digit in digits.iter() {
+        result.push((digit << shift) | carry);
+        carry = digit >> (32 - shift);
+    }
+    if carry != 0 {
+        result.push(carry);
+    }
+    result
+}
+
+fn shift_right(digits: &[u32], shift: u32) -> Vec<u32> {
+    if shift == 0 {
+        return digits.to_vec();
+    }
+    let mut result = vec![0u32; digits.len()];
+    for i in 0..digits.len() {
+        let high = digits.get(i + 1).map_or(0, |&digit| digit << (32 - shift));
+        result[i] = (digits[i] >> shift) | high;
+    }
+    result
+}
+
+impl Ord for Integer {
+    fn cmp(&self, other: &Integer) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_digits(&self.digits, &other.digits),
+            (true, true) => compare_digits(&other.digits, &self.digits)
+        }
+    }
+}
+
+impl PartialOrd for Integer {
+    fn partial_cmp(&self, other: &Integer) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl <'a> Add for &'a Integer {
+    type Output = Integer;
+    fn add(self, other: &'a Integer) -> Integer {
+        if self.negative == other.negative {
+            return Integer::from_digits(self.negative, add_digits(&self.digits, &other.digits));
+        }
+        match compare_digits(&self.digits, &other.digits) {
+            Ordering::Less => Integer::from_digits(other.negative, sub_digits(&other.digits, &self.digits)),
+            _ => Integer::from_digits(self.negative, sub_digits(&self.digits, &other.digits))
+        }
+    }
+}
+
+impl <'a> Sub for &'a Integer {
+    type Output = Integer;
+    fn sub(self, other: &'a Integer) -> Integer {
+        self + &-other
+    }
+}
+
+impl <'a> Mul for &'a Integer {
+    type Output = Integer;
+    fn mul(self, other: &'a Integer) -> Integer {
+        Integer::from_digits(self.negative != other.negative, mul_digits(&self.digits, &other.digits))
+    }
+}
+
+impl <'a> Neg for &'a Integer {
+    type Output = Integer;
+    fn neg(self) -> Integer {
+        Integer::from_digits(!self.negative, self.digits.clone())
+    }
+}
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        //Split the magnitude into groups of decimal digits, least significant first
+        let mut groups = Vec::new();
+        let mut digits = self.digits.clone();
+        while digits.len() != 0 {
+            let (quotient, remainder) = divide_digit(&digits, DECIMAL_BASE);
+            groups.push(remainder);
+            digits = Integer::from_digits(false, quotient).digits;
+        }
+        if self.negative {
+            try!(write!(f, "-"));
+        }
+        try!(write!(f, "{}", groups[groups.len() - 1]));
+        for group in groups.iter().rev().skip(1) {
+            try!(write!(f, "{:01$}", group, DECIMAL_DIGITS));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseIntegerError;
+
+impl FromStr for Integer {
+    type Err = ParseIntegerError;
+    ///Parses an integer written in decimal with an optional leading '-'
+    fn from_str(s: &str) -> Result<Integer, ParseIntegerError> {
+        let (negative, s) = if s.starts_with("-") { (true, &s[1..]) } else { (false, s) };
+        if s.len() == 0 || !s.chars().all(|c| c.is_digit(10)) {
+            return Err(ParseIntegerError);
+        }
+        let mut digits = Vec::new();
+        let first = s.len() % DECIMAL_DIGITS;
+        let mut start = 0;
+        let mut end = if first == 0 { DECIMAL_DIGITS } else { first };
+        while start < s.len() {
+            let group: u32 = s[start..end].parse().unwrap();
+            let mut carry = group as u64;
+            for digit in digits.iter_mut() {
+                let value = *digit as u64 * DECIMAL_BASE as u64 + carry;
+                *digit = value as u32;
+                carry = value >> 32;
+            }
+            if carry != 0 {
+                digits.push(carry as u32);
+            }
+            start = end;
+            end += DECIMAL_DIGITS;
+        }
+        Ok(Integer::from_digits(negative, digits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use integer::Integer;
+
+    fn int(s: &str) -> Integer {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        for s in ["0", "1", "-1", "4294967296", "123456789012345678901234567890", "-1000000000000000000"].iter() {
+            assert_eq!(int(s).to_string(), *s);
+        }
+        assert_eq!(int("0000012"), Integer::from_isize(12));
+        assert_eq!(int("-0"), Integer::zero());
+        assert!("12a".parse::<Integer>().is_err());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let big = int("123456789012345678901234567890");
+        assert_eq!((&big + &big).to_string(), "246913578024691357802469135780");
+        assert_eq!((&big - &int("123456789012345678901234567891")).to_string(), "-1");
+        assert_eq!((&big * &int("-987654321098765432109876543210")).to_string(),
+                   "-121932631137021795226185032733622923332237463801111263526900");
+        assert_eq!((&Integer::from_isize(isize::max_value()) + &Integer::from_isize(1)).to_string(), "9223372036854775808");
+        assert_eq!(Integer::from_isize(isize::min_value()).to_string(), "-9223372036854775808");
+    }
+
+    #[test]
+    fn division() {
+        let dividend = int("121932631137021795226185032733622923332237463801111263526907");
+        let divisor = int("987654321098765432109876543210");
+        let (q, r) = dividend.quot_rem(&divisor).unwrap();
+        assert_eq!(q.to_string(), "123456789012345678901234567890");
+        assert_eq!(r.to_string(), "7");
+        let (q, r) = (-&dividend).quot_rem(&divisor).unwrap();
+        assert_eq!((q.to_string(), r.to_string()), ("-123456789012345678901234567890".to_string(), "-7".to_string()));
+        let (q, r) = (-&dividend).div_mod(&divisor).unwrap();
+        assert_eq!((q.to_string(), r.to_string()),
+                   ("-123456789012345678901234567891".to_string(), "987654321098765432109876543203".to_string()));
+        assert_eq!(Integer::from_isize(-7).div_mod(&Integer::from_isize(2)), Some((Integer::from_isize(-4), Integer::from_isize(1))));
+        assert_eq!(dividend.quot_rem(&Integer::zero()), None);
+        //Divisors where the first estimated quotient digit is too large
+        let a = int("340282366920938463463374607431768211455");
+        let b = int("18446744073709551617");
+        let (q, r) = a.quot_rem(&b).unwrap();
+        assert_eq!(&(&q * &b) + &r, a);
+        assert!(r < b);
+    }
+
+    #[test]
+    fn conversions() {
+        let big = int("18446744073709551617");
+        assert_eq!(big.to_isize(), None);
+        assert_eq!(big.to_isize_wrapping(), 1);
+        assert_eq!(int("-9223372036854775809").to_isize_wrapping(), isize::max_value());
+        assert_eq!(Integer::from_unsigned(-1).to_string(), "18446744073709551615");
+        assert_eq!(int("-42").to_isize(), Some(-42));
+        assert_eq!(big.to_f64(), 18446744073709551617.);
+        assert_eq!(Integer::from_f64(-1e20).map(|i| i.to_string()), Some("-100000000000000000000".to_string()));
+        assert_eq!(Integer::from_f64(2.9), Some(Integer::from_isize(2)));
+        //2^53 + 1 is halfway between two doubles and rounds to even
+        assert_eq!(int("9007199254740993").to_f64(), 9007199254740992.);
+        assert_eq!(int("9007199254740995").to_f64(), 9007199254740996.);
+    }
+
+    #[test]
+    fn ratios() {
+        assert_eq!(Integer::ratio_to_f64(&int("1"), &int("10")), 0.1);
+        assert_eq!(Integer::ratio_to_f64(&int("-2"), &int("3")), -2. / 3.);
+        assert_eq!(Integer::ratio_to_f64(&int("1"), &int("0")), ::std::f64::INFINITY);
+        let huge = int("1").shift_left(1100);
+        assert_eq!(huge.bit_length(), 1101);
+        assert_eq!(Integer::ratio_to_f64(&int("3"), &huge), 3. * 2f64.powi(-1000) * 2f64.powi(-100));
+        assert_eq!(int("-12").gcd(&int("18")), int("6"));
+        assert_eq!(int("0").gcd(&int("-5")), int("5"));
+    }
+}
@@ -71,6 +71,6 @@ impl AsRef<str> for InternedStr {
 
 impl fmt::Display for InternedStr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self.as_ref())
+        write!(f, "{}", self.as_ref())
     }
 }
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::collections::hash_map::Entry;
 use core::*;
 use core::Expr::*;
+use integer::Integer;
 use renamer::{name, NameSupply};
 use renamer::typ::*;
 
@@ -12,7 +13,9 @@ pub fn do_lambda_lift(module: Module<TypeAndStr>) -> Module<Id> {
 }
 
 struct FreeVariables {
-    name_supply: NameSupply
+    name_supply: NameSupply,
+    ///The constraints of the top level binding which is being abstracted
+    constraints: Vec<Constraint<Name>>
 }
 
 fn each_pattern_variables(pattern: &Pattern<Id>, f: &mut FnMut(&Name)) {
@@ -71,6 +74,13 @@ fn free_variables(&mut self, variables: &mut HashMap<Name, isize>, free_vars: &m
                     free_vars.insert(k.clone(), v.clone());
                 }
                 self.abstract_(&free_vars2, &mut bind.expression);
+                //A lambda without free variables is lifted as is but it may still need the dictionary
+                //of the enclosing binding
+                if free_vars2.len() == 0 && bind.name.typ.constraints.len() == 0 {
+                    if let Lambda(..) = bind.expression {
+                        bind.name.typ.constraints = self.used_constraints(&bind.name.typ.value);
+                    }
+                }
             }
             self.free_variables(variables, free_vars, &mut **expr);
             for bind in bindings.iter() {
@@ -100,28 +110,50 @@ fn free_variables(&mut self, variables: &mut HashMap<Name, isize>, free_vars: &m
 ///Adds the free variables, if any, to the expression
 fn abstract_(&mut self, free_vars: &HashMap<Name, TypeAndStr>, input_expr: &mut Expr<TypeAndStr>) {
     if free_vars.len() != 0 {
-        let mut temp = Literal(LiteralData { typ: Type::new_var(self.name_supply.from_str("a").name), value: Integral(0) });
+        let mut temp = Literal(LiteralData { typ: Type::new_var(self.name_supply.from_str("a").name), value: Integral(Integer::zero()) });
         ::std::mem::swap(&mut temp, input_expr);
+        //The first free variable is the first argument so the lambdas are added starting with the last one
+        let vars: Vec<&TypeAndStr> = free_vars.values().collect();
         let mut e = {
             let mut rhs = temp;
             let mut typ = rhs.get_type().clone();
-            for (_, var) in free_vars.iter() {
-                rhs = Lambda(var.clone(), box rhs);
+            for var in vars.iter().rev() {
+                //The argument of a lambda has the type of the entire lambda
                 typ = function_type_(var.get_type().clone(), typ);
+                rhs = Lambda(Id::new(var.name.clone(), typ.clone(), Vec::new()), box rhs);
             }
-            let id = Id::new(self.name_supply.from_str("#sc"), typ.clone(), Vec::new());
+            //A lifted function which uses the type variables of the enclosing binding needs that binding's
+            //dictionary to call any overloaded functions
+            let constraints = self.used_constraints(&typ);
+            let id = Id::new(self.name_supply.from_str("#sc"), typ.clone(), constraints);
             let bind = Binding {
                 name: id.clone(),
                 expression: rhs
             };
             Let(vec![bind], box Identifier(id))
         };
-        for (_, var) in free_vars.iter() {
-            e = Apply(box e, box Identifier(var.clone()));
+        for var in vars.iter() {
+            e = Apply(box e, box Identifier((*var).clone()));
         }
         *input_expr = e
     }
 }
+
+///Returns the constraints of the enclosing binding which mention a type variable in `typ`
+fn used_constraints(&self, typ: &Type<Name>) -> Vec<Constraint<Name>> {
+    self.constraints.iter()
+        .filter(|constraint| occurs(&constraint.variables[0], typ))
+        .cloned()
+        .collect()
+}
+}
+
+fn occurs(var: &TypeVariable, typ: &Type<Name>) -> bool {
+    match *typ {
+        Type::Variable(ref v) | Type::Generic(ref v) => v == var,
+        Type::Application(ref lhs, ref rhs) => occurs(var, &**lhs) || occurs(var, &**rhs),
+        _ => false
+    }
 }
 
 ///Lifts all lambdas in the module to the top level of the program
@@ -192,10 +224,11 @@ pub fn abstract_module(mut module: Module<TypeAndStr>) -> Module<TypeAndStr> {
         fn visit_binding(&mut self, bind: &mut Binding<TypeAndStr>) {
             let mut variables = HashMap::new();
             let mut free_vars = HashMap::new();
+            self.constraints = bind.name.typ.constraints.clone();
             self.free_variables(&mut variables, &mut free_vars, &mut bind.expression);
         }
     }
-    let mut this = FreeVariables { name_supply: NameSupply::new() };
+    let mut this = FreeVariables { name_supply: NameSupply::new(), constraints: Vec::new() };
     this.visit_module(&mut module);
     module
 }
@@ -281,6 +314,8 @@ test2 x =
                 let mut args = Vec::new();
                 match get_let(&bind.expression, &mut args) {
                     &Let(ref binds, ref body) => {
+                        //The arguments were found starting from the last one
+                        args.reverse();
                         //Push the argument of the function itself
                         args.push(intern("x"));
                         assert!(check_args(&binds[0].expression, args.as_ref()));
@@ -294,6 +329,7 @@ test2 x =
                 let mut args = Vec::new();
                 match get_let(&bind.expression, &mut args) {
                     &Let(ref binds, ref body) => {
+                        args.reverse();
                         args.push(intern("y"));
                         assert!(check_args(&binds[0].expression, args.as_ref()));
                         assert_eq!(Identifier(binds[0].name.clone()), **body);
@@ -4,6 +4,7 @@ use std::iter::Peekable;
 use std::rc::Rc;
 use std::cell::RefCell;
 use interner::*;
+use source::{FileId, file_name};
 
 use self::TokenEnum::*;
 
@@ -42,6 +43,8 @@ pub enum TokenEnum {
 	TYPEDECL,
 	DATA,
     NEWTYPE,
+    TYPE,
+    DEFAULT,
     LAMBDA,
     DO,
     IMPORT,
@@ -54,7 +57,7 @@ pub enum TokenEnum {
     ELSE
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub struct Location {
     pub column : isize,
     pub row : isize,
@@ -66,9 +69,49 @@ impl Location {
         Location { column: -1, row: -1, absolute: -1 }
     }
 }
+
+///A range in a source file, `start` and `end` are the locations of the first and last character
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct Span {
+    pub file : FileId,
+    pub start : Location,
+    pub end : Location
+}
+
+impl Span {
+    pub fn new(file: FileId, start: Location, end: Location) -> Span {
+        Span { file: file, start: start, end: end }
+    }
+    pub fn eof() -> Span {
+        Span::new(FileId::default(), Location::eof(), Location::eof())
+    }
+    ///Returns a span which covers both `self` and `end`, assuming that `end` does not start before `self`
+    pub fn to(&self, end: Span) -> Span {
+        if self.start == Location::eof() {
+            end
+        }
+        else if end.start == Location::eof() || end.file != self.file {
+            *self
+        }
+        else {
+            Span::new(self.file, self.start, end.end)
+        }
+    }
+    ///Returns an empty span located at the end of this span
+    pub fn end_span(&self) -> Span {
+        Span::new(self.file, self.end, self.end)
+    }
+}
+
+impl Default for Span {
+    fn default() -> Span {
+        Span::eof()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Located<T> {
-    pub location: Location,
+    pub span: Span,
     pub node: T
 }
 
@@ -80,13 +123,28 @@ impl <T: PartialEq> PartialEq for Located<T> {
     
 impl <T: fmt::Display> fmt::Display for Located<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}: {}", self.location, self.node)
+        write!(f, "{}: {}", self.span, self.node)
     }
 }
 
 impl fmt::Display for Location {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}:{}", self.row, self.column)
+        write!(f, "{}:{}", self.row + 1, self.column)
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{}", file_name(self.file)));
+        if self.start == Location::eof() {
+            Ok(())
+        }
+        else if self.start.row == self.end.row {
+            write!(f, ":{}-{}", self.start, self.end.column)
+        }
+        else {
+            write!(f, ":{}-{}", self.start, self.end)
+        }
     }
 }
 
@@ -94,20 +152,20 @@ impl fmt::Display for Location {
 pub struct Token {
     pub token : TokenEnum,
     pub value : InternedStr,
-    pub location : Location
+    pub span : Span
 }
 impl Token {
-    fn eof() -> Token {
-        Token { token : EOF, value : intern(""), location : Location { column : -1, row : -1, absolute : -1} }
+    fn eof(span : Span) -> Token {
+        Token { token : EOF, value : intern(""), span : span }
     }
 
-    fn new(interner: &Rc<RefCell<Interner>>, token : TokenEnum, value : &str, loc : Location) -> Token {
-        Token { token: token, value: (**interner).borrow_mut().intern(value), location: loc }
+    fn new(interner: &Rc<RefCell<Interner>>, token : TokenEnum, value : &str, span : Span) -> Token {
+        Token { token: token, value: (**interner).borrow_mut().intern(value), span: span }
     }
 
     #[cfg(test)]
     fn new_(token : TokenEnum, value : &str) -> Token {
-        Token::new(&get_local_interner(), token, value, Location { column : -1, row : -1, absolute : -1 })
+        Token::new(&get_local_interner(), token, value, Span::eof())
     }
 }
 
@@ -131,6 +189,8 @@ fn name_or_keyword(tok : &str) -> TokenEnum {
         "->" => ARROW,
         "data" => DATA,
         "newtype" => NEWTYPE,
+        "type" => TYPE,
+        "default" => DEFAULT,
         "do" => DO,
         "import" => IMPORT,
         "infixl" => INFIXL,
@@ -147,7 +207,7 @@ fn name_or_keyword(tok : &str) -> TokenEnum {
 fn is_operator(first_char : char) -> bool {
     match first_char {
         '+' | '-' | '*' | '/' | '.' | '$' |
-        ':' | '=' | '<' | '>' | '|' | '&' | '!' => true,
+        ':' | '=' | '<' | '>' | '|' | '&' | '!' | '%' | '^' | '?' => true,
         _ => false
     }
 }
@@ -155,6 +215,10 @@ fn is_operator(first_char : char) -> bool {
 pub struct Lexer<Stream: Iterator<Item=char>> {
     ///The input which the lexer processes
     input : Peekable<Stream>,
+    ///A character which was read from `input` but which has been put back to be read again
+    pending : Option<char>,
+    ///The file which is being lexed
+    file : FileId,
     ///The current location of the lexer
     location : Location,
     ///All the current unprocessed tokens stored on a stack
@@ -166,7 +230,9 @@ pub struct Lexer<Stream: Iterator<Item=char>> {
     ///The offset into the token buffer at which the current token is at
     offset : usize,
     ///The string interner, cached here for efficency
-    interner: Rc<RefCell<Interner>>
+    interner: Rc<RefCell<Interner>>,
+    ///Errors found while scanning, the lexer continues with a best effort token after each error
+    errors: Vec<Located<String>>
 }
 
 
@@ -174,15 +240,22 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
     
     ///Constructs a new lexer with a default sized token buffer and the local string interner
     pub fn new(input : Stream) -> Lexer<Stream> {
+        Lexer::with_file(input, FileId::default())
+    }
+    ///Constructs a new lexer which attaches `file` to the spans of the tokens it produces
+    pub fn with_file(input : Stream, file : FileId) -> Lexer<Stream> {
         let start = Location { column : 0, row : 0, absolute : 0};
         Lexer { 
             input : input.peekable(),
+            pending : None,
+            file : file,
             location : start,
             unprocessed_tokens : Vec::new(),
             tokens : VecDeque::with_capacity(20),
             indent_levels : Vec::new(),
             offset : 0,
-            interner: get_local_interner()
+            interner: get_local_interner(),
+            errors: Vec::new()
         }
     }
     ///Returns a new token with some special rules necessary for the parsing of the module declaration
@@ -192,7 +265,7 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
         let n = self.next_indent_token(&mut newline);
         self.unprocessed_tokens.push(n);
         let new_token = self.unprocessed_tokens.last().unwrap().token;
-        let loc = self.unprocessed_tokens.last().unwrap().location;
+        let loc = self.unprocessed_tokens.last().unwrap().span;
 
         if new_token != LBRACE && new_token != MODULE {
             self.unprocessed_tokens.push(Token::new(&self.interner, INDENTSTART, "{n}", loc));
@@ -233,6 +306,12 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
         }
     }
 
+    ///Returns the token before the current token, if there is one
+    pub fn previous<'a>(&'a self) -> Option<&'a Token> {
+        let index = self.tokens.len() as isize - 2 - self.offset as isize;
+        if index >= 0 { self.tokens.get(index as usize) } else { None }
+    }
+
     ///Returns a reference to the current token
     pub fn current<'a>(&'a self) -> &'a Token {
         match self.tokens.get(self.tokens.len() - 1 - self.offset) {
@@ -241,6 +320,35 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
         }
     }
 
+    ///Returns the position of the next token in the token buffer
+    pub fn position(&self) -> usize {
+        self.tokens.len() - self.offset
+    }
+
+    ///Returns the number of '{' minus the number of '}' from the token at `position`
+    ///up to and including the current token
+    pub fn brace_depth_since(&self, position: usize) -> isize {
+        let end = self.position();
+        let mut depth = 0;
+        for i in position..end {
+            match self.tokens[i].token {
+                LBRACE => depth += 1,
+                RBRACE => depth -= 1,
+                _ => ()
+            }
+        }
+        depth
+    }
+
+    ///Removes and returns the errors which have been found while scanning
+    pub fn take_errors(&mut self) -> Vec<Located<String>> {
+        ::std::mem::replace(&mut self.errors, Vec::new())
+    }
+
+    fn error(&mut self, span: Span, message: String) {
+        self.errors.push(Located { span: span, node: message });
+    }
+
     ///Moves the lexer back one token
     ///TODO check for overflow in the buffer
     pub fn backtrack(&mut self) {
@@ -252,14 +360,60 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
         self.offset > 0 || match self.tokens.back() { None => true, Some(x) => x.token != EOF }
     }
 
+    ///Returns the span from `start` to the current location of the lexer
+    fn span_from(&self, start : Location) -> Span {
+        Span::new(self.file, start, self.location)
+    }
+
+    ///Returns an empty span located directly after the last token which has been produced,
+    ///used for tokens which are inserted by the layout algorithm
+    fn layout_span(&self, next : &Token) -> Span {
+        match self.tokens.back() {
+            Some(tok) => tok.span.end_span(),
+            None => next.span
+        }
+    }
+
+    ///Reads the rest of an escape sequence in a string or character literal which starts at `start`,
+    ///the backslash has already been read
+    fn read_escape(&mut self, start: Location) -> char {
+        let c = match self.peek_char() {
+            Some(c) if c != '\n' => { self.read_char(); c }
+            _ => return '\\'
+        };
+        match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            'a' => '\x07',
+            'b' => '\x08',
+            'f' => '\x0C',
+            'v' => '\x0B',
+            '\\' | '"' | '\'' => c,
+            _ => {
+                let span = self.span_from(start);
+                self.error(span, format!("unknown escape sequence '\\{}'", c));
+                c
+            }
+        }
+    }
+
     ///Peeks at the next character in the input
     fn peek_char(&mut self) -> Option<char> {
-        self.input.peek().map(|c| *c)
+        match self.pending {
+            Some(c) => Some(c),
+            None => self.input.peek().map(|c| *c)
+        }
     }
 
     ///Reads a character from the input and increments the current position
     fn read_char(&mut self) -> Option<char> {
-        match self.input.next() {
+        let next = match self.pending.take() {
+            Some(c) => Some(c),
+            None => self.input.next()
+        };
+        match next {
             Some(c) => {
                 self.location.absolute += 1;
                 self.location.column += 1;
@@ -302,31 +456,47 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
         let mut token = NUMBER;
         match self.peek_char() {
             Some('.') => {
-                self.input.next();
+                self.read_char();
                 token = FLOAT;
                 number.push('.');
                 number.push_str(self.scan_digits().as_ref());
             }
             _ => ()
         }
-        Token::new(&self.interner, token, number.as_ref(), location)
+        Token::new(&self.interner, token, number.as_ref(), self.span_from(location))
     }
     ///Scans an identifier or a keyword
     fn scan_identifier(&mut self, c: char, start_location: Location) -> Token {
         let mut result = c.to_string();
+        //The start of the last part of a qualified name such as `Map.insert`
+        let mut part_start = 0;
         loop {
             match self.peek_char() {
-                Some(ch) => {
-                    if !ch.is_alphanumeric() && ch != '_' {
-                        break;
-                    }
+                Some(ch) if ch.is_alphanumeric() || ch == '_' || ch == '\'' => {
                     self.read_char();
                     result.push(ch);
                 }
-                None => break
+                //A module name directly followed by `.` and a name is a qualified name
+                Some('.') if result[part_start..].chars().next().map_or(false, |c| c.is_uppercase()) => {
+                    let dot_location = self.location;
+                    self.read_char();
+                    match self.peek_char() {
+                        Some(ch) if ch.is_alphabetic() || ch == '_' => {
+                            result.push('.');
+                            part_start = result.len();
+                        }
+                        _ => {
+                            //Not a qualified name so the `.` is read again as an operator
+                            self.location = dot_location;
+                            self.pending = Some('.');
+                            break
+                        }
+                    }
+                }
+                _ => break
             }
         }
-        return Token::new(&self.interner, name_or_keyword(result.as_ref()), result.as_ref(), start_location);
+        return Token::new(&self.interner, name_or_keyword(result.as_ref()), result.as_ref(), self.span_from(start_location));
     }
 
     ///Returns the next token but if it is not an '}' it will attempt to insert a '}' automatically
@@ -341,8 +511,9 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
                 if m != 0 {//If not a explicit '}'
                     debug!("ParseError on token {:?}, inserting }}", self.current().token);
                     self.indent_levels.pop();
-                    let loc = self.current().location;
-                    self.tokens.push_back(Token::new(&self.interner, RBRACE, "}", loc));
+                    let len = self.tokens.len();
+                    let span = if len >= 2 { self.tokens[len - 2].span.end_span() } else { self.current().span };
+                    self.tokens.push_back(Token::new(&self.interner, RBRACE, "}", span));
                     let len = self.tokens.len();
                     self.tokens.swap(len - 2, len - 1);
                     self.backtrack();
@@ -363,7 +534,7 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
             match self.tokens.back() {
                 Some(tok) => {
                     if tok.token == LET || tok.token == WHERE || tok.token == OF || tok.token == DO {
-                        let loc = self.unprocessed_tokens.last().unwrap().location;
+                        let loc = self.unprocessed_tokens.last().unwrap().span;
                         let indentstart = Token::new(&self.interner, INDENTSTART, "{n}", loc);
                         self.unprocessed_tokens.push(indentstart);
                     }
@@ -372,7 +543,7 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
             }
         }
         if newline {
-            let loc = self.unprocessed_tokens.last().unwrap().location;
+            let loc = self.unprocessed_tokens.last().unwrap().span;
             self.unprocessed_tokens.push(Token::new(&self.interner, INDENTLEVEL, "<n>", loc));
         }
         self.layout_independent_token();
@@ -390,18 +561,20 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
                         //m:ms
                         let m = *self.indent_levels.last().unwrap();
                         //m == n
-                        if m == tok.location.column {
+                        if m == tok.span.start.column {
                             debug!("Indents are same, inserted semicolon");
-                            self.tokens.push_back(Token::new(&self.interner, SEMICOLON, ";", tok.location));
+                            let span = self.layout_span(&tok);
+                            self.tokens.push_back(Token::new(&self.interner, SEMICOLON, ";", span));
                             self.unprocessed_tokens.pop();
                             return;
                         }
-                        else if tok.location.column < m {
+                        else if tok.span.start.column < m {
                             //n < m
                             //TODO
                             debug!("n < m, insert }}");
                             self.indent_levels.pop();
-                            self.tokens.push_back(Token::new(&self.interner, RBRACE, "}", tok.location));
+                            let span = self.layout_span(&tok);
+                            self.tokens.push_back(Token::new(&self.interner, RBRACE, "}", span));
                             return;
                         }
                     }
@@ -416,28 +589,29 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
                 }
                 INDENTSTART => {
                     //{n} token
-                    let n = tok.location.column;
+                    let n = tok.span.start.column;
+                    let span = self.layout_span(&tok);
                     if self.indent_levels.len() != 0 {
                         //m:ms
                         let m = *self.indent_levels.last().unwrap();
                         if n > m {
                             debug!("n > m + INDENTSTART, insert {{");
                             self.unprocessed_tokens.pop();
-                            self.tokens.push_back(Token::new(&self.interner, LBRACE, "{", tok.location));
+                            self.tokens.push_back(Token::new(&self.interner, LBRACE, "{", span));
                             self.indent_levels.push(n);
                             return;
                         }
                     }
                     if n > 0 {
-                        self.tokens.push_back(Token::new(&self.interner, LBRACE, "{", tok.location));
+                        self.tokens.push_back(Token::new(&self.interner, LBRACE, "{", span));
                         self.unprocessed_tokens.pop();
                         self.indent_levels.push(n);
                         return;
                     }
-                    self.tokens.push_back(Token::new(&self.interner, LBRACE, "{", tok.location));
-                    self.tokens.push_back(Token::new(&self.interner, RBRACE, "}", tok.location));
+                    self.tokens.push_back(Token::new(&self.interner, LBRACE, "{", span));
+                    self.tokens.push_back(Token::new(&self.interner, RBRACE, "}", span));
                     self.unprocessed_tokens.pop();
-                    self.unprocessed_tokens.push(Token::new(&self.interner, INDENTLEVEL, "<n>", tok.location));
+                    self.unprocessed_tokens.push(Token::new(&self.interner, INDENTLEVEL, "<n>", tok.span));
                     self.offset += 1;
                     return;
                 }
@@ -448,7 +622,9 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
                         return;
                     }
                     else {
-                        return;//parse-error
+                        //parse-error, pass the '}' on so that the parser can report it
+                        self.tokens.push_back(self.unprocessed_tokens.pop().unwrap());
+                        return;
                     }
                 }
                 LBRACE => {
@@ -470,7 +646,11 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
             else if *self.indent_levels.last().unwrap() != 0 {
                 //Keep pusing right brackets
                 self.indent_levels.pop();
-                self.tokens.push_back(Token::new(&self.interner, RBRACE, "}", self.location));
+                let span = match self.tokens.back() {
+                    Some(tok) => tok.span.end_span(),
+                    None => self.span_from(self.location)
+                };
+                self.tokens.push_back(Token::new(&self.interner, RBRACE, "}", span));
                 return;
             }
         }
@@ -489,7 +669,7 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
                         *newline = true;
                     }
                 }
-                None => { return Token::eof() }
+                None => { return Token::eof(self.span_from(self.location)) }
             }
         }
         let start_location = self.location;
@@ -519,7 +699,7 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
                 "|"  => PIPE,
                 _    => OPERATOR
             };
-            return Token::new(&self.interner, tok, result.as_ref(), start_location);
+            return Token::new(&self.interner, tok, result.as_ref(), self.span_from(start_location));
         }
         else if c.is_digit(10) {
             return self.scan_number(c, start_location);
@@ -528,43 +708,80 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
             return self.scan_identifier(c, start_location);
         }
         else if c == '`' {
-            let x = self.read_char().expect("Unexpected end of input");
-            if !x.is_alphabetic() && x != '_' {
-                panic!("Parse error on '{:?}'", x);
-            }
+            let x = match self.peek_char() {
+                Some(x) if x.is_alphabetic() || x == '_' => { self.read_char(); x }
+                _ => {
+                    let span = self.span_from(start_location);
+                    self.error(span, "expected an identifier after '`'".to_string());
+                    return self.next_indent_token(newline);
+                }
+            };
             let mut token = self.scan_identifier(x, start_location);
-            let end_tick = self.read_char();
-            match end_tick {
-                Some('`') => (),
-                Some(x) => panic!("Parse error on '{:?}'", x),
-                None => panic!("Unexpected end of input")
+            if self.peek_char() == Some('`') {
+                self.read_char();
+            }
+            else {
+                let span = self.span_from(start_location);
+                self.error(span, "unterminated infix identifier, expected '`'".to_string());
             }
             token.token = OPERATOR;
+            token.span.end = self.location;
             return token;
         }
         else if c == '"' {
             let mut string = String::new();
             loop {
-                match self.read_char() {
-                    Some('"') => return Token::new(&self.interner, STRING, string.as_ref(), start_location),
-                    Some(x) => string.push(x),
-                    None => panic!("Unexpected EOF")
+                match self.peek_char() {
+                    Some('"') => {
+                        self.read_char();
+                        return Token::new(&self.interner, STRING, string.as_ref(), self.span_from(start_location));
+                    }
+                    Some('\n') | None => {
+                        let span = self.span_from(start_location);
+                        self.error(span, "unterminated string literal".to_string());
+                        return Token::new(&self.interner, STRING, string.as_ref(), span);
+                    }
+                    Some('\\') => {
+                        self.read_char();
+                        let x = self.read_escape(start_location);
+                        string.push(x);
+                    }
+                    Some(x) => {
+                        self.read_char();
+                        string.push(x);
+                    }
                 }
             }
         }
         else if c == '\'' {
-            match self.read_char() {
-                Some(x) => {
-                    if self.read_char() == Some('\'') {
-                        //FIXME: Slow
-                        return Token::new(&self.interner, CHAR, &*x.to_string(), start_location);
-                    }
-                    else {
-                        panic!("Multi char character")
-                    }
+            let x = match self.peek_char() {
+                Some('\\') => { self.read_char(); self.read_escape(start_location) }
+                Some(x) if x != '\n' => { self.read_char(); x }
+                _ => {
+                    let span = self.span_from(start_location);
+                    self.error(span, "unterminated character literal".to_string());
+                    return Token::new(&self.interner, CHAR, "\0", span);
                 }
-                None => panic!("Unexpected EOF")
+            };
+            if self.peek_char() == Some('\'') {
+                self.read_char();
             }
+            else {
+                //Skip the rest of the literal so that scanning can continue after it
+                while self.peek_char().map_or(false, |c| c != '\'' && c != '\n') {
+                    self.read_char();
+                }
+                let closed = self.peek_char() == Some('\'');
+                if closed {
+                    self.read_char();
+                }
+                let span = self.span_from(start_location);
+                let message = if closed { "character literals must contain exactly one character" }
+                              else { "unterminated character literal" };
+                self.error(span, message.to_string());
+            }
+            //FIXME: Slow
+            return Token::new(&self.interner, CHAR, &*x.to_string(), self.span_from(start_location));
         }
         let tok = match c {
             ';' => SEMICOLON,
@@ -576,10 +793,14 @@ impl <Stream : Iterator<Item=char>> Lexer<Stream> {
             '}' => RBRACE,
             ',' => COMMA,
             '\\'=> LAMBDA,
-            _   => EOF
+            _   => {
+                let span = self.span_from(start_location);
+                self.error(span, format!("unexpected character {:?}", c));
+                return self.next_indent_token(newline);
+            }
         };
         //FIXME: Slow
-        Token::new(&self.interner, tok, c.to_string().as_ref(), start_location)
+        Token::new(&self.interner, tok, c.to_string().as_ref(), self.span_from(start_location))
     }
 }
 
@@ -614,4 +835,49 @@ in test".chars());
     assert_eq!(*lexer.next(), Token::new_(NUMBER, "3"));
 }
 
+#[test]
+fn escape_sequences() {
+    let mut lexer = Lexer::new(r#""a\n\"b\\" '\t' '\''"#.chars());
+
+    assert_eq!(*lexer.next(), Token::new_(STRING, "a\n\"b\\"));
+    assert_eq!(*lexer.next(), Token::new_(CHAR, "\t"));
+    assert_eq!(*lexer.next(), Token::new_(CHAR, "'"));
+    assert!(lexer.take_errors().is_empty());
+}
+
+#[test]
+fn identifiers_with_primes() {
+    let mut lexer = Lexer::new("foldl' x' 'a'".chars());
+
+    assert_eq!(*lexer.next(), Token::new_(NAME, "foldl'"));
+    assert_eq!(*lexer.next(), Token::new_(NAME, "x'"));
+    assert_eq!(*lexer.next(), Token::new_(CHAR, "a"));
+}
+
+#[test]
+fn qualified_names() {
+    let mut lexer = Lexer::new("Map.insert Data.Map.Map Just.f [LT..GT]".chars());
+
+    assert_eq!(*lexer.next(), Token::new_(NAME, "Map.insert"));
+    assert_eq!(*lexer.next(), Token::new_(NAME, "Data.Map.Map"));
+    assert_eq!(*lexer.next(), Token::new_(NAME, "Just.f"));
+    assert_eq!(*lexer.next(), Token::new_(LBRACKET, "["));
+    assert_eq!(*lexer.next(), Token::new_(NAME, "LT"));
+    assert_eq!(*lexer.next(), Token::new_(OPERATOR, ".."));
+    assert_eq!(*lexer.next(), Token::new_(NAME, "GT"));
+}
+
+#[test]
+fn token_spans() {
+    let mut lexer = Lexer::new(
+r"test 2.5
+  ident".chars());
+    let span = lexer.next().span;
+    assert_eq!((span.start.row, span.start.column, span.end.column), (0, 1, 4));
+    let span = lexer.next().span;
+    assert_eq!((span.start.row, span.start.column, span.end.column), (0, 6, 8));
+    let span = lexer.next().span;
+    assert_eq!((span.start.row, span.start.column, span.end.column), (1, 3, 7));
+}
+
 }
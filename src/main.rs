@@ -8,9 +8,13 @@ extern crate getopts;
 extern crate test;
 
 #[cfg(not(test))]
-use vm::execute_main_module;
+use vm::{VMConfig, run_program};
 #[cfg(not(test))]
 use getopts::Options;
+#[cfg(not(test))]
+use diagnostics::ErrorFormat;
+#[cfg(not(test))]
+use typecheck::TypecheckOptions;
 
 #[macro_escape]
 macro_rules! write_core_expr(
@@ -54,45 +58,105 @@ mod renamer;
 mod infix;
 mod builtins;
 mod interner;
+mod integer;
+mod rational;
+mod source;
+mod diagnostics;
 mod deriving;
 #[cfg(not(test))]
 mod repl;
 
+#[cfg(not(test))]
+const USAGE: &'static str = "Usage: vm [OPTIONS|EXPRESSION] [-- PROGRAM ARGUMENTS]";
+
+///Reports an invalid command line and exits without running anything
+#[cfg(not(test))]
+fn usage_error(opts: &Options, message: &str) -> ! {
+    eprintln!("error: {}", message);
+    eprintln!("{} {}", USAGE, opts.usage(""));
+    std::process::exit(2);
+}
+
 #[cfg(not(test))]
 fn main() {
     let mut opts = Options::new();
     opts.optopt("l", "", "Input file", "Module name");
     opts.optflag("i", "interactive", "Starts the REPL");
     opts.optflag("h", "help", "Print help");
+    opts.optopt("", "error-format", "How errors are reported", "human|json");
+    opts.optopt("", "max-reductions", "Stop evaluating after this many reduction steps", "STEPS");
+    opts.optopt("", "max-heap-cells", "Stop evaluating if more cells than this are live in the heap", "CELLS");
+    opts.optopt("", "max-time", "Stop evaluating after this many milliseconds", "MILLISECONDS");
+    opts.optflag("", "no-monomorphism-restriction", "Generalize constrained bindings which have no arguments or type signature");
+    opts.optflag("", "mono-local-binds", "Do not generalize local bindings which have no type signature");
 
-    let matches = {
-        let args: Vec<_> = std::env::args()
+    //The arguments after `--` are passed on to the Haskell program
+    let (args, program_args) = {
+        let mut args: Vec<_> = std::env::args()
             .skip(1)
             .collect();
-        opts.parse(args)
-            .unwrap_or_else(|err| panic!("{}", err))
+        match args.iter().position(|arg| arg == "--") {
+            Some(i) => {
+                let program_args = args.split_off(i + 1);
+                args.pop();
+                (args, program_args)
+            }
+            None => (args, Vec::new())
+        }
     };
+    let matches = opts.parse(args)
+        .unwrap_or_else(|err| usage_error(&opts, &err.to_string()));
 
     if matches.opt_present("h") {
-        println!("Usage: vm [OPTIONS|EXPRESSION] {}", opts.usage(""));
+        println!("{} {}", USAGE, opts.usage(""));
         return;
     }
+    let error_format = match matches.opt_str("error-format") {
+        Some(format) => ErrorFormat::from_str(&format)
+            .unwrap_or_else(|| usage_error(&opts, &format!("unknown error format '{}', expected 'human' or 'json'", format))),
+        None => ErrorFormat::Human
+    };
+    let limit = |name: &str| matches.opt_str(name)
+        .map(|value| value.parse::<usize>()
+            .unwrap_or_else(|_| usage_error(&opts, &format!("expected a number for --{}, got '{}'", name, value))));
+    let mut config = VMConfig {
+        max_reductions: limit("max-reductions"),
+        max_heap_cells: limit("max-heap-cells"),
+        max_time: limit("max-time").map(|millis| std::time::Duration::from_millis(millis as u64)),
+        args: program_args,
+        .. VMConfig::default()
+    };
+    let options = TypecheckOptions {
+        monomorphism_restriction: !matches.opt_present("no-monomorphism-restriction"),
+        mono_local_binds: matches.opt_present("mono-local-binds")
+    };
     match matches.opt_str("l") {
         Some(modulename) => {
-            let result = execute_main_module(modulename.as_ref()).unwrap();
-            match result {
-                Some(x) => println!("{:?}", x),
-                None => println!("Error running module {}", modulename)
+            config.prog_name = modulename.clone();
+            match run_program(modulename.as_ref(), config, options) {
+                Ok(code) => std::process::exit(code),
+                Err(err) => {
+                    diagnostics::report_as(error_format, &err);
+                    std::process::exit(1);
+                }
             }
-            return;
         }
         None => ()
     }
     if matches.opt_present("i") {
-        repl::start();
+        repl::start(config, options);
         return;
     }
-    let expr_str = &*matches.free[0];
-    repl::run_and_print_expr(expr_str);
+    let expr_str = match matches.free.first() {
+        Some(expr_str) => &**expr_str,
+        None => usage_error(&opts, "expected an expression, a module (-l) or --interactive")
+    };
+    match repl::run_and_print_expr(expr_str, config, options) {
+        Ok(()) => (),
+        Err(err) => {
+            diagnostics::report_as(error_format, &err);
+            std::process::exit(1);
+        }
+    }
 }
 
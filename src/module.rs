@@ -1,7 +1,9 @@
 use std::fmt;
 use std::collections::HashMap;
 use interner::{intern, InternedStr};
-use lexer::{Location, Located};
+use lexer::{Span, Located};
+use integer::Integer;
+use rational::Rational;
 pub use std::default::Default;
 pub use types::*;
 
@@ -17,6 +19,9 @@ pub struct Module<Ident = InternedStr> {
     pub instances : Vec<Instance<Ident>>,
     pub data_definitions : Vec<DataDefinition<Ident>>,
     pub newtypes : Vec<Newtype<Ident>>,
+    pub type_synonyms : Vec<TypeSynonym<Ident>>,
+    ///The types of the module's `default` declaration, if it has one
+    pub defaults : Option<Vec<Type<Ident>>>,
     pub fixity_declarations : Vec<FixityDeclaration<Ident>>
 }
 
@@ -25,7 +30,14 @@ pub struct Import<Ident> {
     pub module: InternedStr,
     //None if 'import Name'
     //Some(names) if 'import Name (names)'
-    pub imports: Option<Vec<Ident>>
+    pub imports: Option<Vec<Ident>>,
+    ///The names in 'import Name hiding (names)'
+    pub hiding: Vec<Ident>,
+    ///True if 'import qualified Name', in which case the names can only be referred to as 'Name.name'
+    pub qualified: bool,
+    ///The module name used to qualify the imported names, 'Alias' in 'import Name as Alias'
+    pub alias: Option<InternedStr>,
+    pub span: Span
 }
 
 #[derive(Clone, Debug)]
@@ -34,7 +46,8 @@ pub struct Class<Ident = InternedStr> {
     pub name : Ident,
     pub variable : TypeVariable,
     pub declarations : Vec<TypeDeclaration<Ident>>,
-    pub bindings: Vec<Binding<Ident>>
+    pub bindings: Vec<Binding<Ident>>,
+    pub span: Span
 }
 
 #[derive(Clone, Debug)]
@@ -42,16 +55,18 @@ pub struct Instance<Ident = InternedStr> {
     pub bindings : Vec<Binding<Ident>>,
     pub constraints : Vec<Constraint<Ident>>,
     pub typ : Type<Ident>,
-    pub classname : Ident
+    pub classname : Ident,
+    pub span: Span
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Binding<Ident = InternedStr> {
     pub name : Ident,
-    pub arguments: Vec<Pattern<Ident>>,
+    pub arguments: Vec<Located<Pattern<Ident>>>,
     pub matches: Match<Ident>,
     pub where_bindings : Option<Vec<Binding<Ident>>>,
-    pub typ: Qualified<Type<Ident>, Ident>
+    pub typ: Qualified<Type<Ident>, Ident>,
+    pub span: Span
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -62,20 +77,31 @@ pub struct Constructor<Ident = InternedStr> {
     pub arity : isize
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct DataDefinition<Ident = InternedStr> {
     pub constructors : Vec<Constructor<Ident>>,
     pub typ : Qualified<Type<Ident>, Ident>,
     pub parameters : HashMap<InternedStr, isize>,
-    pub deriving: Vec<Ident>
+    pub deriving: Vec<Ident>,
+    pub span: Span
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Newtype<Ident = InternedStr> {
     pub typ: Qualified<Type>,
     pub constructor_name: Ident,
     pub constructor_type: Qualified<Type<Ident>, Ident>,
-    pub deriving: Vec<Ident>
+    pub deriving: Vec<Ident>,
+    pub span: Span
+}
+
+///A type synonym such as `type ShowS = [Char] -> [Char]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeSynonym<Ident = InternedStr> {
+    pub name: InternedStr,
+    pub parameters: Vec<TypeVariable>,
+    pub typ: Type<Ident>,
+    pub span: Span
 }
 
 #[derive(PartialEq, Clone, Copy, Debug)]
@@ -85,18 +111,21 @@ pub enum Assoc {
     No
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct FixityDeclaration<Ident = InternedStr> {
     pub assoc: Assoc,
     pub precedence: isize,
-    pub operators: Vec<Ident>
+    pub operators: Vec<Ident>,
+    pub span: Span
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct TypeDeclaration<Ident = InternedStr> {
     pub typ : Qualified<Type<Ident>, Ident>,
-    pub name : Ident
+    pub name : Ident,
+    pub span : Span
 }
+
 impl <T : fmt::Display + AsRef<str>> fmt::Display for TypeDeclaration<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{} :: {}", self.name, self.typ)
@@ -108,7 +137,7 @@ impl <T : fmt::Display + AsRef<str>> fmt::Display for TypeDeclaration<T> {
 pub struct TypedExpr<Ident = InternedStr> {
     pub expr : Expr<Ident>,
     pub typ : Type<Ident>,
-    pub location : Location
+    pub span : Span
 }
 
 impl <T: PartialEq> PartialEq for TypedExpr<T> {
@@ -125,10 +154,10 @@ impl <T: fmt::Display + AsRef<str>> fmt::Display for TypedExpr<T> {
 
 impl TypedExpr {
     pub fn new<T: fmt::Display + AsRef<str>>(expr : Expr<T>) -> TypedExpr<T> {
-        TypedExpr { expr : expr, typ : Type::new_var(intern("a")), location : Location { column : -1, row : -1, absolute : -1 } }
+        TypedExpr { expr : expr, typ : Type::new_var(intern("a")), span : Span::eof() }
     }
-    pub fn with_location<T: fmt::Display + AsRef<str>>(expr : Expr<T>, loc : Location) -> TypedExpr<T> {
-        TypedExpr { expr : expr, typ : Type::new_var(intern("a")), location : loc }
+    pub fn with_span<T: fmt::Display + AsRef<str>>(expr : Expr<T>, span : Span) -> TypedExpr<T> {
+        TypedExpr { expr : expr, typ : Type::new_var(intern("a")), span : span }
     }
 }
 
@@ -141,22 +170,37 @@ pub struct Alternative<Ident = InternedStr> {
 
 #[derive(Clone, Debug, PartialOrd, PartialEq, Eq)]
 pub enum Pattern<Ident = InternedStr> {
-    Number(isize),
+    Number(Integer),
     Identifier(Ident),
     Constructor(Ident, Vec<Pattern<Ident>>),
     WildCard
 }
 
+impl <Ident: AsRef<str>> Pattern<Ident> {
+    ///Returns true if the pattern matches every value of its type, that is if it only contains
+    ///variables, wildcards and tuples
+    pub fn is_failure_free(&self) -> bool {
+        match *self {
+            Pattern::Identifier(_) | Pattern::WildCard => true,
+            Pattern::Constructor(ref name, ref patterns) => {
+                let name = name.as_ref();
+                (name == "()" || name.starts_with("(,")) && patterns.iter().all(|p| p.is_failure_free())
+            }
+            Pattern::Number(_) => false
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Match<Ident = InternedStr> {
     Guards(Vec<Guard<Ident>>),
     Simple(TypedExpr<Ident>)
 }
 impl <Ident> Match<Ident> {
-    pub fn location<'a>(&'a self) -> &'a Location {
+    pub fn span<'a>(&'a self) -> &'a Span {
         match *self {
-            Match::Guards(ref gs) => &gs[0].predicate.location,
-            Match::Simple(ref e) => &e.location
+            Match::Guards(ref gs) => &gs[0].predicate.span,
+            Match::Simple(ref e) => &e.span
         }
     }
 }
@@ -176,8 +220,8 @@ pub enum DoBinding<Ident = InternedStr> {
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum LiteralData {
-    Integral(isize),
-    Fractional(f64),
+    Integral(Integer),
+    Fractional(Rational),
     String(InternedStr),
     Char(char)
 }
@@ -187,12 +231,12 @@ pub enum Expr<Ident = InternedStr> {
     Apply(Box<TypedExpr<Ident>>, Box<TypedExpr<Ident>>),
     OpApply(Box<TypedExpr<Ident>>, Ident, Box<TypedExpr<Ident>>),
     Literal(LiteralData),
-    Lambda(Pattern<Ident>, Box<TypedExpr<Ident>>),
+    Lambda(Located<Pattern<Ident>>, Box<TypedExpr<Ident>>),
     Let(Vec<Binding<Ident>>, Box<TypedExpr<Ident>>),
     Case(Box<TypedExpr<Ident>>, Vec<Alternative<Ident>>),
     IfElse(Box<TypedExpr<Ident>>, Box<TypedExpr<Ident>>, Box<TypedExpr<Ident>>),
     Do(Vec<DoBinding<Ident>>, Box<TypedExpr<Ident>>),
-    TypeSig(Box<TypedExpr<Ident>>, Qualified<Type<Ident>, Ident>),
+    TypeSig(Box<TypedExpr<Ident>>, Located<Qualified<Type<Ident>, Ident>>),
     Paren(Box<TypedExpr<Ident>>)
 }
 impl <T: fmt::Display + AsRef<str>> fmt::Display for Binding<T> {
@@ -223,7 +267,7 @@ impl <T: fmt::Display + AsRef<str>> fmt::Display for Expr<T> {
                 write!(f, "{} }}", *expr)
             }
             OpApply(ref lhs, ref op, ref rhs) => write!(f, "({} {} {})", lhs, op, rhs),
-            TypeSig(ref expr, ref typ) => write!(f, "{} {}", expr, typ),
+            TypeSig(ref expr, ref typ) => write!(f, "{} {}", expr, typ.node),
             Paren(ref expr) => write!(f, "({})", expr),
             _ => Ok(())
         }
@@ -267,8 +311,8 @@ impl <T: fmt::Display + AsRef<str>> fmt::Display for Match<T> {
 impl fmt::Display for LiteralData {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            LiteralData::Integral(i) => write!(f, "{}", i),
-            LiteralData::Fractional(v) => write!(f, "{}", v),
+            LiteralData::Integral(ref i) => write!(f, "{}", i),
+            LiteralData::Fractional(ref v) => write!(f, "{}", v),
             LiteralData::String(ref s) => write!(f, "\"{}\"", *s),
             LiteralData::Char(c) => write!(f, "'{}'", c)
         }
@@ -301,6 +345,9 @@ pub fn walk_module<Ident, V: Visitor<Ident>>(visitor: &mut V, module: &Module<Id
     for bind in module.instances.iter().flat_map(|i| i.bindings.iter()) {
         visitor.visit_binding(bind);
     }
+    for bind in module.classes.iter().flat_map(|c| c.bindings.iter()) {
+        visitor.visit_binding(bind);
+    }
     for bind in module.bindings.iter() {
         visitor.visit_binding(bind);
     }
@@ -309,7 +356,12 @@ pub fn walk_module<Ident, V: Visitor<Ident>>(visitor: &mut V, module: &Module<Id
 pub fn walk_binding<Ident, V: Visitor<Ident>>(visitor: &mut V, binding: &Binding<Ident>) {
     match binding.matches {
         Match::Simple(ref e) => visitor.visit_expr(e),
-        _ => panic!()
+        Match::Guards(ref gs) => {
+            for g in gs.iter() {
+                visitor.visit_expr(&g.predicate);
+                visitor.visit_expr(&g.expression);
+            }
+        }
     }
 }
 
@@ -420,6 +472,9 @@ pub fn walk_module_mut<Ident, V: MutVisitor<Ident>>(visitor: &mut V, module: &mu
     for bind in module.instances.iter_mut().flat_map(|i| i.bindings.iter_mut()) {
         visitor.visit_binding(bind);
     }
+    for bind in module.classes.iter_mut().flat_map(|c| c.bindings.iter_mut()) {
+        visitor.visit_binding(bind);
+    }
     for bind in module.bindings.iter_mut() {
         visitor.visit_binding(bind);
     }
@@ -2,6 +2,8 @@ use std::mem::{swap};
 use std::io;
 use std::io::Read;
 use std::fs::File;
+use std::env;
+use std::path::PathBuf;
 use std::collections::{HashSet, HashMap};
 use std::str::FromStr;
 use std::fmt;
@@ -12,6 +14,9 @@ use module::*;
 use module::Expr::*;
 use module::LiteralData::*;
 use interner::*;
+use source::{FileId, add_file};
+use renamer::Errors;
+use diagnostics::{Diagnostic, ToDiagnostics, write_diagnostics};
 
 ///The Parser is a recursive descent parser which has a method for each production
 ///in the AST. By calling such a production method it is expected that the parser is
@@ -22,12 +27,19 @@ use interner::*;
 ///it can continue parsing without having to move the lexer's position.
 pub struct Parser<Iter: Iterator<Item=char>> {
     lexer : Lexer<Iter>,
+    ///Errors in declarations which the parser has recovered from
+    errors : Vec<ParseError>
 }
 
 #[derive(Debug, Eq, PartialEq)]
 enum Error {
-    UnexpectedToken(&'static [TokenEnum], TokenEnum),
-    Message(::std::string::String)
+    ///The expected tokens, the actual token and the span of the token before the actual token
+    UnexpectedToken(&'static [TokenEnum], TokenEnum, Span),
+    Message(::std::string::String),
+    Io(::std::string::String),
+    CyclicDependency(InternedStr),
+    ModuleNotFound(InternedStr, ::std::string::String),
+    Lexical(::std::string::String)
 }
 
 #[derive(Debug, PartialEq)]
@@ -37,21 +49,114 @@ pub type ParseResult<T> = Result<T, ParseError>;
 
 impl From<io::Error> for ParseError {
     fn from(io_error: io::Error) -> ParseError {
-        ParseError(Located { location: Location::eof(), node: Error::Message(io_error.to_string()) })
+        ParseError(Located { span: Span::eof(), node: Error::Io(io_error.to_string()) })
     }
 }
 
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.0.node {
-            Error::UnexpectedToken(unexpected, expected) => {
-                write!(f, "Expected token {:?}, but found {:?}", unexpected, expected)
+        write_diagnostics(f, self)
+    }
+}
+
+impl ToDiagnostics for ParseError {
+    fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        let span = self.0.span;
+        let diagnostic = match self.0.node {
+            Error::UnexpectedToken(expected, actual, previous) => {
+                let expected_names: Vec<_> = expected.iter().map(|&t| describe_token(t)).collect();
+                let diagnostic = Diagnostic::error("E0001", format!("unexpected {}", describe_token(actual)))
+                    .with_label(span, format!("expected {}", expected_names.join(" or ")));
+                match (expected, token_text(expected[0])) {
+                    ([_], Some(text)) if previous.start != Location::eof() =>
+                        diagnostic.with_insertion(previous.end_span(), format!("try inserting `{}`", text), text.to_string()),
+                    _ => diagnostic
+                }
             }
-            Error::Message(ref message) => write!(f, "{}", message)
-        }
+            Error::Message(ref message) => {
+                Diagnostic::error("E0002", message.clone())
+                    .with_label(span, "syntax error".to_string())
+            }
+            Error::Io(ref message) => Diagnostic::error("E0003", message.clone()),
+            Error::CyclicDependency(module) => {
+                Diagnostic::error("E0004", format!("cyclic dependency when importing module `{}`", module))
+                    .with_label(span, "this import leads back to a module which imports it".to_string())
+            }
+            Error::ModuleNotFound(module, ref message) => {
+                Diagnostic::error("E0005", format!("could not load module `{}`: {}", module, message))
+                    .with_label(span, "imported here".to_string())
+                    .with_help(format!("modules are loaded from the file `{}.hs`", module))
+            }
+            Error::Lexical(ref message) => {
+                Diagnostic::error("E0006", message.clone())
+                    .with_label(span, "invalid token".to_string())
+            }
+        };
+        vec![diagnostic]
     }
 }
 
+///Returns the text of tokens which can only be written in one way
+fn token_text(token: TokenEnum) -> Option<&'static str> {
+    let text = match token {
+        LPARENS => "(",
+        RPARENS => ")",
+        LBRACKET => "[",
+        RBRACKET => "]",
+        LBRACE => "{",
+        RBRACE => "}",
+        COMMA => ",",
+        PIPE => "|",
+        CONTEXTARROW => "=>",
+        EQUALSSIGN => "=",
+        SEMICOLON => ";",
+        WHERE => "where",
+        IN => "in",
+        OF => "of",
+        ARROW => "->",
+        LARROW => "<-",
+        TYPEDECL => "::",
+        THEN => "then",
+        ELSE => "else",
+        _ => return None
+    };
+    Some(text)
+}
+
+///Describes a token the way it is written in the source, or by its kind if it can be written in many ways
+fn describe_token(token: TokenEnum) -> ::std::string::String {
+    let text = match token {
+        EOF => return "end of input".to_string(),
+        NAME => return "identifier".to_string(),
+        OPERATOR => return "operator".to_string(),
+        NUMBER => return "integer literal".to_string(),
+        FLOAT => return "floating point literal".to_string(),
+        STRING => return "string literal".to_string(),
+        CHAR => return "character literal".to_string(),
+        INDENTSTART => return "start of a layout block".to_string(),
+        INDENTLEVEL => return "new line in a layout block".to_string(),
+        MODULE => "module",
+        CLASS => "class",
+        INSTANCE => "instance",
+        LET => "let",
+        CASE => "case",
+        DATA => "data",
+        NEWTYPE => "newtype",
+        TYPE => "type",
+        DEFAULT => "default",
+        LAMBDA => "\\",
+        DO => "do",
+        IMPORT => "import",
+        INFIXL => "infixl",
+        INFIXR => "infixr",
+        INFIX => "infix",
+        DERIVING => "deriving",
+        IF => "if",
+        _ => token_text(token).expect("Expected a token with a fixed spelling")
+    };
+    format!("`{}`", text)
+}
+
 impl error::Error for ParseError {
     fn description(&self) -> &str { "parse error" }
 }
@@ -107,7 +212,11 @@ macro_rules! unexpected (
 impl <Iter : Iterator<Item=char>> Parser<Iter> {
 
 pub fn new(iterator : Iter) -> Parser<Iter> {
-    Parser { lexer : Lexer::new(iterator) }
+    Parser { lexer : Lexer::new(iterator), errors: Vec::new() }
+}
+
+pub fn with_file(iterator : Iter, file : FileId) -> Parser<Iter> {
+    Parser { lexer : Lexer::with_file(iterator, file), errors: Vec::new() }
 }
 
 fn next<'a>(&'a mut self, expected : TokenEnum) -> &'a Token {
@@ -121,21 +230,118 @@ fn next<'a>(&'a mut self, expected : TokenEnum) -> &'a Token {
 
 fn error<T>(&self, message: ::std::string::String) -> ParseResult<T> {
     Err(ParseError(Located {
-        location: self.lexer.current().location,
+        span: self.lexer.current().span,
         node: Error::Message(message)
     }))
 }
+///Converts the current NUMBER token into an integer
+fn number<T: FromStr>(&self) -> ParseResult<T> {
+    let value = self.lexer.current().value;
+    match FromStr::from_str(value.as_ref()) {
+        Ok(i) => Ok(i),
+        Err(_) => self.error(format!("integer literal {} is out of range", value))
+    }
+}
+
 fn unexpected_token(&self, expected: &'static [TokenEnum], actual: TokenEnum) -> ParseError {
+    //The lexer may already have been moved back from the unexpected token
+    let previous = if self.lexer.current().token == actual {
+        self.lexer.previous()
+    }
+    else {
+        Some(self.lexer.current())
+    };
     ParseError(Located {
-        location: self.lexer.current().location,
-        node: Error::UnexpectedToken(expected, actual)
+        span: self.lexer.current().span,
+        node: Error::UnexpectedToken(expected, actual, previous.map(|tok| tok.span).unwrap_or_default())
     })
 }
 
-pub fn module(&mut self) -> ParseResult<Module> {
+///Skips tokens until the end of the declaration which started at the token at `start`.
+///The parser is left before the next ';' or '}' which is not nested in a block opened after `start`
+fn recover(&mut self, start: usize) {
+    let mut depth = ::std::cmp::max(0, self.lexer.brace_depth_since(start));
+    loop {
+        match self.lexer.next().token {
+            LBRACE => depth += 1,
+            RBRACE if depth > 0 => depth -= 1,
+            SEMICOLON if depth > 0 => (),
+            SEMICOLON | RBRACE | EOF => {
+                self.lexer.backtrack();
+                return
+            }
+            _ => ()
+        }
+    }
+}
+
+///Parses the declarations of a block separated by ';'.
+///If a declaration fails to parse the error is stored and parsing continues with the next declaration
+fn declarations<T, F>(&mut self, mut f: F) -> Vec<T>
+    where F: FnMut(&mut Parser<Iter>) -> ParseResult<T> {
+    let mut result = Vec::new();
+    loop {
+        let start = self.lexer.position();
+        match f(self) {
+            Ok(x) => result.push(x),
+            Err(err) => {
+                self.errors.push(err);
+                self.recover(start);
+            }
+        }
+        if self.lexer.next().token != SEMICOLON {
+            self.lexer.backtrack();
+            break;
+        }
+    }
+    result
+}
+
+///Returns all errors which have been found, ordered by their location in the source
+fn take_errors(&mut self, error: Option<ParseError>) -> Errors<ParseError> {
+    let mut errors: Vec<ParseError> = self.lexer.take_errors().into_iter()
+        .map(|Located { span, node }| ParseError(Located { span: span, node: Error::Lexical(node) }))
+        .collect();
+    errors.extend(::std::mem::replace(&mut self.errors, Vec::new()));
+    errors.extend(error);
+    errors.sort_by_key(|err| (err.0.span.start.row, err.0.span.start.column));
+    let mut result = Errors::new();
+    for err in errors {
+        result.insert(err);
+    }
+    result
+}
+
+///Returns the span of the next token without consuming it
+fn next_span(&mut self) -> Span {
+    self.lexer.peek().span
+}
+
+///Returns a span which starts at `start` and ends at the last token which was consumed
+fn span_from(&self, start: Span) -> Span {
+    start.to(self.lexer.current().span)
+}
+
+///Parses a module, returning every syntax error in it if it could not be parsed
+pub fn module(&mut self) -> Result<Module, Errors<ParseError>> {
+    match self.module_() {
+        Ok(module) => self.take_errors(None).into_result(module),
+        Err(err) => Err(self.take_errors(Some(err)))
+    }
+}
+
+///Parses an expression such as a line of input to the REPL
+pub fn complete_expression(&mut self) -> Result<TypedExpr, Errors<ParseError>> {
+    match self.expression_() {
+        Ok(expr) => self.take_errors(None).into_result(expr),
+        Err(err) => Err(self.take_errors(Some(err)))
+    }
+}
+
+fn module_(&mut self) -> ParseResult<Module> {
 	let modulename = match self.lexer.module_next().token {
         MODULE => {
-            let modulename = expect!(self, NAME).value.clone();
+            let modulename = try!(self.module_name());
             expect!(self, WHERE);
             expect!(self, LBRACE);
             modulename
@@ -150,7 +356,14 @@ pub fn module(&mut self) -> ParseResult<Module> {
     let mut imports = Vec::new();
     loop {
         if self.lexer.peek().token == IMPORT {
-            imports.push(try!(self.import()));
+            let start = self.lexer.position();
+            match self.import() {
+                Ok(import) => imports.push(import),
+                Err(err) => {
+                    self.errors.push(err);
+                    self.recover(start);
+                }
+            }
             if self.lexer.peek().token == SEMICOLON {
                 self.lexer.next();
             }
@@ -169,35 +382,60 @@ pub fn module(&mut self) -> ParseResult<Module> {
     let mut type_declarations = Vec::new();
     let mut data_definitions = Vec::new();
     let mut newtypes = Vec::new();
+    let mut type_synonyms = Vec::new();
+    let mut defaults = None;
     let mut fixity_declarations = Vec::new();
 	loop {
 		//Do a lookahead to see what the next top level binding is
+		let start = self.lexer.position();
 		let token = self.lexer.peek().token;
-		if token == NAME || token == LPARENS {
-            match try!(self.binding_or_type_declaration()) {
+		let result = if token == NAME || token == LPARENS {
+            self.binding_or_type_declaration().map(|decl| match decl {
                 BindOrTypeDecl::Binding(bind) => bindings.push(bind),
                 BindOrTypeDecl::TypeDecl(decl) => type_declarations.push(decl)
-            }
+            })
 		}
 		else if token == CLASS {
-			classes.push(try!(self.class()));
+			self.class().map(|class| classes.push(class))
 		}
 		else if token == INSTANCE {
-			instances.push(try!(self.instance()));
+			self.instance().map(|instance| instances.push(instance))
 		}
 		else if token == DATA {
-			data_definitions.push(try!(self.data_definition()));
+			self.data_definition().map(|data| data_definitions.push(data))
 		}
 		else if token == NEWTYPE {
-			newtypes.push(try!(self.newtype()));
+			self.newtype().map(|newtype| newtypes.push(newtype))
+		}
+		else if token == TYPE {
+			self.type_synonym().map(|synonym| type_synonyms.push(synonym))
+		}
+		else if token == DEFAULT {
+			self.default_declaration().map(|types| defaults = Some(types))
 		}
 		else if token == INFIXL || token == INFIXR || token == INFIX {
-            fixity_declarations.push(try!(self.fixity_declaration()));
+            self.fixity_declaration().map(|fixity| fixity_declarations.push(fixity))
         }
-        else {
+        else if token == RBRACE || token == EOF {
             self.lexer.next();
 			break;
 		}
+        else if token == SEMICOLON {
+            //Empty declaration
+            Ok(())
+        }
+        else {
+            self.lexer.next();
+            static EXPECTED: &'static [TokenEnum] = &[NAME, LPARENS, CLASS, INSTANCE, DATA, NEWTYPE, TYPE, DEFAULT, INFIXL, INFIXR, INFIX];
+            Err(self.unexpected_token(EXPECTED, token))
+        };
+        match result {
+            Ok(()) => (),
+            Err(err) => {
+                self.errors.push(err);
+                self.recover(start);
+            }
+        }
 		let semicolon = self.lexer.next();
         debug!("More bindings? {:?}", semicolon.token);
 	    if semicolon.token != SEMICOLON {
@@ -218,13 +456,38 @@ pub fn module(&mut self) -> ParseResult<Module> {
         instances : instances,
         data_definitions : data_definitions,
         newtypes: newtypes,
+        type_synonyms: type_synonyms,
+        defaults: defaults,
         fixity_declarations : fixity_declarations
     })
 }
 
+///Parses a module name which may be made of several names separated by dots such as `System.Exit`
+fn module_name(&mut self) -> ParseResult<InternedStr> {
+    let first = expect!(self, NAME).value;
+    if !(self.lexer.peek().token == OPERATOR && self.lexer.peek().value.as_ref() == ".") {
+        return Ok(first)
+    }
+    let mut name = ::std::string::String::from(first.as_ref());
+    while self.lexer.peek().token == OPERATOR && self.lexer.peek().value.as_ref() == "." {
+        self.lexer.next();
+        name.push('.');
+        name.push_str(expect!(self, NAME).value.as_ref());
+    }
+    Ok(intern(name.as_ref()))
+}
+
 fn import(&mut self) -> ParseResult<Import<InternedStr>> {
-    expect!(self, IMPORT);
-    let module_name = expect!(self, NAME).value;
+    let start = expect!(self, IMPORT).span;
+    let qualified = self.contextual_keyword("qualified");
+    let module_name = try!(self.module_name());
+    let alias = if self.contextual_keyword("as") {
+        Some(try!(self.module_name()))
+    }
+    else {
+        None
+    };
+    let hiding = self.contextual_keyword("hiding");
     let imports = if self.lexer.peek().token == LPARENS {
         self.lexer.next();
         let x = if self.lexer.peek().token == RPARENS {
@@ -238,19 +501,43 @@ fn import(&mut self) -> ParseResult<Import<InternedStr>> {
         };
         Some(x)
     }
+    else if hiding {
+        return self.error("Expected a list of names after `hiding`".to_string())
+    }
     else {
         None
     };
-    Ok(Import { module: module_name, imports: imports })
+    let (imports, hiding) = if hiding { (None, imports.unwrap_or(Vec::new())) } else { (imports, Vec::new()) };
+    Ok(Import {
+        module: module_name,
+        imports: imports,
+        hiding: hiding,
+        qualified: qualified,
+        alias: alias,
+        span: self.span_from(start)
+    })
+}
+
+///Consumes the next token if it is the name `keyword`, such as the `qualified` in an import.
+///Returns whether the token was consumed
+fn contextual_keyword(&mut self, keyword: &str) -> bool {
+    let found = {
+        let token = self.lexer.peek();
+        token.token == NAME && token.value.as_ref() == keyword
+    };
+    if found {
+        self.lexer.next();
+    }
+    found
 }
 
 fn class(&mut self) -> ParseResult<Class> {
-	expect!(self, CLASS);
+	let start = expect!(self, CLASS).span;
     let (constraints, typ) = try!(self.constrained_type());
 
 	expect!(self, WHERE);
 	expect!(self, LBRACE);
-	let x = try!(self.sep_by_1(|this| this.binding_or_type_declaration(), SEMICOLON));
+	let x = self.declarations(|this| this.binding_or_type_declaration());
     let mut bindings = Vec::new();
     let mut declarations = Vec::new();
     for decl_or_binding in x.into_iter() {
@@ -285,7 +572,8 @@ fn class(&mut self) -> ParseResult<Class> {
                         name: classname.name,
                         variable: var,
                         declarations: declarations,
-                        bindings: bindings
+                        bindings: bindings,
+                        span: self.span_from(start)
                     });
                 }
                 _ => ()
@@ -297,7 +585,7 @@ fn class(&mut self) -> ParseResult<Class> {
 }
 
 fn instance(&mut self) -> ParseResult<Instance> {
-	expect!(self, INSTANCE);
+	let start = expect!(self, INSTANCE).span;
 
     let (constraints, instance_type) = try!(self.constrained_type());
     match instance_type {
@@ -306,19 +594,20 @@ fn instance(&mut self) -> ParseResult<Instance> {
                 Type::Constructor(TypeConstructor { name: classname, ..}) => classname,
                 _ => return self.error("Expected type operator".to_string())
             };
+            let type_name = match *extract_applied_type(&*arg) {
+                Type::Constructor(ref ctor) => ctor.name,
+                _ => return self.error("Expected a type constructor in instance".to_string())
+            };
             expect!(self, WHERE);
             expect!(self, LBRACE);
 
-            let mut bindings = try!(self.sep_by_1(|this| this.binding(), SEMICOLON));
-            {
-                let inner_type = extract_applied_type(&*arg);
-                for bind in bindings.iter_mut() {
-                    bind.name = encode_binding_identifier(inner_type.ctor().name, bind.name);
-                }
+            let mut bindings = self.declarations(|this| this.binding());
+            for bind in bindings.iter_mut() {
+                bind.name = encode_binding_identifier(type_name, bind.name);
             }
 
             expect!(self, RBRACE);
-            Ok(Instance { typ : *arg, classname : classname, bindings : bindings, constraints: constraints })
+            Ok(Instance { typ : *arg, classname : classname, bindings : bindings, constraints: constraints, span: self.span_from(start) })
         }
         _ => return self.error("TypeVariable in instance".to_string())
     }
@@ -328,8 +617,8 @@ pub fn expression_(&mut self) -> ParseResult<TypedExpr> {
     match try!(self.expression()) {
         Some(expr) => Ok(expr),
         None => Err(ParseError(Located {
-            location: self.lexer.current().location,
-            node: Error::Message(format!("Failed to parse expression at {:?}", self.lexer.current().location))
+            span: self.lexer.current().span,
+            node: Error::Message("Failed to parse expression".to_string())
         }))
     }
 }
@@ -340,11 +629,14 @@ pub fn expression(&mut self) -> ParseResult<Option<TypedExpr>> {
         Some(expr) => {
             //Try to parse a type signature on this expression
             if self.lexer.next().token == TYPEDECL {
+                let start = self.next_span();
                 let (constraints, typ) = try!(self.constrained_type());
-                let loc = expr.location;
-                Ok(Some(TypedExpr::with_location(
-                    TypeSig(box expr, Qualified { constraints: constraints, value: typ }),
-                    loc)))
+                let annotation = Located {
+                    span: self.span_from(start),
+                    node: Qualified { constraints: constraints, value: typ }
+                };
+                let span = self.span_from(expr.span);
+                Ok(Some(TypedExpr::with_span(TypeSig(box expr, annotation), span)))
             }
             else {
                 self.lexer.backtrack();
@@ -369,35 +661,39 @@ fn list(&mut self) -> ParseResult<TypedExpr> {
             break;
         }
 	}
-    expect!(self, RBRACKET);
+    let end = expect!(self, RBRACKET).span;
 
-	let nil = TypedExpr::new(Identifier(intern("[]")));
+	let nil = TypedExpr::with_span(Identifier(intern("[]")), end);
     Ok(expressions.into_iter().rev().fold(nil, |application, expr| {
+        let span = expr.span;
 		let arguments = vec![expr, application];
-		make_application(TypedExpr::new(Identifier(intern(":"))), arguments.into_iter())
+		make_application(TypedExpr::with_span(Identifier(intern(":")), span), arguments.into_iter())
 	}))
 }
 
 fn sub_expression(&mut self) -> ParseResult<Option<TypedExpr>> {
 	let token = self.lexer.next().token;
+    let start = self.lexer.current().span;
     debug!("Begin SubExpr {:?}", self.lexer.current());
 	let expr = match token {
 	    LPARENS => {
-            let location = self.lexer.current().location;
             if self.lexer.peek().token == RPARENS {
                 self.lexer.next();
-                Some(TypedExpr::with_location(Identifier(intern("()")), location))
+                Some(TypedExpr::new(Identifier(intern("()"))))
+            }
+            else if let Some(operator) = self.operator_in_parens() {
+                Some(TypedExpr::new(Identifier(operator)))
             }
             else {
                 let mut expressions = try!(self.sep_by_1(|this| this.expression_(), COMMA));
                 expect!(self, RPARENS);
                 if expressions.len() == 1 {
                     let expr = expressions.pop().unwrap();
-                    let loc = expr.location;
-                    Some(TypedExpr::with_location(Paren(box expr), loc))
+                    Some(TypedExpr::new(Paren(box expr)))
                 }
                 else {
-                    Some(new_tuple(expressions))
+                    let span = self.span_from(start);
+                    Some(new_tuple(expressions, span))
                 }
             }
 		}
@@ -414,7 +710,6 @@ fn sub_expression(&mut self) -> ParseResult<Option<TypedExpr>> {
             }
 		}
 	    CASE => {
-            let location = self.lexer.current().location;
 			let expr = try!(self.expression());
 
 			expect!(self, OF);
@@ -423,12 +718,11 @@ fn sub_expression(&mut self) -> ParseResult<Option<TypedExpr>> {
 			let alts = try!(self.sep_by_1(|this| this.alternative(), SEMICOLON));
             expect!(self, RBRACE);
 			match expr {
-                Some(e) => Some(TypedExpr::with_location(Case(box e, alts), location)),
+                Some(e) => Some(TypedExpr::new(Case(box e, alts))),
                 None => None
             }
 		}
         IF => {
-            let location = self.lexer.current().location;
             let pred = try!(self.expression_());
             if self.lexer.peek().token == SEMICOLON {
                 self.lexer.next();
@@ -440,56 +734,77 @@ fn sub_expression(&mut self) -> ParseResult<Option<TypedExpr>> {
             }
             expect!(self, ELSE);
             let if_false = try!(self.expression_());
-            Some(TypedExpr::with_location(IfElse(box pred, box if_true, box if_false), location))
+            Some(TypedExpr::new(IfElse(box pred, box if_true, box if_false)))
         }
         LAMBDA => {
             let args = try!(self.pattern_arguments());
+            if args.len() == 0 {
+                let actual = self.lexer.next().token;
+                unexpected!(self, actual, NAME);
+            }
             expect!(self, ARROW);
             Some(make_lambda(args.into_iter(), try!(self.expression_())))
         }
         DO => {
-            let location = self.lexer.current().location;
             expect!(self, LBRACE);
             let mut bindings = try!(self.sep_by_1(|this| this.do_binding(), SEMICOLON));
             expect!(self, RBRACE);
             if bindings.len() == 0 {
-                return Err(ParseError(Located {
-                    location: self.lexer.current().location,
-                    node: Error::Message(format!("{:?}: Parse error: Empty do", self.lexer.current().location))
-                }));
+                return self.error("Parse error: Empty do".to_string());
             }
             let expr = match bindings.pop().unwrap() {
                 DoBinding::DoExpr(e) => e,
                 _ => return self.error("Parse error: Last binding in do must be an expression".to_string())
             };
-            Some(TypedExpr::with_location(Do(bindings, box expr), location))
+            Some(TypedExpr::new(Do(bindings, box expr)))
         }
         NAME => {
             let token = self.lexer.current();
-            Some(TypedExpr::with_location(Identifier(token.value.clone()), token.location))
-        }
-        NUMBER => {
-            let token = self.lexer.current();
-            Some(TypedExpr::with_location(Literal(Integral(FromStr::from_str(token.value.as_ref()).unwrap())), token.location))
+            Some(TypedExpr::new(Identifier(token.value.clone())))
         }
+        NUMBER => Some(TypedExpr::new(Literal(Integral(try!(self.number()))))),
 	    FLOAT => {
             let token = self.lexer.current();
-            Some(TypedExpr::with_location(Literal(Fractional(FromStr::from_str(token.value.as_ref()).unwrap())), token.location))
+            match FromStr::from_str(token.value.as_ref()) {
+                Ok(f) => Some(TypedExpr::new(Literal(Fractional(f)))),
+                Err(_) => return self.error(format!("invalid floating point literal {}", token.value))
+            }
         }
         STRING => {
             let token = self.lexer.current();
-            Some(TypedExpr::with_location(Literal(String(token.value.clone())), token.location))
+            Some(TypedExpr::new(Literal(String(token.value.clone()))))
         }
         CHAR => {
             let token = self.lexer.current();
-            Some(TypedExpr::with_location(Literal(Char(token.value.chars().next().expect("char at 0"))), token.location))
+            Some(TypedExpr::new(Literal(Char(token.value.chars().next().expect("char at 0")))))
         }
 	    _ => {
             self.lexer.backtrack();
             None
         }
     };
-    Ok(expr)
+    //Every sub expression covers all the tokens which were consumed while parsing it
+    Ok(expr.map(|mut expr| {
+        expr.span = self.span_from(start);
+        expr
+    }))
+}
+
+///Parses an operator used as a function, such as `(+)`, after the opening parenthesis has been read.
+///Returns `None` without consuming any tokens if the parentheses hold anything else
+fn operator_in_parens(&mut self) -> Option<InternedStr> {
+    if self.lexer.peek().token != OPERATOR {
+        return None
+    }
+    let operator = self.lexer.next().value.clone();
+    if self.lexer.peek().token == RPARENS {
+        self.lexer.next();
+        Some(operator)
+    }
+    else {
+        self.lexer.backtrack();
+        None
+    }
 }
 
 fn do_binding(&mut self) -> ParseResult<DoBinding> {
@@ -513,10 +828,7 @@ fn do_binding(&mut self) -> ParseResult<DoBinding> {
                 return self.expression_().map(move |e| DoBinding::DoBind(p, e));
             }
             EOF => {
-                return Err(ParseError(Located {
-                    location: self.lexer.current().location,
-                    node: Error::Message("Unexpected EOF".to_string())
-                }))
+                return self.error("Unexpected EOF".to_string())
             }
             _ => { debug!("Lookahead {:?}", self.lexer.current()); }
         }
@@ -550,30 +862,31 @@ fn binary_expression(&mut self, lhs : Option<TypedExpr>) -> ParseResult<Option<T
     debug!("Parse operator expression, {:?}", self.lexer.current());
     if self.lexer.next().token == OPERATOR {
 		let op = self.lexer.current().value;
-        let loc = self.lexer.current().location;
+        let loc = self.lexer.current().span;
 		let rhs = try!(self.application());
         let rhs = try!(self.binary_expression(rhs));
         let expr = match (lhs, rhs) {
             (Some(lhs), Some(rhs)) => {
-                Some(TypedExpr::with_location(OpApply(box lhs, op, box rhs), loc))
+                let span = lhs.span.to(rhs.span);
+                Some(TypedExpr::with_span(OpApply(box lhs, op, box rhs), span))
             }
             (Some(lhs), None) => {
-		        let name = TypedExpr::with_location(Identifier(op), loc);
-                Some(TypedExpr::with_location(Apply(box name, box lhs), loc))
+		        let name = TypedExpr::with_span(Identifier(op), loc);
+                let span = lhs.span.to(loc);
+                Some(TypedExpr::with_span(Apply(box name, box lhs), span))
             }
             (None, Some(rhs)) => {
                 if op == intern("-") {
-		            let name = TypedExpr::with_location(Identifier(intern("negate")), loc);
+		            let name = TypedExpr::with_span(Identifier(intern("negate")), loc);
                     let args = vec![rhs];
                     Some(make_application(name, args.into_iter()))
                 }
                 else {
-		            let name = TypedExpr::with_location(Identifier(intern("negate")), loc);
-                    let args = vec![TypedExpr::with_location(Identifier(intern("#")), loc), rhs];
-                    let mut apply = make_application(name, args.into_iter());
-                    apply.location = loc;
+		            let name = TypedExpr::with_span(Identifier(intern("negate")), loc);
+                    let args = vec![TypedExpr::with_span(Identifier(intern("#")), loc), rhs];
+                    let apply = make_application(name, args.into_iter());
                     let params = vec![intern("#")];
-                    Some(make_lambda(params.into_iter().map(|a| Pattern::Identifier(a)), apply))
+                    Some(make_lambda(params.into_iter().map(|a| Located { span: loc, node: Pattern::Identifier(a) }), apply))
                 }
             }
             (None, None) => return Ok(None)
@@ -599,9 +912,7 @@ fn application(&mut self) -> ParseResult<Option<TypedExpr>> {
                 }
             }
             if expressions.len() > 0 {
-                let loc = lhs.location;
-                lhs = make_application(lhs, expressions.into_iter());//, loc);
-                lhs.location = loc;
+                lhs = make_application(lhs, expressions.into_iter());
             }
             Ok(Some(lhs))
         }
@@ -623,6 +934,7 @@ fn binding(&mut self) -> ParseResult<Binding> {
 	//or
 	//name2 x y = expr
 	let name_token = self.lexer.next().token;
+    let start = self.lexer.current().span;
 	let mut name = self.lexer.current().value.clone();
 	if name_token == LPARENS {
 		//Parse a name within parentheses
@@ -654,6 +966,7 @@ fn binding(&mut self) -> ParseResult<Binding> {
         arguments: arguments,
         where_bindings : where_bindings,
         matches : matches,
+        span: self.span_from(start)
     })
 }
 
@@ -687,6 +1000,7 @@ fn binding_or_type_declaration(&mut self) -> ParseResult<BindOrTypeDecl> {
 }
 
 fn fixity_declaration(&mut self) -> ParseResult<FixityDeclaration> {
+    let start = self.next_span();
     let assoc = {
         match self.lexer.next().token {
             INFIXL => Assoc::Left,
@@ -696,14 +1010,14 @@ fn fixity_declaration(&mut self) -> ParseResult<FixityDeclaration> {
         }
     };
     let precedence = match self.lexer.next().token {
-        NUMBER => FromStr::from_str(self.lexer.current().value.as_ref()).unwrap(),
+        NUMBER => try!(self.number()),
         _ => {
             self.lexer.backtrack();
             9
         }
     };
     let operators = try!(self.sep_by_1(|this| Ok(expect!(this, OPERATOR).value), COMMA));
-    Ok(FixityDeclaration { assoc: assoc, precedence: precedence, operators: operators })
+    Ok(FixityDeclaration { assoc: assoc, precedence: precedence, operators: operators, span: self.span_from(start) })
 }
 
 fn expr_or_guards(&mut self, end_token_and_pipe: &'static [TokenEnum]) -> ParseResult<Match> {
@@ -742,37 +1056,37 @@ fn make_pattern<F>(&mut self, name: InternedStr, args: F) -> ParseResult<Pattern
     }
 }
 
-fn pattern_arguments(&mut self) -> ParseResult<Vec<Pattern>> {
+fn pattern_arguments(&mut self) -> ParseResult<Vec<Located<Pattern>>> {
 	let mut parameters = Vec::new();
 	loop {
+        let start = self.next_span();
 		let token = self.lexer.next().token;
-		match token {
+		let pattern = match token {
             NAME => {
                 let name = self.lexer.current().value;
-                let p = try!(self.make_pattern(name, |_| Ok(vec![])));
-                parameters.push(p);
+                try!(self.make_pattern(name, |_| Ok(vec![])))
             }
-            NUMBER => parameters.push(Pattern::Number(FromStr::from_str(self.lexer.current().value.as_ref()).unwrap())),
+            NUMBER => Pattern::Number(try!(self.number())),
 		    LPARENS => {
                 self.lexer.backtrack();
-				parameters.push(try!(self.pattern()));
+				try!(self.pattern())
 			}
             LBRACKET => {
                 expect!(self, RBRACKET);
-                parameters.push(Pattern::Constructor(intern("[]"), vec![]));
+                Pattern::Constructor(intern("[]"), vec![])
             }
 		    _ => { break; }
-		}
+		};
+        parameters.push(Located { span: self.span_from(start), node: pattern });
 	}
 	self.lexer.backtrack();
 	Ok(parameters)
 }
 
 fn located_pattern(&mut self) -> ParseResult<Located<Pattern>> {
-    let location = self.lexer.next().location;
-    self.lexer.backtrack();
-    self.pattern()
-        .map(|pattern| Located { location: location, node: pattern })
+    let start = self.next_span();
+    let pattern = try!(self.pattern());
+    Ok(Located { span: self.span_from(start), node: pattern })
 }
 
 fn pattern(&mut self) -> ParseResult<Pattern> {
@@ -783,8 +1097,10 @@ fn pattern(&mut self) -> ParseResult<Pattern> {
             expect!(self, RBRACKET);
             Pattern::Constructor(intern("[]"), vec![])
         }
-        NAME => try!(self.make_pattern(name, |this| this.pattern_arguments())),
-        NUMBER => Pattern::Number(FromStr::from_str(name.as_ref()).unwrap()),
+        NAME => try!(self.make_pattern(name, |this| {
+            this.pattern_arguments().map(|args| args.into_iter().map(|arg| arg.node).collect())
+        })),
+        NUMBER => Pattern::Number(try!(self.number())),
         LPARENS => {
             if self.lexer.peek().token == RPARENS {
                 self.lexer.next();
@@ -814,6 +1130,7 @@ fn pattern(&mut self) -> ParseResult<Pattern> {
 }
 
 fn type_declaration(&mut self) -> ParseResult<TypeDeclaration> {
+    let start = self.next_span();
     let mut name;
 	{
         let name_token = self.lexer.next().token;
@@ -833,7 +1150,7 @@ fn type_declaration(&mut self) -> ParseResult<TypeDeclaration> {
     }
     expect!(self, TYPEDECL);
     let (context, typ) = try!(self.constrained_type());
-	Ok(TypeDeclaration { name : name, typ : Qualified { constraints : context, value: typ } })
+	Ok(TypeDeclaration { name : name, typ : Qualified { constraints : context, value: typ }, span: self.span_from(start) })
 }
 
 fn constrained_type(&mut self) -> ParseResult<(Vec<Constraint>, Type)> {
@@ -870,7 +1187,17 @@ fn constrained_type(&mut self) -> ParseResult<(Vec<Constraint>, Type)> {
             Ok(make_tuple_type(args))
         }
     });
-	Ok((make_constraints(maybe_constraints), typ))
+    let mut constraints = try!(self.make_constraints(maybe_constraints));
+    //The variables in a context are parsed without any arguments so take their kinds from the type instead,
+    //otherwise a constraint such as `Applicative f` would not match the `f` in `f a`
+    for constraint in constraints.iter_mut() {
+        for var in constraint.variables.iter_mut() {
+            if let Some(kind) = find_variable_kind(var.id, &typ) {
+                var.kind = kind;
+            }
+        }
+    }
+	Ok((constraints, typ))
 }
 
 fn constructor_type(&mut self, arity : &mut isize, data_def: &DataDefinition) -> ParseResult<Type> {
@@ -892,6 +1219,12 @@ fn constructor_type(&mut self, arity : &mut isize, data_def: &DataDefinition) ->
         expect!(self, RPARENS);
         function_type_(arg, try!(self.constructor_type(arity, data_def)))
     }
+    else if token == LBRACKET {
+        *arity += 1;
+        let arg = try!(self.parse_type());
+        expect!(self, RBRACKET);
+        function_type_(list_type(arg), try!(self.constructor_type(arity, data_def)))
+    }
     else {
 		data_def.typ.value.clone()
 	};
@@ -900,13 +1233,14 @@ fn constructor_type(&mut self, arity : &mut isize, data_def: &DataDefinition) ->
 
 
 fn data_definition(&mut self) -> ParseResult<DataDefinition> {
-	expect!(self, DATA);
+	let start = expect!(self, DATA).span;
 
 	let mut definition = DataDefinition {
         constructors : Vec::new(),
         typ : qualified(vec![], Type::new_var(intern("a"))),
         parameters : HashMap::new(),
-        deriving: Vec::new()
+        deriving: Vec::new(),
+        span: start
     };
     definition.typ.value = try!(self.data_lhs());
     expect!(self, EQUALSSIGN);
@@ -917,12 +1251,13 @@ fn data_definition(&mut self) -> ParseResult<DataDefinition> {
 		definition.constructors[ii].tag = ii as isize;
 	}
     definition.deriving = try!(self.deriving());
+    definition.span = self.span_from(start);
 	Ok(definition)
 }
 
 fn newtype(&mut self) -> ParseResult<Newtype> {
     debug!("Parsing newtype");
-    expect!(self, NEWTYPE);
+    let start = expect!(self, NEWTYPE).span;
     let typ = try!(self.data_lhs());
     expect!(self, EQUALSSIGN);
     let name = expect!(self, NAME).value;
@@ -931,14 +1266,48 @@ fn newtype(&mut self) -> ParseResult<Newtype> {
         None => return self.error("Parse error when parsing argument to new type".to_string())
     };
     
+    let deriving = try!(self.deriving());
     Ok(Newtype {
         typ: qualified(Vec::new(), typ.clone()),
         constructor_name: name,
         constructor_type: qualified(Vec::new(), function_type_(arg_type, typ)),
-        deriving: try!(self.deriving())
+        deriving: deriving,
+        span: self.span_from(start)
+    })
+}
+
+fn type_synonym(&mut self) -> ParseResult<TypeSynonym> {
+    debug!("Parsing type synonym");
+    let start = expect!(self, TYPE).span;
+    let name = expect!(self, NAME).value;
+    let mut parameters = Vec::new();
+    while self.lexer.next().token == NAME {
+        parameters.push(TypeVariable::new(self.lexer.current().value));
+    }
+    self.lexer.backtrack();
+    expect!(self, EQUALSSIGN);
+    let typ = try!(self.parse_type());
+    Ok(TypeSynonym {
+        name: name,
+        parameters: parameters,
+        typ: typ,
+        span: self.span_from(start)
     })
 }
 
+///Parses a declaration such as `default (Integer, Double)`
+fn default_declaration(&mut self) -> ParseResult<Vec<Type>> {
+    expect!(self, DEFAULT);
+    expect!(self, LPARENS);
+    if self.lexer.peek().token == RPARENS {
+        self.lexer.next();
+        return Ok(Vec::new())
+    }
+    let types = try!(self.sep_by_1(|this| this.parse_type(), COMMA));
+    expect!(self, RPARENS);
+    Ok(types)
+}
+
 fn data_lhs(&mut self) -> ParseResult<Type> {
 	let name = expect!(self, NAME).value.clone();
     let mut typ = Type::Constructor(TypeConstructor { name: name, kind: Kind::Star.clone() });
@@ -977,13 +1346,9 @@ fn set_kind(typ: &mut Type, kind: isize) {
 fn sub_type(&mut self) -> ParseResult<Option<Type>> {
 	let token = (*self.lexer.next()).clone();
 	let t = match token.token {
-	    LBRACKET => {
-            self.lexer.backtrack();
-            Some(try!(self.parse_type()))
-		}
-	    LPARENS => {
+	    LBRACKET | LPARENS => {
             self.lexer.backtrack();
-			Some(try!(self.parse_type()))
+            Some(try!(self.atomic_type()))
 		}
 	    NAME => {
 			if token.value.chars().next().expect("char at 0").is_uppercase() {
@@ -998,26 +1363,35 @@ fn sub_type(&mut self) -> ParseResult<Option<Type>> {
     Ok(t)
 }
 
-fn parse_type(&mut self) -> ParseResult<Type> {
+///Parses a type which is enclosed in brackets or parentheses, not including any `->` which follows it
+fn atomic_type(&mut self) -> ParseResult<Type> {
 	let token = (*self.lexer.next()).clone();
 	match token.token {
 	    LBRACKET => {
             if self.lexer.next().token == RBRACKET {
-                let list = Type::new_op_kind(intern("[]"), vec![], Kind::new(2));
-                self.parse_return_type(list)
+                Ok(Type::new_op_kind(intern("[]"), vec![], Kind::new(2)))
             }
             else {
                 self.lexer.backtrack();
                 let t = try!(self.parse_type());
                 expect!(self, RBRACKET);
-                let list = list_type(t);
-                self.parse_return_type(list)
+                Ok(list_type(t))
             }
 		}
 	    LPARENS => {
             if self.lexer.peek().token == RPARENS {
                 self.lexer.next();
-                self.parse_return_type(Type::new_op(intern("()"), vec![]))
+                Ok(Type::new_op(intern("()"), vec![]))
+            }
+            else if self.lexer.peek().token == COMMA {
+                //The constructor of a tuple type, such as `(,)` or `(,,)`
+                let mut arity = 1;
+                while self.lexer.peek().token == COMMA {
+                    self.lexer.next();
+                    arity += 1;
+                }
+                expect!(self, RPARENS);
+                Ok(Type::new_op_kind(intern(tuple_name(arity).as_ref()), vec![], Kind::new(arity as isize + 1)))
             }
             else {
                 let t = try!(self.parse_type());
@@ -1027,10 +1401,10 @@ fn parse_type(&mut self) -> ParseResult<Type> {
                         tuple_args.insert(0, t);
                         expect!(self, RPARENS);
 
-                        self.parse_return_type(make_tuple_type(tuple_args))
+                        Ok(make_tuple_type(tuple_args))
                     }
                     RPARENS => {
-                        self.parse_return_type(t)
+                        Ok(t)
                     }
                     _ => {
                         unexpected!(self, [COMMA, RPARENS])
@@ -1038,6 +1412,24 @@ fn parse_type(&mut self) -> ParseResult<Type> {
                 }
             }
 		}
+	    _ => unexpected!(self, [LBRACKET, LPARENS])
+	}
+}
+
+fn parse_type(&mut self) -> ParseResult<Type> {
+	let token = (*self.lexer.next()).clone();
+	match token.token {
+	    LBRACKET | LPARENS => {
+            self.lexer.backtrack();
+            let mut t = try!(self.atomic_type());
+            //A bare type constructor such as `(,)` or `[]` may be applied to arguments
+            if let Type::Constructor(_) = t {
+                while let Some(arg) = try!(self.sub_type()) {
+                    t = Type::Application(box t, box arg);
+                }
+            }
+            self.parse_return_type(t)
+		}
 	    NAME => {
 			let mut type_arguments = Vec::new();
             loop {
@@ -1070,6 +1462,26 @@ fn parse_return_type(&mut self, typ : Type) -> ParseResult<Type> {
     }
 }
 
+fn make_constraints(&self, types: Vec<Type>) -> ParseResult<Vec<Constraint>> {
+    let mut constraints = Vec::new();
+    for typ in types.into_iter() {
+        match typ {
+            Type::Application(lhs, rhs) => {
+                match (*lhs, *rhs) {
+                    (Type::Constructor(class), Type::Variable(var)) => {
+                        constraints.push(Constraint { class: class.name, variables: vec![var] });
+                        continue
+                    }
+                    _ => ()
+                }
+            }
+            _ => ()
+        }
+        return self.error("Parse error in constraint, expected a class applied to a type variable".to_string())
+    }
+    Ok(constraints)
+}
+
 fn sep_by_1<T, F>(&mut self, f : F, sep : TokenEnum) -> ParseResult<Vec<T>>
     where F: FnMut(&mut Parser<Iter>) -> ParseResult<T> {
     self.sep_by_1_func(f, |tok| tok.token == sep)
@@ -1089,41 +1501,39 @@ fn sep_by_1_func<T, F, P>(&mut self, mut f : F, mut sep: P) -> ParseResult<Vec<T
 }
 }//end impl Parser
 
-fn make_constraints(types: Vec<Type>) -> Vec<Constraint> {
-    types.into_iter().map(|typ| {
-        match typ {
-            Type::Application(lhs, rhs) => {
-                Constraint { class: lhs.ctor().name.clone(), variables: vec![rhs.var().clone()] }
-            }
-            _ => panic!("Parse error in constraint, non applied type")
-        }
-    }).collect()
-}
-
 fn make_application<I: Iterator<Item=TypedExpr>>(f : TypedExpr, args : I) -> TypedExpr {
     let mut func = f;
 	for a in args {
-        let loc = func.location.clone();
-		func = TypedExpr::with_location(Apply(box func, box a), loc);
+        let span = func.span.to(a.span);
+		func = TypedExpr::with_span(Apply(box func, box a), span);
 	}
     func
 }
 
-fn make_lambda<Iter: DoubleEndedIterator<Item=Pattern<InternedStr>>>(args : Iter, body : TypedExpr) -> TypedExpr {
+fn make_lambda<Iter: DoubleEndedIterator<Item=Located<Pattern<InternedStr>>>>(args : Iter, body : TypedExpr) -> TypedExpr {
 	let mut body = body;
 	for a in args.rev() {
-        let loc = body.location.clone();
-		body = TypedExpr::with_location(Lambda(a, box body), loc);
+        let span = body.span;
+		body = TypedExpr::with_span(Lambda(a, box body), span);
 	}
     body
 }
 
 //Create a tuple with the constructor name inferred from the number of arguments passed in
-fn new_tuple(arguments : Vec<TypedExpr>) -> TypedExpr {
-	let name = TypedExpr::new(Identifier(intern(tuple_name(arguments.len()).as_ref())));
+fn new_tuple(arguments : Vec<TypedExpr>, span : Span) -> TypedExpr {
+	let name = TypedExpr::with_span(Identifier(intern(tuple_name(arguments.len()).as_ref())), span);
 	make_application(name, arguments.into_iter())
 }
 
+///Returns the kind of the first occurence of the variable `id` in `typ`
+fn find_variable_kind(id: InternedStr, typ: &Type) -> Option<Kind> {
+    match *typ {
+        Type::Variable(ref var) if var.id == id => Some(var.kind.clone()),
+        Type::Application(ref lhs, ref rhs) => find_variable_kind(id, &**lhs).or_else(|| find_variable_kind(id, &**rhs)),
+        _ => None
+    }
+}
+
 fn make_tuple_type(mut types : Vec<Type>) -> Type {
     if types.len() == 1 {
         types.pop().unwrap()
@@ -1133,46 +1543,76 @@ fn make_tuple_type(mut types : Vec<Type>) -> Type {
     }
 }
 
-pub fn parse_string(contents: &str) -> ParseResult<Vec<Module>> {
+pub fn parse_string(contents: &str) -> Result<Vec<Module>, Errors<ParseError>> {
     let mut modules = Vec::new();
     let mut visited = HashSet::new();
-    try!(parse_modules_(&mut visited, &mut modules, "<input>", contents));
+    try!(parse_modules_(&mut visited, &mut modules, "<input>", "<input>", contents));
     Ok(modules)
 }
 
 ///Parses a module and all its imports
 ///If the modules contain a cyclic dependency fail is called.
-pub fn parse_modules(modulename: &str) -> ParseResult<Vec<Module>> {
+pub fn parse_modules(modulename: &str) -> Result<Vec<Module>, Errors<ParseError>> {
     let mut modules = Vec::new();
     let mut visited = HashSet::new();
-    let contents = try!(get_contents(modulename));
-    try!(parse_modules_(&mut visited, &mut modules, modulename, contents.as_ref()));
+    let (filename, contents) = try!(get_contents(modulename).map_err(ParseError::from));
+    try!(parse_modules_(&mut visited, &mut modules, modulename, filename.as_ref(), contents.as_ref()));
     Ok(modules)
 }
 
-fn get_contents(modulename: &str) -> io::Result<::std::string::String> {
-    let mut filename = ::std::string::String::from(modulename);
+///Returns the directories which are searched for modules which are not found relative to the current directory.
+///These are the paths in `HASKELL_LIBRARY_PATH` if it is set, otherwise the directory holding the standard library
+pub fn library_directories() -> Vec<PathBuf> {
+    match env::var_os("HASKELL_LIBRARY_PATH") {
+        Some(paths) => env::split_paths(&paths).collect(),
+        None => vec![PathBuf::from(env!("CARGO_MANIFEST_DIR"))]
+    }
+}
+
+///Reads the file which contains the module `modulename`, returning the name of the file and its contents
+fn get_contents(modulename: &str) -> io::Result<(::std::string::String, ::std::string::String)> {
+    //The module `A.B` is found in the file `A/B.hs`
+    let mut filename = modulename.replace(".", "/");
     filename.push_str(".hs");
-    let mut file = try!(File::open(&filename));
+    let (filename, mut file) = match File::open(&filename) {
+        Ok(file) => (filename, file),
+        Err(err) => {
+            let found = library_directories().into_iter()
+                .map(|dir| dir.join(&filename))
+                .filter_map(|path| File::open(&path).ok().map(|file| (path.to_string_lossy().into_owned(), file)))
+                .next();
+            match found {
+                Some(found) => found,
+                None => return Err(err)
+            }
+        }
+    };
     let mut contents = ::std::string::String::new();
     try!(file.read_to_string(&mut contents));
-    Ok(contents)
+    Ok((filename, contents))
 }
 
-fn parse_modules_(visited: &mut HashSet<InternedStr>, modules: &mut Vec<Module>, modulename: &str, contents: &str) -> ParseResult<()> {
-    let mut parser = Parser::new(contents.chars());
+fn parse_modules_(visited: &mut HashSet<InternedStr>, modules: &mut Vec<Module>, modulename: &str, filename: &str, contents: &str) -> Result<(), Errors<ParseError>> {
+    let mut parser = Parser::with_file(contents.chars(), add_file(filename, contents));
     let module = try!(parser.module());
     let interned_name = intern(modulename);
     visited.insert(interned_name);
     for import in module.imports.iter() {
         if visited.contains(&import.module) {
-            return parser.error("Cyclic dependency in modules".to_string());
+            return Err(Errors::from(ParseError(Located {
+                span: import.span,
+                node: Error::CyclicDependency(import.module)
+            })));
         }
         else if modules.iter().all(|m| m.name != import.module) {
             //parse the module if it is not parsed
             let import_module = import.module.as_ref();
-            let contents_next = try!(get_contents(import_module));
-            try!(parse_modules_(visited, modules, import_module, contents_next.as_ref()));
+            let (filename_next, contents_next) = try!(get_contents(import_module)
+                .map_err(|err| ParseError(Located {
+                    span: import.span,
+                    node: Error::ModuleNotFound(import.module, err.to_string())
+                })));
+            try!(parse_modules_(visited, modules, import_module, filename_next.as_ref(), contents_next.as_ref()));
         }
     }
     visited.remove(&interned_name);
@@ -1184,11 +1624,13 @@ fn parse_modules_(visited: &mut HashSet<InternedStr>, modules: &mut Vec<Module>,
 mod tests {
 
 use interner::*;
-use lexer::{Location, Located };
+use lexer::{Span, Located, Location};
+use renamer::Errors;
+use source::add_file;
 use parser::*;
 use module::*;
 use module::Expr::*;
-use typecheck::{identifier, apply, op_apply, number, rational, let_, case, if_else};
+use typecheck::{identifier, apply, op_apply, number, rational, let_, case, if_else, paren, EraseSpans};
 use std::path::Path;
 use std::io::Read;
 use std::fs::File;
@@ -1207,7 +1649,7 @@ fn binding()
 {
     let mut parser = Parser::new("test x = x + 3".chars());
     let bind = parser.binding().unwrap();
-    assert_eq!(bind.arguments, vec![Pattern::Identifier(intern("x"))]);
+    assert_eq!(bind.arguments, vec![Located { span: Span::eof(), node: Pattern::Identifier(intern("x")) }]);
     assert_eq!(bind.matches, Match::Simple(op_apply(identifier("x"), intern("+"), number(3))));
     assert_eq!(bind.name, intern("test"));
 }
@@ -1217,7 +1659,7 @@ fn double()
 {
     let mut parser = Parser::new("test = 3.14".chars());
     let bind = parser.binding().unwrap();
-    assert_eq!(bind.matches, Match::Simple(rational(3.14)));
+    assert_eq!(bind.matches, Match::Simple(rational("3.14")));
     assert_eq!(bind.name, intern("test"));
 }
 
@@ -1228,9 +1670,10 @@ r"
 let
     test = add 3 2
 in test - 2".chars());
-    let expr = parser.expression_().unwrap();
+    let mut expr = parser.expression_().unwrap();
+    EraseSpans.visit_expr(&mut expr);
     let bind = Binding { arguments: vec![], name: intern("test"), typ: Default::default(),
-        matches: Match::Simple(apply(apply(identifier("add"), number(3)), number(2))), where_bindings: None };
+        matches: Match::Simple(apply(apply(identifier("add"), number(3)), number(2))), where_bindings: None, span: Span::eof() };
     assert_eq!(expr, let_(vec![bind], op_apply(identifier("test"), intern("-"), number(2))));
 }
 
@@ -1244,14 +1687,14 @@ r"case [] of
     let expression = parser.expression_().unwrap();
     let alt = Alternative {
         pattern: Located {
-            location: Location::eof(),
+            span: Span::eof(),
             node: Pattern::Constructor(intern(":"), vec![Pattern::Identifier(intern("x")), Pattern::Identifier(intern("xs"))])
         },
         matches: Match::Simple(identifier("x")),
         where_bindings: None
     };
     let alt2 = Alternative {
-        pattern: Located { location: Location::eof(), node: Pattern::Constructor(intern("[]"), vec![]) },
+        pattern: Located { span: Span::eof(), node: Pattern::Constructor(intern("[]"), vec![]) },
         matches: Match::Simple(number(2)),
         where_bindings: None
     };
@@ -1271,6 +1714,19 @@ r"(.) :: (b -> c) -> (a -> b) -> (a -> c)".chars());
     assert_eq!(type_decl.name, intern("."));
     assert_eq!(type_decl.typ.value, f);
 }
+
+#[test]
+fn parse_type_application_with_list_argument() {
+    let mut parser = Parser::new(
+r"found :: Maybe [Char] -> IO (Maybe Int) -> Int".chars());
+    let type_decl = parser.type_declaration().unwrap();
+    let maybe = |typ| Type::new_op(intern("Maybe"), vec![typ]);
+    let int = Type::new_op(intern("Int"), vec![]);
+    let io = Type::new_op(intern("IO"), vec![maybe(int.clone())]);
+    let f = function_type_(maybe(list_type(Type::new_op(intern("Char"), vec![]))), function_type_(io, int));
+
+    assert_eq!(type_decl.typ.value, f);
+}
 #[test]
 fn parse_data() {
     let mut parser = Parser::new(
@@ -1317,14 +1773,29 @@ r"case () :: () of
     () -> 1".chars());
     let expr = parser.expression_().unwrap();
 
-    assert_eq!(expr, case(TypedExpr::new(TypeSig(box identifier("()"), qualified(vec![], Type::new_op(intern("()"), vec![])))), 
+    assert_eq!(expr, case(TypedExpr::new(TypeSig(box identifier("()"), Located { span: Span::eof(), node: qualified(vec![], Type::new_op(intern("()"), vec![])) })), 
         vec![Alternative {
-        pattern: Located { location: Location::eof(), node: Pattern::Constructor(intern("()"), vec![])  },
+        pattern: Located { span: Span::eof(), node: Pattern::Constructor(intern("()"), vec![])  },
         matches: Match::Simple(number(1)),
         where_bindings: None
     } ]));
 }
 
+#[test]
+fn parse_operator_as_function() {
+    let mut parser = Parser::new("foldr (&&) True (map (-) xs)".chars());
+    let expr = parser.expression_().unwrap();
+    assert_eq!(expr, apply(apply(apply(identifier("foldr"), identifier("&&")), identifier("True")),
+        paren(apply(apply(identifier("map"), identifier("-")), identifier("xs")))));
+}
+
+#[test]
+fn parse_negation_in_parens() {
+    let mut parser = Parser::new("(- 1)".chars());
+    let expr = parser.expression_().unwrap();
+    assert_eq!(expr, paren(apply(identifier("negate"), number(1))));
+}
+
 #[test]
 fn test_operators() {
     let mut parser = Parser::new("1 : 2 : []".chars());
@@ -1355,6 +1826,25 @@ instance Eq a => Eq [a] where
     assert_eq!(module.instances[0].typ, list_type(Type::new_var(intern("a"))));
 }
 #[test]
+fn parse_tuple_constructor_instance() {
+    let mut parser = Parser::new(
+r"instance Functor ((,) a) where
+    fmap f x = undefined".chars());
+    let module = parser.module().unwrap();
+
+    let pair = Type::new_op_kind(intern("(,)"), vec![], Kind::new(3));
+    assert_eq!(module.instances[0].typ, Type::Application(box pair, box Type::new_var(intern("a"))));
+    assert_eq!(module.instances[0].bindings[0].name, intern("#(,)fmap"));
+}
+#[test]
+fn parse_higher_kinded_constraint() {
+    let mut parser = Parser::new(
+r"traverse :: Applicative f => (a -> f b) -> [a] -> f [b]".chars());
+    let type_decl = parser.type_declaration().unwrap();
+
+    assert_eq!(type_decl.typ.constraints[0].variables[0].kind, Kind::new(2));
+}
+#[test]
 fn parse_super_class() {
     let mut parser = Parser::new(
 r"class Eq a => Ord a where
@@ -1382,7 +1872,7 @@ r"main = do
 
     let b = TypedExpr::new(Do(vec![
         DoBinding::DoExpr(apply(identifier("putStrLn"), identifier("test"))),
-        DoBinding::DoBind(Located { location: Location::eof(), node: Pattern::Identifier(intern("s")) }, identifier("getContents"))
+        DoBinding::DoBind(Located { span: Span::eof(), node: Pattern::Identifier(intern("s")) }, identifier("getContents"))
         ], box apply(identifier("return"), identifier("s"))));
     assert_eq!(module.bindings[0].matches, Match::Simple(b));
 }
@@ -1391,7 +1881,7 @@ fn lambda_pattern() {
     let mut parser = Parser::new(r"\(x, _) -> x".chars());
     let expr = parser.expression_().unwrap();
     let pattern = Pattern::Constructor(intern("(,)"), vec![Pattern::Identifier(intern("x")), Pattern::WildCard]);
-    assert_eq!(expr, TypedExpr::new(Lambda(pattern, box identifier("x"))));
+    assert_eq!(expr, TypedExpr::new(Lambda(Located { span: Span::eof(), node: pattern }, box identifier("x"))));
 }
 
 
@@ -1401,6 +1891,7 @@ fn parse_imports() {
 r"import Hello
 import World ()
 import Prelude (id, sum)
+import System.Exit
 
 ".chars());
     let module = parser.module().unwrap();
@@ -1411,6 +1902,27 @@ import Prelude (id, sum)
     assert_eq!(module.imports[1].imports, Some(Vec::new()));
     assert_eq!(module.imports[2].module.as_ref(), "Prelude");
     assert_eq!(module.imports[2].imports, Some(vec![intern("id"), intern("sum")]));
+    assert_eq!(module.imports[3].module.as_ref(), "System.Exit");
+}
+#[test]
+fn parse_qualified_imports() {
+    let mut parser = Parser::new(
+r"import qualified Data.Map as Map
+import Data.Set as Set
+import Prelude hiding (map, filter)
+test = Map.insert 1 2 Map.empty
+".chars());
+    let module = parser.module().unwrap();
+
+    assert_eq!(module.imports[0].module.as_ref(), "Data.Map");
+    assert!(module.imports[0].qualified);
+    assert_eq!(module.imports[0].alias, Some(intern("Map")));
+    assert_eq!(module.imports[1].module.as_ref(), "Data.Set");
+    assert!(!module.imports[1].qualified);
+    assert_eq!(module.imports[1].alias, Some(intern("Set")));
+    assert_eq!(module.imports[2].imports, None);
+    assert_eq!(module.imports[2].hiding, vec![intern("map"), intern("filter")]);
+    assert_eq!(module.bindings[0].matches, Match::Simple(apply(apply(apply(identifier("Map.insert"), number(1)), number(2)), identifier("Map.empty"))));
 }
 #[test]
 fn parse_module_imports() {
@@ -1429,13 +1941,15 @@ test x
     | x = 1
     | otherwise = 0
 ".chars());
-    let binding = parser.binding().unwrap();
-    let b2 = Binding { arguments: vec![Pattern::Identifier(intern("x"))], name: intern("test"), typ: Default::default(),
+    let mut binding = parser.binding().unwrap();
+    EraseSpans.visit_binding(&mut binding);
+    let b2 = Binding { arguments: vec![Located { span: Span::eof(), node: Pattern::Identifier(intern("x")) }], name: intern("test"), typ: Default::default(),
         matches: Match::Guards(vec![
             Guard { predicate: identifier("x"), expression: number(1) },
             Guard { predicate: identifier("otherwise"), expression: number(0) },
         ]),
-        where_bindings: None
+        where_bindings: None,
+        span: Span::eof()
     };
     assert_eq!(binding, b2);
 }
@@ -1452,10 +1966,11 @@ infixr 6 `test2`, |<
 
 test2 x y = 1
 ".chars());
-    let module = parser.module().unwrap();
+    let mut module = parser.module().unwrap();
+    EraseSpans.visit_module(&mut module);
     assert_eq!(module.fixity_declarations, [
-        FixityDeclaration { assoc: Assoc::Right, precedence: 5, operators: vec![intern("test")] },
-        FixityDeclaration { assoc: Assoc::Right, precedence: 6, operators: vec![intern("test2"), intern("|<")] },
+        FixityDeclaration { assoc: Assoc::Right, precedence: 5, operators: vec![intern("test")], span: Span::eof() },
+        FixityDeclaration { assoc: Assoc::Right, precedence: 6, operators: vec![intern("test2"), intern("|<")], span: Span::eof() },
     ]);
 }
 
@@ -1541,6 +2056,44 @@ newtype IntPair a = IntPair (a, Int)
     assert_eq!(module.newtypes[0].constructor_type.value, function_type_(Type::new_op(intern("(,)"), vec![a, int_type()]), typ));
 }
 
+#[test]
+fn binding_and_expression_spans() {
+    let contents =
+r"main = 1
+
+test x = case x of
+    y -> primIntAdd y 1
+";
+    let file = add_file("Spans.hs", contents);
+    let module = Parser::with_file(contents.chars(), file).module().unwrap();
+    let bind = &module.bindings[1];
+    assert_eq!(bind.span.file, file);
+    assert_eq!(format!("{}", bind.span), "Spans.hs:3:1-4:23");
+    assert_eq!(format!("{}", bind.arguments[0].span), "Spans.hs:3:6-6");
+    match bind.matches {
+        Match::Simple(ref e) => {
+            assert_eq!(format!("{}", e.span), "Spans.hs:3:10-4:23");
+            match e.expr {
+                Case(_, ref alts) => assert_eq!(format!("{}", alts[0].pattern.span), "Spans.hs:4:5-5"),
+                _ => panic!("Expected case")
+            }
+        }
+        _ => panic!("Expected simple binding")
+    }
+}
+
+#[test]
+fn parse_error_location() {
+    let contents =
+r"main = 1
+test = (1, 2";
+    let file = add_file("Error.hs", contents);
+    let err = Parser::with_file(contents.chars(), file).module().unwrap_err();
+    let message = format!("{}", err);
+    assert!(message.starts_with("error[E0001]"), "{}", message);
+    assert!(message.contains("--> Error.hs:2:"), "{}", message);
+}
+
 #[test]
 fn parse_prelude() {
     let path = &Path::new("Prelude.hs");
@@ -1549,9 +2102,10 @@ fn parse_prelude() {
     let mut parser = Parser::new(contents.chars());
     let module = parser.module().unwrap();
 
-    assert!(module.bindings.iter().any(|bind| bind.name == intern("foldl")));
+    assert!(module.bindings.iter().any(|bind| bind.name == intern("concat")));
     assert!(module.bindings.iter().any(|bind| bind.name == intern("id")));
     assert!(module.classes.iter().any(|class| class.name == intern("Eq")));
+    assert!(module.classes.iter().any(|class| class.name == intern("Foldable")));
 }
 
 #[bench]
@@ -1565,4 +2119,94 @@ fn bench_prelude(b: &mut Bencher) {
     });
 }
 
+
+///Parses every file in tests/parse_errors, all of which contain syntax errors
+fn parse_error_corpus() -> Vec<(::std::string::String, Result<Module, Errors<ParseError>>)> {
+    let mut paths: Vec<_> = ::std::fs::read_dir("tests/parse_errors").unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    paths.into_iter().map(|path| {
+        let mut contents = ::std::string::String::new();
+        File::open(&path).and_then(|mut f| f.read_to_string(&mut contents)).unwrap();
+        let name = path.to_str().unwrap().to_string();
+        let mut parser = Parser::with_file(contents.chars(), add_file(&name, &contents));
+        let result = parser.module();
+        (name, result)
+    }).collect()
+}
+
+#[test]
+fn malformed_input_reports_errors() {
+    for (name, result) in parse_error_corpus() {
+        match result {
+            Ok(_) => panic!("Expected {} to fail to parse", name),
+            Err(errors) => {
+                assert!(errors.len() != 0);
+                for error in errors.iter() {
+                    assert!(error.0.span.start != Location::eof(), "{}: {}", name, error);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn multiple_errors_in_one_file() {
+    let (_, result) = parse_error_corpus().into_iter()
+        .find(|&(ref name, _)| name.ends_with("several_errors.hs"))
+        .unwrap();
+    let errors = result.unwrap_err();
+    let rows: Vec<isize> = errors.iter().map(|error| error.0.span.start.row).collect();
+    assert_eq!(rows, vec![1, 3]);
+}
+
+#[test]
+fn recovered_declarations_are_parsed() {
+    let mut parser = Parser::new(
+r"a = 1
+b = (
+class C a where
+    f :: a -> a
+    g = )
+    h x = x
+c = 2".chars());
+    let errors = parser.module().unwrap_err();
+    assert_eq!(errors.len(), 2);
+    //The parser continues after each error so it sees both of them but no errors after them
+    let mut parser = Parser::new("a = 1 @ 2\nb = \"abc".chars());
+    let errors = parser.module().unwrap_err();
+    assert_eq!(errors.len(), 2);
+}
+
+///Truncates and mutates the Prelude in many different ways to check that the parser never panics
+#[test]
+fn mutated_prelude_does_not_panic() {
+    let mut contents = ::std::string::String::new();
+    File::open("Prelude.hs").and_then(|mut f| f.read_to_string(&mut contents)).unwrap();
+    let chars: Vec<char> = contents.chars().collect();
+    let replacements = ['(', ')', '[', ']', '{', '}', ',', ';', '=', '|', '\\', '"', '\'', '`', '\n', ' ', 'x', '1'];
+    //A simple linear congruential generator so that the test is deterministic
+    let mut seed: u64 = 12345;
+    let mut next = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as usize
+    };
+    for _ in 0..200 {
+        let mut mutated = chars.clone();
+        for _ in 0..(1 + next() % 3) {
+            let i = next() % mutated.len();
+            match next() % 3 {
+                0 => { mutated.remove(i); }
+                1 => mutated[i] = replacements[next() % replacements.len()],
+                _ => mutated.truncate(i)
+            }
+            if mutated.len() == 0 {
+                break
+            }
+        }
+        let mut parser = Parser::new(mutated.iter().cloned());
+        let _ = parser.module();
+    }
+}
 }
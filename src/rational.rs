@@ -0,0 +1,83 @@
+use std::fmt;
+use std::str::FromStr;
+use integer::{Integer, ParseIntegerError};
+
+///An exact fraction in lowest terms with a positive denominator.
+///Used as the value of fractional literals so that they can be converted exactly to `Rational`
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: Integer,
+    denominator: Integer
+}
+
+impl Rational {
+    ///Creates the rational `numerator / denominator`, panics if the denominator is zero
+    pub fn new(numerator: Integer, denominator: Integer) -> Rational {
+        assert!(!denominator.is_zero(), "Rational with a zero denominator");
+        let divisor = numerator.gcd(&denominator);
+        let divisor = if denominator.is_negative() { -&divisor } else { divisor };
+        Rational {
+            numerator: numerator.quot_rem(&divisor).unwrap().0,
+            denominator: denominator.quot_rem(&divisor).unwrap().0
+        }
+    }
+
+    pub fn numerator(&self) -> &Integer {
+        &self.numerator
+    }
+
+    pub fn denominator(&self) -> &Integer {
+        &self.denominator
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        Integer::ratio_to_f64(&self.numerator, &self.denominator)
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_f64())
+    }
+}
+
+impl fmt::Debug for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} % {}", self.numerator, self.denominator)
+    }
+}
+
+///Parses decimal fractions such as `3.14` or `-0.5`
+impl FromStr for Rational {
+    type Err = ParseIntegerError;
+    fn from_str(s: &str) -> Result<Rational, ParseIntegerError> {
+        let (whole, fraction) = match s.find('.') {
+            Some(index) => (&s[..index], &s[index + 1..]),
+            None => (s, "")
+        };
+        if fraction.starts_with("-") || fraction.starts_with("+") {
+            return Err(ParseIntegerError);
+        }
+        let numerator: Integer = try!(format!("{}{}", whole, fraction).parse());
+        let denominator = try!(format!("1{}", "0".repeat(fraction.len())).parse());
+        Ok(Rational::new(numerator, denominator))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rational::Rational;
+
+    #[test]
+    fn parse_and_reduce() {
+        let r: Rational = "2.50".parse().unwrap();
+        assert_eq!(r.numerator().to_string(), "5");
+        assert_eq!(r.denominator().to_string(), "2");
+        let r: Rational = "-0.125".parse().unwrap();
+        assert_eq!(format!("{:?}", r), "-1 % 8");
+        assert_eq!("0.1".parse::<Rational>().unwrap().to_f64(), 0.1);
+        assert_eq!("3".parse::<Rational>().unwrap().to_f64(), 3.);
+        assert!("1.x".parse::<Rational>().is_err());
+        assert!("1.-2".parse::<Rational>().is_err());
+    }
+}
@@ -1,7 +1,9 @@
 use std::fmt;
 use std::error;
+use std::collections::HashMap;
 use module::*;
-use lexer::Located;
+use lexer::{Located, Span};
+use diagnostics::{Diagnostic, ToDiagnostics, write_diagnostics};
 use scoped_map::ScopedMap;
 use interner::*;
 
@@ -58,7 +60,13 @@ impl <T> Errors<T> {
         self.errors.push(e);
     }
     pub fn has_errors(&self) -> bool {
-        self.errors.len() != 0
+        self.len() != 0
+    }
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+    pub fn iter(&self) -> ::std::slice::Iter<T> {
+        self.errors.iter()
     }
 
     pub fn into_result<V>(&mut self, value: V) -> Result<V, Errors<T>> {
@@ -70,22 +78,42 @@ impl <T> Errors<T> {
         }
     }
 }
-impl <T: fmt::Display> Errors<T> {
-    pub fn report_errors(&self, f: &mut fmt::Formatter, pass: &str) -> fmt::Result {
-        try!(write!(f, "Found {} errors in compiler pass: {}", self.errors.len(), pass));
-        for error in self.errors.iter() {
-            try!(write!(f, "{}", error));
-        }
-        Ok(())
+impl <T> From<T> for Errors<T> {
+    fn from(error: T) -> Errors<T> {
+        Errors { errors: vec![error] }
+    }
+}
+
+impl <T: ToDiagnostics> ToDiagnostics for Errors<T> {
+    fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        self.iter()
+            .flat_map(|error| error.to_diagnostics().into_iter())
+            .collect()
     }
 }
 
+impl <T: ToDiagnostics> fmt::Display for Errors<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_diagnostics(f, self)
+    }
+}
+
+impl <T: ToDiagnostics + fmt::Debug> error::Error for Errors<T> {
+    fn description(&self) -> &str { "compile errors" }
+}
+
 #[derive(Debug)]
-pub struct RenamerError(Errors<Error>);
+pub struct RenamerError(Errors<Located<Error>>);
 
 impl fmt::Display for RenamerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.0.report_errors(f, "renamer")
+        write_diagnostics(f, self)
+    }
+}
+
+impl ToDiagnostics for RenamerError {
+    fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        self.0.to_diagnostics()
     }
 }
 
@@ -95,19 +123,41 @@ impl error::Error for RenamerError {
 
 #[derive(Debug)]
 enum Error {
-    MultipleDefinitions(InternedStr),
+    ///The name is defined multiple times, the span is the location of the previous definition if it is known
+    MultipleDefinitions(InternedStr, Option<Span>),
     UndefinedModule(InternedStr),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::MultipleDefinitions(s) => write!(f, "{} is defined multiple times", s),
+            Error::MultipleDefinitions(s, _) => write!(f, "{} is defined multiple times", s),
             Error::UndefinedModule(s) => write!(f, "Module {} is not defined", s)
         }
     }
 }
 
+impl ToDiagnostics for Located<Error> {
+    fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        let diagnostic = match self.node {
+            Error::MultipleDefinitions(s, previous) => {
+                let diagnostic = Diagnostic::error("E0101", format!("`{}` is defined multiple times", s))
+                    .with_label(self.span, format!("`{}` redefined here", s));
+                match previous {
+                    Some(previous) => diagnostic.with_secondary_label(previous, format!("previous definition of `{}` here", s)),
+                    None => diagnostic
+                }
+            }
+            Error::UndefinedModule(s) => {
+                Diagnostic::error("E0102", format!("module `{}` is not defined", s))
+                    .with_label(self.span, "imported here".to_string())
+                    .with_help(format!("check that `{}` is spelled correctly and that the module has been loaded", s))
+            }
+        };
+        vec![diagnostic]
+    }
+}
+
 ///A NameSupply can turn simple strings into unique Names
 pub struct NameSupply {
     unique_id: usize
@@ -145,33 +195,42 @@ impl NameSupply {
 struct Renamer {
     ///Mapping of strings into the unique name
     uniques: ScopedMap<InternedStr, Name>,
+    ///The span of the definition of each unique name, used when reporting duplicate definitions
+    definitions: HashMap<Name, Span>,
     name_supply: NameSupply,
+    ///The type synonyms of all modules renamed so far, they are expanded when renaming types
+    synonyms: HashMap<InternedStr, TypeSynonym<InternedStr>>,
     ///All errors found while renaming are stored here
-    errors: Errors<Error>
+    errors: Errors<Located<Error>>
 }
 
 
 impl Renamer {
     fn new() -> Renamer {
-        Renamer { uniques: ScopedMap::new(), name_supply: NameSupply::new(), errors: Errors::new() }
-    }
-
-    fn import_globals<T: Eq + Copy>(&mut self, module: &Module<T>, str_fn: &mut FnMut(T) -> InternedStr, uid: usize) {
-        let names = module.data_definitions.iter()
-            .flat_map(|data| data.constructors.iter().map(|ctor| ctor.name))
-            .chain(module.newtypes.iter().map(|newtype| newtype.constructor_name))
-            .chain(module.classes.iter().flat_map(|class|
-                Some(class.name).into_iter()
-                .chain(class.declarations.iter().map(|decl| decl.name))
-                .chain(binding_groups(&*class.bindings).map(|binds| binds[0].name))))
-            .chain(binding_groups(module.bindings.as_ref()).map(|binds| binds[0].name));
-        for name in names {
-            self.declare_global(str_fn(name), uid);
+        Renamer {
+            uniques: ScopedMap::new(),
+            definitions: HashMap::new(),
+            name_supply: NameSupply::new(),
+            synonyms: HashMap::new(),
+            errors: Errors::new()
+        }
+    }
+
+    ///Declares all the globals of `module`.
+    ///If `import_span` is given it is used as the location of every name instead of the span of its definition
+    fn import_globals<T: Eq + Copy>(&mut self, module: &Module<T>, str_fn: &mut FnMut(T) -> InternedStr, uid: usize, import_span: Option<Span>) {
+        for (name, span) in global_names(module) {
+            self.declare_global(str_fn(name), uid, import_span.unwrap_or(span));
         }
+        self.import_instances(module, str_fn, import_span);
+    }
+
+    ///Declares the bindings of the instances in `module`
+    fn import_instances<T: Eq + Copy>(&mut self, module: &Module<T>, str_fn: &mut FnMut(T) -> InternedStr, import_span: Option<Span>) {
         for instance in module.instances.iter() {
             let class_uid = self.get_name(str_fn(instance.classname)).uid;
             for binds in binding_groups(instance.bindings.as_ref()) {
-                self.declare_global(str_fn(binds[0].name), class_uid);
+                self.declare_global(str_fn(binds[0].name), class_uid, import_span.unwrap_or(binds[0].span));
             }
         }
     }
@@ -180,28 +239,59 @@ impl Renamer {
     ///This includes putting all globals from the imports and the the globals of the module itself
     ///into scope
     fn insert_globals(&mut self, module_env: &[Module<Name>], module: &Module<InternedStr>, uid: usize) {
-        self.import_globals(module, &mut |name| name, uid);
+        self.import_globals(module, &mut |name| name, uid, None);
         for import in module.imports.iter() {
             let imported_module = module_env.iter()
                 .find(|m| m.name.name == import.module);
             let imported_module = match imported_module {
                 Some(x) => x,
                 None => {
-                    self.errors.insert(Error::UndefinedModule(import.module));
+                    self.errors.insert(Located { span: import.span, node: Error::UndefinedModule(import.module) });
                     continue;
                 }
             };
             let uid = imported_module.name.uid;
-            match import.imports {
-                Some(ref imports) => {
-                    for &imported_str in imports.iter() {
-                        self.declare_global(imported_str, uid);
+            if !import.qualified && import.alias.is_none() && import.hiding.is_empty() {
+                match import.imports {
+                    Some(ref imports) => {
+                        for &imported_str in imports.iter() {
+                            self.declare_global(imported_str, uid, import.span);
+                        }
+                    }
+                    None => {//Import everything
+                        self.import_globals(imported_module, &mut |name| name.name, uid, Some(import.span))
                     }
                 }
-                None => {//Import everything
-                    self.import_globals(imported_module, &mut |name| name.name, imported_module.name.uid)
+                continue;
+            }
+            let names: Vec<InternedStr> = match import.imports {
+                Some(ref imports) => imports.clone(),
+                None => global_names(imported_module).into_iter()
+                    .map(|(name, _)| name.name)
+                    .filter(|name| !import.hiding.contains(name))
+                    .collect()
+            };
+            let qualifier = import.alias.unwrap_or(import.module);
+            for name in names {
+                let global = if import.qualified {
+                    Name { name: name, uid: uid }
                 }
+                else {
+                    self.declare_global(name, uid, import.span)
+                };
+                let qualified_name = intern(&format!("{}.{}", qualifier, name));
+                self.declare_alias(qualified_name, global, import.span);
             }
+            for typ in type_names(imported_module) {
+                if import.hiding.contains(&typ.name) || import.imports.as_ref().map_or(false, |imports| !imports.contains(&typ.name)) {
+                    continue;
+                }
+                let qualified_name = intern(&format!("{}.{}", qualifier, typ.name));
+                if !self.uniques.in_current_scope(&qualified_name) {
+                    self.declare_alias(qualified_name, typ, import.span);
+                }
+            }
+            self.import_instances(imported_module, &mut |name| name.name, Some(import.span));
         }
     }
 
@@ -209,11 +299,11 @@ impl Renamer {
         //Add all bindings in the scope
         if !is_global {
             for bind in binding_groups(bindings.as_ref()) {
-                self.make_unique(bind[0].name.clone());
+                self.make_unique(bind[0].name.clone(), bind[0].span);
             }
         }
         bindings.into_iter().map(|binding| {
-            let Binding { name, arguments, matches, typ, where_bindings  } = binding;
+            let Binding { name, arguments, matches, typ, where_bindings, span } = binding;
             let n = self.uniques.find(&name)
                 .map(|u| u.clone())
                 .unwrap_or_else(|| unreachable!("Variable {} should already have been defined", name));
@@ -223,7 +313,8 @@ impl Renamer {
                 arguments: self.rename_arguments(arguments),
                 where_bindings: where_bindings.map(|bs| self.rename_bindings(bs, false)),
                 matches: self.rename_matches(matches),
-                typ: self.rename_qualified_type(typ)
+                typ: self.rename_qualified_type(typ),
+                span: span
             };
             self.uniques.exit_scope();
             b
@@ -233,15 +324,16 @@ impl Renamer {
     fn rename(&mut self, input_expr: TypedExpr<InternedStr>) -> TypedExpr<Name> {
         use module::Expr::*;
         use module::DoBinding::*;
-        let TypedExpr { expr, typ, location } = input_expr;
+        let TypedExpr { expr, typ, span } = input_expr;
         let e = match expr {
             Literal(l) => Literal(l),
             Identifier(i) => Identifier(self.get_name(i)),
             Apply(func, arg) => Apply(box self.rename(*func), box self.rename(*arg)),
             OpApply(lhs, op, rhs) => OpApply(box self.rename(*lhs), self.get_name(op), box self.rename(*rhs)),
-            Lambda(arg, body) => {
+            Lambda(Located { span: arg_span, node: arg }, body) => {
                 self.uniques.enter_scope();
-                let l = Lambda(self.rename_pattern(arg), box self.rename(*body));
+                let arg = Located { span: arg_span, node: self.rename_pattern(arg, arg_span) };
+                let l = Lambda(arg, box self.rename(*body));
                 self.uniques.exit_scope();
                 l
             }
@@ -255,13 +347,13 @@ impl Renamer {
             Case(expr, alts) => {
                 let a: Vec<Alternative<Name>> = alts.into_iter().map(|alt| {
                     let Alternative {
-                        pattern: Located { location: loc, node: pattern },
+                        pattern: Located { span: pattern_span, node: pattern },
                         matches,
                         where_bindings
                     } = alt;
                     self.uniques.enter_scope();
                     let a = Alternative {
-                        pattern: Located { location: loc, node: self.rename_pattern(pattern) },
+                        pattern: Located { span: pattern_span, node: self.rename_pattern(pattern, pattern_span) },
                         where_bindings: where_bindings.map(|bs| self.rename_bindings(bs, false)),
                         matches: self.rename_matches(matches)
                     };
@@ -281,32 +373,33 @@ impl Renamer {
                         DoExpr(expr) => DoExpr(self.rename(expr)),
                         DoLet(bs) => DoLet(self.rename_bindings(bs, false)),
                         DoBind(pattern, expr) => {
-                            let Located { location, node } = pattern;
-                            let loc = Located { location: location, node: self.rename_pattern(node) };
+                            let Located { span, node } = pattern;
+                            let loc = Located { span: span, node: self.rename_pattern(node, span) };
                             DoBind(loc, self.rename(expr))
                         }
                     }
                 }).collect();
                 Do(bs, box self.rename(*expr))
             }
-            TypeSig(expr, sig) => {
-                TypeSig(box self.rename(*expr), self.rename_qualified_type(sig))
+            TypeSig(expr, Located { span: sig_span, node: sig }) => {
+                let sig = Located { span: sig_span, node: self.rename_qualified_type(sig) };
+                TypeSig(box self.rename(*expr), sig)
             }
             Paren(expr) => Paren(box self.rename(*expr))
         };
-        let mut t = TypedExpr::with_location(e, location);
+        let mut t = TypedExpr::with_span(e, span);
         t.typ = self.rename_type(typ);
         t
     }
 
-    fn rename_pattern(&mut self, pattern: Pattern<InternedStr>) -> Pattern<Name> {
+    fn rename_pattern(&mut self, pattern: Pattern<InternedStr>, span: Span) -> Pattern<Name> {
         match pattern {
             Pattern::Number(i) => Pattern::Number(i),
             Pattern::Constructor(s, ps) => {
-                let ps2: Vec<Pattern<Name>> = ps.into_iter().map(|p| self.rename_pattern(p)).collect();
+                let ps2: Vec<Pattern<Name>> = ps.into_iter().map(|p| self.rename_pattern(p, span)).collect();
                 Pattern::Constructor(self.get_name(s), ps2)
             }
-            Pattern::Identifier(s) => Pattern::Identifier(self.make_unique(s)),
+            Pattern::Identifier(s) => Pattern::Identifier(self.make_unique(s, span)),
             Pattern::WildCard => Pattern::WildCard
         }
     }
@@ -314,7 +407,7 @@ impl Renamer {
     ///If the name was not found it is assumed to be global
     fn get_name(&self, s: InternedStr) -> Name {
         match self.uniques.find(&s) {
-            Some(&Name { uid, .. }) => Name { name: s, uid: uid },
+            Some(&name) => name,
             None => Name { name: s, uid: 0 }//Primitive
         }
     }
@@ -330,8 +423,10 @@ impl Renamer {
         }
     }
 
-    fn rename_arguments(&mut self, arguments: Vec<Pattern<InternedStr>>) -> Vec<Pattern<Name>> {
-        arguments.into_iter().map(|a| self.rename_pattern(a)).collect()
+    fn rename_arguments(&mut self, arguments: Vec<Located<Pattern<InternedStr>>>) -> Vec<Located<Pattern<Name>>> {
+        arguments.into_iter()
+            .map(|Located { span, node }| Located { span: span, node: self.rename_pattern(node, span) })
+            .collect()
     }
 
     fn rename_qualified_type(&mut self, typ: Qualified<Type<InternedStr>, InternedStr>) -> Qualified<Type<Name>, Name> {
@@ -345,34 +440,125 @@ impl Renamer {
     }
     fn rename_type_declarations(&mut self, decls: Vec<TypeDeclaration<InternedStr>>) -> Vec<TypeDeclaration<Name>> {
         let decls2: Vec<TypeDeclaration<Name>> = decls.into_iter()
-            .map(|decl| TypeDeclaration { name: self.get_name(decl.name), typ: self.rename_qualified_type(decl.typ) })
+            .map(|decl| TypeDeclaration { name: self.get_name(decl.name), typ: self.rename_qualified_type(decl.typ), span: decl.span })
             .collect();
         decls2
     }
 
     ///Introduces a new Name to the current scope.
     ///If the name was already declared in the current scope an error is added
-    fn make_unique(&mut self, name: InternedStr) -> Name {
+    fn make_unique(&mut self, name: InternedStr, span: Span) -> Name {
         if self.uniques.in_current_scope(&name) {
-            self.errors.insert(Error::MultipleDefinitions(name));
-            self.uniques.find(&name).map(|x| x.clone()).unwrap()
+            let previous = self.uniques.find(&name).map(|x| x.clone()).unwrap();
+            let previous_span = self.definitions.get(&previous).cloned();
+            self.errors.insert(Located { span: span, node: Error::MultipleDefinitions(name, previous_span) });
+            previous
         }
         else {
             let u = self.name_supply.from_interned(name.clone());
             self.uniques.insert(name, u.clone());
+            self.definitions.insert(u, span);
             u
         }
     }
-    fn declare_global(&mut self, s: InternedStr, module_id: usize) -> Name {
-        self.make_unique(s);
-        let name = self.uniques.find_mut(&s).unwrap();
-        name.uid = module_id;
-        *name
+    fn declare_global(&mut self, s: InternedStr, module_id: usize, span: Span) -> Name {
+        self.make_unique(s, span);
+        let name = {
+            let name = self.uniques.find_mut(&s).unwrap();
+            name.uid = module_id;
+            *name
+        };
+        self.definitions.entry(name).or_insert(span);
+        name
+    }
+
+    ///Declares `alias` as another name for the global `name`, such as `Map.insert` for `insert`
+    fn declare_alias(&mut self, alias: InternedStr, name: Name, span: Span) {
+        self.make_unique(alias, span);
+        *self.uniques.find_mut(&alias).unwrap() = name;
     }
 
     fn rename_type(&mut self, typ: Type<InternedStr>) -> Type<Name> {
+        let typ = self.expand_synonyms(typ);
         typ.map(|s| self.get_name(s))
     }
+
+    ///Replaces every fully applied type synonym in 'typ' with the type it stands for
+    fn expand_synonyms(&self, typ: Type<InternedStr>) -> Type<InternedStr> {
+        fn split_application(typ: Type<InternedStr>, args: &mut Vec<Type<InternedStr>>) -> Type<InternedStr> {
+            match typ {
+                Type::Application(lhs, rhs) => {
+                    let head = split_application(*lhs, args);
+                    args.push(*rhs);
+                    head
+                }
+                typ => typ
+            }
+        }
+        fn substitute(typ: Type<InternedStr>, substitution: &[(TypeVariable, Type<InternedStr>)]) -> Type<InternedStr> {
+            match typ {
+                Type::Variable(var) => {
+                    substitution.iter()
+                        .find(|&&(ref param, _)| param.id == var.id)
+                        .map(|&(_, ref arg)| arg.clone())
+                        .unwrap_or(Type::Variable(var))
+                }
+                Type::Application(lhs, rhs) => {
+                    Type::Application(box substitute(*lhs, substitution), box substitute(*rhs, substitution))
+                }
+                typ => typ
+            }
+        }
+        let mut args = Vec::new();
+        let head = split_application(typ, &mut args);
+        let mut args = args.into_iter().map(|arg| self.expand_synonyms(arg));
+        let synonym = match head {
+            Type::Constructor(ref op) => self.synonyms.get(&op.name),
+            _ => None
+        };
+        match synonym {
+            Some(synonym) if args.len() >= synonym.parameters.len() => {
+                let substitution: Vec<(TypeVariable, Type<InternedStr>)> = synonym.parameters.iter()
+                    .cloned()
+                    .zip(args.by_ref())
+                    .collect();
+                let expanded = self.expand_synonyms(substitute(synonym.typ.clone(), &substitution));
+                args.fold(expanded, |f, arg| Type::Application(box f, box arg))
+            }
+            _ => args.fold(head, |f, arg| Type::Application(box f, box arg))
+        }
+    }
+}
+
+///Returns the names of all globals defined in `module` together with the location of their definition
+fn global_names<T: Eq + Copy>(module: &Module<T>) -> Vec<(T, Span)> {
+    module.data_definitions.iter()
+        .flat_map(|data| data.constructors.iter().map(move |ctor| (ctor.name, data.span)))
+        .chain(module.newtypes.iter().map(|newtype| (newtype.constructor_name, newtype.span)))
+        .chain(module.classes.iter().flat_map(|class|
+            Some((class.name, class.span)).into_iter()
+            .chain(class.declarations.iter().map(|decl| (decl.name, decl.span)))
+            .chain(binding_groups(&*class.bindings).map(|binds| (binds[0].name, binds[0].span)))))
+        .chain(binding_groups(module.bindings.as_ref()).map(|binds| (binds[0].name, binds[0].span)))
+        .collect()
+}
+
+///Returns the names of the types defined by the data and newtype declarations in `module`
+fn type_names(module: &Module<Name>) -> Vec<Name> {
+    let mut names = Vec::new();
+    for data in module.data_definitions.iter() {
+        if let Type::Constructor(ref op) = *extract_applied_type(&data.typ.value) {
+            names.push(op.name);
+        }
+    }
+    for newtype in module.newtypes.iter() {
+        if let Type::Constructor(ref op) = *extract_applied_type(&newtype.typ.value) {
+            //Newtypes keep their type unrenamed so resolve it the same way `get_name` would
+            let uid = if newtype.constructor_name.name == op.name { newtype.constructor_name.uid } else { 0 };
+            names.push(Name { name: op.name, uid: uid });
+        }
+    }
+    names
 }
 
 pub fn rename_expr(expr: TypedExpr<InternedStr>) -> Result<TypedExpr<Name>, RenamerError> {
@@ -389,7 +575,7 @@ pub fn rename_module(module: Module<InternedStr>) -> Result<Module<Name>, Rename
         .map_err(RenamerError)
 }
 fn rename_module_(renamer: &mut Renamer, module_env: &[Module<Name>], module: Module<InternedStr>) -> Module<Name> {
-    let mut name = renamer.make_unique(module.name);
+    let mut name = renamer.make_unique(module.name, Span::eof());
     if name.as_ref() == "Prelude" {
         renamer.uniques.find_mut(&name.name).unwrap().uid = 0;
         name.uid = 0;
@@ -405,8 +591,13 @@ fn rename_module_(renamer: &mut Renamer, module_env: &[Module<Name>], module: Mo
         type_declarations,
         bindings,
         instances,
+        type_synonyms,
+        defaults,
         fixity_declarations
     } = module;
+    for synonym in type_synonyms.iter() {
+        renamer.synonyms.insert(synonym.name, synonym.clone());
+    }
 
     let imports2: Vec<Import<Name>> = imports.into_iter().map(|import| {
         let imports = import.imports.as_ref().map(|x| {
@@ -415,7 +606,17 @@ fn rename_module_(renamer: &mut Renamer, module_env: &[Module<Name>], module: Mo
                 .collect();
             is
         });
-        Import { module: import.module, imports: imports }
+        let hiding = import.hiding.iter()
+            .map(|&x| renamer.get_name(x))
+            .collect();
+        Import {
+            module: import.module,
+            imports: imports,
+            hiding: hiding,
+            qualified: import.qualified,
+            alias: import.alias,
+            span: import.span
+        }
     }).collect();
 
     let data_definitions2 : Vec<DataDefinition<Name>> = data_definitions.into_iter().map(|data| {
@@ -423,7 +624,8 @@ fn rename_module_(renamer: &mut Renamer, module_env: &[Module<Name>], module: Mo
             constructors,
             typ,
             parameters,
-            deriving
+            deriving,
+            span
         } = data;
         let c: Vec<Constructor<Name>> = constructors.into_iter().map(|ctor| {
             let Constructor {
@@ -447,12 +649,13 @@ fn rename_module_(renamer: &mut Renamer, module_env: &[Module<Name>], module: Mo
             typ : renamer.rename_qualified_type(typ),
             parameters : parameters,
             constructors : c,
-            deriving : d
+            deriving : d,
+            span : span
         }
     }).collect();
 
     let newtypes2: Vec<Newtype<Name>> = newtypes.into_iter().map(|newtype| {
-        let Newtype { typ, constructor_name, constructor_type, deriving } = newtype;
+        let Newtype { typ, constructor_name, constructor_type, deriving, span } = newtype;
         let deriving2: Vec<Name> = deriving.into_iter().map(|s| {
             renamer.get_name(s)
         }).collect();
@@ -460,7 +663,8 @@ fn rename_module_(renamer: &mut Renamer, module_env: &[Module<Name>], module: Mo
             typ: typ,
             constructor_name: renamer.get_name(constructor_name),
             constructor_type: renamer.rename_qualified_type(constructor_type),
-            deriving: deriving2
+            deriving: deriving2,
+            span: span
         }
     }).collect();
     
@@ -469,7 +673,8 @@ fn rename_module_(renamer: &mut Renamer, module_env: &[Module<Name>], module: Mo
             bindings,
             constraints,
             typ,
-            classname
+            classname,
+            span
         } = instance;
         let constraints2: Vec<Constraint<Name>> = constraints.into_iter()
             .map(|Constraint { class, variables }| {
@@ -480,7 +685,8 @@ fn rename_module_(renamer: &mut Renamer, module_env: &[Module<Name>], module: Mo
             bindings : renamer.rename_bindings(bindings, true),
             constraints : constraints2,
             typ : renamer.rename_type(typ),
-            classname : renamer.get_name(classname)
+            classname : renamer.get_name(classname),
+            span : span
         }
     }).collect();
 
@@ -491,7 +697,8 @@ fn rename_module_(renamer: &mut Renamer, module_env: &[Module<Name>], module: Mo
             name,
             variable,
             declarations,
-            bindings
+            bindings,
+            span
         } = class;
         let constraints2: Vec<Constraint<Name>> = constraints.into_iter()
             .map(|Constraint { class, variables }| {
@@ -503,24 +710,32 @@ fn rename_module_(renamer: &mut Renamer, module_env: &[Module<Name>], module: Mo
             name: renamer.get_name(name),
             variable: variable,
             declarations: renamer.rename_type_declarations(declarations),
-            bindings: renamer.rename_bindings(bindings, true)
+            bindings: renamer.rename_bindings(bindings, true),
+            span: span
         }
     }).collect();
     
     let bindings2 = renamer.rename_bindings(bindings, true);
 
     let fixity_declarations2: Vec<FixityDeclaration<Name>> = fixity_declarations.into_iter()
-        .map(|FixityDeclaration { assoc, precedence, operators }| {
+        .map(|FixityDeclaration { assoc, precedence, operators, span }| {
             
             let ops: Vec<Name> = operators.into_iter()
                 .map(|s| renamer.get_name(s))
                 .collect();
             FixityDeclaration { assoc: assoc, precedence: precedence,
-                operators: ops
+                operators: ops,
+                span: span
             }
         })
         .collect();
     let decls2 = renamer.rename_type_declarations(type_declarations);
+    let type_synonyms2: Vec<TypeSynonym<Name>> = type_synonyms.into_iter()
+        .map(|TypeSynonym { name, parameters, typ, span }| {
+            TypeSynonym { name: name, parameters: parameters, typ: renamer.rename_type(typ), span: span }
+        })
+        .collect();
+    let defaults2 = defaults.map(|types| types.into_iter().map(|typ| renamer.rename_type(typ)).collect());
     renamer.uniques.exit_scope();
     Module {
         name: name,
@@ -531,6 +746,8 @@ fn rename_module_(renamer: &mut Renamer, module_env: &[Module<Name>], module: Mo
         bindings : bindings2,
         instances: instances2,
         newtypes: newtypes2,
+        type_synonyms: type_synonyms2,
+        defaults: defaults2,
         fixity_declarations: fixity_declarations2
     }
 }
@@ -596,6 +813,10 @@ pub mod typ {
     pub fn int_type() -> Type<Name> {
         Type::new_op(name("Int"), vec![])
     }
+    ///Returns the type for the arbitrary precision Integer type
+    pub fn integer_type() -> Type<Name> {
+        Type::new_op(name("Integer"), vec![])
+    }
     ///Returns the type for the Bool type
     pub fn bool_type() -> Type<Name> {
         Type::new_op(name("Bool"), vec![])
@@ -604,6 +825,10 @@ pub mod typ {
     pub fn double_type() -> Type<Name> {
         Type::new_op(name("Double"), vec![])
     }
+    ///Returns the type for `Rational`, a synonym for `Ratio Integer`
+    pub fn rational_type() -> Type<Name> {
+        Type::new_op(name("Ratio"), vec![integer_type()])
+    }
     ///Creates a function type
     pub fn function_type(arg: &Type<Name>, result: &Type<Name>) -> Type<Name> {
         function_type_(arg.clone(), result.clone())
@@ -656,6 +881,18 @@ main = 2".chars());
         rename_modules(vec!(module));
     }
     #[test]
+    fn duplicate_binding_location() {
+        let modules = parse_string(
+r"main = 1
+test = []
+main = 2").unwrap();
+        let err = super::rename_modules(modules).unwrap_err();
+        let message = format!("{}", err);
+        assert!(message.starts_with("error[E0101]: `main` is defined multiple times"), "{}", message);
+        assert!(message.contains("<input>:3:1-8"), "{}", message);
+        assert!(message.contains("previous definition of `main` here"), "{}", message);
+    }
+    #[test]
     fn import_binding() {
         let file =
 r"
@@ -666,6 +903,19 @@ main = id";
         rename_modules(modules);
     }
     #[test]
+    fn expand_type_synonym() {
+        let modules = parse_string(
+r"
+type Pair a = (a, a)
+type IntPair = Pair Int
+swap :: IntPair -> Pair Int
+swap p = case p of
+    (x, y) -> (y, x)").unwrap();
+        let modules = rename_modules(modules);
+        let decl = &modules[0].type_declarations[0];
+        assert_eq!(format!("{}", decl.typ.value), "(,)_0 Int_0 Int_0 -> (,)_0 Int_0 Int_0");
+    }
+    #[test]
     #[should_panic]
     fn missing_import() {
         let mut parser = Parser::new(
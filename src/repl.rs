@@ -1,43 +1,22 @@
 use std::io::BufRead;
 
 use compiler::*;
-use typecheck::*;
 use vm::*;
 use interner::*;
-use core::{Module, Type, Qualified};
-use core::Type::{Application, Constructor};
-use core::translate::*;
-use lambda_lift::*;
+use core::{Type, Qualified};
+use module::TypedExpr;
 use parser::Parser;
 use renamer::{Name, rename_expr};
+use source::add_file;
+use diagnostics;
+use typecheck::TypecheckOptions;
 
-///Returns whether the type in question is an IO action
-fn is_io(typ: &Type<Name>) -> bool {
-    match *typ {
-        Type::Application(ref lhs, _) => 
-            match **lhs {
-                Type::Constructor(ref op) => op.name.as_ref() == "IO",
-                _ => false
-            },
-        _ => false
-    }
-}
-
-///Compiles an expression into an assembly
-fn compile_expr(prelude: &Assembly, expr_str: &str) -> Result<Assembly, VMError> {
-    let mut parser = Parser::new(expr_str.chars());
-    let expr = try!(parser.expression_());
-    let mut expr = try!(rename_expr(expr));
-
-    let mut type_env = TypeEnvironment::new();
-    type_env.add_types(prelude as &DataTypes);
-    try!(type_env.typecheck_expr(&mut expr));
-    let temp_module = Module::from_expr(translate_expr(expr));
-    let m = do_lambda_lift(temp_module);
-    
-    let mut compiler = Compiler::new();
-    compiler.assemblies.push(prelude);
-    Ok(compiler.compile_module(&m))
+///Parses and renames an expression entered in the REPL
+fn parse_expr(expr_str: &str) -> Result<TypedExpr<Name>, VMError> {
+    let file = add_file("<interactive>", expr_str);
+    let mut parser = Parser::with_file(expr_str.chars(), file);
+    let expr = try!(parser.complete_expression());
+    Ok(try!(rename_expr(expr)))
 }
 
 ///Finds the main function and if it is an IO function, adds instructions to push the "RealWorld" argument
@@ -63,25 +42,43 @@ fn find_main(assembly: &Assembly) -> (Vec<Instruction>, Qualified<Type<Name>, Na
         .expect("Expected main function")
 }
 
-pub fn run_and_print_expr(expr_str: &str) {
-    let prelude = compile_file("Prelude.hs")
-        .unwrap();
-    let mut vm = VM::new();
-    vm.add_assembly(prelude);
-    let assembly = compile_expr(vm.get_assembly(0), expr_str.as_ref())
-        .unwrap();
-    let (instructions, type_decl) = find_main(&assembly);
+///Evaluates and prints an expression which is typechecked with `options`.
+///Values whose type has a `Show` instance are printed by evaluating `show` on them
+fn print_expr(vm: &mut VM, expr_str: &str, options: TypecheckOptions) -> Result<(), VMError> {
+    let expr = try!(parse_expr(expr_str));
+    let assembly = try!(compile_expr(&[vm.get_assembly(0)], expr.clone(), options));
+    let (instructions, typ) = find_main(&assembly);
+    if !is_io(&typ.value) {
+        let shown = compile_show_expr(&[vm.get_assembly(0)], expr, options);
+        if let Some(shown) = shown {
+            let string = try!(vm.evaluate_string(shown));
+            println!("{}  {}", string, typ);
+            return Ok(())
+        }
+    }
     let assembly_index = vm.add_assembly(assembly);
-    let result = vm.evaluate(&*instructions, assembly_index);//TODO 0 is not necessarily correct
-    println!("{:?}  {}", result, type_decl);
+    let result = try!(vm.evaluate(&*instructions, assembly_index));//TODO 0 is not necessarily correct
+    println!("{:?}  {}", vm.show(result), typ);
+    Ok(())
 }
 
-///Starts the REPL
-pub fn start() {
-    let mut vm = VM::new();
+///Evaluates and prints an expression in a VM which is limited by `config`
+pub fn run_and_print_expr(expr_str: &str, config: VMConfig, options: TypecheckOptions) -> Result<(), VMError> {
+    let prelude = try!(compile_file("Prelude.hs"));
+    let mut vm = VM::with_config(config);
+    vm.add_assembly(prelude);
+    print_expr(&mut vm, expr_str, options)
+}
+
+///Starts the REPL, each expression is typechecked with `options` and evaluated within the limits of `config`
+pub fn start(config: VMConfig, options: TypecheckOptions) {
+    let mut vm = VM::with_config(config);
     match compile_file("Prelude.hs") {
         Ok(prelude) => { vm.add_assembly(prelude); }
-        Err(err) => println!("Failed to compile the prelude\nReason: {}", err)
+        Err(err) => {
+            println!("Failed to compile the prelude");
+            diagnostics::report(&err);
+        }
     }
 
     let stdin = ::std::io::stdin();
@@ -90,16 +87,9 @@ pub fn start() {
             Ok(l) => l,
             Err(e) => panic!("Reading line failed with '{:?}'", e)
         };
-        let assembly = match compile_expr(vm.get_assembly(0), expr_str.as_ref()) {
-            Ok(assembly) => assembly,
-            Err(err) => {
-                println!("{}", err);
-                continue
-            }
-        };
-        let (instructions, typ) = find_main(&assembly);
-        let assembly_index = vm.add_assembly(assembly);
-        let result = vm.evaluate(&*instructions, assembly_index);//TODO 0 is not necessarily correct
-        println!("{:?}  {}", result, typ);
+        match print_expr(&mut vm, expr_str.as_ref(), options) {
+            Ok(()) => (),
+            Err(err) => diagnostics::report(&err)
+        }
     }
 }
@@ -0,0 +1,71 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+///Identifies a source file which has been registered with the local source map.
+///The default FileId refers to anonymous input which has not been registered
+#[derive(Eq, PartialEq, Clone, Copy, Default, Hash, Debug)]
+pub struct FileId(usize);
+
+pub struct SourceFile {
+    pub name: String,
+    pub contents: String
+}
+
+///Stores the name and the contents of every file which has been handed to the parser
+///so that spans can be mapped back to the source they came from
+pub struct SourceMap {
+    files: Vec<SourceFile>
+}
+
+impl SourceMap {
+
+    pub fn new() -> SourceMap {
+        SourceMap { files: vec![SourceFile { name: "<input>".to_string(), contents: String::new() }] }
+    }
+
+    pub fn add_file(&mut self, name: &str, contents: &str) -> FileId {
+        self.files.push(SourceFile { name: name.to_string(), contents: contents.to_string() });
+        FileId(self.files.len() - 1)
+    }
+
+    pub fn get_file<'a>(&'a self, FileId(i): FileId) -> &'a SourceFile {
+        if i < self.files.len() {
+            &self.files[i]
+        }
+        else {
+            panic!("Invalid FileId {:?}", i)
+        }
+    }
+}
+
+///Returns a reference to the source map stored in TLD
+pub fn get_local_source_map() -> Rc<RefCell<SourceMap>> {
+    thread_local!(static SOURCE_MAP: Rc<RefCell<SourceMap>> = Rc::new(RefCell::new(SourceMap::new())));
+    SOURCE_MAP.with(|map| map.clone())
+}
+
+///Registers a file in the local source map, returning the id which spans into the file should use
+pub fn add_file(name: &str, contents: &str) -> FileId {
+    let map = get_local_source_map();
+    let mut map = map.borrow_mut();
+    map.add_file(name, contents)
+}
+
+///Returns the name of the file which `file` refers to
+pub fn file_name(file: FileId) -> String {
+    let map = get_local_source_map();
+    let map = map.borrow();
+    map.get_file(file).name.clone()
+}
+
+///Returns the line at `row` (counted from 0) of `file`, if the contents of the file are known
+pub fn source_line(file: FileId, row: isize) -> Option<String> {
+    if row < 0 {
+        return None;
+    }
+    let map = get_local_source_map();
+    let map = map.borrow();
+    map.get_file(file).contents.lines()
+        .nth(row as usize)
+        .map(|line| line.to_string())
+}
@@ -6,11 +6,13 @@ use std::error;
 use module::*;
 use module::Expr::*;
 use module::LiteralData::*;
-use lexer::Location;
+use lexer::{Span, Located};
+use diagnostics::{Diagnostic, ToDiagnostics, write_diagnostics};
 use graph::{Graph, VertexIndex, strongly_connected_components};
 use builtins::builtins;
 use renamer::*;
 use interner::*;
+use vm::VMError;
 
 pub type TcType = Type<Name>;
 
@@ -108,15 +110,54 @@ pub struct TypeEnvironment<'a> {
     ///The current age for newly created variables.
     ///Age is used to determine whether variables need to be quantified or not.
     variable_age : isize,
+    ///The span of the type signature of each binding which has one
+    signatures: HashMap<Name, Span>,
+    ///The types which ambiguous type variables are defaulted to, tried in order
+    defaults: Vec<TcType>,
+    ///If true the extended defaulting rules of the REPL are used which also defaults variables
+    ///which are not numeric and tries `()` and `[]` before the types in `defaults`
+    extended_defaults: bool,
+    options: TypecheckOptions,
+    ///The global bindings whose types contain type variables which may not be generalized
+    monomorphic: Vec<Name>,
+    ///The type variables in the types of the `monomorphic` bindings
+    monomorphic_vars: Vec<TypeVariable>,
+    ///The types which monomorphic type variables were bound to by the binding groups after them
+    monomorphic_subs: Substitution,
     errors: Errors<TypeErrorInfo>
 }
 
+///Options which change which bindings the typechecker generalizes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TypecheckOptions {
+    ///If true the constrained type variables of a binding group are not generalized if a binding
+    ///in the group has no arguments and no type signature (the monomorphism restriction)
+    pub monomorphism_restriction: bool,
+    ///If true local bindings without a type signature are never generalized (`MonoLocalBinds`)
+    pub mono_local_binds: bool
+}
+
+impl Default for TypecheckOptions {
+    fn default() -> TypecheckOptions {
+        TypecheckOptions {
+            monomorphism_restriction: true,
+            mono_local_binds: false
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TypeError(Errors<TypeErrorInfo>);
 
 impl fmt::Display for TypeError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.0.report_errors(f, "typecheck")
+        write_diagnostics(f, self)
+    }
+}
+
+impl ToDiagnostics for TypeError {
+    fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        self.0.to_diagnostics()
     }
 }
 
@@ -241,6 +282,25 @@ impl <'a> TypeEnvironment<'a> {
         add_primitives(&mut globals, "Double");
         insert_to(&mut globals,"primIntToDouble", typ::function_type_(typ::int_type(), typ::double_type()));
         insert_to(&mut globals, "primDoubleToInt", typ::function_type_(typ::double_type(), typ::int_type()));
+        for name in ["Sqrt", "Exp", "Log", "Sin", "Cos", "Tan", "Asin", "Acos", "Atan", "Sinh", "Cosh", "Tanh",
+                     "Truncate", "Round", "Floor", "Ceiling", "ToFloat"].iter() {
+            insert_to(&mut globals, prim("Double", name).as_ref(), typ::function_type_(typ::double_type(), typ::double_type()));
+        }
+        for name in ["IsNaN", "IsInfinite", "IsNegativeZero"].iter() {
+            insert_to(&mut globals, prim("Double", name).as_ref(), typ::function_type_(typ::double_type(), typ::bool_type()));
+        }
+        for name in ["Pow", "Atan2"].iter() {
+            let binop = typ::function_type_(typ::double_type(), typ::function_type_(typ::double_type(), typ::double_type()));
+            insert_to(&mut globals, prim("Double", name).as_ref(), binop);
+        }
+        insert_to(&mut globals, "primCharToInt", typ::function_type_(typ::char_type(), typ::int_type()));
+        insert_to(&mut globals, "primIntToChar", typ::function_type_(typ::int_type(), typ::char_type()));
+        for name in ["IsSpace", "IsAlpha", "IsAlphaNum", "IsUpper", "IsLower", "IsPunctuation", "IsControl"].iter() {
+            insert_to(&mut globals, prim("Char", name).as_ref(), typ::function_type_(typ::char_type(), typ::bool_type()));
+        }
+        for name in ["ToUpper", "ToLower"].iter() {
+            insert_to(&mut globals, prim("Char", name).as_ref(), typ::function_type_(typ::char_type(), typ::char_type()));
+        }
         let var = Type::Generic(TypeVariable::new_var_kind(intern("a"), Kind::Star.clone()));
         
         for (name, typ) in builtins().into_iter() {
@@ -263,6 +323,13 @@ impl <'a> TypeEnvironment<'a> {
             classes: Vec::new(),
             data_definitions : Vec::new(),
             variable_age : 0 ,
+            signatures: HashMap::new(),
+            defaults: standard_defaults(),
+            extended_defaults: false,
+            options: TypecheckOptions::default(),
+            monomorphic: Vec::new(),
+            monomorphic_vars: Vec::new(),
+            monomorphic_subs: Substitution { subs: HashMap::new() },
             errors: Errors::new()
         }
     }
@@ -271,6 +338,16 @@ impl <'a> TypeEnvironment<'a> {
         self.assemblies.push(types);
     }
 
+    ///Enables the extended defaulting rules used for expressions entered in the REPL
+    pub fn set_extended_defaults(&mut self, extended: bool) {
+        self.extended_defaults = extended;
+    }
+
+    ///Sets the options which decides which bindings are generalized
+    pub fn set_options(&mut self, options: TypecheckOptions) {
+        self.options = options;
+    }
+
     ///Typechecks a module
     ///If the typecheck is successful the types in the module are updated with the new types.
     ///If any errors were found while typechecking panic! is called.
@@ -281,6 +358,7 @@ impl <'a> TypeEnvironment<'a> {
     }
     pub fn typecheck_module2(&mut self, module: &mut Module<Name>) {
         let start_var_age = self.variable_age + 1;
+        self.defaults = module.defaults.clone().unwrap_or_else(standard_defaults);
         for data_def in module.data_definitions.iter_mut() {
             for constructor in data_def.constructors.iter_mut() {
                 let mut typ = constructor.typ.clone();
@@ -326,7 +404,7 @@ impl <'a> TypeEnvironment<'a> {
             for binding in class.bindings.iter_mut() {
                 let classname = &class.name;
                 let decl = class.declarations.iter()
-                    .find(|decl| binding.name.name.as_ref().ends_with(decl.name.as_ref()))
+                    .find(|decl| binding.name.name == encode_binding_identifier(classname.name, decl.name.name))
                     .unwrap_or_else(|| panic!("Could not find {:?} in class {:?}", binding.name, classname));
                 binding.typ = decl.typ.clone();
                 {
@@ -344,6 +422,7 @@ impl <'a> TypeEnvironment<'a> {
             self.classes.push((class.constraints.clone(), class.name.clone()));
         }
         let data_definitions = module.data_definitions.clone();
+        let mut declared_instance_types = Vec::new();
         for instance in module.instances.iter_mut() {
             let (_class_constraints, class_var, class_decls) = module.classes.iter()
                 .find(|class| class.name == instance.classname)
@@ -355,52 +434,65 @@ impl <'a> TypeEnvironment<'a> {
                 })
                 .unwrap_or_else(|| panic!("Could not find class {:?}", instance.classname));
             //Update the kind of the type for the instance to be the same as the class kind (since we have no proper kind inference
-            match instance.typ {
+            //The type may be partially applied, such as `(Either a)`, so the kind is set on the constructor itself
+            match *extract_applied_type_mut(&mut instance.typ) {
                 Type::Constructor(ref mut op) => {
                     let maybe_data = self.assemblies.iter().filter_map(|a| a.find_data_type(op.name))
                         .next();
                     op.kind = maybe_data
                         .or_else(|| data_definitions.iter().find(|data| op.name == extract_applied_type(&data.typ.value).ctor().name))
                         .map(|data| extract_applied_type(&data.typ.value).kind().clone())
-                        .unwrap_or_else(|| if intern("[]") == op.name { Kind::Function(box Kind::Star, box Kind::Star) } else { Kind::Star });
+                        .unwrap_or_else(|| {
+                            let name = op.name.name.as_ref();
+                            if intern("[]") == op.name.name { Kind::Function(box Kind::Star, box Kind::Star) }
+                            //A tuple constructor `(,...)` of arity n has the kind of a function taking n types
+                            else if name.starts_with("(,") { Kind::new(name.len() as isize - 1) }
+                            else { Kind::Star }
+                        });
                 }
                 _ => ()
             }
+            let type_name = extract_applied_type(&instance.typ).ctor().name.name;
             for binding in instance.bindings.iter_mut() {
                 let classname = &instance.classname;
-                let decl = class_decls.iter().find(|decl| binding.name.as_ref().ends_with(decl.name.as_ref()))
+                let decl = class_decls.iter().find(|decl| binding.name.name == encode_binding_identifier(type_name, decl.name.name))
                     .unwrap_or_else(|| panic!("Could not find {:?} in class {:?}", binding.name, classname));
                 binding.typ = decl.typ.clone();
+                //The constraint of the class itself is fulfilled by the instance
+                binding.typ.constraints.retain(|constraint| constraint.variables[0] != *class_var);
+                //Give the variables of the method new names first so that they can't be confused
+                //with the variables of the instance
+                let mut mapping = HashMap::new();
+                mapping.insert(class_var.clone(), Type::Variable(class_var.clone()));
+                self.freshen_qualified_type(&mut binding.typ, mapping);
                 replace_var(&mut binding.typ.value, class_var, &instance.typ);
+                //The function is passed the dictionary for the instance's context followed by
+                //the dictionary for the constraints on the method itself
+                let method_constraints = ::std::mem::replace(&mut binding.typ.constraints, instance.constraints.clone());
+                binding.typ.constraints.extend(method_constraints);
                 self.freshen_qualified_type(&mut binding.typ, HashMap::new());
-                {
-                    let mut context = vec![];
-                    swap(&mut context, &mut binding.typ.constraints);
-                    let mut vec_context: Vec<Constraint<Name>> = context.into_iter().collect();
-                    for constraint in instance.constraints.iter() {
-                        vec_context.push(constraint.clone());
-                    }
-                    binding.typ.constraints = vec_context;
-                }
+                declared_instance_types.push(binding.typ.clone());
             }
-            {
-                let mut missing_super_classes = self.find_class_constraints(instance.classname)
-                    .unwrap_or_else(|| panic!("Error: Missing class {:?}", instance.classname))
-                    .iter()//Make sure we have an instance for all of the constraints
-                    .filter(|constraint| self.has_instance(constraint.class, &instance.typ, &mut Vec::new()).is_err())
-                    .peekable();
-                if missing_super_classes.peek().is_some() {
-                    let mut buffer = ::std::string::String::new();
-                    buffer.push_str(missing_super_classes.next().unwrap().class.as_ref());
-                    for constraint in missing_super_classes {
-                        buffer.push_str(", ");
-                        buffer.push_str(constraint.class.as_ref());
-                    }
-                    panic!("The type {:?} does not have all necessary super class instances required for {:?}.\n Missing: {:?}",
-                        instance.typ, instance.classname, buffer);
+            self.instances.push((instance.constraints.clone(), instance.classname.clone(), instance.typ.clone()));
+        }
+        //The super class instances are checked once all instances of the module are known
+        //so that they can be declared in any order
+        for instance in module.instances.iter() {
+            let mut missing_super_classes = self.find_class_constraints(instance.classname)
+                .unwrap_or_else(|| panic!("Error: Missing class {:?}", instance.classname))
+                .iter()//Make sure we have an instance for all of the constraints
+                .filter(|constraint| self.has_instance(constraint.class, &instance.typ, &mut Vec::new()).is_err())
+                .peekable();
+            if missing_super_classes.peek().is_some() {
+                let mut buffer = ::std::string::String::new();
+                buffer.push_str(missing_super_classes.next().unwrap().class.as_ref());
+                for constraint in missing_super_classes {
+                    buffer.push_str(", ");
+                    buffer.push_str(constraint.class.as_ref());
                 }
+                panic!("The type {:?} does not have all necessary super class instances required for {:?}.\n Missing: {:?}",
+                    instance.typ, instance.classname, buffer);
             }
-            self.instances.push((instance.constraints.clone(), instance.classname.clone(), instance.typ.clone()));
         }
         
         for type_decl in module.type_declarations.iter_mut() {
@@ -408,6 +500,7 @@ impl <'a> TypeEnvironment<'a> {
             match module.bindings.iter_mut().find(|bind| bind.name == type_decl.name) {
                 Some(bind) => {
                     bind.typ = type_decl.typ.clone();
+                    self.signatures.insert(type_decl.name, type_decl.span);
                 }
                 None => panic!("Error: Type declaration for '{:?}' has no binding", type_decl.name)
             }
@@ -417,6 +510,20 @@ impl <'a> TypeEnvironment<'a> {
             let mut subs = Substitution { subs: HashMap::new() }; 
             self.typecheck_global_bindings(start_var_age, &mut subs, module);
         }
+        self.default_monomorphic(module);
+        //Inference only finds the constraints which the instance functions use but the dictionaries
+        //which they are called with has the layout of the declared constraints
+        let instance_bindings = module.instances.iter_mut().flat_map(|instance| instance.bindings.iter_mut());
+        for (binding, declared) in instance_bindings.zip(declared_instance_types.iter()) {
+            binding.typ.constraints = declared.constraints.iter()
+                .filter_map(|constraint| {
+                    match find_specialized(&binding.typ.value, &declared.value, &constraint.variables[0]) {
+                        Some(&Type::Variable(ref var)) => Some(Constraint { class: constraint.class, variables: vec![var.clone()] }),
+                        _ => None
+                    }
+                })
+                .collect();
+        }
     }
 
     ///Typechecks an expression.
@@ -425,8 +532,16 @@ impl <'a> TypeEnvironment<'a> {
     pub fn typecheck_expr(&mut self, expr: &mut TypedExpr<Name>) -> Result<(), TypeError> {
         let mut subs = Substitution { subs: HashMap::new() }; 
         let mut typ = self.typecheck(expr, &mut subs);
-        unify_location(self, &mut subs, &expr.location, &mut typ, &mut expr.typ);
+        unify_location(self, &mut subs, &expr.span, &mut typ, &mut expr.typ);
         self.substitute(&mut subs, expr);
+        //The expression is evaluated directly so every constrained variable in it needs to be defaulted
+        let ambiguous = {
+            let mut visitor = AmbiguityVisitor { env: self, bound: Vec::new(), ambiguous: Vec::new() };
+            visitor.visit_expr(expr);
+            visitor.ambiguous
+        };
+        let defaults = self.find_defaults(&ambiguous);
+        DefaultVisitor { env: self, subs: &defaults }.visit_expr(expr);
         self.errors.into_result(())
             .map_err(TypeError)
     }
@@ -555,19 +670,17 @@ impl <'a> TypeEnvironment<'a> {
                     constraints.iter()
                         .filter(|c| c.variables[0] == *rvar)
                         .map(|constraint| {
-                            let result = self.has_instance(constraint.class, &**rtype, new_constraints);
-                            if result.is_ok() {
-                                match **rtype {
-                                    Type::Variable(ref var) => {
-                                        new_constraints.push(Constraint {
-                                            class: constraint.class,
-                                            variables: vec![var.clone()]
-                                        });
-                                    }
-                                    _ => ()
+                            match **rtype {
+                                //A variable can still become any type so it gets the constraint instead
+                                Type::Variable(ref var) => {
+                                    new_constraints.push(Constraint {
+                                        class: constraint.class,
+                                        variables: vec![var.clone()]
+                                    });
+                                    Ok(())
                                 }
+                                _ => self.has_instance(constraint.class, &**rtype, new_constraints)
                             }
-                            result
                         })
                         .find(|result| result.is_err())
                         .unwrap_or_else(|| self.check_instance_constraints(constraints, &**lvar, &**ltype, new_constraints))
@@ -602,22 +715,22 @@ impl <'a> TypeEnvironment<'a> {
         match *matches {
             Match::Simple(ref mut e) => {
                 let mut typ = self.typecheck(e, subs);
-                unify_location(self, subs, &e.location, &mut typ, &mut e.typ);
+                unify_location(self, subs, &e.span, &mut typ, &mut e.typ);
                 typ
             }
             Match::Guards(ref mut gs) => {
                 let mut typ = None;
                 for guard in gs.iter_mut() {
                     let mut typ2 = self.typecheck(&mut guard.expression, subs);
-                    unify_location(self, subs, &guard.expression.location, &mut typ2, &mut guard.expression.typ);
+                    unify_location(self, subs, &guard.expression.span, &mut typ2, &mut guard.expression.typ);
                     match typ {
-                        Some(mut typ) => unify_location(self, subs, &guard.expression.location, &mut typ, &mut typ2),
+                        Some(mut typ) => unify_location(self, subs, &guard.expression.span, &mut typ, &mut typ2),
                         None => ()
                     }
                     typ = Some(typ2);
                     let mut predicate = self.typecheck(&mut guard.predicate, subs);
-                    unify_location(self, subs, &guard.predicate.location, &mut predicate, &mut typ::bool_type());
-                    unify_location(self, subs, &guard.predicate.location, &mut predicate, &mut guard.predicate.typ);
+                    unify_location(self, subs, &guard.predicate.span, &mut predicate, &mut typ::bool_type());
+                    unify_location(self, subs, &guard.predicate.span, &mut predicate, &mut guard.predicate.typ);
                 }
                 typ.unwrap()
             }
@@ -663,26 +776,26 @@ impl <'a> TypeEnvironment<'a> {
                         expr.typ = t.clone();
                         t
                     }
-                    None => panic!("Undefined identifier '{:?}' at {:?}", *name, expr.location)
+                    None => panic!("Undefined identifier '{:?}' at {}", *name, expr.span)
                 }
             }
             Apply(ref mut func, ref mut arg) => {
                 let func_type = self.typecheck(&mut **func, subs);
-                self.typecheck_apply(&expr.location, subs, func_type, &mut **arg)
+                self.typecheck_apply(&expr.span, subs, func_type, &mut **arg)
             }
             OpApply(ref mut lhs, ref op, ref mut rhs) => {
                 let op_type = match self.fresh(op) {
                     Some(typ) => typ,
-                    None => panic!("Undefined identifier '{:?}' at {:?}", *op, expr.location)
+                    None => panic!("Undefined identifier '{:?}' at {}", *op, expr.span)
                 };
-                let first = self.typecheck_apply(&expr.location, subs, op_type, &mut **lhs);
-                self.typecheck_apply(&expr.location, subs, first, &mut **rhs)
+                let first = self.typecheck_apply(&expr.span, subs, op_type, &mut **lhs);
+                self.typecheck_apply(&expr.span, subs, first, &mut **rhs)
             }
             Lambda(ref arg, ref mut body) => {
                 let mut arg_type = self.new_var();
                 let mut result = typ::function_type_(arg_type.clone(), self.new_var());
 
-                self.typecheck_pattern(&expr.location, subs, arg, &mut arg_type);
+                self.typecheck_pattern(&arg.span, subs, &arg.node, &mut arg_type);
                 let body_type = self.typecheck(&mut **body, subs);
                 with_arg_return(&mut result, |_, return_type| {
                     *return_type = body_type.clone();
@@ -696,40 +809,46 @@ impl <'a> TypeEnvironment<'a> {
             }
             Case(ref mut case_expr, ref mut alts) => {
                 let mut match_type = self.typecheck(&mut **case_expr, subs);
-                self.typecheck_pattern(&alts[0].pattern.location, subs, &alts[0].pattern.node, &mut match_type);
+                self.typecheck_pattern(&alts[0].pattern.span, subs, &alts[0].pattern.node, &mut match_type);
                 match *&mut alts[0].where_bindings {
                     Some(ref mut bindings) => self.typecheck_local_bindings(subs, &mut BindingsWrapper { value: &mut **bindings }),
                     None => ()
                 }
                 let mut alt0_ = self.typecheck_match(&mut alts[0].matches, subs);
                 for alt in alts.iter_mut().skip(1) {
-                    self.typecheck_pattern(&alt.pattern.location, subs, &alt.pattern.node, &mut match_type);
+                    self.typecheck_pattern(&alt.pattern.span, subs, &alt.pattern.node, &mut match_type);
                     match alt.where_bindings {
                         Some(ref mut bindings) => self.typecheck_local_bindings(subs, &mut BindingsWrapper { value: &mut **bindings }),
                         None => ()
                     }
                     let mut alt_type = self.typecheck_match(&mut alt.matches, subs);
-                    unify_location(self, subs, &alt.pattern.location, &mut alt0_, &mut alt_type);
+                    unify_location(self, subs, &alt.pattern.span, &mut alt0_, &mut alt_type);
                 }
                 alt0_
             }
             IfElse(ref mut pred, ref mut if_true, ref mut if_false) => {
                 let mut p = self.typecheck(&mut **pred, subs);
-                unify_location(self, subs, &expr.location, &mut p, &mut typ::bool_type());
+                unify_location(self, subs, &expr.span, &mut p, &mut typ::bool_type());
                 let mut t = self.typecheck(&mut **if_true, subs);
                 let mut f = self.typecheck(&mut **if_false, subs);
-                unify_location(self, subs, &expr.location, &mut t, &mut f);
+                unify_location(self, subs, &expr.span, &mut t, &mut f);
                 t
             }
             Do(ref mut bindings, ref mut last_expr) => {
                 let mut previous = self.new_var_kind(Kind::Function(box Kind::Star, box Kind::Star));
-                self.constraints.insert(previous.var().clone(), vec!(Name { name: intern("Monad"), uid: 0 }));
+                //A pattern which can fail to match calls `fail` so the monad needs to be a `MonadFail`
+                let can_fail = bindings.iter().any(|bind| match *bind {
+                    DoBinding::DoBind(ref pattern, _) => !pattern.node.is_failure_free(),
+                    _ => false
+                });
+                let class = if can_fail { "MonadFail" } else { "Monad" };
+                self.constraints.insert(previous.var().clone(), vec!(Name { name: intern(class), uid: 0 }));
                 previous = Type::Application(box previous, box self.new_var());
                 for bind in bindings.iter_mut() {
                     match *bind {
                         DoBinding::DoExpr(ref mut e) => {
                             let mut typ = self.typecheck(e, subs);
-                            unify_location(self, subs, &e.location, &mut typ, &mut previous);
+                            unify_location(self, subs, &e.span, &mut typ, &mut previous);
                         }
                         DoBinding::DoLet(ref mut bindings) => {
                             self.typecheck_local_bindings(subs, &mut BindingsWrapper { value: &mut **bindings });
@@ -737,12 +856,12 @@ impl <'a> TypeEnvironment<'a> {
                         }
                         DoBinding::DoBind(ref mut pattern, ref mut e) => {
                             let mut typ = self.typecheck(e, subs);
-                            unify_location(self, subs, &e.location, &mut typ, &mut previous);
+                            unify_location(self, subs, &e.span, &mut typ, &mut previous);
                             let inner_type = match typ {
                                 Type::Application(_, ref mut t) => t,
                                 _ => panic!("Not a monadic type: {:?}", typ)
                             };
-                            self.typecheck_pattern(&pattern.location, subs, &pattern.node, &mut **inner_type);
+                            self.typecheck_pattern(&pattern.span, subs, &pattern.node, &mut **inner_type);
                         }
                     }
                     match previous {
@@ -753,13 +872,13 @@ impl <'a> TypeEnvironment<'a> {
                     }
                 }
                 let mut typ = self.typecheck(&mut **last_expr, subs);
-                unify_location(self, subs, &last_expr.location, &mut typ, &mut previous);
+                unify_location(self, subs, &last_expr.span, &mut typ, &mut previous);
                 typ
             }
             TypeSig(ref mut expr, ref mut qualified_type) => {
                 let mut typ = self.typecheck(&mut **expr, subs);
-                self.freshen_qualified_type(qualified_type, HashMap::new());
-                match_or_fail(self, subs, &expr.location, &mut typ, &mut qualified_type.value);
+                self.freshen_qualified_type(&mut qualified_type.node, HashMap::new());
+                match_or_fail(self, subs, &expr.span, Some(qualified_type.span), &mut typ, &mut qualified_type.node.value);
                 typ
             }
             Paren(ref mut expr) => self.typecheck(&mut **expr, subs)
@@ -768,7 +887,7 @@ impl <'a> TypeEnvironment<'a> {
         expr.typ = x.clone();
         x
     }
-    fn typecheck_apply(&mut self, location: &Location, subs: &mut Substitution, mut func_type: TcType, arg: &mut TypedExpr<Name>) -> TcType {
+    fn typecheck_apply(&mut self, location: &Span, subs: &mut Substitution, mut func_type: TcType, arg: &mut TypedExpr<Name>) -> TcType {
         let arg_type = self.typecheck(arg, subs);
         let mut result = typ::function_type_(arg_type, self.new_var());
         unify_location(self, subs, location, &mut func_type, &mut result);
@@ -780,13 +899,15 @@ impl <'a> TypeEnvironment<'a> {
     }
     ///Typechecks a pattern.
     ///Checks that the pattern has the type 'match_type' and adds all variables in the pattern.
-    fn typecheck_pattern(&mut self, location: &Location, subs: &mut Substitution, pattern: &Pattern<Name>, match_type: &mut TcType) {
+    fn typecheck_pattern(&mut self, location: &Span, subs: &mut Substitution, pattern: &Pattern<Name>, match_type: &mut TcType) {
         match pattern {
             &Pattern::Identifier(ref ident) => {
                 self.local_types.insert(ident.clone(), qualified(vec![], match_type.clone()));
             }
             &Pattern::Number(_) => {
-                let mut typ = typ::int_type();
+                //Numeric literals in patterns are compared with (==) against (fromInteger n)
+                let mut typ = self.new_var();
+                self.constraints.insert(typ.var().clone(), vec![prelude_name("Eq"), prelude_name("Num")]);
                 unify_location(self, subs, location, &mut typ, match_type);
             }
             &Pattern::Constructor(ref ctorname, ref patterns) => {
@@ -804,7 +925,7 @@ impl <'a> TypeEnvironment<'a> {
         }
     }
     ///Walks through the arguments of a pattern and typechecks each of them.
-    fn pattern_rec(&mut self, i: usize, location: &Location, subs: &mut Substitution, patterns: &[Pattern<Name>], func_type: &mut TcType) {
+    fn pattern_rec(&mut self, i: usize, location: &Span, subs: &mut Substitution, patterns: &[Pattern<Name>], func_type: &mut TcType) {
         if i < patterns.len() {
             let p = &patterns[i];
             with_arg_return(func_type, |arg_type, return_type| {
@@ -829,10 +950,10 @@ impl <'a> TypeEnvironment<'a> {
         let mut previous_type = None;
         for bind in bindings.iter_mut() {
             if argument_types.len() != bind.arguments.len() {
-                panic!("Binding {:?} do not have the same number of arguments", bind.name);//TODO re add location
+                panic!("{}: Binding {:?} do not have the same number of arguments", bind.span, bind.name);
             }
             for (arg, typ) in bind.arguments.iter_mut().zip(argument_types.iter_mut()) {
-                self.typecheck_pattern(&Location::eof(), subs, arg, typ);
+                self.typecheck_pattern(&arg.span, subs, &arg.node, typ);
             }
             match bind.where_bindings {
                 Some(ref mut bindings) => self.typecheck_local_bindings(subs, &mut BindingsWrapper { value: &mut **bindings }),
@@ -845,7 +966,7 @@ impl <'a> TypeEnvironment<'a> {
             }
             typ = make_function(argument_types.as_ref(), &typ);
             match previous_type {
-                Some(mut prev) => unify_location(self, subs, bind.matches.location(), &mut typ, &mut prev),
+                Some(mut prev) => unify_location(self, subs, bind.matches.span(), &mut typ, &mut prev),
                 None => ()
             }
             replace(&mut self.constraints, &mut typ, subs);
@@ -856,10 +977,11 @@ impl <'a> TypeEnvironment<'a> {
         //In that case we need to unify that variable to 'typ' to make sure that environment becomes updated
         //Otherwise a type declaration exists and we need to do a match to make sure that the type is not to specialized
         if type_var.is_none() {
-            match_or_fail(self, subs, &Location::eof(), &mut final_type, &bindings[0].typ.value);
+            let signature = self.signatures.get(&bindings[0].name).cloned();
+            match_or_fail(self, subs, &bindings[0].span, signature, &mut final_type, &bindings[0].typ.value);
         }
         else {
-            unify_location(self, subs, &Location::eof(), &mut final_type, &mut bindings[0].typ.value);
+            unify_location(self, subs, &bindings[0].span, &mut final_type, &mut bindings[0].typ.value);
         }
         match type_var {
             Some(var) => { subs.subs.insert(var, final_type); }
@@ -893,9 +1015,17 @@ impl <'a> TypeEnvironment<'a> {
         let groups = strongly_connected_components(&graph);
 
         for group in groups.iter() {
+            //Only the unconstrained variables of a group restricted by the monomorphism restriction are generalized
+            //and with `MonoLocalBinds` no variables of a local group are generalized
+            let mut restricted = false;
+            let mut monomorphic = false;
             for index in group.iter() {
                 let bind_index = graph.get_vertex(*index).value;
                 let binds = bindings.get_mut(bind_index);
+                if binds[0].typ.value == Type::<Name>::new_var(intern("a")) {
+                    restricted = restricted || (self.options.monomorphism_restriction && binds[0].arguments.len() == 0);
+                    monomorphic = monomorphic || (self.options.mono_local_binds && !is_global);
+                }
                 for bind in binds.iter_mut() {
                     if bind.typ.value == Type::<Name>::new_var(intern("a")) {
                         bind.typ.value = self.new_var();
@@ -920,12 +1050,30 @@ impl <'a> TypeEnvironment<'a> {
                             replace(&mut self.constraints, &mut self.named_types.get_mut(&bind.name).unwrap().value, subs);
                         }
                     }
+                    for name in self.monomorphic.iter() {
+                        replace(&mut self.constraints, &mut self.named_types.get_mut(name).unwrap().value, subs);
+                    }
                     self.local_types.clear();
                 }
                 else {
                     self.apply_locals(subs);
                 }
             }
+            //Variables which are in the type of a monomorphic global binding can't be generalized
+            let mut fixed = if is_global { self.monomorphic_variables() } else { Vec::new() };
+            if restricted || monomorphic {
+                for index in group.iter() {
+                    let binds = bindings.get_mut(graph.get_vertex(*index).value);
+                    let typ = if is_global { &self.named_types[&binds[0].name] } else { &self.local_types[&binds[0].name] };
+                    let constraints = &self.constraints;
+                    each_type(&typ.value, |var| {
+                        let constrained = constraints.get(var).map_or(false, |cs| cs.len() != 0);
+                        if (monomorphic || constrained) && !fixed.contains(var) {
+                            fixed.push(var.clone());
+                        }
+                    }, |_| ());
+                }
+            }
             for index in group.iter() {
                 let bind_index = graph.get_vertex(*index).value;
                 let binds = bindings.get_mut(bind_index);
@@ -941,15 +1089,41 @@ impl <'a> TypeEnvironment<'a> {
                             self.local_types.get_mut(&bind.name).unwrap()
                         };
                         bind.typ.value = typ.value.clone();
-                        quantify(start_var_age, typ);
+                        //The variables of a type signature are created before 'start_var_age'
+                        //so all variables in a global binding are quantified
+                        quantify_except(if is_global { 0 } else { start_var_age }, &fixed, typ);
                     }
                     bind.typ.constraints = self.find_constraints(&bind.typ.value);
+                    //The dictionaries for the fixed variables are not passed to the binding
+                    bind.typ.constraints.retain(|constraint| !fixed.contains(&constraint.variables[0]));
+                    if !is_global {
+                        //Constraints on variables bound by an enclosing binding are passed through that
+                        //binding's dictionary so only the generalized variables takes a dictionary here
+                        bind.typ.constraints.retain(|constraint| constraint.variables[0].age >= start_var_age);
+                    }
+                }
+                //Later uses of the binding are instantiated with the constraints of the generalized variables
+                {
+                    let typ = if is_global {
+                        self.named_types.get_mut(&binds[0].name).unwrap()
+                    }
+                    else {
+                        self.local_types.get_mut(&binds[0].name).unwrap()
+                    };
+                    typ.constraints = binds[0].typ.constraints.clone();
+                }
+                if is_global && fixed.iter().any(|var| occurs(var, &binds[0].typ.value)) {
+                    self.monomorphic.push(binds[0].name);
                 }
                 debug!("End typecheck {:?} :: {:?}", binds[0].name, binds[0].typ);
             }
             if is_global {
+                for index in group.iter() {
+                    let binds = bindings.get_mut(graph.get_vertex(*index).value);
+                    self.default_bindings(binds);
+                }
+                self.update_monomorphic(subs);
                 subs.subs.clear();
-                self.constraints.clear();
             }
         }
     }
@@ -963,6 +1137,148 @@ impl <'a> TypeEnvironment<'a> {
         self.typecheck_mutually_recursive_bindings(start_var_age, subs, bindings, true);
     }
     
+    ///Defaults the constrained type variables in 'bindings' which do not appear in the type of any binding
+    fn default_bindings(&mut self, bindings: &mut [Binding<Name>]) {
+        let ambiguous = {
+            //The variables of monomorphic bindings are determined by later bindings or defaulted with the whole module
+            let bound = self.monomorphic_variables();
+            let mut visitor = AmbiguityVisitor { env: self, bound: bound, ambiguous: Vec::new() };
+            for bind in bindings.iter() {
+                each_type(&bind.typ.value, |var| visitor.bound.push(var.clone()), |_| ());
+                visitor.visit_binding(bind);
+            }
+            visitor.ambiguous
+        };
+        if ambiguous.len() == 0 {
+            return
+        }
+        let defaults = self.find_defaults(&ambiguous);
+        let mut visitor = DefaultVisitor { env: self, subs: &defaults };
+        for bind in bindings.iter_mut() {
+            visitor.visit_binding(bind);
+        }
+    }
+
+    ///Returns the type variables in the types of the monomorphic global bindings
+    fn monomorphic_variables(&self) -> Vec<TypeVariable> {
+        let mut vars = Vec::new();
+        for name in self.monomorphic.iter() {
+            each_type(&self.named_types[name].value, |var| {
+                if !vars.contains(var) {
+                    vars.push(var.clone());
+                }
+            }, |_| ());
+        }
+        vars
+    }
+
+    ///Records what the monomorphic type variables were bound to by a global binding group.
+    ///The constraints of the variables which are still unbound are kept for the next groups
+    fn update_monomorphic(&mut self, subs: &Substitution) {
+        for (_, typ) in self.monomorphic_subs.subs.iter_mut() {
+            replace(&mut self.constraints, typ, subs);
+        }
+        for var in self.monomorphic_vars.iter() {
+            match subs.subs.get(var) {
+                Some(typ) => { self.monomorphic_subs.subs.insert(var.clone(), typ.clone()); }
+                None => ()
+            }
+        }
+        self.monomorphic_vars = self.monomorphic_variables();
+        let constraints: Vec<_> = self.monomorphic_vars.iter()
+            .filter_map(|var| self.constraints.get(var).map(|constraints| (var.clone(), constraints.clone())))
+            .collect();
+        self.constraints.clear();
+        self.constraints.extend(constraints);
+    }
+
+    ///Defaults the monomorphic type variables which are still unbound once every binding in the module
+    ///has been typechecked and updates the bindings with the types the variables were bound to
+    fn default_monomorphic(&mut self, module: &mut Module<Name>) {
+        if self.monomorphic.len() != 0 {
+            let ambiguous: Vec<_> = self.monomorphic_vars.iter()
+                .filter(|var| self.constraints.get(*var).map_or(false, |constraints| constraints.len() != 0))
+                .map(|var| {
+                    let name = self.monomorphic.iter()
+                        .find(|name| occurs(var, &self.named_types[*name].value));
+                    let span = module.bindings.iter()
+                        .find(|bind| Some(&bind.name) == name)
+                        .map_or(Span::default(), |bind| bind.span);
+                    (var.clone(), span, true)
+                })
+                .collect();
+            let defaults = self.find_defaults(&ambiguous);
+            let mut subs = ::std::mem::replace(&mut self.monomorphic_subs, Substitution { subs: HashMap::new() });
+            for (_, typ) in subs.subs.iter_mut() {
+                replace(&mut self.constraints, typ, &defaults);
+            }
+            subs.subs.extend(defaults.subs.into_iter());
+            for name in self.monomorphic.iter() {
+                let typ = self.named_types.get_mut(name).unwrap();
+                replace(&mut self.constraints, &mut typ.value, &subs);
+                quantify(0, typ);
+            }
+            let mut visitor = DefaultVisitor { env: self, subs: &subs };
+            for bind in module.bindings.iter_mut() {
+                visitor.visit_binding(bind);
+            }
+            for bind in module.instances.iter_mut().flat_map(|instance| instance.bindings.iter_mut()) {
+                visitor.visit_binding(bind);
+            }
+            for bind in module.classes.iter_mut().flat_map(|class| class.bindings.iter_mut()) {
+                visitor.visit_binding(bind);
+            }
+        }
+        self.monomorphic.clear();
+        self.monomorphic_vars.clear();
+        self.monomorphic_subs.subs.clear();
+        self.constraints.clear();
+    }
+
+    ///Finds the default type of each ambiguous variable, reporting an error for each variable without one
+    fn find_defaults(&mut self, ambiguous: &[(TypeVariable, Span, bool)]) -> Substitution {
+        let mut subs = Substitution { subs: HashMap::new() };
+        for &(ref var, span, _) in ambiguous.iter() {
+            let classes = self.constraints.get(var).cloned().unwrap_or(Vec::new());
+            match self.find_default(var, &classes) {
+                Some(typ) => { subs.subs.insert(var.clone(), typ); }
+                None => {
+                    let typ = Type::Variable(var.clone());
+                    self.errors.insert(TypeErrorInfo { span: span, signature: None, lhs: typ.clone(), rhs: typ, error: Error::Ambiguous(classes) })
+                }
+            }
+        }
+        subs
+    }
+
+    ///Returns the first default type which has an instance for every class in 'classes'.
+    ///A variable is only defaulted if one of its classes are numeric and all of them are standard classes,
+    ///the extended rules also allows any variable with a numeric class or `Show`, `Eq` or `Ord` constraint
+    fn find_default(&self, var: &TypeVariable, classes: &[Name]) -> Option<TcType> {
+        let num = prelude_name("Num");
+        let numeric = classes.iter().any(|class| *class == num || *class == prelude_name("Fractional") || self.exists_as_super_class(*class, num));
+        let defaultable = if self.extended_defaults {
+            numeric || classes.iter().any(|class| ["Show", "Eq", "Ord"].iter().any(|name| *class == prelude_name(name)))
+        }
+        else {
+            numeric && classes.iter().all(|class| STANDARD_CLASSES.iter().any(|name| class.name == intern(name)))
+        };
+        if !defaultable {
+            return None
+        }
+        let extended = if self.extended_defaults {
+            let list = Type::Constructor(TypeConstructor { name: prelude_name("[]"), kind: Kind::Function(box Kind::Star, box Kind::Star) });
+            vec![typ::unit(), list]
+        }
+        else {
+            Vec::new()
+        };
+        extended.iter().chain(self.defaults.iter())
+            .find(|typ| *typ.kind() == var.kind
+                && classes.iter().all(|class| self.has_instance(*class, typ, &mut Vec::new()).is_ok() || builtin_literal_instance(*class, typ)))
+            .cloned()
+    }
+
     ///Workaround to make all imported functions quantified without requiring their type variables to be generic
     fn find_fresh(&self, name: &Name) -> Option<Qualified<TcType, Name>> {
         self.local_types.get(name)
@@ -1035,37 +1351,122 @@ impl <'a> TypeEnvironment<'a> {
 }
 
 
+///The defaults used by a module without a `default` declaration, `default (Integer, Double)`
+fn standard_defaults() -> Vec<TcType> {
+    vec![typ::integer_type(), typ::double_type()]
+}
+
+///The classes of the Prelude and the standard library modules, only variables constrained by these are defaulted
+const STANDARD_CLASSES: &'static [&'static str] = &["Eq", "Ord", "Show", "Read", "Enum", "Bounded", "Ix",
+    "Num", "Real", "Integral", "Fractional", "Floating", "RealFrac", "RealFloat",
+    "Functor", "Applicative", "Monad", "MonadFail", "Alternative", "MonadPlus",
+    "Semigroup", "Monoid", "Foldable", "Traversable"];
+
+///Returns whether `typ` is a builtin number type which the classes of literals are accepted for
+///without an instance, so that literals can be used in a module which does not import the Prelude
+fn builtin_literal_instance(class: Name, typ: &TcType) -> bool {
+    match *typ {
+        Type::Constructor(ref op) if *typ.kind() == Kind::Star => {
+            if class.name == intern("Num") || class.name == intern("Eq") {
+                op.name == intern("Int") || op.name == intern("Integer") || op.name == intern("Double")
+            }
+            else {
+                class.name == intern("Fractional") && op.name == intern("Double")
+            }
+        }
+        _ => false
+    }
+}
+
+///Collects the constrained type variables which are not determined by the type of any binding,
+///together with the location of the first expression which has the variable in its type
+struct AmbiguityVisitor<'a: 'b, 'b> {
+    env: &'b TypeEnvironment<'a>,
+    ///Variables which are in the type of a global binding or constrained in a local binding
+    bound: Vec<TypeVariable>,
+    ///Each variable, where it was found and whether the expression has exactly the variable as its type
+    ambiguous: Vec<(TypeVariable, Span, bool)>
+}
+
+impl <'a, 'b> Visitor<Name> for AmbiguityVisitor<'a, 'b> {
+    fn visit_expr(&mut self, expr: &TypedExpr<Name>) {
+        let AmbiguityVisitor { env, ref bound, ref mut ambiguous } = *self;
+        each_type(&expr.typ,
+        |var| {
+            let constrained = env.constraints.get(var).map_or(false, |constraints| constraints.len() != 0);
+            if constrained && !bound.iter().any(|v| v.id == var.id) {
+                //Prefer pointing at an expression which has the variable as its type
+                let exact = match expr.typ { Type::Variable(ref v) => v.id == var.id, _ => false };
+                match ambiguous.iter().position(|&(ref v, _, _)| v.id == var.id) {
+                    Some(i) => if exact && !ambiguous[i].2 { ambiguous[i] = (var.clone(), expr.span, true) },
+                    None => ambiguous.push((var.clone(), expr.span, exact))
+                }
+            }
+        },
+        |_| ());
+        walk_expr(self, expr);
+    }
+    fn visit_binding(&mut self, binding: &Binding<Name>) {
+        for constraint in binding.typ.constraints.iter() {
+            self.bound.push(constraint.variables[0].clone());
+        }
+        match binding.where_bindings {
+            Some(ref bindings) => {
+                for bind in bindings.iter() {
+                    self.visit_binding(bind);
+                }
+            }
+            None => ()
+        }
+        walk_binding(self, binding);
+    }
+}
+
+///Replaces the defaulted variables in every type of an expression or binding
+struct DefaultVisitor<'a: 'b, 'b, 'c> {
+    env: &'b mut TypeEnvironment<'a>,
+    subs: &'c Substitution
+}
+
+impl <'a, 'b, 'c> MutVisitor<Name> for DefaultVisitor<'a, 'b, 'c> {
+    fn visit_expr(&mut self, expr: &mut TypedExpr<Name>) {
+        replace(&mut self.env.constraints, &mut expr.typ, self.subs);
+        walk_expr_mut(self, expr);
+    }
+    fn visit_binding(&mut self, binding: &mut Binding<Name>) {
+        replace(&mut self.env.constraints, &mut binding.typ.value, self.subs);
+        match binding.where_bindings {
+            Some(ref mut bindings) => {
+                for bind in bindings.iter_mut() {
+                    self.visit_binding(bind);
+                }
+            }
+            None => ()
+        }
+        walk_binding_mut(self, binding);
+    }
+}
+
 ///Searches through a type, comparing it with the type on the identifier, returning all the specialized constraints
 pub fn find_specialized_instances(typ: &TcType, actual_type: &TcType, constraints: &[Constraint<Name>]) -> Vec<(Name, TcType)> {
     debug!("Finding specialization {:?} => {:?} <-> {:?}", constraints, typ, actual_type);
-    let mut result = Vec::new();
-    find_specialized(&mut result, actual_type, typ, constraints);
     if constraints.len() == 0 {
         panic!("Could not find the specialized instance between {:?} <-> {:?}", typ, actual_type);
     }
-    result
+    //The instances are returned in the same order as the constraints so that the dictionary
+    //has the same layout as the one expected by the function
+    constraints.iter()
+        .filter_map(|c| find_specialized(actual_type, typ, &c.variables[0]).map(|t| (c.class.clone(), t.clone())))
+        .collect()
 }
-fn find_specialized(result: &mut Vec<(Name, TcType)>, actual_type: &TcType, typ: &TcType, constraints: &[Constraint<Name>]) {
+fn find_specialized<'a>(actual_type: &'a TcType, typ: &TcType, var: &TypeVariable) -> Option<&'a TcType> {
     match (actual_type, typ) {
-        (_, &Type::Variable(ref var)) => {
-            for c in constraints.iter().filter(|c| c.variables[0] == *var) {
-                if result.iter().find(|x| x.0 == c.class) == None {
-                    result.push((c.class.clone(), actual_type.clone()));
-                }
-            }
-        }
+        (_, &Type::Variable(ref v)) | (_, &Type::Generic(ref v)) if v == var => Some(actual_type),
         (&Type::Application(ref lhs1, ref rhs1), &Type::Application(ref lhs2, ref rhs2)) => {
-            find_specialized(result, &**lhs1, &**lhs2, constraints);
-            find_specialized(result, &**rhs1, &**rhs2, constraints);
-        }
-        (_, &Type::Generic(ref var)) => {
-            for c in constraints.iter().filter(|c| c.variables[0] == *var) {
-                if result.iter().find(|x| x.0 == c.class) == None {
-                    result.push((c.class.clone(), actual_type.clone()));
-                }
-            }
+            find_specialized(&**lhs1, &**lhs2, var)
+                .or_else(|| find_specialized(&**rhs1, &**rhs2, var))
         }
-        _ => ()
+        _ => None
     }
 }
 
@@ -1095,6 +1496,26 @@ fn quantify(start_var_age: isize, typ: &mut Qualified<TcType, Name>) {
     quantify_(start_var_age, &mut typ.value);
 }
 
+///Quantifies the type variables like `quantify` except for those in `fixed`
+fn quantify_except(start_var_age: isize, fixed: &[TypeVariable], typ: &mut Qualified<TcType, Name>) {
+    fn quantify_(start_var_age: isize, fixed: &[TypeVariable], typ: &mut TcType) {
+        let x = match *typ {
+            Type::Variable(ref id) if id.age >= start_var_age && !fixed.contains(id) => Some(id.clone()),
+            Type::Application(ref mut lhs, ref mut rhs) => {
+                quantify_(start_var_age, fixed, &mut **lhs);
+                quantify_(start_var_age, fixed, &mut **rhs);
+                None
+            }
+            _ => None
+        };
+        match x {
+            Some(var) => *typ = Type::Generic(var),
+            None => ()
+        }
+    }
+    quantify_(start_var_age, fixed, &mut typ.value);
+}
+
 ///Replaces all occurences of 'var' in 'typ' with the the type 'replacement'
 pub fn replace_var(typ: &mut TcType, var: &TypeVariable, replacement: &TcType) {
     let new = match *typ {
@@ -1249,19 +1670,21 @@ fn freshen_var(env: &mut TypeEnvironment, subs: &mut Substitution, constraints:
 }
 
 ///Takes two types and attempts to make them the same type
-fn unify_location(env: &mut TypeEnvironment, subs: &mut Substitution, location: &Location, lhs: &mut TcType, rhs: &mut TcType) {
+fn unify_location(env: &mut TypeEnvironment, subs: &mut Substitution, location: &Span, lhs: &mut TcType, rhs: &mut TcType) {
     debug!("{:?} Unifying {:?} <-> {:?}", location, *lhs, *rhs);
     match unify(env, subs, lhs, rhs) {
         Ok(()) => (),
         Err(error) => {
-            env.errors.insert(TypeErrorInfo { location: location.clone(), lhs: lhs.clone(), rhs: rhs.clone(), error: error })
+            env.errors.insert(TypeErrorInfo { span: location.clone(), signature: None, lhs: lhs.clone(), rhs: rhs.clone(), error: error })
         }
     }
 }
 
 #[derive(Debug)]
 struct TypeErrorInfo {
-    location: Location,
+    span: Span,
+    ///The span of the type signature which the types were matched against, if any
+    signature: Option<Span>,
     lhs: TcType,
     rhs: TcType,
     error: Error
@@ -1272,32 +1695,122 @@ enum Error {
     UnifyFail(TcType, TcType),
     RecursiveUnification,
     WrongArity(TcType, TcType),
-    MissingInstance(InternedStr, TcType, TypeVariable)
+    MissingInstance(InternedStr, TcType, TypeVariable),
+    ///The type variable (stored in `lhs`) is constrained by these classes but can't be determined or defaulted
+    Ambiguous(Vec<Name>)
 }
 
 impl fmt::Display for TypeErrorInfo {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.error {
-            Error::UnifyFail(ref l, ref r) =>
-                write!(f, "{} Error: Could not unify types\n{}\nand\n{}\nin types\n{}\nand\n{}",
-                    self.location, l, r, self.lhs, self.rhs),
+        write_diagnostics(f, self)
+    }
+}
+
+impl ToDiagnostics for TypeErrorInfo {
+    fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        let mut names = TypeNames::new();
+        let lhs = names.show(&self.lhs);
+        let rhs = names.show(&self.rhs);
+        let unifying = format!("while unifying `{}` with `{}`", lhs, rhs);
+        let diagnostic = match self.error {
+            Error::UnifyFail(ref l, ref r) => {
+                let (l, r) = (names.show(l), names.show(r));
+                let label = match self.signature {
+                    Some(_) => format!("expected `{}`, found `{}`", r, l),
+                    None => format!("`{}` does not match `{}`", l, r)
+                };
+                Diagnostic::error("E0201", format!("mismatched types `{}` and `{}`", l, r))
+                    .with_label(self.span, label)
+                    .with_note(unifying)
+            }
             Error::RecursiveUnification =>
-                write!(f, "{} Error: Recursive unification between {}\nand\n{}",
-                    self.location, self.lhs, self.rhs),
-            Error::WrongArity(ref l, ref r) =>
-                write!(f, "{} Error: Types do not have the same arity.\n{} <-> {}\n{} <-> {}\n{}\nand\n{}"
-                    , self.location, l, r, l.kind(), r.kind(), self.lhs, self.rhs),
-            Error::MissingInstance(ref class, ref typ, ref id) =>
-                write!(f, "{} Error: The instance {} {} was not found as required by {} when unifying {}\nand\n{}",
-                    self.location, class, typ, id, self.lhs, self.rhs)
+                Diagnostic::error("E0202", "cannot construct an infinite type".to_string())
+                    .with_label(self.span, "the infinite type arises here".to_string())
+                    .with_note(unifying),
+            Error::WrongArity(ref l, ref r) => {
+                let (l_kind, r_kind) = (l.kind().clone(), r.kind().clone());
+                let (l, r) = (names.show(l), names.show(r));
+                Diagnostic::error("E0203", format!("kind mismatch between `{}` and `{}`", l, r))
+                    .with_label(self.span, format!("`{}` has kind `{}` but `{}` has kind `{}`", l, l_kind, r, r_kind))
+                    .with_note(unifying)
+            }
+            Error::MissingInstance(class, ref typ, ref var) => {
+                let typ = names.show(typ);
+                let var = names.show(&Type::Variable(var.clone()));
+                Diagnostic::error("E0204", format!("no instance for `{} {}`", class, typ))
+                    .with_label(self.span, format!("`{} {}` is required here", class, typ))
+                    .with_note(format!("required by the constraint `{} {}`", class, var))
+                    .with_help(format!("add an instance declaration for `{} {}`", class, typ))
+            }
+            Error::Ambiguous(ref classes) => {
+                let constraints: Vec<_> = classes.iter()
+                    .map(|class| format!("{} {}", class.name, lhs))
+                    .collect();
+                let context = if constraints.len() == 1 { constraints[0].clone() } else { format!("({})", constraints.join(", ")) };
+                Diagnostic::error("E0205", format!("ambiguous type variable `{}` in the constraint `{}`", lhs, context))
+                    .with_label(self.span, format!("cannot infer the type `{}` used here", lhs))
+                    .with_help("add a type annotation to fix the type of the expression".to_string())
+            }
+        };
+        let diagnostic = match self.signature {
+            Some(signature) => diagnostic.with_secondary_label(signature, "expected because of this signature".to_string()),
+            None => diagnostic
+        };
+        vec![diagnostic]
+    }
+}
+
+///Formats types for diagnostics.
+///Type constructors are shown without their unique ids and type variables are named a, b, c ...
+///in the order they are first seen, so the same variable gets the same name in every type shown
+struct TypeNames {
+    variables: HashMap<InternedStr, InternedStr>
+}
+
+impl TypeNames {
+    fn new() -> TypeNames {
+        TypeNames { variables: HashMap::new() }
+    }
+
+    fn show(&mut self, typ: &TcType) -> ::std::string::String {
+        let typ = self.rename(typ);
+        format!("{}", typ)
+    }
+
+    fn rename(&mut self, typ: &TcType) -> Type<InternedStr> {
+        match *typ {
+            Type::Variable(ref var) => Type::Variable(self.rename_var(var)),
+            Type::Generic(ref var) => Type::Variable(self.rename_var(var)),
+            Type::Constructor(ref op) => Type::Constructor(TypeConstructor { name: op.name.name, kind: op.kind.clone() }),
+            Type::Application(ref lhs, ref rhs) => Type::Application(box self.rename(&**lhs), box self.rename(&**rhs))
         }
     }
+
+    fn rename_var(&mut self, var: &TypeVariable) -> TypeVariable {
+        let count = self.variables.len();
+        let id = *self.variables.entry(var.id)
+            .or_insert_with(|| {
+                if count < 26 { intern(&((b'a' + count as u8) as char).to_string()) }
+                else { intern(&format!("t{}", count)) }
+            });
+        TypeVariable { id: id, kind: var.kind.clone(), age: var.age }
+    }
 }
 
 ///Tries to bind the type to the variable.
 ///Returns Ok if the binding was possible.
 ///Returns Error if the binding was not possible and the reason for the error.
 fn bind_variable(env: &mut TypeEnvironment, subs: &mut Substitution, var: &TypeVariable, typ: &TcType) -> Result<(), Error> {
+    //If 'typ' is a variable which is already bound, 'var' is bound to what that variable is bound to
+    //as the substitution is only applied a single step
+    let bound = match *typ {
+        Type::Variable(ref var2) => subs.subs.get(var2).cloned(),
+        _ => None
+    };
+    let typ = match bound {
+        Some(ref bound) if !occurs(var, bound) => bound,
+        _ => typ
+    };
     match *typ {
         Type::Variable(ref var2) => {
             if var != var2 {
@@ -1335,16 +1848,8 @@ fn bind_variable(env: &mut TypeEnvironment, subs: &mut Substitution, var: &TypeV
                             let result = env.has_instance(*c, typ, &mut new_constraints);
                             match result {
                                 Err(missing_instance) => {
-                                    match *typ {
-                                        Type::Constructor(ref op) => {
-                                            if c.name == intern("Num") && (op.name == intern("Int") || op.name == intern("Double")) && *typ.kind() == Kind::Star {
-                                                continue;
-                                            }
-                                            else if c.name == intern("Fractional") && intern("Double") == op.name && *typ.kind() == Kind::Star {
-                                                continue;
-                                            }
-                                        }
-                                        _ => ()
+                                    if builtin_literal_instance(*c, typ) {
+                                        continue;
                                     }
                                     return Err(Error::MissingInstance(missing_instance, typ.clone(), var.clone()));
                                 }
@@ -1422,11 +1927,11 @@ fn unify(env: &mut TypeEnvironment, subs: &mut Substitution, lhs: &mut TcType, r
     }
 }
 
-fn match_or_fail(env: &mut TypeEnvironment, subs: &mut Substitution, location: &Location, lhs: &mut TcType, rhs: &TcType) {
+fn match_or_fail(env: &mut TypeEnvironment, subs: &mut Substitution, location: &Span, signature: Option<Span>, lhs: &mut TcType, rhs: &TcType) {
     debug!("Match {:?} --> {:?}", *lhs, *rhs);
     match match_(env, subs, lhs, rhs) {
         Ok(()) => (),
-        Err(error) => env.errors.insert(TypeErrorInfo { location: location.clone(), lhs: lhs.clone(), rhs: rhs.clone(), error: error })
+        Err(error) => env.errors.insert(TypeErrorInfo { span: location.clone(), signature: signature, lhs: lhs.clone(), rhs: rhs.clone(), error: error })
     }
 }
 ///Match performs matching which is walks through the same process as unify but only allows
@@ -1575,15 +2080,15 @@ pub fn identifier(i : &str) -> TypedExpr {
 }
 #[cfg(test)]
 pub fn lambda(arg : &str, body : TypedExpr) -> TypedExpr {
-    TypedExpr::new(Lambda(Pattern::Identifier(intern(arg)), box body))
+    TypedExpr::new(Lambda(Located { span: Span::eof(), node: Pattern::Identifier(intern(arg)) }, box body))
 }
 #[cfg(test)]
 pub fn number(i : isize) -> TypedExpr {
-    TypedExpr::new(Literal(Integral(i)))
+    TypedExpr::new(Literal(Integral(::integer::Integer::from_isize(i))))
 }
 #[cfg(test)]
-pub fn rational(i : f64) -> TypedExpr {
-    TypedExpr::new(Literal(Fractional(i)))
+pub fn rational(s : &str) -> TypedExpr {
+    TypedExpr::new(Literal(Fractional(s.parse().unwrap())))
 }
 #[cfg(test)]
 pub fn apply(func : TypedExpr, arg : TypedExpr) -> TypedExpr {
@@ -1609,32 +2114,75 @@ pub fn if_else(expr: TypedExpr, t: TypedExpr, f: TypedExpr) -> TypedExpr {
 pub fn paren(expr : TypedExpr) -> TypedExpr {
     TypedExpr::new(Paren(box expr))
 }
+///Resets the spans of the declarations and bindings it visits so that parsed code can be compared
+///with code which is constructed in tests
+#[cfg(test)]
+pub struct EraseSpans;
+#[cfg(test)]
+impl <Ident> MutVisitor<Ident> for EraseSpans {
+    fn visit_module(&mut self, module: &mut Module<Ident>) {
+        for decl in module.type_declarations.iter_mut() {
+            decl.span = Span::eof();
+        }
+        for data in module.data_definitions.iter_mut() {
+            data.span = Span::eof();
+        }
+        for newtype in module.newtypes.iter_mut() {
+            newtype.span = Span::eof();
+        }
+        for synonym in module.type_synonyms.iter_mut() {
+            synonym.span = Span::eof();
+        }
+        for fixity in module.fixity_declarations.iter_mut() {
+            fixity.span = Span::eof();
+        }
+        walk_module_mut(self, module);
+    }
+    fn visit_binding(&mut self, binding: &mut Binding<Ident>) {
+        binding.span = Span::eof();
+        if let Some(ref mut bindings) = binding.where_bindings {
+            for bind in bindings.iter_mut() {
+                self.visit_binding(bind);
+            }
+        }
+        walk_binding_mut(self, binding);
+    }
+}
 
-pub fn typecheck_string(module: &str) -> Result<Vec<Module<Name>>, ::std::string::String> {
+pub fn typecheck_string(module: &str) -> Result<Vec<Module<Name>>, VMError> {
+    typecheck_string_with(module, TypecheckOptions::default())
+}
+
+///Typechecks a module and its imported modules with the bindings generalized as specified by `options`
+pub fn typecheck_string_with(module: &str, options: TypecheckOptions) -> Result<Vec<Module<Name>>, VMError> {
     use parser::parse_string;
-    parse_string(module)
-        .map_err(|e| format!("{:?}", e))
-        .and_then(typecheck_modules_common)
+    let modules = try!(parse_string(module));
+    typecheck_modules_common(modules, options)
 }
 
 ///Parses a module, renames and typechecks it, as well as all of its imported modules
-pub fn typecheck_module(module: &str) -> Result<Vec<Module<Name>>, ::std::string::String> {
+pub fn typecheck_module(module: &str) -> Result<Vec<Module<Name>>, VMError> {
+    typecheck_module_with(module, TypecheckOptions::default())
+}
+
+///Typechecks a module and its imported modules, loaded from files, with the bindings generalized as specified by `options`
+pub fn typecheck_module_with(module: &str, options: TypecheckOptions) -> Result<Vec<Module<Name>>, VMError> {
     use parser::parse_modules;
-    parse_modules(module)
-        .map_err(|e| format!("{:?}", e))
-        .and_then(typecheck_modules_common)
+    let modules = try!(parse_modules(module));
+    typecheck_modules_common(modules, options)
 }
 
-fn typecheck_modules_common(modules: Vec<Module>) -> Result<Vec<Module<Name>>, ::std::string::String> {
+fn typecheck_modules_common(modules: Vec<Module>, options: TypecheckOptions) -> Result<Vec<Module<Name>>, VMError> {
     use renamer::rename_modules;
     use infix::PrecedenceVisitor;
-    let mut modules = try!(rename_modules(modules).map_err(|e| format!("{}", e)));
+    let mut modules = try!(rename_modules(modules));
     let mut prec_visitor = PrecedenceVisitor::new();
     for module in modules.iter_mut() {
         prec_visitor.visit_module(module);
     }
     let result = {
         let mut env = TypeEnvironment::new();
+        env.set_options(options);
         for module in modules.iter_mut() {
             env.typecheck_module2(module);
             env.assemblies.push(module);
@@ -1642,7 +2190,7 @@ fn typecheck_modules_common(modules: Vec<Module>) -> Result<Vec<Module<Name>>, :
         env.errors.into_result(())
     };
     result.map(|()| modules)
-        .map_err(|e| format!("{}", TypeError(e)))
+        .map_err(|e| VMError::from(TypeError(e)))
 }
 
 
@@ -1668,6 +2216,7 @@ pub fn do_typecheck(input: &str) -> Module<Name> {
 pub fn do_typecheck_with(input: &str, types: &[&DataTypes]) -> Module<Name> {
     let mut parser = ::parser::Parser::new(input.chars());
     let mut module = rename_module(parser.module().unwrap());
+    ::infix::PrecedenceVisitor::new().visit_module(&mut module);
     let mut env = TypeEnvironment::new();
     for t in types.iter() {
         env.add_types(*t);
@@ -1676,6 +2225,14 @@ pub fn do_typecheck_with(input: &str, types: &[&DataTypes]) -> Module<Name> {
     module
 }
 
+///Typechecks Prelude.hs so that other modules can be typechecked against it
+fn typecheck_prelude_file() -> Module<Name> {
+    let path = &Path::new("Prelude.hs");
+    let mut contents = ::std::string::String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut contents)).unwrap();
+    do_typecheck(contents.as_ref())
+}
+
 fn un_name_type(typ: Type<Name>) -> Type<InternedStr> {
     typ.map(|name| name.name)
 }
@@ -1721,7 +2278,7 @@ fn typecheck_let() {
 
     //let test x = add x in test
     let unary_bind = lambda("x", apply(apply(identifier("primIntAdd"), identifier("x")), number(1)));
-    let e = let_(vec![Binding { arguments: vec![], name: intern("test"), matches: Match::Simple(unary_bind), typ: Default::default() , where_bindings: None }], identifier("test"));
+    let e = let_(vec![Binding { arguments: vec![], name: intern("test"), matches: Match::Simple(unary_bind), typ: Default::default() , where_bindings: None, span: Default::default() }], identifier("test"));
     let mut expr = rename_expr(e);
     env.typecheck_expr_(&mut expr);
 
@@ -1833,7 +2390,7 @@ r"class Test a where
 instance Test Int where
     test x = 10
 
-main = test 1";
+main = test (1 :: Int)";
 
     let module = do_typecheck(file);
 
@@ -1921,6 +2478,27 @@ test x y = case x < y of
     assert_eq!(typ.constraints[0].class.as_ref(), "Ord");
 }
 
+#[test]
+fn typecheck_class_default_uses_class_variable() {
+    let module = do_typecheck(
+r"
+class Empty a where
+    empty :: a
+    first :: [a] -> a
+    first xs = case xs of
+        y:ys -> y
+        [] -> empty
+
+instance Empty Int where
+    empty = 0
+");
+    let a = Type::new_var(intern("a"));
+    let typ = &module.classes[0].bindings[0].typ;
+    assert_eq!(typ.value, function_type_(list_type(a.clone()), a));
+    assert_eq!(typ.constraints.len(), 1);
+    assert_eq!(typ.constraints[0].class.as_ref(), "Empty");
+}
+
 #[test]
 #[should_panic]
 fn typecheck_missing_super_class() {
@@ -2034,10 +2612,7 @@ main = fmap add2 3");
 
 #[test]
 fn typecheck_prelude() {
-    let path = &Path::new("Prelude.hs");
-    let mut contents = ::std::string::String::new();
-    File::open(path).and_then(|mut f| f.read_to_string(&mut contents)).unwrap();
-    let module = do_typecheck(contents.as_ref());
+    let module = typecheck_prelude_file();
 
     let id = module.bindings.iter().find(|bind| bind.name.as_ref() == "id");
     assert!(id != None);
@@ -2048,12 +2623,7 @@ fn typecheck_prelude() {
 #[test]
 fn typecheck_import() {
    
-    let prelude = {
-        let path = &Path::new("Prelude.hs");
-        let mut contents = ::std::string::String::new();
-        File::open(path).and_then(|mut f| f.read_to_string(&mut contents)).unwrap();
-        do_typecheck(contents.as_ref())
-    };
+    let prelude = typecheck_prelude_file();
 
     let file = 
 r"
@@ -2067,6 +2637,111 @@ test2 = id (primIntAdd 2 0)";
     assert_eq!(module.bindings[1].typ.value, int_type());
 }
 
+#[test]
+fn defaulting() {
+    use renamer::typ;
+    let prelude = typecheck_prelude_file();
+    fn argument_type(bind: &Binding<Name>) -> &TcType {
+        match bind.matches {
+            Match::Simple(TypedExpr { expr: Apply(_, ref arg), .. }) => &arg.typ,
+            _ => panic!("Expected an application")
+        }
+    }
+
+    let file =
+r"
+test1 = show (2 ^ 10)
+test2 = show (1.5 * 2)";
+    let module = do_typecheck_with(file, &[&prelude as &DataTypes]);
+    assert_eq!(*argument_type(&module.bindings[0]), typ::integer_type());
+    assert_eq!(*argument_type(&module.bindings[1]), typ::double_type());
+
+    let file =
+r"
+default (Double)
+test1 = show (2 + 3)";
+    let module = do_typecheck_with(file, &[&prelude as &DataTypes]);
+    assert_eq!(*argument_type(&module.bindings[0]), typ::double_type());
+
+    let mut env = TypeEnvironment::new();
+    env.add_types(&prelude);
+    env.set_extended_defaults(true);
+    let mut expr = rename_expr(op_apply(identifier("[]"), intern("=="), identifier("[]")));
+    env.typecheck_expr_(&mut expr);
+    match expr.expr {
+        OpApply(ref lhs, _, _) => assert_eq!(lhs.typ, typ::list_type(typ::unit())),
+        _ => panic!("Expected an operator application")
+    }
+}
+
+#[test]
+fn ambiguous_type_diagnostic() {
+    let err = typecheck_string(
+r"
+class Test a where
+    test :: a -> Int
+
+main = test 1
+").unwrap_err();
+    let message = format!("{}", err);
+    assert!(message.starts_with("error[E0205]: ambiguous type variable `a` in the constraint `(Test a, Num a)`"), "{}", message);
+    assert!(message.contains("<input>:5:13"), "{}", message);
+}
+
+#[test]
+fn monomorphism_restriction() {
+    use renamer::typ;
+    let prelude = typecheck_prelude_file();
+    let file =
+r"
+x = 3
+y = x + (1 :: Int)
+plus = (+)
+";
+    let module = do_typecheck_with(file, &[&prelude as &DataTypes]);
+    assert_eq!(module.bindings[0].typ.value, typ::int_type());
+    assert!(module.bindings[0].typ.constraints.is_empty());
+    let integer = typ::integer_type();
+    assert_eq!(module.bindings[2].typ.value, typ::function_type_(integer.clone(), typ::function_type_(integer.clone(), integer)));
+
+    let mut env = TypeEnvironment::new();
+    env.add_types(&prelude);
+    env.set_options(TypecheckOptions { monomorphism_restriction: false, .. TypecheckOptions::default() });
+    let mut module = rename_module(Parser::new(file.chars()).module().unwrap());
+    env.typecheck_module_(&mut module);
+    assert_eq!(module.bindings[0].typ.constraints[0].class.as_ref(), "Num");
+    assert_eq!(module.bindings[2].typ.constraints[0].class.as_ref(), "Num");
+}
+
+#[test]
+fn monomorphism_restriction_defaults_literals_without_prelude() {
+    let module = do_typecheck(
+r"
+x = 3.14
+y =
+    let z = 2
+    in primIntAdd z z
+");
+    assert_eq!(module.bindings[0].typ.value, typ::double_type());
+    assert_eq!(module.bindings[1].typ.value, typ::int_type());
+}
+
+#[test]
+fn mono_local_binds() {
+    let prelude = typecheck_prelude_file();
+    let file =
+r"
+test = let f x = x in (f True, f 'a')
+";
+    do_typecheck_with(file, &[&prelude as &DataTypes]);
+
+    let mut env = TypeEnvironment::new();
+    env.add_types(&prelude);
+    env.set_options(TypecheckOptions { mono_local_binds: true, .. TypecheckOptions::default() });
+    let mut module = rename_module(Parser::new(file.chars()).module().unwrap());
+    assert!(env.typecheck_module(&mut module).is_err());
+}
+
 #[test]
 fn type_declaration() {
     
@@ -2085,15 +2760,20 @@ test2 x y = primIntAdd (test x) y";
     assert_eq!(module.bindings[0].typ, module.type_declarations[0].typ);
 }
 
+#[test]
+fn type_declaration_variables_are_generalized() {
+    do_typecheck(
+r"
+fail :: [Char] -> a
+fail msg = fail msg
+
+main = (primIntAdd (fail []) 1, [fail [], 'c'])");
+}
+
 #[test]
 fn do_expr_simple() {
     
-    let prelude = {
-        let path = &Path::new("Prelude.hs");
-        let mut contents = ::std::string::String::new();
-        File::open(path).and_then(|mut f| f.read_to_string(&mut contents)).unwrap();
-        do_typecheck(contents.as_ref())
-    };
+    let prelude = typecheck_prelude_file();
 
     let file = 
 r"
@@ -2111,12 +2791,7 @@ test x = do
 #[test]
 fn do_expr_pattern() {
     
-    let prelude = {
-        let path = &Path::new("Prelude.hs");
-        let mut contents = ::std::string::String::new();
-        File::open(path).and_then(|mut f| f.read_to_string(&mut contents)).unwrap();
-        do_typecheck(&contents)
-    };
+    let prelude = typecheck_prelude_file();
 
     let file = 
 r"
@@ -2129,7 +2804,7 @@ test x = do
     let var = Type::new_var(intern("a"));
     let t = function_type_(Type::new_var_args(intern("c"), vec![list_type(var.clone())]), Type::new_var_args(intern("c"), vec![var.clone()]));
     assert_eq!(module.bindings[0].typ.value, t);
-    assert_eq!(module.bindings[0].typ.constraints[0].class.as_ref(), "Monad");
+    assert_eq!(module.bindings[0].typ.constraints[0].class.as_ref(), "MonadFail");
 }
 
 #[test]
@@ -2192,7 +2867,7 @@ import Prelude
 
 test x y = [x] == [y]
 ")
-    .unwrap_or_else(|err| panic!(err));
+    .unwrap_or_else(|err| panic!("{}", err));
     let module = modules.last().unwrap();
     let a = Type::new_var(intern("a"));
     let cs = vec![Constraint { class: intern("Eq"), variables: vec![a.var().clone()] } ];
@@ -2212,7 +2887,7 @@ makeEven i
     | otherwise = undefined
 
 "
-).unwrap_or_else(|err| panic!(err));
+).unwrap_or_else(|err| panic!("{}", err));
     let module = modules.last().unwrap();
     assert_eq!(un_name(module.bindings[0].typ.clone()), qualified(Vec::new(), function_type_(int_type(), Type::new_op(intern("Even"), Vec::new()))));
 }
@@ -2230,12 +2905,7 @@ test x = primIntAdd 2 x :: Num a => a
 #[should_panic]
 fn do_expr_wrong_monad() {
     
-    let prelude = {
-        let path = &Path::new("Prelude.hs");
-        let mut contents = ::std::string::String::new();
-        File::open(path).and_then(|mut f| f.read_to_string(&mut contents)).unwrap();
-        do_typecheck(contents.as_ref())
-    };
+    let prelude = typecheck_prelude_file();
 
     let file = 
 r"
@@ -2291,7 +2961,7 @@ test :: Test a => a -> a
 test x = test x
 
 test2 = test (Just True)")
-    .unwrap_or_else(|err| panic!(err));
+    .unwrap_or_else(|err| panic!("{}", err));
 }
 
 #[test]
@@ -2305,7 +2975,19 @@ test = case 1 :: Int of
     where
         y = x 1
 ")
-        .unwrap_or_else(|err| panic!(err));
+        .unwrap_or_else(|err| panic!("{}", err));
+}
+
+#[test]
+fn signature_mismatch_diagnostic() {
+    let err = typecheck_string(
+r"
+test :: Int
+test = []
+").unwrap_err();
+    let message = format!("{}", err);
+    assert!(message.starts_with("error[E0201]: mismatched types `[a]` and `Int`"), "{}", message);
+    assert!(message.contains("expected because of this signature"), "{}", message);
 }
 
 #[test]
@@ -2319,7 +3001,7 @@ newtype IntPair a = IntPair (a, Int)
 test = IntPair (True, False)
 
 "
-).unwrap_or_else(|err| panic!(err));
+).unwrap_or_else(|err| panic!("{}", err));
 }
 
 #[bench]
@@ -2328,7 +3010,8 @@ fn bench_prelude(b: &mut Bencher) {
     let mut contents = ::std::string::String::new();
     File::open(path).and_then(|mut f| f.read_to_string(&mut contents)).unwrap();
     let mut parser = Parser::new(contents.chars());
-    let module = rename_module(parser.module().unwrap());
+    let mut module = rename_module(parser.module().unwrap());
+    ::infix::PrecedenceVisitor::new().visit_module(&mut module);
 
     b.iter(|| {
         let mut env = TypeEnvironment::new();
@@ -437,3 +437,10 @@ pub fn extract_applied_type<Id>(typ: &Type<Id>) -> &Type<Id> {
         _ => typ
     }
 }
+///Mutable version of `extract_applied_type`
+pub fn extract_applied_type_mut<Id>(typ: &mut Type<Id>) -> &mut Type<Id> {
+    match *typ {
+        Type::Application(ref mut lhs, _) => extract_applied_type_mut(&mut **lhs),
+        ref mut typ => typ
+    }
+}
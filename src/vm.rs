@@ -1,20 +1,29 @@
 use std::fmt;
 use std::rc::Rc;
-use std::cell::{Ref, RefMut, RefCell};
+use std::cell::{Cell, Ref, RefMut, RefCell};
+use std::cmp::max;
 use std::path::Path;
 use std::io;
-use std::io::Read;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::fs::File;
 use std::error::Error;
-use std::num::Wrapping;
-use typecheck::TypeEnvironment;
+use std::mem;
+use std::time::{Duration, Instant};
+use typecheck::{TypeEnvironment, TypecheckOptions};
 use compiler::*;
 use parser::Parser;
 use core::translate::translate_module;
 use lambda_lift::do_lambda_lift;
-use renamer::rename_module;
-use vm::primitive::{BuiltinFun, get_builtin};
+use renamer::{Errors, rename_module};
+use infix::PrecedenceVisitor;
+use module::MutVisitor;
+use vm::primitive::{Builtin, get_builtin};
 use interner::*;
+use types::Type;
+use renamer::Name;
+use source::add_file;
+use diagnostics::{Diagnostic, ToDiagnostics, write_diagnostics};
+use integer::Integer;
 
 use self::Node_::*;
 
@@ -28,110 +37,144 @@ enum DictionaryEntry {
     App(usize, InstanceDictionary)
 }
 
-pub enum Node_<'a> {
-    Application(Node<'a>, Node<'a>),
+#[derive(Clone)]
+pub enum Node_ {
+    Application(Node, Node),
     Int(isize),
+    ///An arbitrary precision integer (`Integer`)
+    Integer(Integer),
     Float(f64),
     Char(char),
-    Combinator(&'a SuperCombinator),
-    Indirection(Node<'a>),
-    Constructor(u16, Vec<Node<'a>>),
+    ///A supercombinator, stored as its index in the global table
+    Combinator(usize),
+    Indirection(Node),
+    Constructor(u16, Vec<Node>),
     Dictionary(InstanceDictionary),
-    BuiltinFunction(usize, BuiltinFun)
+    ///A builtin function, stored as its index in the table of builtins
+    BuiltinFunction(usize),
+    ///A node which is being evaluated, entering it again means that its value depends on itself.
+    ///Holds a copy of the node as it was before evaluation started
+    BlackHole(Node),
+    ///A mutable reference (`IORef`) to the node it currently holds
+    MutVar(Node),
+    ///A mutable array of nodes
+    Array(Vec<Node>),
+    ///A mutable array of unboxed integers
+    IntArray(Vec<isize>),
+    ///A synchronising variable (`MVar`) which is either empty or holds a node
+    MVar(Option<Node>),
+    ///A transactional variable (`TVar`) holding a node and the number of times it has been written
+    TVar(Node, usize)
 }
-impl <'a> Clone for Node_<'a> {
-    fn clone(&self) -> Node_<'a> {
-        match self {
-            &Application(ref func, ref arg) => Application(func.clone(), arg.clone()),
-            &Int(i) => Int(i),
-            &Float(i) => Float(i),
-            &Char(c) => Char(c),
-            &Combinator(sc) => Combinator(sc),
-            &Indirection(ref n) => Indirection(n.clone()),
-            &Constructor(ref tag, ref args) => Constructor(tag.clone(), args.clone()),
-            &Dictionary(ref dict) => Dictionary(dict.clone()),
-            &BuiltinFunction(arity, f) => BuiltinFunction(arity, f)
+
+impl Node_ {
+    ///Pushes every node which this node refers to
+    fn push_children(&self, children: &mut Vec<Node>) {
+        match *self {
+            Application(func, arg) => {
+                children.push(func);
+                children.push(arg);
+            }
+            Indirection(node) | BlackHole(node) | MutVar(node) | MVar(Some(node)) | TVar(node, _) => children.push(node),
+            Constructor(_, ref args) | Array(ref args) => children.extend(args.iter().cloned()),
+            _ => ()
         }
     }
 }
 
-#[derive(Clone)]
-pub struct Node<'a> {
-    node: Rc<RefCell<Node_<'a>>>
-}
+///A handle to a cell in the heap of a `VM`.
+///Handles stay the same when the garbage collector moves the cell they refer to.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Node(usize);
 
-impl <'a> Node<'a> {
-    ///Creates a new node
-    fn new(n : Node_<'a>) -> Node<'a> {
-        Node { node: Rc::new(RefCell::new(n)) }
-    }
-    fn borrow<'b>(&'b self) -> Ref<'b, Node_<'a>> {
-        (*self.node).borrow()
-    }
-    fn borrow_mut<'b>(&'b self) -> RefMut<'b, Node_<'a>> {
-        (*self.node).borrow_mut()
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
     }
 }
-impl <'a> fmt::Debug for Node<'a> {
+
+impl fmt::Debug for Node_ {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", *self.borrow())
+        match *self {
+            Application(func, arg) => write!(f, "({:?} {:?})", func, arg),
+            Int(i) => write!(f, "{:?}", i),
+            Integer(ref i) => write!(f, "{}", i),
+            Float(i) => write!(f, "{:?}f", i),
+            Char(c) => write!(f, "'{:?}'", c),
+            Combinator(index) => write!(f, "<global {:?}>", index),
+            Indirection(n) => write!(f, "(~> {:?})", n),
+            Constructor(tag, ref args) => {
+                try!(write!(f, "{{{:?}", tag));
+                for arg in args.iter() {
+                    try!(write!(f, " {:?}", arg));
+                }
+                write!(f, "}}")
+            }
+            Dictionary(ref dict) => write!(f, "{:?}", dict),
+            BuiltinFunction(..) => write!(f, "<extern function>"),
+            BlackHole(n) => write!(f, "<blackhole {:?}>", n),
+            MutVar(n) => write!(f, "<mutvar {:?}>", n),
+            Array(ref nodes) => write!(f, "<array of {:?}>", nodes.len()),
+            IntArray(ref values) => write!(f, "<int array of {:?}>", values.len()),
+            MVar(Some(n)) => write!(f, "<mvar {:?}>", n),
+            MVar(None) => write!(f, "<empty mvar>"),
+            TVar(n, version) => write!(f, "<tvar {:?} version {:?}>", n, version)
+        }
+    }
+}
+
+///Displays the value a node refers to, following the node's fields
+pub struct ShowNode<'a> {
+    vm: &'a VM,
+    node: Node
+}
+
+impl <'a> ShowNode<'a> {
+    fn field(&self, node: Node) -> ShowNode<'a> {
+        ShowNode { vm: self.vm, node: node }
     }
 }
-impl <'a> fmt::Debug for Node_<'a> {
+
+impl <'a> fmt::Debug for ShowNode<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            &Application(ref func, ref arg) => write!(f, "({:?} {:?})", *func, *arg),
-            &Int(i) => write!(f, "{:?}", i),
-            &Float(i) => write!(f, "{:?}f", i),
-            &Char(c) => write!(f, "'{:?}'", c),
-            &Combinator(ref sc) => write!(f, "{:?}", sc.name),
-            &Indirection(ref n) => write!(f, "(~> {:?})", *n),
-            &Constructor(ref tag, ref args) => {
-                let cons = args;
-                if cons.len() > 0 {
-                    match *cons[0].borrow() {
-                        Char(_) => {
-                            fn print_string<'a>(f: &mut fmt::Formatter, cons: &Vec<Node<'a>>) -> fmt::Result {
-                                if cons.len() >= 2 {
-                                    match *cons[0].borrow() {
-                                        Char(c) =>  { try!(write!(f, "{:?}", c)); },
-                                        _ => ()
-                                    }
-                                    match *cons[1].borrow() {
-                                        Constructor(_, ref args2) => return print_string(f, args2),
-                                        _ => ()
-                                    }
-                                }
-                                Ok(())
-                            }
-                            try!(write!(f, "\""));
-                            try!(print_string(f, cons));
-                            write!(f, "\"")
-                        }
-                        _ => {
-                            //Print a normal constructor
-                            try!(write!(f, "{{{:?}", *tag));
-                            for arg in args.iter() {
-                                try!(write!(f, " {:?}", *arg.borrow()));
-                            }
-                            write!(f, "}}")
+        match *self.vm.get(self.node) {
+            Application(func, arg) => write!(f, "({:?} {:?})", self.field(func), self.field(arg)),
+            Combinator(index) => write!(f, "{:?}", self.vm.global(index).name),
+            Indirection(n) => write!(f, "(~> {:?})", self.field(n)),
+            Constructor(tag, ref args) => {
+                let is_string = args.len() > 0 && match *self.vm.get(args[0]) {
+                    Char(_) => true,
+                    _ => false
+                };
+                if is_string {
+                    try!(write!(f, "\""));
+                    let mut node = self.node;
+                    loop {
+                        let (c, tail) = match *self.vm.get(node) {
+                            Constructor(_, ref args) if args.len() >= 2 => (args[0], args[1]),
+                            _ => break
+                        };
+                        if let Char(c) = *self.vm.get(c) {
+                            try!(write!(f, "{:?}", c));
                         }
+                        node = tail;
                     }
+                    write!(f, "\"")
                 }
                 else {
                     //Print a normal constructor
-                    try!(write!(f, "{{{:?}", *tag));
+                    try!(write!(f, "{{{:?}", tag));
                     for arg in args.iter() {
-                        try!(write!(f, " {:?}", *arg.borrow()));
+                        try!(write!(f, " {:?}", self.field(*arg)));
                     }
                     write!(f, "}}")
                 }
             }
-            &Dictionary(ref dict) => write!(f, "{:?}", dict),
-            &BuiltinFunction(..) => write!(f, "<extern function>")
+            ref node => write!(f, "{:?}", node)
         }
     }
 }
+
 impl fmt::Debug for InstanceDictionary {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         try!(write!(f, "["));
@@ -153,1086 +196,5002 @@ impl fmt::Debug for DictionaryEntry {
     }
 }
 
-pub struct VM {
-    ///Vector of all assemblies which are loaded.
-    assembly : Vec<Assembly>,
-    ///A pair of (assembly_index, function_index).
-    globals: Vec<(usize, usize)>,
+///The number of cells which can be allocated before the first garbage collection
+pub const DEFAULT_HEAP_SIZE: usize = 1 << 16;
+
+///Statistics about the garbage collection done by a `VM`
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct GcStats {
+    ///The number of collections which have been run
+    pub collections: usize,
+    ///The total number of cells which have been allocated
+    pub allocated: usize,
+    ///The total number of cells which have been freed by collections
+    pub freed: usize,
+    ///The number of cells which survived the last collection
+    pub live: usize,
+    ///The largest number of cells which have survived a collection
+    pub max_live: usize
 }
 
-impl <'a> VM {
-    pub fn new() -> VM {
-        VM { assembly : Vec::new(), globals: Vec::new() }
-    }
+///Marks a handle which does not refer to any cell
+const FREE: usize = !0;
 
-    ///Adds an assembly to the VM, adding entries to the global table as necessary
-    pub fn add_assembly(&mut self, assembly: Assembly) -> usize {
-        self.assembly.push(assembly);
-        let assembly_index = self.assembly.len() - 1;
-        for index in 0..self.assembly.last().unwrap().super_combinators.len() {
-            self.globals.push((assembly_index, index));
+///The heap which every node is allocated in.
+///Nodes refer to cells through a table of handles which lets the collector compact the cells
+///without having to update the nodes which refer to them.
+struct Heap {
+    ///The index in `cells` of each handle, or `FREE` if the handle is unused
+    handles: Vec<usize>,
+    free_handles: Vec<usize>,
+    ///The cells of the heap along with the handle which refers to each of them
+    cells: Vec<(usize, Node_)>,
+    ///A collection is run when an allocation would exceed this many cells
+    limit: usize,
+    ///The configured size of the heap, `limit` never shrinks below this
+    size: usize,
+    ///The number of allocations which were reserved and must not collect garbage
+    reserved: usize,
+    ///Collect garbage before every allocation which is not reserved
+    stress: bool,
+    stats: GcStats
+}
+
+impl Heap {
+    fn new(size: usize) -> Heap {
+        Heap {
+            handles: Vec::new(),
+            free_handles: Vec::new(),
+            cells: Vec::new(),
+            limit: size,
+            size: size,
+            reserved: 0,
+            stress: false,
+            stats: GcStats::default()
         }
-        assembly_index
     }
-    ///Returns a reference to the assembly at the index
-    pub fn get_assembly(&self, index: usize) -> &Assembly {
-        &self.assembly[index]
+
+    ///Returns whether garbage needs to be collected before the next allocation
+    fn needs_collection(&self) -> bool {
+        self.reserved == 0 && (self.stress || self.cells.len() >= self.limit)
+    }
+
+    fn alloc(&mut self, value: Node_) -> Node {
+        let handle = match self.free_handles.pop() {
+            Some(handle) => handle,
+            None => {
+                self.handles.push(FREE);
+                self.handles.len() - 1
+            }
+        };
+        self.handles[handle] = self.cells.len();
+        self.cells.push((handle, value));
+        self.reserved = self.reserved.saturating_sub(1);
+        self.stats.allocated += 1;
+        Node(handle)
     }
 
-    ///Evaluates the code into Head Normal Form (HNF)
-    pub fn evaluate(&self, code: &[Instruction], assembly_id: usize) -> Node_ {
-        let mut stack = Vec::new();
-        self.execute(&mut stack, code, assembly_id);
-        self.deepseq(stack, assembly_id)
+    fn get(&self, node: Node) -> &Node_ {
+        &self.cells[self.handles[node.0]].1
     }
-    
-    ///Evaluates the what is at the top of the stack into HNF
-    fn deepseq(&'a self, mut stack: Vec<Node<'a>>, assembly_id: usize) -> Node_<'a> {
-        static EVALCODE : &'static [Instruction] = &[Instruction::Eval];
-        self.execute(&mut stack, EVALCODE, assembly_id);
-        match *stack[0].borrow() {
-            Constructor(tag, ref vals) => {
-                let mut ret = Vec::new();
-                for v in vals.iter() {
-                    let s = vec!(v.clone());
-                    let x = self.deepseq(s, assembly_id);
-                    ret.push(Node::new(x));
-                }
-                Constructor(tag, ret)
+
+    fn get_mut(&mut self, node: Node) -> &mut Node_ {
+        &mut self.cells[self.handles[node.0]].1
+    }
+
+    fn set(&mut self, node: Node, value: Node_) {
+        let index = self.handles[node.0];
+        self.cells[index].1 = value;
+    }
+
+    ///Frees every cell which can't be reached from the roots and compacts the remaining cells
+    fn collect(&mut self, roots: &[&[Node]]) {
+        let mut marked = vec![false; self.cells.len()];
+        let mut work: Vec<Node> = roots.iter().flat_map(|nodes| nodes.iter().cloned()).collect();
+        while let Some(node) = work.pop() {
+            let index = self.handles[node.0];
+            if !marked[index] {
+                marked[index] = true;
+                self.cells[index].1.push_children(&mut work);
             }
-            _ => stack[0].borrow().clone()
         }
-    }
 
-    ///Executes a sequence of instructions, leaving the result on the top of the stack
-    pub fn execute(&'a self, stack: &mut Vec<Node<'a>>, code: &[Instruction], assembly_id: usize) {
-        use compiler::Instruction::*;
-        debug!("----------------------------");
-        debug!("Entering frame with stack");
-        for x in stack.iter() {
-            debug!("{:?}", *x.borrow());
-        }
-        debug!("");
-        let mut i = Wrapping(0);
-        while i.0 < code.len() {
-            debug!("Executing instruction {:?} : {:?}", i.0, code[i.0]);
-            match code[i.0] {
-                Add => primitive(stack, |l, r| { l + r }),
-                Sub => primitive(stack, |l, r| { l - r }),
-                Multiply => primitive(stack, |l, r| { l * r }),
-                Divide => primitive(stack, |l, r| { l / r }),
-                Remainder => primitive(stack, |l, r| { l % r }),
-                IntEQ => primitive_int(stack, |l, r| { if l == r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
-                IntLT => primitive_int(stack, |l, r| { if l < r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
-                IntLE => primitive_int(stack, |l, r| { if l <= r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
-                IntGT => primitive_int(stack, |l, r| { if l > r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
-                IntGE => primitive_int(stack, |l, r| { if l >= r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
-                DoubleAdd => primitive_float(stack, |l, r| { Float(l + r) }),
-                DoubleSub => primitive_float(stack, |l, r| { Float(l - r) }),
-                DoubleMultiply => primitive_float(stack, |l, r| { Float(l * r) }),
-                DoubleDivide => primitive_float(stack, |l, r| { Float(l / r) }),
-                DoubleRemainder => primitive_float(stack, |l, r| { Float(l % r) }),
-                DoubleEQ => primitive_float(stack, |l, r| { if l == r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
-                DoubleLT => primitive_float(stack, |l, r| { if l < r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
-                DoubleLE => primitive_float(stack, |l, r| { if l <= r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
-                DoubleGT => primitive_float(stack, |l, r| { if l > r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
-                DoubleGE => primitive_float(stack, |l, r| { if l >= r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
-                IntToDouble => {
-                    let top = stack.pop().unwrap();
-                    stack.push(match *top.borrow() {
-                        Int(i) => Node::new(Float(i as f64)),
-                        _ => panic!("Excpected Int in Int -> Double cast")
-                    });
-                }
-                DoubleToInt => {
-                    let top = stack.pop().unwrap();
-                    stack.push(match *top.borrow() {
-                        Float(f) => Node::new(Int(f as isize)),
-                        _ => panic!("Excpected Double in Double -> Int cast")
-                    });
-                }
-                PushInt(value) => { stack.push(Node::new(Int(value))); }
-                PushFloat(value) => { stack.push(Node::new(Float(value))); }
-                PushChar(value) => { stack.push(Node::new(Char(value))); }
-                Push(index) => {
-                    let x = stack[index].clone();
-                    debug!("Pushed {:?}", *x.borrow());
-                    for j in 0..stack.len() {
-                        debug!(" {:?}  {:?}", j, *stack[j].borrow());
-                    }
-                    stack.push(x);
-                }
-                PushGlobal(index) => {
-                    let (assembly_index, index) = self.globals[index];
-                    let sc = &self.assembly[assembly_index].super_combinators[index];
-                    stack.push(Node::new(Combinator(sc)));
-                }
-                PushBuiltin(index) => {
-                    let (arity, f) = get_builtin(index);
-                    stack.push(Node::new(BuiltinFunction(arity, f)));
-                }
-                Mkap => {
-                    assert!(stack.len() >= 2);
-                    let func = stack.pop().unwrap();
-                    let arg = stack.pop().unwrap();
-                    debug!("Mkap {:?} {:?}", *func.borrow(), *arg.borrow());
-                    stack.push(Node::new(Application(func, arg)));
-                }
-                Eval => {
-                    static UNWINDCODE : &'static [Instruction] = &[Unwind];
-                    let old = stack.pop().unwrap();
-                    let mut new_stack = vec!(old.clone());
-                    self.execute(&mut new_stack, UNWINDCODE, assembly_id);
-                    stack.push(new_stack.pop().unwrap());
-                    debug!("{:?}", stack);
-                    let new = stack.last().unwrap().borrow().clone();
-                    *(*old.node).borrow_mut() = new;
-                    debug!("{:?}", stack);
-                }
-                Pop(num) => {
-                    for _ in 0..num {
-                        stack.pop();
-                    }
-                }
-                Update(index) => {
-                    stack[index] = Node::new(Indirection(stack.last().unwrap().clone()));
-                }
-                Unwind => {
-                    fn unwind<'a, F>(i_ptr: &mut Wrapping<usize>, arity: usize, stack: &mut Vec<Node<'a>>, f: F)
-                        where F: FnOnce(&mut Vec<Node<'a>>) -> Node<'a> {
-                        if stack.len() - 1 < arity {
-                            while stack.len() > 1 {
-                                stack.pop();
-                            }
-                        }
-                        else {
-                            for j in (stack.len() - arity - 1)..(stack.len() - 1) {
-                                let temp = match *stack[j].borrow() {
-                                    Application(_, ref arg) => arg.clone(),
-                                    _ => panic!("Expected Application")
-                                };
-                                stack[j] = temp;
-                            }
-                            let value = {
-                                let mut new_stack = Vec::new();
-                                for i in 0..arity {
-                                    let index = stack.len() - i - 2;
-                                    new_stack.push(stack[index].clone());
-                                }
-                                f(&mut new_stack)
-                            };
-                            for _ in 0..(arity + 1) {
-                                stack.pop();
-                            }
-                            stack.push(value);
-                            *i_ptr = *i_ptr - Wrapping(1);
-                        }
-                    }
-                    let x = (*stack.last().unwrap().borrow()).clone();
-                    debug!("Unwinding {:?}", x);
-                    match x {
-                        Application(func, _) => {
-                            stack.push(func);
-                            i = i - Wrapping(1);//Redo the unwind instruction
-                        }
-                        Combinator(comb) => {
-                            debug!(">>> Call {:?}", comb.name);
-                            unwind(&mut i, comb.arity, stack, |new_stack| {
-                                self.execute(new_stack, &*comb.instructions, comb.assembly_id);
-                                new_stack.pop().unwrap()
-                            });
-                        }
-                        BuiltinFunction(arity, func) => {
-                            unwind(&mut i, arity, stack, |new_stack| func(self, new_stack.as_ref()));
-                        }
-                        Indirection(node) => {
-                            *stack.last_mut().unwrap() = node;
-                            i = i - Wrapping(1);//Redo the unwind instruction
-                        }
-                        _ => ()
-                    }
-                }
-                Slide(size) => {
-                    let top = stack.pop().unwrap();
-                    for _ in 0..size {
-                        stack.pop();
-                    }
-                    stack.push(top);
-                }
-                Split(_) => {
-                    let temp = stack.pop().unwrap();
-                    let temp = temp.borrow();
-                    match *temp {
-                        Constructor(_, ref fields) => {
-                            for field in fields.iter() {
-                                stack.push(field.clone());
-                            }
-                        }
-                        _ => panic!("Expected constructor in Split instruction")
-                    }
-                }
-                Pack(tag, arity) => {
-                    let mut args = Vec::new();
-                    for _ in 0..arity {
-                        args.push(stack.pop().unwrap());
-                    }
-                    stack.push(Node::new(Constructor(tag, args)));
-                }
-                JumpFalse(address) => {
-                    match *stack.last().unwrap().borrow() {
-                        Constructor(0, _) => (),
-                        Constructor(1, _) => i = Wrapping(address - 1),
-                        _ => ()
-                    }
-                    stack.pop();
-                }
-                CaseJump(jump_tag) => {
-                    let jumped = match *stack.last().unwrap().borrow() {
-                        Constructor(tag, _) => {
-                            if jump_tag == tag as usize {
-                                i = i + Wrapping(1);//Skip the jump instruction ie continue to the next test
-                                true
-                            }
-                            else {
-                                false
-                            }
-                        }
-                        ref x => panic!("Expected constructor when executing CaseJump, got {:?}", *x),
-                    };
-                    if !jumped {
-                        stack.pop();
-                    }
-                }
-                Jump(to) => {
-                    i = Wrapping(to - 1);
-                }
-                PushDictionary(index) => {
-                    let assembly = &self.assembly[assembly_id];
-                    let dict : &[usize] = &*assembly.instance_dictionaries[index];
-                    let dict = InstanceDictionary { entries: dict.iter().map(|i| Rc::new(DictionaryEntry::Function(*i))).collect() };
-                    stack.push(Node::new(Dictionary(dict)));
-                }
-                PushDictionaryMember(index) => {
-                    let sc = {
-                        let x = stack[0].borrow();
-                        let dict = match *x {
-                            Dictionary(ref x) => x,
-                            ref x => panic!("Attempted to retrieve {:?} as dictionary", *x)
-                        };
-                        match *dict.entries[index] {
-                            DictionaryEntry::Function(gi) => {
-                                let (assembly_index, i) = self.globals[gi];
-                                Combinator(&self.assembly[assembly_index].super_combinators[i])
-                            }
-                            DictionaryEntry::App(gi, ref dict) => {
-                                let (assembly_index, i) = self.globals[gi];
-                                let sc = &self.assembly[assembly_index].super_combinators[i];
-                                Application(Node::new(Combinator(sc)), Node::new(Dictionary(dict.clone())))
-                            }
-                        }
-                    };
-                    stack.push(Node::new(sc));
-                }
-                MkapDictionary => {
-                    let a = stack.pop().unwrap();
-                    let a = a.borrow();
-                    let arg = match *a {
-                        Dictionary(ref d) => {
-                            d
-                        }
-                        _ => panic!()
-                    };
-                    let func = stack.pop().unwrap();
-                    let mut new_dict = InstanceDictionary { entries: Vec::new() };
-                    match *func.borrow() {
-                        Dictionary(ref d) => {
-                            for entry in d.entries.iter() {
-                                match **entry {
-                                    DictionaryEntry::Function(index) => {
-                                        new_dict.entries.push(Rc::new(DictionaryEntry::App(index, arg.clone())));
-                                    }
-                                    _ => panic!()
-                                }
-                            }
-                        }
-                        _ => panic!()
-                    }
-                    stack.push(Node::new(Dictionary(new_dict)));
-                }
-                ConstructDictionary(size) => {
-                    let mut new_dict = InstanceDictionary { entries: Vec::new() };
-                    for _ in 0..size {
-                        let temp = stack.pop().unwrap();
-                        let temp = temp.borrow();
-                        match *temp {
-                            Dictionary(ref d) => {
-                                new_dict.entries.extend(d.entries.iter().map(|x| x.clone()));
-                            }
-                            ref x => panic!("Unexpected {:?}", x)
-                        }
-                    }
-                    stack.push(Node::new(Dictionary(new_dict)));
-                }
-                PushDictionaryRange(start, size) => {
-                    let mut new_dict = InstanceDictionary { entries: Vec::new() };
-                    match *stack[0].borrow() {
-                        Dictionary(ref d) => {
-                            new_dict.entries.extend(d.entries.iter().skip(start).take(size).map(|x| x.clone()));
-                        }
-                        _ => panic!()
-                    }
-                    stack.push(Node::new(Dictionary(new_dict)));
-                }
+        let mut live = 0;
+        for index in 0..self.cells.len() {
+            let handle = self.cells[index].0;
+            if marked[index] {
+                //Every cell before `index` has been moved or freed so the cell can be slid down
+                self.cells.swap(live, index);
+                self.handles[handle] = live;
+                live += 1;
+            }
+            else {
+                self.handles[handle] = FREE;
+                self.free_handles.push(handle);
             }
-            i = i + Wrapping(1);
         }
-        debug!("End frame");
-        debug!("--------------------------");
+        let freed = self.cells.len() - live;
+        self.cells.truncate(live);
+        debug!("Garbage collection freed {} cells, {} cells are live", freed, live);
+
+        self.stats.collections += 1;
+        self.stats.freed += freed;
+        self.stats.live = live;
+        self.stats.max_live = max(self.stats.max_live, live);
+        //Grow the heap if most of it is still in use so that the next collection does not run immediately
+        self.limit = max(self.size, live * 2);
     }
 }
 
+///A Haskell exception which is being propagated through the VM.
+///The node holds a value of the Prelude type `SomeException`
+pub struct Exception(Node);
 
-///Exucutes a binary primitive instruction taking two integers
-fn primitive_int<'a, F>(stack: &mut Vec<Node<'a>>, f: F) where F: FnOnce(isize, isize) -> Node_<'a> {
-    let l = stack.pop().unwrap();
-    let r = stack.pop().unwrap();
-    let l = l.borrow();
-    let r = r.borrow();
-    match (&*l, &*r) {
-        (&Int(lhs), &Int(rhs)) => stack.push(Node::new(f(lhs, rhs))),
-        (lhs, rhs) => panic!("Expected fully evaluted numbers in primitive instruction\n LHS: {:?}\nRHS: {:?} ", lhs, rhs)
+impl Exception {
+    ///Creates the exception `SomeErrorCall (ErrorCall message)`
+    fn error_call(vm: &VM, message: Node) -> Exception {
+        let exception = vm.alloc(Constructor(0, vec![message]));
+        Exception::new(vm, 0, exception)
     }
-}
-///Exucutes a binary primitive instruction taking two doubles
-fn primitive_float<'a, F>(stack: &mut Vec<Node<'a>>, f: F) where F: FnOnce(f64, f64) -> Node_<'a> {
-    let l = stack.pop().unwrap();
-    let r = stack.pop().unwrap();
-    let l = l.borrow();
-    let r = r.borrow();
-    match (&*l, &*r) {
-        (&Float(lhs), &Float(rhs)) => stack.push(Node::new(f(lhs, rhs))),
-        (lhs, rhs) => panic!("Expected fully evaluted numbers in primitive instruction\n LHS: {:?}\nRHS: {:?} ", lhs, rhs)
+    ///Creates the exception `SomeArithException e` where `tag` is the tag of `e` in `ArithException`
+    fn arith(vm: &VM, tag: u16) -> Exception {
+        let exception = vm.alloc(Constructor(tag, Vec::new()));
+        Exception::new(vm, 1, exception)
+    }
+    ///Creates the exception `SomePatternMatchFail (PatternMatchFail message)`
+    fn pattern_match_fail(vm: &VM, message: &str) -> Exception {
+        let message = primitive::create_string(vm, message);
+        let exception = vm.alloc(Constructor(0, vec![message]));
+        Exception::new(vm, 2, exception)
+    }
+    ///Creates the exception `SomeAsyncException e` where `tag` is the tag of `e` in `AsyncException`
+    fn async_exception(vm: &VM, tag: u16) -> Exception {
+        let exception = vm.alloc(Constructor(tag, Vec::new()));
+        Exception::new(vm, 3, exception)
+    }
+    ///Creates the exception `SomeIOException (IOError typ message)` where `typ` is the tag of
+    ///the `IOErrorType` which describes `error`
+    fn io_error(vm: &VM, error: &io::Error, message: &str) -> Exception {
+        use std::io::ErrorKind::*;
+        let typ = match error.kind() {
+            AlreadyExists => 0,
+            NotFound => 1,
+            UnexpectedEof => 4,
+            InvalidInput => 5,
+            PermissionDenied => 6,
+            _ => 8
+        };
+        //Building the message may collect garbage so `typ` is kept on the stack until it is referred to
+        let typ = vm.alloc(Constructor(typ, Vec::new()));
+        vm.push(typ);
+        let message = primitive::create_string(vm, message);
+        let typ = vm.pop();
+        let exception = vm.alloc(Constructor(0, vec![typ, message]));
+        Exception::new(vm, 5, exception)
+    }
+    ///Creates the exception `SomeNonTermination (NonTermination name)`
+    fn non_termination(vm: &VM, name: &str) -> Exception {
+        let name = primitive::create_string(vm, name);
+        let exception = vm.alloc(Constructor(0, vec![name]));
+        Exception::new(vm, 4, exception)
+    }
+    ///Creates the exception `SomeBlockedIndefinitelyOnMVar BlockedIndefinitelyOnMVar`
+    fn blocked_indefinitely(vm: &VM) -> Exception {
+        let exception = vm.alloc(Constructor(0, Vec::new()));
+        Exception::new(vm, 7, exception)
+    }
+    ///Creates the exception `SomeBlockedIndefinitelyOnSTM BlockedIndefinitelyOnSTM`
+    fn blocked_indefinitely_on_stm(vm: &VM) -> Exception {
+        let exception = vm.alloc(Constructor(0, Vec::new()));
+        Exception::new(vm, 8, exception)
+    }
+    fn new(vm: &VM, tag: u16, exception: Node) -> Exception {
+        Exception(vm.alloc(Constructor(tag, vec![exception])))
     }
-}
-fn primitive<F>(stack: &mut Vec<Node>, f: F) where F: FnOnce(isize, isize) -> isize {
-    primitive_int(stack, move |l, r| Int(f(l, r)))
 }
 
-#[derive(PartialEq, Debug)]
-pub enum VMResult {
-    Int(isize),
-    Double(f64),
-    Constructor(u16, Vec<VMResult>)
+///The tags of the constructors in the Prelude's `ArithException`
+const OVERFLOW: u16 = 0;
+const DIVIDE_BY_ZERO: u16 = 1;
+///The tag of `StackOverflow` in the Prelude's `AsyncException`
+const STACK_OVERFLOW: u16 = 0;
+///The tag of `ThreadKilled` in the Prelude's `AsyncException`
+const THREAD_KILLED: u16 = 2;
+///The tag of `ExitSuccess` in the Prelude's `ExitCode`
+const EXIT_SUCCESS: u16 = 0;
+
+///The number of frames which can be suspended while evaluating before `StackOverflow` is thrown
+pub const DEFAULT_MAX_DEPTH: usize = 1 << 20;
+///A file or standard stream which a Haskell `Handle` refers to by its index in the VM's table of handles
+enum IoHandle {
+    Stdin,
+    Stdout,
+    Stderr,
+    File { file: BufReader<File>, readable: bool, writable: bool },
+    Closed
 }
 
-macro_rules! vm_error {
-    ($($pre: ident :: $post: ident),+) => {
+impl IoHandle {
+    fn illegal(message: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, message)
+    }
 
-    #[derive(Debug)]
-    pub enum VMError {
-        Io(io::Error),
-        $($post(::$pre::$post)),+
+    fn reader(&mut self) -> io::Result<Option<&mut BufReader<File>>> {
+        match *self {
+            IoHandle::Stdin => Ok(None),
+            IoHandle::File { ref mut file, readable: true, .. } => Ok(Some(file)),
+            IoHandle::Closed => Err(IoHandle::illegal("handle is closed")),
+            _ => Err(IoHandle::illegal("handle is not open for reading"))
+        }
     }
 
-    impl fmt::Display for VMError {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            match *self {
-                VMError::Io(ref e) => write!(f, "{}", e),
-                $(VMError::$post(ref e) => write!(f, "{}", e)),+
-            }
+    ///Reads a line without its line terminator, returning `None` at the end of the input
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        let count = match try!(self.reader()) {
+            Some(file) => try!(file.read_line(&mut line)),
+            None => try!(io::stdin().read_line(&mut line))
+        };
+        if count == 0 {
+            return Ok(None)
         }
+        if line.ends_with("\n") {
+            line.pop();
+        }
+        Ok(Some(line))
     }
 
-    impl Error for VMError {
-        fn description(&self) -> &str {
-            match *self {
-                VMError::Io(ref e) => e.description(),
-                $(VMError::$post(ref e) => e.description()),+
+    fn read_to_string(&mut self) -> io::Result<String> {
+        let mut contents = String::new();
+        match try!(self.reader()) {
+            Some(file) => try!(file.read_to_string(&mut contents)),
+            None => try!(io::stdin().read_to_string(&mut contents))
+        };
+        Ok(contents)
+    }
+
+    fn is_eof(&mut self) -> io::Result<bool> {
+        match try!(self.reader()) {
+            Some(file) => file.fill_buf().map(|buffer| buffer.is_empty()),
+            None => {
+                let stdin = io::stdin();
+                let mut stdin = stdin.lock();
+                stdin.fill_buf().map(|buffer| buffer.is_empty())
             }
         }
     }
 
-    impl From<io::Error> for VMError {
-        fn from(e: io::Error) -> Self { VMError::Io(e) }
+    fn write(&mut self, s: &str) -> io::Result<()> {
+        match *self {
+            //Output to the standard streams is flushed right away so that prompts are shown before reading input
+            IoHandle::Stdout => {
+                let stdout = io::stdout();
+                let mut stdout = stdout.lock();
+                try!(stdout.write_all(s.as_bytes()));
+                stdout.flush()
+            }
+            IoHandle::Stderr => io::stderr().write_all(s.as_bytes()),
+            IoHandle::File { ref mut file, writable: true, .. } => {
+                //Seeking discards what has been read ahead so that the write starts after what was read
+                try!(file.seek(SeekFrom::Current(0)));
+                file.get_mut().write_all(s.as_bytes())
+            }
+            IoHandle::Closed => Err(IoHandle::illegal("handle is closed")),
+            _ => Err(IoHandle::illegal("handle is not open for writing"))
+        }
     }
 
-    $(impl From<::$pre::$post> for VMError {
-        fn from(e: ::$pre::$post) -> Self { VMError::$post(e) }
-    })+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            IoHandle::Stdout => io::stdout().flush(),
+            IoHandle::Stderr => io::stderr().flush(),
+            IoHandle::File { ref mut file, .. } => file.get_mut().flush(),
+            IoHandle::Stdin => Ok(()),
+            IoHandle::Closed => Err(IoHandle::illegal("handle is closed"))
+        }
     }
 }
-vm_error! { parser::ParseError, renamer::RenamerError, typecheck::TypeError }
 
-fn compile_iter<T : Iterator<Item=char>>(iterator: T) -> Result<Assembly, VMError> {
-    let mut parser = Parser::new(iterator);
-    let module = try!(parser.module());
-    let mut module = try!(rename_module(module));
-    
-    let mut typer = TypeEnvironment::new();
-    try!(typer.typecheck_module(&mut module));
-    let core_module = do_lambda_lift(translate_module(module));
-    
-    let mut compiler = Compiler::new();
-    Ok(compiler.compile_module(&core_module))
+///The number of steps which a thread runs before the next thread is scheduled
+pub const DEFAULT_TIME_SLICE: usize = 1 << 12;
+
+///What a green thread is waiting for before it can run again
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ThreadState {
+    ///The thread has not started running its action yet
+    New(Node),
+    Runnable,
+    ///Waiting for the `MVar` to be filled
+    Taking(Node),
+    ///Waiting for the `MVar` to be emptied
+    Putting(Node),
+    ///Waiting for another thread to finish evaluating the blackhole
+    Evaluating(Node),
+    Sleeping(Instant),
+    ///Waiting for one of the `TVar`s which its transaction read to be written, the reads are in its log
+    Retrying,
+    Finished
 }
 
-///Compiles a single file
-pub fn compile_file(filename: &str) -> Result<Assembly, VMError> {
-    let path = &Path::new(filename);
-    let mut file = try!(File::open(path));
-    let mut contents = ::std::string::String::new();
-    try!(file.read_to_string(&mut contents));
-    compile_iter(contents.chars())
+///An access of a transaction to a `TVar`
+#[derive(Clone, Copy, Debug)]
+struct LogEntry {
+    tvar: Node,
+    ///The version of the `TVar` when the transaction first accessed it
+    version: usize,
+    ///The value of the `TVar` as seen by the transaction
+    value: Node,
+    written: bool
 }
 
-fn extract_result(node: Node_) -> Option<VMResult> {
-    match node {
-        Constructor(tag, fields) => {
-            let mut result = Vec::new();
-            for field in fields.iter() {
-                match extract_result((*field.borrow()).clone()) {
-                    Some(x) => result.push(x),
-                    None => return None
-                }
-            }
-            Some(VMResult::Constructor(tag, result))
-        }
-        Int(i) => Some(VMResult::Int(i)),
-        Float(i) => Some(VMResult::Double(i)),
-        x => {
-            println!("Can't extract result {:?}", x);
-            None
+///The log of a transaction, `orElse` runs its first alternative in a nested log
+type TransactionLog = Vec<LogEntry>;
+
+///A thread of the program, every thread is run by the VM on the same OS thread
+struct Thread {
+    state: ThreadState,
+    ///The stack of the thread while another thread is running
+    stack: Vec<Node>,
+    ///The number of frames on the dump of the thread while another thread is running
+    depth: usize,
+    ///An exception thrown to the thread by `throwTo` which the thread has not received yet
+    pending: Option<Node>,
+    ///The logs of the transaction of the thread while another thread is running
+    transaction: Vec<TransactionLog>
+}
+
+impl Thread {
+    fn new(state: ThreadState) -> Thread {
+        Thread { state: state, stack: Vec::new(), depth: 0, pending: None, transaction: Vec::new() }
+    }
+
+    ///Pushes the nodes which the thread refers to outside of its stack
+    fn push_roots(&self, roots: &mut Vec<Node>) {
+        match self.state {
+            ThreadState::New(node) | ThreadState::Taking(node) | ThreadState::Putting(node) |
+            ThreadState::Evaluating(node) => roots.push(node),
+            _ => ()
         }
+        roots.extend(self.pending.iter().cloned());
+        push_log_roots(&self.transaction, roots);
     }
 }
 
-pub fn execute_main_string(module: &str) -> Result<Option<VMResult>, String> {
-    let assemblies = try!(compile_string(module));
-    execute_main_module_(assemblies)
+fn push_log_roots(logs: &[TransactionLog], roots: &mut Vec<Node>) {
+    for entry in logs.iter().flat_map(|log| log.iter()) {
+        roots.push(entry.tvar);
+        roots.push(entry.value);
+    }
 }
 
-///Takes a module with a main function and compiles it and all its imported modules
-///and then executes the main function
-pub fn execute_main_module(modulename: &str) -> Result<Option<VMResult>, String> {
-    let assemblies = try!(compile_module(modulename));
-    execute_main_module_(assemblies)
+///The threads of the program, the index of a thread is its `ThreadId`.
+///There are no threads until the first one is forked, the thread which was running then becomes thread 0
+struct Scheduler {
+    threads: Vec<Thread>,
+    current: usize
 }
 
-fn execute_main_module_(assemblies: Vec<Assembly>) -> Result<Option<VMResult>, String> {
-    let mut vm = VM::new();
-    for assembly in assemblies.into_iter() {
-        vm.add_assembly(assembly);
+impl Scheduler {
+    fn new() -> Scheduler {
+        Scheduler { threads: Vec::new(), current: 0 }
     }
-    let x = vm.assembly.iter().flat_map(|a| a.super_combinators.iter()).find(|sc| sc.name.name == intern("main"));
-    match x {
-        Some(sc) => {
-            assert!(sc.arity == 0);
-            let result = vm.evaluate(&*sc.instructions, sc.assembly_id);
-            Ok(extract_result(result))
+
+    ///Returns the thread which is running, creating thread 0 if no threads exist yet
+    fn running(&mut self) -> &mut Thread {
+        if self.threads.is_empty() {
+            self.threads.push(Thread::new(ThreadState::Runnable));
+            self.current = 0;
         }
-        None => Ok(None)
+        &mut self.threads[self.current]
     }
 }
 
-//We mirror the naming scheme from Haskell here which is camelCase
-#[allow(non_snake_case)]
-mod primitive {
+///How many steps are executed between each check of the heap and time limits
+const LIMIT_CHECK_INTERVAL: usize = 1 << 10;
 
-    use std::io::Read;
-    use std::fs::File;
-    use vm::{VM, Node, Node_};
-    use vm::Node_::{Application, Constructor, BuiltinFunction, Char};
-    use compiler::Instruction;
-    use compiler::Instruction::Eval;
+///Configures the resources which a `VM` may use and the environment its program is run in.
+///The limits which are `None` are not checked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VMConfig {
+    ///The number of cells which can be allocated before the first garbage collection
+    pub heap_size: usize,
+    ///The number of frames which can be suspended before `StackOverflow` is thrown
+    pub max_depth: usize,
+    ///The number of reduction steps (executed instructions) which one evaluation may take
+    pub max_reductions: Option<usize>,
+    ///The number of cells which may be live in the heap
+    pub max_heap_cells: Option<usize>,
+    ///The wall-clock time which one evaluation may take
+    pub max_time: Option<Duration>,
+    ///The arguments returned by `getArgs`
+    pub args: Vec<String>,
+    ///The name returned by `getProgName`
+    pub prog_name: String,
+    ///The number of reduction steps which a thread runs before it is preempted
+    pub time_slice: usize,
+    ///Collects garbage before every allocation instead of when the heap is full.
+    ///Slow, but finds nodes which are used without being reachable from a root
+    pub gc_stress: bool
+}
 
-    pub fn get_builtin(i: usize) -> (usize, BuiltinFun) {
-        match i {
-            0 => (1, error),
-            1 => (2, seq),
-            2 => (2, readFile),
-            3 => (3, io_bind),
-            4 => (2, io_return),
-            5 => (2, putStrLn),
-            6 => (2, compare_tags),
-            _ => panic!("undefined primitive")
+impl Default for VMConfig {
+    fn default() -> VMConfig {
+        VMConfig {
+            heap_size: DEFAULT_HEAP_SIZE,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_reductions: None,
+            max_heap_cells: None,
+            max_time: None,
+            args: Vec::new(),
+            prog_name: "<interactive>".to_string(),
+            time_slice: DEFAULT_TIME_SLICE,
+            gc_stress: false
         }
     }
+}
 
-    pub type BuiltinFun = for <'a> extern "Rust" fn (&'a VM, &[Node<'a>]) -> Node<'a>;
+///A limit of the `VMConfig` which was exceeded, stopping the evaluation
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LimitExceeded {
+    Reductions(usize),
+    HeapCells(usize),
+    Time(Duration)
+}
 
-    fn error<'a>(vm: &'a VM, stack: &[Node<'a>]) -> Node<'a> {
-        let mut vec = Vec::new();
-        vec.push(stack[0].clone());
-        let node = vm.deepseq(vec, 123);
-        panic!("error: {:?}", node)
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_diagnostics(f, self)
     }
-    fn eval<'a>(vm: &'a VM, node: Node<'a>) -> Node<'a> {
-        static EVALCODE : &'static [Instruction] = &[Eval];
-        let mut temp = Vec::new();
-        temp.push(node);
-        vm.execute(&mut temp, EVALCODE, 123);
-        temp.pop().unwrap()
+}
+
+impl ToDiagnostics for LimitExceeded {
+    fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        let diagnostic = match *self {
+            LimitExceeded::Reductions(max) =>
+                Diagnostic::error("E0401", format!("evaluation exceeded the limit of {} reduction steps", max)),
+            LimitExceeded::HeapCells(max) =>
+                Diagnostic::error("E0402", format!("evaluation exceeded the limit of {} live heap cells", max)),
+            LimitExceeded::Time(max) => {
+                let millis = max.as_secs() * 1000 + (max.subsec_nanos() / 1_000_000) as u64;
+                Diagnostic::error("E0403", format!("evaluation exceeded the time limit of {} ms", millis))
+            }
+        };
+        vec![diagnostic]
+    }
+}
+
+impl Error for LimitExceeded {
+    fn description(&self) -> &str { "resource limit exceeded" }
+}
+
+pub struct VM {
+    ///Vector of all assemblies which are loaded.
+    assembly : Vec<Assembly>,
+    ///A pair of (assembly_index, function_index).
+    globals: Vec<(usize, usize)>,
+    ///The node of each global without arguments once it has been used so that it is only evaluated once
+    constants: RefCell<Vec<Option<Node>>>,
+    ///The stack which is shared by all frames, each frame is identified by the index of its first node.
+    ///The stack is the root set of the garbage collector so every node which is in use must be reachable from it.
+    stack: RefCell<Vec<Node>>,
+    heap: RefCell<Heap>,
+    ///The number of frames which are currently suspended on the dump
+    depth: Cell<usize>,
+    config: VMConfig,
+    ///The number of steps and the time at which the current evaluation started
+    steps: Cell<usize>,
+    started: Cell<Instant>,
+    ///Set when a limit is exceeded, the exception which is thrown then can not be caught
+    limit: Cell<Option<LimitExceeded>>,
+    ///The handles which have been opened, starting with stdin, stdout and stderr
+    handles: RefCell<Vec<IoHandle>>,
+    scheduler: RefCell<Scheduler>,
+    ///The number of executions which are running, threads are only switched by the outermost one
+    executions: Cell<usize>,
+    ///The step at which the running thread is preempted
+    slice_end: Cell<usize>,
+    ///Set when the running thread has to let the next thread run
+    reschedule: Cell<bool>,
+    ///The logs of the transaction which the running thread is in, the innermost log is the last one
+    transaction: RefCell<Vec<TransactionLog>>,
+    ///Set while `retry` unwinds the dump to the innermost `orElse` or `atomically`
+    retrying: Cell<bool>
+}
+
+impl VM {
+    pub fn new() -> VM {
+        VM::with_config(VMConfig::default())
+    }
+
+    ///Creates a VM which uses the resources described by `config`.
+    ///The heap grows beyond `config.heap_size` if the live cells do not fit in it.
+    pub fn with_config(config: VMConfig) -> VM {
+        let mut heap = Heap::new(config.heap_size);
+        heap.stress = config.gc_stress;
+        VM {
+            assembly : Vec::new(),
+            globals: Vec::new(),
+            constants: RefCell::new(Vec::new()),
+            stack: RefCell::new(Vec::new()),
+            heap: RefCell::new(heap),
+            depth: Cell::new(0),
+            config: config,
+            steps: Cell::new(0),
+            started: Cell::new(Instant::now()),
+            limit: Cell::new(None),
+            handles: RefCell::new(vec![IoHandle::Stdin, IoHandle::Stdout, IoHandle::Stderr]),
+            scheduler: RefCell::new(Scheduler::new()),
+            executions: Cell::new(0),
+            slice_end: Cell::new(!0),
+            reschedule: Cell::new(false),
+            transaction: RefCell::new(Vec::new()),
+            retrying: Cell::new(false)
+        }
+    }
+
+    ///Adds an assembly to the VM, adding entries to the global table as necessary
+    pub fn add_assembly(&mut self, assembly: Assembly) -> usize {
+        self.assembly.push(assembly);
+        let assembly_index = self.assembly.len() - 1;
+        for index in 0..self.assembly.last().unwrap().super_combinators.len() {
+            self.globals.push((assembly_index, index));
+            self.constants.borrow_mut().push(None);
+        }
+        assembly_index
+    }
+    ///Returns a reference to the assembly at the index
+    pub fn get_assembly(&self, index: usize) -> &Assembly {
+        &self.assembly[index]
+    }
+
+    ///Returns the supercombinator at `index` in the global table
+    fn global(&self, index: usize) -> &SuperCombinator {
+        let (assembly_index, index) = self.globals[index];
+        &self.assembly[assembly_index].super_combinators[index]
+    }
+
+    ///Returns the node for the global at `index`.
+    ///A global without arguments is a constant so the same node is returned each time,
+    ///which is updated with the value of the global when it is evaluated
+    fn global_node(&self, index: usize) -> Node {
+        if self.global(index).arity != 0 {
+            return self.alloc(Combinator(index))
+        }
+        let constant = self.constants.borrow()[index];
+        match constant {
+            Some(node) => node,
+            None => {
+                let node = self.alloc(Combinator(index));
+                self.constants.borrow_mut()[index] = Some(node);
+                node
+            }
+        }
+    }
+
+    ///Returns the index in the global table of the first supercombinator called `name`
+    pub fn find_global(&self, name: &str) -> Option<usize> {
+        let name = intern(name);
+        (0..self.globals.len()).find(|&index| self.global(index).name.name == name)
+    }
+
+    ///Returns the statistics of the garbage collector
+    pub fn gc_stats(&self) -> GcStats {
+        self.heap.borrow().stats
+    }
+
+    ///Runs a garbage collection, freeing every node which is not reachable from the stack
+    pub fn collect_garbage(&self) {
+        self.collect(&mut self.heap.borrow_mut(), &[]);
+    }
+
+    ///Collects the garbage in `heap`, keeping the nodes which are reachable from the stack,
+    ///the suspended threads or `pending` alive
+    fn collect(&self, heap: &mut Heap, pending: &[Node]) {
+        let stack = self.stack.borrow();
+        let scheduler = self.scheduler.borrow();
+        let mut nodes = pending.to_vec();
+        nodes.extend(self.constants.borrow().iter().filter_map(|node| *node));
+        push_log_roots(&self.transaction.borrow(), &mut nodes);
+        for thread in scheduler.threads.iter() {
+            thread.push_roots(&mut nodes);
+        }
+        let mut roots: Vec<&[Node]> = vec![&stack, &nodes];
+        roots.extend(scheduler.threads.iter().map(|thread| &thread.stack[..]));
+        heap.collect(&roots);
+    }
+
+    ///Allocates a new node, collecting garbage first if the heap is full.
+    ///The nodes which `value` refers to are kept alive by the collection.
+    fn alloc(&self, value: Node_) -> Node {
+        let mut heap = self.heap.borrow_mut();
+        if heap.needs_collection() {
+            let mut pending = Vec::new();
+            value.push_children(&mut pending);
+            self.collect(&mut heap, &pending);
+        }
+        heap.alloc(value)
+    }
+
+    ///Makes sure that the next `count` allocations do not collect garbage, letting
+    ///nodes which are not on the stack be used while they are allocated
+    fn reserve(&self, count: usize) {
+        let mut heap = self.heap.borrow_mut();
+        //An enclosing reservation already covers these allocations
+        if heap.reserved >= count {
+            return
+        }
+        if heap.stress || heap.cells.len() + count > heap.limit {
+            self.collect(&mut heap, &[]);
+            heap.limit = max(heap.limit, heap.cells.len() + count);
+        }
+        heap.reserved = count;
+    }
+
+    fn get(&self, node: Node) -> Ref<Node_> {
+        Ref::map(self.heap.borrow(), |heap| heap.get(node))
+    }
+
+    fn get_mut(&self, node: Node) -> RefMut<Node_> {
+        RefMut::map(self.heap.borrow_mut(), |heap| heap.get_mut(node))
+    }
+
+    fn set(&self, node: Node, value: Node_) {
+        self.heap.borrow_mut().set(node, value)
+    }
+
+    ///Returns a value which displays what `node` evaluated to
+    pub fn show(&self, node: Node) -> ShowNode {
+        ShowNode { vm: self, node: node }
+    }
+
+    fn push(&self, node: Node) {
+        self.stack.borrow_mut().push(node)
+    }
+    fn pop(&self) -> Node {
+        self.stack.borrow_mut().pop().expect("Pop on an empty stack")
+    }
+    fn top(&self) -> Node {
+        *self.stack.borrow().last().expect("Empty stack")
+    }
+    fn stack_len(&self) -> usize {
+        self.stack.borrow().len()
+    }
+    fn stack_at(&self, index: usize) -> Node {
+        self.stack.borrow()[index]
+    }
+    fn set_stack(&self, index: usize, node: Node) {
+        self.stack.borrow_mut()[index] = node
+    }
+    fn truncate(&self, len: usize) {
+        self.stack.borrow_mut().truncate(len)
+    }
+
+    ///Evaluates the code into Normal Form (NF).
+    ///The returned node is only valid until the VM allocates again.
+    ///If an exception is thrown and not caught it is returned as a `RuntimeError`,
+    ///exceeding a limit of the VM's configuration returns `LimitExceeded`
+    pub fn evaluate(&self, code: &[Instruction], assembly_id: usize) -> Result<Node, VMError> {
+        self.reset_limits();
+        let frame = self.stack_len();
+        let result = self.execute(frame, code, assembly_id)
+            .and_then(|()| {
+                let top = self.pop();
+                self.deepseq(top)
+            });
+        self.truncate(frame);
+        result.map_err(|exception| self.runtime_error(exception))
+    }
+
+    ///Adds `assembly` to the VM and evaluates its `main` binding which must be a string,
+    ///such as an assembly created by `compile_show_expr`
+    pub fn evaluate_string(&mut self, assembly: Assembly) -> Result<String, VMError> {
+        let instructions = assembly.super_combinators.iter()
+            .find(|sc| sc.name == Name { name: intern("main"), uid: 0 })
+            .map(|sc| sc.instructions.clone())
+            .expect("Expected main function");
+        let assembly_index = self.add_assembly(assembly);
+        let result = try!(self.evaluate(&instructions, assembly_index));
+        Ok(primitive::get_string(self, result))
+    }
+
+    ///Evaluates the node into WHNF
+    fn eval(&self, node: Node) -> Result<Node, Exception> {
+        let frame = self.stack_len();
+        self.push(node);
+        try!(self.execute(frame, EVALCODE, 0));
+        let result = self.pop();
+        self.truncate(frame);
+        Ok(result)
+    }
+
+    ///Evaluates the node and all of its fields into NF, updating them in place
+    fn deepseq(&self, node: Node) -> Result<Node, Exception> {
+        let frame = self.stack_len();
+        self.push(node);
+        //The nodes above the first one in the frame are the ones left to evaluate
+        self.push(node);
+        while self.stack_len() > frame + 1 {
+            let next = self.pop();
+            let next = try!(self.eval(next));
+            let fields = match *self.get(next) {
+                Constructor(_, ref fields) => fields.clone(),
+                _ => Vec::new()
+            };
+            //Evaluate the fields from left to right
+            for field in fields.into_iter().rev() {
+                self.push(field);
+            }
+        }
+        Ok(self.pop())
+    }
+
+    ///Runs the supercombinator `main`, passing it the "RealWorld" if it is an IO action
+    pub fn run_main(&self, main: usize) -> Result<Node, VMError> {
+        self.reset_limits();
+        let io = is_io(&self.global(main).typ.value);
+        let frame = self.stack_len();
+        let mut node = self.alloc(Combinator(main));
+        if io {
+            //The "RealWorld" can be any value
+            self.push(node);
+            let world = self.alloc(Int(42));
+            node = self.alloc(Application(node, world));
+        }
+        let result = self.deepseq(node)
+            .map(|node| {
+                let value = match *self.get(node) {
+                    //Only return the value from the (a, RealWorld) pair
+                    Constructor(_, ref fields) if io => fields[0],
+                    _ => node
+                };
+                value
+            });
+        self.truncate(frame);
+        result.map_err(|exception| self.runtime_error(exception))
+    }
+
+    ///Starts counting the resources used by an evaluation from zero
+    fn reset_limits(&self) {
+        self.steps.set(0);
+        self.started.set(Instant::now());
+        self.limit.set(None);
+        self.retrying.set(false);
+    }
+
+    ///Counts a step of execution and throws an exception which can not be caught if it exceeds a limit.
+    ///Preempts the running thread once it has used up its time slice
+    fn check_limits(&self) -> Result<(), Exception> {
+        let steps = self.steps.get() + 1;
+        self.steps.set(steps);
+        if steps >= self.slice_end.get() && self.can_switch() {
+            self.reschedule.set(true);
+        }
+        let mut exceeded = match self.config.max_reductions {
+            Some(max) if steps > max => Some(LimitExceeded::Reductions(max)),
+            _ => None
+        };
+        //Collecting garbage and reading the clock is too expensive to do on every step
+        if exceeded.is_none() && steps % LIMIT_CHECK_INTERVAL == 0 {
+            if let Some(max) = self.config.max_heap_cells {
+                if self.heap.borrow().cells.len() > max {
+                    self.collect_garbage();
+                    if self.heap.borrow().cells.len() > max {
+                        exceeded = Some(LimitExceeded::HeapCells(max));
+                    }
+                }
+            }
+            if let Some(max) = self.config.max_time {
+                if self.started.get().elapsed() > max {
+                    exceeded = Some(LimitExceeded::Time(max));
+                }
+            }
+        }
+        match exceeded {
+            Some(limit) => {
+                self.limit.set(Some(limit));
+                //The exception is never caught so its value is not used
+                Err(Exception(self.alloc(Constructor(0, Vec::new()))))
+            }
+            None => Ok(())
+        }
+    }
+
+    ///Converts an uncaught exception into an error which can be reported to the user
+    fn runtime_error(&self, exception: Exception) -> VMError {
+        if let Some(limit) = self.limit.get() {
+            return VMError::LimitExceeded(limit)
+        }
+        let Exception(node) = exception;
+        let frame = self.stack_len();
+        let result = self.deepseq(node);
+        self.truncate(frame);
+        let node = match result {
+            Ok(node) => node,
+            //Showing the exception threw another exception so report that one instead
+            Err(exception) => return self.runtime_error(exception)
+        };
+        let (tag, value) = match *self.get(node) {
+            Constructor(tag, ref args) => (tag, args[0]),
+            ref node => panic!("Expected SomeException, got {:?}", node)
+        };
+        let (value_tag, fields) = match *self.get(value) {
+            Constructor(tag, ref args) => (tag, args.clone()),
+            ref value => panic!("Expected exception constructor, got {:?}", value)
+        };
+        let message = || primitive::get_string(self, *fields.last().expect("Expected an exception with a message"));
+        VMError::RuntimeError(match (tag, value_tag) {
+            (0, _) => RuntimeError::ErrorCall(message()),
+            (1, OVERFLOW) => RuntimeError::ArithException("arithmetic overflow".to_string()),
+            (1, DIVIDE_BY_ZERO) => RuntimeError::ArithException("divide by zero".to_string()),
+            (1, _) => RuntimeError::ArithException("arithmetic exception".to_string()),
+            (3, STACK_OVERFLOW) => RuntimeError::AsyncException("stack overflow".to_string()),
+            (3, THREAD_KILLED) => RuntimeError::AsyncException("thread killed".to_string()),
+            (3, _) => RuntimeError::AsyncException("asynchronous exception".to_string()),
+            (4, _) => RuntimeError::NonTermination(message()),
+            (5, _) => RuntimeError::IOException(message()),
+            (6, EXIT_SUCCESS) => RuntimeError::Exit(0),
+            (6, _) => match *self.get(fields[0]) {
+                Int(code) => RuntimeError::Exit(code as i32),
+                ref code => panic!("Expected an exit code, got {:?}", code)
+            },
+            (7, _) => RuntimeError::BlockedIndefinitelyOnMVar,
+            (8, _) => RuntimeError::BlockedIndefinitelyOnSTM,
+            (_, _) => RuntimeError::PatternMatchFail(message())
+        })
+    }
+
+    ///Executes a sequence of instructions in the frame starting at `base`, leaving the result on the top of the stack
+    ///Returns the exception if one was thrown and not caught during execution
+    pub fn execute<'c>(&'c self, base: usize, code: &'c [Instruction], assembly_id: usize) -> Result<(), Exception> {
+        let depth = self.depth.get();
+        let executions = self.executions.get();
+        let mut frame = Frame { code: code, pc: 0, base: base, assembly_id: assembly_id };
+        let mut dump = Vec::new();
+        self.executions.set(executions + 1);
+        let result = if executions == 0 {
+            self.run_threads(&mut frame, &mut dump)
+        }
+        else {
+            self.run(&mut frame, &mut dump)
+        };
+        self.executions.set(executions);
+        self.depth.set(depth);
+        result
+    }
+
+    ///Runs instructions until the frame which execution started in returns or until the running thread
+    ///has to let another thread run.
+    ///Evaluating a node or calling a function suspends the current frame on the dump
+    ///instead of recursing so that deep evaluations do not overflow the native stack.
+    fn run<'c>(&'c self, frame: &mut Frame<'c>, dump: &mut Vec<(Frame<'c>, Return)>) -> Result<(), Exception> {
+        loop {
+            if self.reschedule.get() {
+                return Ok(())
+            }
+            if let Err(exception) = self.check_limits() {
+                try!(self.throw(exception, frame, dump));
+            }
+            if frame.pc < frame.code.len() {
+                if let Err(exception) = self.step(frame, dump) {
+                    try!(self.throw(exception, frame, dump));
+                }
+            }
+            else {
+                debug!("End frame");
+                match dump.pop() {
+                    Some((caller, ret)) => {
+                        if let Err(exception) = self.return_to(frame, dump, caller, ret) {
+                            try!(self.throw(exception, frame, dump));
+                        }
+                    }
+                    None => return Ok(())
+                }
+            }
+        }
+    }
+
+    ///Runs the frame which execution started in as thread 0 along with every thread which is forked while it runs.
+    ///The threads take turns in a round-robin order, switching when the running thread blocks, yields or has used
+    ///up its time slice. The threads which are left when thread 0 finishes are abandoned.
+    fn run_threads<'c>(&'c self, frame: &mut Frame<'c>, dump: &mut Vec<(Frame<'c>, Return)>) -> Result<(), Exception> {
+        //The frames of the threads which are not running, indexed by their `ThreadId`
+        let mut suspended: Vec<Option<(Frame<'c>, Vec<(Frame<'c>, Return)>)>> = Vec::new();
+        let result = loop {
+            //An exception which is thrown to the thread stays in `pending`, where the garbage collector
+            //can see it, until it is received
+            let pending = {
+                let mut scheduler = self.scheduler.borrow_mut();
+                let current = scheduler.current;
+                scheduler.threads.get_mut(current).and_then(|thread| thread.pending.take())
+            };
+            let result = match pending {
+                Some(exception) => self.throw(Exception(exception), frame, dump)
+                    .and_then(|()| self.run(frame, dump)),
+                None => self.run(frame, dump)
+            };
+            if self.scheduler.borrow().threads.is_empty() {
+                break result
+            }
+            let current = self.scheduler.borrow().current;
+            let finished = match result {
+                Ok(()) => !self.reschedule.get(),
+                Err(exception) => {
+                    if current == 0 || self.limit.get().is_some() {
+                        break Err(exception)
+                    }
+                    self.report_uncaught(exception);
+                    true
+                }
+            };
+            if finished && current == 0 {
+                break Ok(())
+            }
+            self.reschedule.set(false);
+
+            let stack = mem::replace(&mut *self.stack.borrow_mut(), Vec::new());
+            let transaction = mem::replace(&mut *self.transaction.borrow_mut(), Vec::new());
+            {
+                let mut scheduler = self.scheduler.borrow_mut();
+                let thread = &mut scheduler.threads[current];
+                if finished {
+                    thread.state = ThreadState::Finished;
+                }
+                else {
+                    thread.stack = stack;
+                    thread.depth = self.depth.get();
+                    thread.transaction = transaction;
+                }
+            }
+            if !finished {
+                let placeholder = Frame { code: UNWINDCODE, pc: 0, base: 0, assembly_id: 0 };
+                while suspended.len() <= current {
+                    suspended.push(None);
+                }
+                suspended[current] = Some((mem::replace(frame, placeholder), mem::replace(dump, Vec::new())));
+            }
+
+            let next = self.next_thread();
+            let (state, stack, depth, transaction, pending) = {
+                let mut scheduler = self.scheduler.borrow_mut();
+                scheduler.current = next;
+                let thread = &mut scheduler.threads[next];
+                let transaction = mem::replace(&mut thread.transaction, Vec::new());
+                (thread.state, mem::replace(&mut thread.stack, Vec::new()), thread.depth, transaction, thread.pending.is_some())
+            };
+            *self.stack.borrow_mut() = stack;
+            self.depth.set(depth);
+            *self.transaction.borrow_mut() = match state {
+                //The transaction is run again from the start
+                ThreadState::Retrying => vec![Vec::new()],
+                _ => transaction
+            };
+            if !pending && !self.transaction_valid() {
+                //Another thread has written to a `TVar` which the transaction used so it is restarted
+                self.retrying.set(true);
+                let exception = self.alloc(Constructor(0, Vec::new()));
+                self.scheduler.borrow_mut().threads[next].pending = Some(exception);
+            }
+            let (next_frame, next_dump) = match state {
+                ThreadState::New(action) => {
+                    //The "RealWorld" can be any value
+                    self.push(action);
+                    let world = self.alloc(Int(42));
+                    let node = self.alloc(Application(action, world));
+                    self.set_stack(0, node);
+                    (Frame { code: UNWINDCODE, pc: 0, base: 0, assembly_id: 0 }, Vec::new())
+                }
+                _ => suspended[next].take().expect("Expected a suspended thread")
+            };
+            self.scheduler.borrow_mut().threads[next].state = ThreadState::Runnable;
+            *frame = next_frame;
+            *dump = next_dump;
+            self.slice_end.set(self.steps.get() + self.config.time_slice);
+        };
+        self.abandon_threads(suspended);
+        result
+    }
+
+    ///Restores the blackholes of the threads which have not finished and removes every thread,
+    ///making the stack of thread 0 the stack of the VM again
+    fn abandon_threads<'c>(&'c self, suspended: Vec<Option<(Frame<'c>, Vec<(Frame<'c>, Return)>)>>) {
+        let current = self.scheduler.borrow().current;
+        for (index, continuation) in suspended.into_iter().enumerate() {
+            if let Some((mut frame, dump)) = continuation {
+                let stack = mem::replace(&mut self.scheduler.borrow_mut().threads[index].stack, Vec::new());
+                let stack = mem::replace(&mut *self.stack.borrow_mut(), stack);
+                for (caller, ret) in dump.into_iter().rev() {
+                    self.restore_blackhole(&frame, ret);
+                    frame = caller;
+                }
+                let stack = mem::replace(&mut *self.stack.borrow_mut(), stack);
+                self.scheduler.borrow_mut().threads[index].stack = stack;
+            }
+        }
+        let mut scheduler = self.scheduler.borrow_mut();
+        if current != 0 {
+            let stack = mem::replace(&mut scheduler.threads[0].stack, Vec::new());
+            *self.stack.borrow_mut() = stack;
+            self.depth.set(scheduler.threads[0].depth);
+            *self.transaction.borrow_mut() = Vec::new();
+        }
+        *scheduler = Scheduler::new();
+        self.slice_end.set(!0);
+        self.reschedule.set(false);
+    }
+
+    ///Picks the thread which runs after the current one by going through the threads in a round-robin order.
+    ///If every thread is blocked the threads waiting on an `MVar` or a `retry` are woken by throwing
+    ///`BlockedIndefinitelyOnMVar` or `BlockedIndefinitelyOnSTM` to them, the threads waiting on a blackhole
+    ///are woken with `NonTermination` if there are no such threads.
+    fn next_thread(&self) -> usize {
+        loop {
+            let now = Instant::now();
+            let mut wake: Option<Instant> = None;
+            let next = {
+                let scheduler = self.scheduler.borrow();
+                let count = scheduler.threads.len();
+                (1..count + 1)
+                    .map(|i| (scheduler.current + i) % count)
+                    .find(|&i| match scheduler.threads[i].state {
+                        ThreadState::New(_) | ThreadState::Runnable => true,
+                        ThreadState::Taking(mvar) => self.is_full(mvar),
+                        ThreadState::Putting(mvar) => !self.is_full(mvar),
+                        ThreadState::Evaluating(node) => match *self.get(node) {
+                            BlackHole(_) => false,
+                            _ => true
+                        },
+                        ThreadState::Sleeping(time) => {
+                            wake = Some(wake.map_or(time, |wake| ::std::cmp::min(wake, time)));
+                            time <= now
+                        }
+                        ThreadState::Retrying => scheduler.threads[i].transaction.iter()
+                            .flat_map(|log| log.iter())
+                            .any(|entry| match *self.get(entry.tvar) {
+                                TVar(_, version) => version != entry.version,
+                                _ => panic!("Expected TVar")
+                            }),
+                        ThreadState::Finished => false
+                    })
+            };
+            if let Some(next) = next {
+                return next
+            }
+            if let Some(wake) = wake {
+                ::std::thread::sleep(wake - now);
+                continue
+            }
+            let blocked: Vec<(usize, ThreadState)> = self.scheduler.borrow().threads.iter()
+                .map(|thread| thread.state)
+                .enumerate()
+                .filter(|&(_, state)| state != ThreadState::Finished)
+                .collect();
+            let on_variable = blocked.iter().any(|&(_, state)| match state {
+                ThreadState::Taking(_) | ThreadState::Putting(_) | ThreadState::Retrying => true,
+                _ => false
+            });
+            for (index, state) in blocked.into_iter() {
+                let exception = match state {
+                    ThreadState::Taking(_) | ThreadState::Putting(_) => Exception::blocked_indefinitely(self),
+                    ThreadState::Retrying => Exception::blocked_indefinitely_on_stm(self),
+                    ThreadState::Evaluating(node) if !on_variable => {
+                        let work = match *self.get(node) {
+                            BlackHole(work) => work,
+                            _ => node
+                        };
+                        self.non_termination(work)
+                    }
+                    _ => continue
+                };
+                let mut scheduler = self.scheduler.borrow_mut();
+                scheduler.threads[index].pending = Some(exception.0);
+                scheduler.threads[index].state = ThreadState::Runnable;
+            }
+        }
+    }
+
+    ///Reports an exception which a forked thread did not catch, killed threads finish silently
+    fn report_uncaught(&self, exception: Exception) {
+        let Exception(node) = exception;
+        self.push(node);
+        let killed = self.eval(node).ok()
+            .and_then(|node| match *self.get(node) {
+                Constructor(3, ref fields) => Some(fields[0]),
+                _ => None
+            })
+            .and_then(|value| self.eval(value).ok())
+            .map_or(false, |value| match *self.get(value) {
+                Constructor(THREAD_KILLED, _) => true,
+                _ => false
+            });
+        if !killed {
+            let error = self.runtime_error(Exception(node));
+            let _ = writeln!(io::stderr(), "{}", error);
+        }
+        self.pop();
+    }
+
+    ///Returns whether the thread which is running can be suspended to let another thread run
+    fn can_switch(&self) -> bool {
+        self.executions.get() == 1
+    }
+
+    ///Suspends the running thread until it is no longer blocked in `state`.
+    ///Throws `BlockedIndefinitelyOnMVar` if the thread can not be suspended since nothing could wake it then
+    fn wait(&self, state: ThreadState) -> Result<(), Exception> {
+        if !self.can_switch() {
+            return Err(Exception::blocked_indefinitely(self))
+        }
+        self.scheduler.borrow_mut().running().state = state;
+        self.reschedule.set(true);
+        Ok(())
+    }
+
+    ///Lets the next thread run after the current step if there are other threads
+    fn yield_thread(&self) {
+        if self.can_switch() && self.scheduler.borrow().threads.len() > 1 {
+            self.reschedule.set(true);
+        }
+    }
+
+    ///Creates a thread which runs the IO action `action` and returns its `ThreadId`
+    fn fork(&self, action: Node) -> usize {
+        let mut scheduler = self.scheduler.borrow_mut();
+        scheduler.running();
+        scheduler.threads.push(Thread::new(ThreadState::New(action)));
+        if self.slice_end.get() == !0 {
+            self.slice_end.set(self.steps.get() + self.config.time_slice);
+        }
+        scheduler.threads.len() - 1
+    }
+
+    ///Returns the `ThreadId` of the thread which is running
+    fn thread_id(&self) -> usize {
+        self.scheduler.borrow().current
+    }
+
+    ///Throws `exception` to the thread `id`, the thread receives it the next time it runs.
+    ///A thread which has not started yet finishes without running
+    fn throw_to(&self, id: usize, exception: Node) -> Result<(), Exception> {
+        if id == self.thread_id() {
+            return Err(Exception(exception))
+        }
+        let mut scheduler = self.scheduler.borrow_mut();
+        if let Some(thread) = scheduler.threads.get_mut(id) {
+            match thread.state {
+                ThreadState::Finished => (),
+                ThreadState::New(_) => thread.state = ThreadState::Finished,
+                _ => {
+                    thread.pending = Some(exception);
+                    thread.state = ThreadState::Runnable;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    ///Returns whether the `MVar` holds a value
+    fn is_full(&self, mvar: Node) -> bool {
+        match *self.get(mvar) {
+            MVar(value) => value.is_some(),
+            _ => panic!("Expected MVar")
+        }
+    }
+
+    ///Returns whether the blackhole `node` is being evaluated by another thread than the running one,
+    ///in which case the running thread waits for it instead of throwing `NonTermination`
+    fn evaluated_by_other_thread<'c>(&self, node: Node, frame: &Frame<'c>, dump: &[(Frame<'c>, Return)]) -> bool {
+        if !self.can_switch() || self.scheduler.borrow().threads.len() < 2 {
+            return false
+        }
+        let mut base = frame.base;
+        for &(ref caller, ret) in dump.iter().rev() {
+            match ret {
+                Return::Eval | Return::Strict if self.stack_at(base - 1) == node => return false,
+                _ => ()
+            }
+            base = caller.base;
+        }
+        true
+    }
+
+    ///Runs the IO action at `action` with the "RealWorld" at `world` as a transaction or as the first alternative
+    ///of an `orElse`, starting a new transaction if the running thread is not in one
+    fn start_transaction<'c>(&self, frame: &mut Frame<'c>, dump: &mut Vec<(Frame<'c>, Return)>, action: usize, world: usize, ret: Return) -> Result<(), Exception> {
+        if self.transaction.borrow().is_empty() {
+            self.transaction.borrow_mut().push(Vec::new());
+        }
+        self.truncate(world + 1);
+        let node = self.alloc(Application(self.stack_at(action), self.stack_at(world)));
+        self.push(node);
+        let callee = Frame { code: UNWINDCODE, pc: 0, base: world + 1, assembly_id: frame.assembly_id };
+        self.enter(frame, dump, callee, ret)
+    }
+
+    ///Returns the value of `tvar` as seen by the running transaction, recording the read in its log.
+    ///Outside of a transaction the value is read directly
+    fn read_tvar(&self, tvar: Node) -> Node {
+        let (value, version) = match *self.get(tvar) {
+            TVar(value, version) => (value, version),
+            _ => panic!("Expected TVar")
+        };
+        let mut logs = self.transaction.borrow_mut();
+        if let Some(entry) = logs.iter().rev().flat_map(|log| log.iter()).find(|entry| entry.tvar == tvar) {
+            return entry.value
+        }
+        if let Some(log) = logs.last_mut() {
+            log.push(LogEntry { tvar: tvar, version: version, value: value, written: false });
+        }
+        value
+    }
+
+    ///Writes `value` to `tvar` in the log of the running transaction
+    fn write_tvar(&self, tvar: Node, value: Node) {
+        let version = match *self.get(tvar) {
+            TVar(_, version) => version,
+            _ => panic!("Expected TVar")
+        };
+        let mut logs = self.transaction.borrow_mut();
+        let version = logs.iter().flat_map(|log| log.iter())
+            .find(|entry| entry.tvar == tvar)
+            .map_or(version, |entry| entry.version);
+        let log = logs.last_mut().expect("writeTVar outside of a transaction");
+        match log.iter_mut().find(|entry| entry.tvar == tvar) {
+            Some(entry) => {
+                entry.value = value;
+                entry.written = true;
+                return
+            }
+            None => ()
+        }
+        log.push(LogEntry { tvar: tvar, version: version, value: value, written: true });
+    }
+
+    ///Returns whether none of the `TVar`s which the running transaction accessed have been written since
+    fn transaction_valid(&self) -> bool {
+        self.transaction.borrow().iter().flat_map(|log| log.iter())
+            .all(|entry| match *self.get(entry.tvar) {
+                TVar(_, version) => version == entry.version,
+                _ => panic!("Expected TVar")
+            })
+    }
+
+    ///Writes the values of the running transaction to its `TVar`s if it is still valid and ends the transaction.
+    ///Returns false if the transaction has to be run again
+    fn commit_transaction(&self) -> bool {
+        let valid = self.transaction_valid();
+        let logs = mem::replace(&mut *self.transaction.borrow_mut(), Vec::new());
+        if valid {
+            for entry in logs.iter().flat_map(|log| log.iter()).filter(|entry| entry.written) {
+                self.set(entry.tvar, TVar(entry.value, entry.version + 1));
+            }
+        }
+        valid
+    }
+
+    ///Merges the log of the first alternative of an `orElse` which finished into the enclosing log
+    fn merge_alternative(&self) {
+        let mut logs = self.transaction.borrow_mut();
+        let nested = logs.pop().expect("Expected the log of an orElse");
+        let log = logs.last_mut().expect("Expected the log of a transaction");
+        for entry in nested.into_iter() {
+            match log.iter_mut().find(|outer| outer.tvar == entry.tvar) {
+                Some(outer) => {
+                    if entry.written {
+                        outer.value = entry.value;
+                        outer.written = true;
+                    }
+                }
+                None => log.push(entry)
+            }
+        }
+    }
+
+    ///Discards the log of the first alternative of an `orElse` which called `retry`.
+    ///Its reads are kept since the transaction waits for them to change if the second alternative retries as well
+    fn abandon_alternative(&self) {
+        let mut logs = self.transaction.borrow_mut();
+        let nested = logs.pop().expect("Expected the log of an orElse");
+        let log = logs.last_mut().expect("Expected the log of a transaction");
+        for entry in nested.into_iter() {
+            if log.iter().all(|outer| outer.tvar != entry.tvar) {
+                let value = match *self.get(entry.tvar) {
+                    TVar(value, _) => value,
+                    _ => panic!("Expected TVar")
+                };
+                log.push(LogEntry { value: value, written: false, .. entry });
+            }
+        }
+    }
+
+    ///Suspends `frame` on the dump and starts executing `callee` instead.
+    ///Throws `StackOverflow` if the dump is already at its maximum depth
+    fn enter<'c>(&self, frame: &mut Frame<'c>, dump: &mut Vec<(Frame<'c>, Return)>, callee: Frame<'c>, ret: Return) -> Result<(), Exception> {
+        if self.depth.get() >= self.config.max_depth {
+            return Err(Exception::async_exception(self, STACK_OVERFLOW))
+        }
+        self.depth.set(self.depth.get() + 1);
+        debug!("Entering frame at {:?}", callee.base);
+        let caller = mem::replace(frame, callee);
+        dump.push((caller, ret));
+        Ok(())
+    }
+
+    ///Passes the result of `frame` which has finished executing back to `caller`.
+    ///A transaction which can not be committed is run again instead
+    fn return_to<'c>(&self, frame: &mut Frame<'c>, dump: &mut Vec<(Frame<'c>, Return)>, caller: Frame<'c>, ret: Return) -> Result<(), Exception> {
+        self.depth.set(self.depth.get() - 1);
+        match ret {
+            Return::Eval | Return::Strict => {
+                let new = self.pop();
+                self.truncate(frame.base);
+                //Update the evaluated node so that it is only evaluated once.
+                //Mutable values are not copied since every reference must see the same cell
+                let old = self.stack_at(frame.base - 1);
+                let value = match *self.get(new) {
+                    MutVar(_) | Array(_) | IntArray(_) | MVar(_) | TVar(..) => Indirection(new),
+                    ref value => value.clone()
+                };
+                self.set(old, value);
+                match ret {
+                    Return::Eval => self.set_stack(frame.base - 1, new),
+                    _ => { self.pop(); }
+                }
+            }
+            Return::Call => self.truncate(frame.base + 1),
+            Return::Catch(args) | Return::Atomically(args) | Return::OrElse(args) => {
+                match ret {
+                    Return::Atomically(_) if !self.commit_transaction() => {
+                        *frame = caller;
+                        return self.start_transaction(frame, dump, args, args + 1, ret)
+                    }
+                    Return::OrElse(_) => self.merge_alternative(),
+                    _ => ()
+                }
+                let value = self.pop();
+                self.truncate(args);
+                self.push(value);
+            }
+        }
+        *frame = caller;
+        Ok(())
+    }
+
+    ///Unwinds the dump to the innermost `catch` and calls its handler with the exception.
+    ///Returns the exception if there is no `catch` in the frames of this execution or if a limit was exceeded
+    fn throw<'c>(&self, exception: Exception, frame: &mut Frame<'c>, dump: &mut Vec<(Frame<'c>, Return)>) -> Result<(), Exception> {
+        let Exception(mut exception) = exception;
+        while let Some((caller, ret)) = dump.pop() {
+            self.depth.set(self.depth.get() - 1);
+            match ret {
+                Return::Eval | Return::Strict => self.restore_blackhole(frame, ret),
+                Return::Catch(handler) if self.limit.get().is_none() && !self.retrying.get() => {
+                    let action = self.alloc(Application(self.stack_at(handler), exception));
+                    let action = self.alloc(Application(action, self.stack_at(handler + 1)));
+                    self.truncate(handler);
+                    self.push(action);
+                    *frame = caller;
+                    return Ok(())
+                }
+                Return::Atomically(args) if self.retrying.get() => {
+                    self.retrying.set(false);
+                    if !self.transaction_valid() {
+                        //A `TVar` which was read has already changed so the transaction can run again right away
+                        self.transaction.borrow_mut().clear();
+                    }
+                    else if self.can_switch() {
+                        //The log is kept so that the scheduler can see when a `TVar` in it changes
+                        self.scheduler.borrow_mut().running().state = ThreadState::Retrying;
+                        self.reschedule.set(true);
+                    }
+                    else {
+                        self.transaction.borrow_mut().clear();
+                        exception = Exception::blocked_indefinitely_on_stm(self).0;
+                        *frame = caller;
+                        continue
+                    }
+                    *frame = caller;
+                    return self.start_transaction(frame, dump, args, args + 1, ret)
+                }
+                Return::OrElse(args) if self.retrying.get() && self.transaction_valid() => {
+                    //Run the second alternative in place of the `orElse`
+                    self.retrying.set(false);
+                    self.abandon_alternative();
+                    let action = self.alloc(Application(self.stack_at(args + 1), self.stack_at(args + 2)));
+                    self.truncate(args);
+                    self.push(action);
+                    *frame = caller;
+                    return Ok(())
+                }
+                Return::Atomically(_) => self.transaction.borrow_mut().clear(),
+                Return::OrElse(_) => { self.transaction.borrow_mut().pop(); }
+                Return::Catch(_) | Return::Call => ()
+            }
+            *frame = caller;
+        }
+        Err(Exception(exception))
+    }
+
+    ///Restores the node which `frame` was evaluating if its evaluation is abandoned so that it can be evaluated again
+    fn restore_blackhole<'c>(&self, frame: &Frame<'c>, ret: Return) {
+        match ret {
+            Return::Eval | Return::Strict => {
+                let old = self.stack_at(frame.base - 1);
+                let value = match *self.get(old) {
+                    BlackHole(work) => Some(self.get(work).clone()),
+                    _ => None
+                };
+                if let Some(value) = value {
+                    self.set(old, value);
+                }
+            }
+            _ => ()
+        }
+    }
+
+    ///Executes the instruction at `frame.pc`
+    fn step<'c>(&'c self, frame: &mut Frame<'c>, dump: &mut Vec<(Frame<'c>, Return)>) -> Result<(), Exception> {
+        use compiler::Instruction::*;
+        let base = frame.base;
+        debug!("Executing instruction {:?} : {:?}", frame.pc, frame.code[frame.pc]);
+        match frame.code[frame.pc] {
+            Add => primitive(self, |l, r| { l.wrapping_add(r) }),
+            Sub => primitive(self, |l, r| { l.wrapping_sub(r) }),
+            Multiply => primitive(self, |l, r| { l.wrapping_mul(r) }),
+            Divide => try!(checked_primitive(self, |l, r| l.checked_div(r))),
+            Remainder => try!(checked_primitive(self, |l, r| l.checked_rem(r))),
+            IntEQ => primitive_int(self, |l, r| { if l == r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
+            IntLT => primitive_int(self, |l, r| { if l < r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
+            IntLE => primitive_int(self, |l, r| { if l <= r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
+            IntGT => primitive_int(self, |l, r| { if l > r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
+            IntGE => primitive_int(self, |l, r| { if l >= r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
+            DoubleAdd => primitive_float(self, |l, r| { Float(l + r) }),
+            DoubleSub => primitive_float(self, |l, r| { Float(l - r) }),
+            DoubleMultiply => primitive_float(self, |l, r| { Float(l * r) }),
+            DoubleDivide => primitive_float(self, |l, r| { Float(l / r) }),
+            DoubleRemainder => primitive_float(self, |l, r| { Float(l % r) }),
+            DoubleEQ => primitive_float(self, |l, r| { if l == r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
+            DoubleLT => primitive_float(self, |l, r| { if l < r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
+            DoubleLE => primitive_float(self, |l, r| { if l <= r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
+            DoubleGT => primitive_float(self, |l, r| { if l > r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
+            DoubleGE => primitive_float(self, |l, r| { if l >= r { Constructor(0, Vec::new()) } else { Constructor(1, Vec::new()) } }),
+            IntToDouble => {
+                let top = self.pop();
+                let value = match *self.get(top) {
+                    Int(i) => Float(i as f64),
+                    _ => panic!("Excpected Int in Int -> Double cast")
+                };
+                let node = self.alloc(value);
+                self.push(node);
+            }
+            DoubleToInt => {
+                let top = self.pop();
+                let value = match *self.get(top) {
+                    Float(f) => Int(f as isize),
+                    _ => panic!("Excpected Double in Double -> Int cast")
+                };
+                let node = self.alloc(value);
+                self.push(node);
+            }
+            DoubleSqrt => primitive_float_unary(self, |x| Float(x.sqrt())),
+            DoubleExp => primitive_float_unary(self, |x| Float(x.exp())),
+            DoubleLog => primitive_float_unary(self, |x| Float(x.ln())),
+            DoubleSin => primitive_float_unary(self, |x| Float(x.sin())),
+            DoubleCos => primitive_float_unary(self, |x| Float(x.cos())),
+            DoubleTan => primitive_float_unary(self, |x| Float(x.tan())),
+            DoubleAsin => primitive_float_unary(self, |x| Float(x.asin())),
+            DoubleAcos => primitive_float_unary(self, |x| Float(x.acos())),
+            DoubleAtan => primitive_float_unary(self, |x| Float(x.atan())),
+            DoubleSinh => primitive_float_unary(self, |x| Float(x.sinh())),
+            DoubleCosh => primitive_float_unary(self, |x| Float(x.cosh())),
+            DoubleTanh => primitive_float_unary(self, |x| Float(x.tanh())),
+            DoublePow => primitive_float(self, |l, r| Float(l.powf(r))),
+            DoubleAtan2 => primitive_float(self, |l, r| Float(l.atan2(r))),
+            DoubleTruncate => primitive_float_unary(self, |x| Float(x.trunc())),
+            DoubleRound => primitive_float_unary(self, |x| {
+                let rounded = if (x - x.trunc()).abs() == 0.5 { 2.0 * (x / 2.0).round() } else { x.round() };
+                Float(rounded)
+            }),
+            DoubleFloor => primitive_float_unary(self, |x| Float(x.floor())),
+            DoubleCeiling => primitive_float_unary(self, |x| Float(x.ceil())),
+            DoubleIsNaN => primitive_float_unary(self, |x| Constructor(if x.is_nan() { 0 } else { 1 }, Vec::new())),
+            DoubleIsInfinite => primitive_float_unary(self, |x| Constructor(if x.is_infinite() { 0 } else { 1 }, Vec::new())),
+            DoubleIsNegativeZero => primitive_float_unary(self, |x| {
+                Constructor(if x == 0.0 && x.is_sign_negative() { 0 } else { 1 }, Vec::new())
+            }),
+            DoubleToFloat => primitive_float_unary(self, |x| Float(x as f32 as f64)),
+            CharToInt => primitive_char_unary(self, |c| Int(c as isize)),
+            IntToChar => {
+                let top = self.pop();
+                let code = match *self.get(top) {
+                    Int(i) => i,
+                    ref x => panic!("Expected fully evaluted Int in primitive instruction\n {:?}", x)
+                };
+                let c = if code >= 0 && code <= 0x10FFFF { ::std::char::from_u32(code as u32) } else { None };
+                match c {
+                    Some(c) => {
+                        let node = self.alloc(Char(c));
+                        self.push(node);
+                    }
+                    None => {
+                        let message = primitive::create_string(self, &format!("Prelude.chr: bad argument: {}", code));
+                        return Err(Exception::error_call(self, message))
+                    }
+                }
+            }
+            CharIsSpace => primitive_char_unary(self, |c| bool_node(c.is_whitespace())),
+            CharIsAlpha => primitive_char_unary(self, |c| bool_node(c.is_alphabetic())),
+            CharIsAlphaNum => primitive_char_unary(self, |c| bool_node(c.is_alphanumeric())),
+            CharIsUpper => primitive_char_unary(self, |c| bool_node(c.is_uppercase())),
+            CharIsLower => primitive_char_unary(self, |c| bool_node(c.is_lowercase())),
+            CharIsPunctuation => primitive_char_unary(self, |c| bool_node("!\"#%&'()*,-./:;?@[\\]_{}".contains(c))),
+            CharIsControl => primitive_char_unary(self, |c| bool_node(c.is_control())),
+            CharToUpper => primitive_char_unary(self, |c| Char(c.to_uppercase().next().unwrap_or(c))),
+            CharToLower => primitive_char_unary(self, |c| Char(c.to_lowercase().next().unwrap_or(c))),
+            NumberEQ => {
+                let l = self.pop();
+                let r = self.pop();
+                let equal = match (&*self.get(l), &*self.get(r)) {
+                    (&Int(lhs), &Int(rhs)) => lhs == rhs,
+                    (&Int(lhs), &Integer(ref rhs)) => Some(lhs) == rhs.to_isize(),
+                    (&Int(lhs), &Float(rhs)) => lhs as f64 == rhs,
+                    (lhs, rhs) => panic!("Expected fully evaluted numbers in NumberEQ\n LHS: {:?}\nRHS: {:?} ", lhs, rhs)
+                };
+                let node = self.alloc(Constructor(if equal { 0 } else { 1 }, Vec::new()));
+                self.push(node);
+            }
+            PushInt(value) => { let node = self.alloc(Int(value)); self.push(node); }
+            PushFloat(value) => { let node = self.alloc(Float(value)); self.push(node); }
+            PushChar(value) => { let node = self.alloc(Char(value)); self.push(node); }
+            Push(index) => {
+                let x = self.stack_at(base + index);
+                debug!("Pushed {:?}", self.show(x));
+                self.push(x);
+            }
+            PushGlobal(index) => {
+                let node = self.global_node(index);
+                self.push(node);
+            }
+            PushBuiltin(index) => {
+                let node = self.alloc(BuiltinFunction(index));
+                self.push(node);
+            }
+            Mkap => {
+                assert!(self.stack_len() >= base + 2);
+                let func = self.pop();
+                let arg = self.pop();
+                debug!("Mkap {:?} {:?}", self.show(func), self.show(arg));
+                let node = self.alloc(Application(func, arg));
+                self.push(node);
+            }
+            Eval => {
+                if frame.pc > 0 && frame.code[frame.pc - 1] == Mkap {
+                    if let Some(unwind) = returns_top(frame.code, frame.pc + 1) {
+                        //The application was just created so nothing else can refer to it and it does not need
+                        //to be updated, unwinding it in place of this frame lets tail calls run in constant space
+                        let top = self.pop();
+                        self.truncate(base);
+                        self.push(top);
+                        frame.pc = unwind;
+                        return Ok(())
+                    }
+                }
+                //The node is left below the new frame so that it can be updated when the frame returns
+                let old = self.top();
+                let evaluated = match *self.get(old) {
+                    Int(_) | Float(_) | Char(_) | Constructor(..) | Dictionary(_) | BuiltinFunction(_) |
+                    MutVar(_) | Array(_) | IntArray(_) | MVar(_) | TVar(..) => true,
+                    _ => false
+                };
+                if evaluated {
+                    frame.pc += 1;
+                    return Ok(())
+                }
+                let work = match try!(self.blackhole(old, frame, dump)) {
+                    Some(work) => work,
+                    //Another thread is evaluating the node, the instruction is retried once it is done
+                    None => return Ok(())
+                };
+                let callee = Frame { code: UNWINDCODE, pc: 0, base: self.stack_len(), assembly_id: frame.assembly_id };
+                self.push(work);
+                frame.pc += 1;
+                return self.enter(frame, dump, callee, Return::Eval)
+            }
+            Pop(num) => {
+                let len = self.stack_len();
+                self.truncate(len - num);
+            }
+            Update(index) => {
+                let top = self.top();
+                let node = self.alloc(Indirection(top));
+                self.set_stack(base + index, node);
+            }
+            Unwind => return self.unwind(frame, dump),
+            Slide(size) => {
+                let top = self.pop();
+                let len = self.stack_len();
+                self.truncate(len - size);
+                self.push(top);
+            }
+            Split(_) => {
+                let temp = self.pop();
+                let fields = match *self.get(temp) {
+                    Constructor(_, ref fields) => fields.clone(),
+                    _ => panic!("Expected constructor in Split instruction")
+                };
+                for field in fields.into_iter() {
+                    self.push(field);
+                }
+            }
+            Pack(tag, arity) => {
+                let mut args = Vec::new();
+                for _ in 0..arity {
+                    args.push(self.pop());
+                }
+                let node = self.alloc(Constructor(tag, args));
+                self.push(node);
+            }
+            JumpFalse(address) => {
+                let jump = match *self.get(self.top()) {
+                    Constructor(1, _) => true,
+                    _ => false
+                };
+                self.pop();
+                if jump {
+                    frame.pc = address;
+                    return Ok(())
+                }
+            }
+            CaseJump(jump_tag) => {
+                let matched = match *self.get(self.top()) {
+                    Constructor(tag, _) => jump_tag == tag as usize,
+                    ref x => panic!("Expected constructor when executing CaseJump, got {:?}", *x),
+                };
+                if matched {
+                    frame.pc += 2;//Skip the jump instruction ie continue to the next test
+                    return Ok(())
+                }
+                self.pop();
+            }
+            Jump(to) => {
+                frame.pc = to;
+                return Ok(())
+            }
+            PatternMatchFail => {
+                return Err(Exception::pattern_match_fail(self, "non-exhaustive patterns in case"))
+            }
+            PushDictionary(index) => {
+                let assembly = &self.assembly[frame.assembly_id];
+                let dict : &[usize] = &*assembly.instance_dictionaries[index];
+                let dict = InstanceDictionary { entries: dict.iter().map(|i| Rc::new(DictionaryEntry::Function(*i))).collect() };
+                let node = self.alloc(Dictionary(dict));
+                self.push(node);
+            }
+            PushDictionaryMember(index) => {
+                let entry = match *self.get(self.stack_at(base)) {
+                    Dictionary(ref x) => x.entries[index].clone(),
+                    ref x => panic!("Attempted to retrieve {:?} as dictionary", *x)
+                };
+                let node = match *entry {
+                    DictionaryEntry::Function(gi) => self.alloc(Combinator(gi)),
+                    DictionaryEntry::App(gi, ref dict) => {
+                        self.reserve(3);
+                        let func = self.alloc(Combinator(gi));
+                        let dict = self.alloc(Dictionary(dict.clone()));
+                        self.alloc(Application(func, dict))
+                    }
+                };
+                self.push(node);
+            }
+            MkapDictionary => {
+                let a = self.pop();
+                let func = self.pop();
+                let mut new_dict = InstanceDictionary { entries: Vec::new() };
+                {
+                    let a = self.get(a);
+                    let arg = match *a {
+                        Dictionary(ref d) => {
+                            d
+                        }
+                        _ => panic!()
+                    };
+                    match *self.get(func) {
+                        Dictionary(ref d) => {
+                            for entry in d.entries.iter() {
+                                match **entry {
+                                    DictionaryEntry::Function(index) => {
+                                        new_dict.entries.push(Rc::new(DictionaryEntry::App(index, arg.clone())));
+                                    }
+                                    _ => panic!()
+                                }
+                            }
+                        }
+                        _ => panic!()
+                    }
+                }
+                let node = self.alloc(Dictionary(new_dict));
+                self.push(node);
+            }
+            MkapMethodDictionary => {
+                //Collecting garbage while `func` and `dict` are on the stack keeps them alive
+                self.reserve(2);
+                let func = self.pop();
+                let dict = self.pop();
+                let merged = match *self.get(func) {
+                    Application(f, inst) => match (&*self.get(inst), &*self.get(dict)) {
+                        (&Dictionary(ref inst), &Dictionary(ref dict)) => {
+                            let entries = inst.entries.iter().chain(dict.entries.iter()).cloned().collect();
+                            Some((f, InstanceDictionary { entries: entries }))
+                        }
+                        _ => None
+                    },
+                    _ => None
+                };
+                let node = match merged {
+                    Some((f, new_dict)) => {
+                        let new_dict = self.alloc(Dictionary(new_dict));
+                        self.alloc(Application(f, new_dict))
+                    }
+                    None => self.alloc(Application(func, dict))
+                };
+                self.push(node);
+            }
+            ConstructDictionary(size) => {
+                let mut new_dict = InstanceDictionary { entries: Vec::new() };
+                for _ in 0..size {
+                    let temp = self.pop();
+                    match *self.get(temp) {
+                        Dictionary(ref d) => {
+                            new_dict.entries.extend(d.entries.iter().map(|x| x.clone()));
+                        }
+                        ref x => panic!("Unexpected {:?}", x)
+                    }
+                }
+                let node = self.alloc(Dictionary(new_dict));
+                self.push(node);
+            }
+            Alloc(count) => {
+                //The nodes are blackholes until they are rewritten since they can not be evaluated before that
+                for _ in 0..count {
+                    let node = self.alloc(Int(0));
+                    self.set(node, BlackHole(node));
+                    self.push(node);
+                }
+            }
+            Rewrite(index) => {
+                let top = self.pop();
+                self.set(self.stack_at(base + index), Indirection(top));
+            }
+            PushDictionaryRange(start, size) => {
+                let mut new_dict = InstanceDictionary { entries: Vec::new() };
+                match *self.get(self.stack_at(base)) {
+                    Dictionary(ref d) => {
+                        new_dict.entries.extend(d.entries.iter().skip(start).take(size).map(|x| x.clone()));
+                    }
+                    _ => panic!()
+                }
+                let node = self.alloc(Dictionary(new_dict));
+                self.push(node);
+            }
+        }
+        frame.pc += 1;
+        Ok(())
+    }
+
+    ///Executes the `Unwind` instruction, the instruction is repeated until the top of the stack is in WHNF
+    fn unwind<'c>(&'c self, frame: &mut Frame<'c>, dump: &mut Vec<(Frame<'c>, Return)>) -> Result<(), Exception> {
+        let x = self.get(self.top()).clone();
+        debug!("Unwinding {:?}", x);
+        match x {
+            Application(func, _) => self.push(func),
+            Indirection(node) => {
+                let len = self.stack_len();
+                self.set_stack(len - 1, node);
+            }
+            Combinator(index) => {
+                let comb = self.global(index);
+                debug!(">>> Call {:?}", comb.name);
+                match self.take_arguments(frame.base, comb.arity) {
+                    Some(args) => {
+                        let callee = Frame { code: &*comb.instructions, pc: 0, base: args, assembly_id: comb.assembly_id };
+                        if args == frame.base && frame.pc + 1 == frame.code.len() {
+                            //Tail call, nothing is left to do in this frame so it can be replaced
+                            *frame = callee;
+                        }
+                        else {
+                            return self.enter(frame, dump, callee, Return::Call)
+                        }
+                    }
+                    None => frame.pc += 1
+                }
+            }
+            BuiltinFunction(index) => {
+                match get_builtin(index) {
+                    Builtin::Function(arity, strict, function) => {
+                        let len = self.stack_len();
+                        if len - frame.base - 1 >= arity {
+                            for &i in strict.iter() {
+                                let arg = match *self.get(self.stack_at(len - i - 2)) {
+                                    Application(_, arg) => arg,
+                                    _ => panic!("Expected Application")
+                                };
+                                if !self.is_whnf(arg) {
+                                    //Evaluate the argument and then retry the call
+                                    self.push(arg);
+                                    let work = match try!(self.blackhole(arg, frame, dump)) {
+                                        Some(work) => work,
+                                        None => {
+                                            self.pop();
+                                            return Ok(())
+                                        }
+                                    };
+                                    let callee = Frame { code: UNWINDCODE, pc: 0, base: len + 1, assembly_id: frame.assembly_id };
+                                    self.push(work);
+                                    return self.enter(frame, dump, callee, Return::Strict)
+                                }
+                            }
+                        }
+                        match self.take_arguments(frame.base, arity) {
+                            Some(args) => {
+                                let value = try!(function(self, args));
+                                self.truncate(args);
+                                self.push(value);
+                            }
+                            None => frame.pc += 1
+                        }
+                    }
+                    Builtin::Catch => {
+                        match self.take_arguments(frame.base, 3) {
+                            Some(args) => {
+                                //Run the action to completion so that any exception it throws is caught here
+                                //[action, handler, world] -> [handler, world, action world]
+                                let action = self.stack_at(args);
+                                let handler = self.stack_at(args + 1);
+                                let world = self.stack_at(args + 2);
+                                let node = self.alloc(Application(action, world));
+                                self.set_stack(args, handler);
+                                self.set_stack(args + 1, world);
+                                self.set_stack(args + 2, node);
+                                let callee = Frame { code: UNWINDCODE, pc: 0, base: args + 2, assembly_id: frame.assembly_id };
+                                return self.enter(frame, dump, callee, Return::Catch(args))
+                            }
+                            None => frame.pc += 1
+                        }
+                    }
+                    Builtin::Atomically => {
+                        match self.take_arguments(frame.base, 2) {
+                            Some(args) => {
+                                if !self.transaction.borrow().is_empty() {
+                                    let message = primitive::create_string(self, "Control.Concurrent.STM.atomically was nested");
+                                    return Err(Exception::error_call(self, message))
+                                }
+                                return self.start_transaction(frame, dump, args, args + 1, Return::Atomically(args))
+                            }
+                            None => frame.pc += 1
+                        }
+                    }
+                    Builtin::OrElse => {
+                        match self.take_arguments(frame.base, 3) {
+                            Some(args) => {
+                                self.transaction.borrow_mut().push(Vec::new());
+                                return self.start_transaction(frame, dump, args, args + 2, Return::OrElse(args))
+                            }
+                            None => frame.pc += 1
+                        }
+                    }
+                }
+            }
+            BlackHole(work) => {
+                let node = self.top();
+                if self.evaluated_by_other_thread(node, frame, dump) {
+                    return self.wait(ThreadState::Evaluating(node))
+                }
+                return Err(self.non_termination(work))
+            }
+            _ => frame.pc += 1
+        }
+        Ok(())
+    }
+
+    ///Marks `node` as being under evaluation and returns a copy of it which is evaluated in its place.
+    ///Throws `NonTermination` if the node is already being evaluated by the running thread and
+    ///returns `None` if the running thread has to wait for another thread to finish evaluating it
+    fn blackhole<'c>(&self, node: Node, frame: &Frame<'c>, dump: &[(Frame<'c>, Return)]) -> Result<Option<Node>, Exception> {
+        let value = self.get(node).clone();
+        if let BlackHole(work) = value {
+            if self.evaluated_by_other_thread(node, frame, dump) {
+                try!(self.wait(ThreadState::Evaluating(node)));
+                return Ok(None)
+            }
+            return Err(self.non_termination(work))
+        }
+        let work = self.alloc(value);
+        self.set(node, BlackHole(work));
+        Ok(Some(work))
+    }
+
+    ///Creates the `NonTermination` exception for entering the blackhole which was evaluating `work`,
+    ///naming the supercombinator which was being called
+    fn non_termination(&self, work: Node) -> Exception {
+        let mut node = work;
+        let name = loop {
+            node = match *self.get(node) {
+                Application(func, _) => func,
+                Indirection(next) => next,
+                Combinator(index) => break self.global(index).name.as_ref().to_string(),
+                _ => break "<unknown>".to_string()
+            };
+        };
+        Exception::non_termination(self, &name)
+    }
+
+    ///Replaces the spine of an application of `arity` arguments on the top of the stack with
+    ///the arguments in the order they are applied in and returns the index of the first argument.
+    ///If there are not enough arguments the frame is left with only the root of the application.
+    fn take_arguments(&self, base: usize, arity: usize) -> Option<usize> {
+        let len = self.stack_len();
+        if len - base - 1 < arity {
+            self.truncate(base + 1);
+            return None
+        }
+        let root = len - arity - 1;
+        let args: Vec<Node> = (0..arity)
+            .map(|i| match *self.get(self.stack_at(len - i - 2)) {
+                Application(_, arg) => arg,
+                _ => panic!("Expected Application")
+            })
+            .collect();
+        self.truncate(root);
+        for arg in args.into_iter() {
+            self.push(arg);
+        }
+        Some(root)
+    }
+
+    ///Returns whether the node is in weak head normal form
+    fn is_whnf(&self, node: Node) -> bool {
+        let mut args = 0;
+        let mut node = node;
+        loop {
+            node = match *self.get(node) {
+                Application(func, _) => {
+                    args += 1;
+                    func
+                }
+                //Evaluating to a mutable value leaves an indirection to it
+                Indirection(next) if args == 0 => next,
+                Indirection(_) => return false,
+                Combinator(index) => return self.global(index).arity > args,
+                BuiltinFunction(index) => return get_builtin(index).arity() > args,
+                BlackHole(_) => return false,
+                _ => return true
+            };
+        }
+    }
+}
+
+///If the code starting at `pc` does nothing but return the node on the top of the stack from the frame,
+///returns the index of the `Unwind` instruction which ends the frame
+fn returns_top(code: &[Instruction], pc: usize) -> Option<usize> {
+    use compiler::Instruction::*;
+    let mut pc = pc;
+    loop {
+        match code[pc] {
+            //Slide only removes nodes below the top and evaluating is done by unwinding anyway
+            Slide(_) | Eval => pc += 1,
+            Jump(to) => pc = to,
+            Update(0) => {
+                let unwind = match code[pc + 1] {
+                    Pop(_) => pc + 2,
+                    _ => pc + 1
+                };
+                return match code.get(unwind) {
+                    Some(&Unwind) if unwind + 1 == code.len() => Some(unwind),
+                    _ => None
+                }
+            }
+            _ => return None
+        }
+    }
+}
+
+static UNWINDCODE : &'static [Instruction] = &[Instruction::Unwind];
+static EVALCODE : &'static [Instruction] = &[Instruction::Eval];
+
+///A frame of instructions which are being executed
+struct Frame<'c> {
+    code: &'c [Instruction],
+    pc: usize,
+    ///The index of the first node on the stack which belongs to this frame
+    base: usize,
+    assembly_id: usize
+}
+
+///Describes how a frame on the dump receives the result of the frame above it
+#[derive(Clone, Copy)]
+enum Return {
+    ///The frame executed `Eval`, the evaluated node is below the frame's base and is updated with the result
+    Eval,
+    ///Evaluates an argument of a builtin function the same way as `Eval` and then retries the call
+    Strict,
+    ///A supercombinator was called, its result replaces the application which was unwound
+    Call,
+    ///The frame is running the action of a `catch`, the handler and the "RealWorld" are on the stack at the index
+    Catch(usize),
+    ///The frame is running a transaction, the transaction and the "RealWorld" are on the stack at the index
+    Atomically(usize),
+    ///The frame is running the first alternative of an `orElse`, the alternatives and the "RealWorld"
+    ///are on the stack at the index
+    OrElse(usize)
+}
+
+
+///Exucutes a binary primitive instruction taking two integers
+fn primitive_int<F>(vm: &VM, f: F) where F: FnOnce(isize, isize) -> Node_ {
+    let l = vm.pop();
+    let r = vm.pop();
+    let value = match (&*vm.get(l), &*vm.get(r)) {
+        (&Int(lhs), &Int(rhs)) => f(lhs, rhs),
+        (lhs, rhs) => panic!("Expected fully evaluted numbers in primitive instruction\n LHS: {:?}\nRHS: {:?} ", lhs, rhs)
+    };
+    let node = vm.alloc(value);
+    vm.push(node);
+}
+///Exucutes a binary primitive instruction taking two doubles
+fn primitive_float<F>(vm: &VM, f: F) where F: FnOnce(f64, f64) -> Node_ {
+    let l = vm.pop();
+    let r = vm.pop();
+    let value = match (&*vm.get(l), &*vm.get(r)) {
+        (&Float(lhs), &Float(rhs)) => f(lhs, rhs),
+        (lhs, rhs) => panic!("Expected fully evaluted numbers in primitive instruction\n LHS: {:?}\nRHS: {:?} ", lhs, rhs)
+    };
+    let node = vm.alloc(value);
+    vm.push(node);
+}
+///Exucutes a unary primitive instruction taking a double
+fn primitive_float_unary<F>(vm: &VM, f: F) where F: FnOnce(f64) -> Node_ {
+    let top = vm.pop();
+    let value = match *vm.get(top) {
+        Float(x) => f(x),
+        ref x => panic!("Expected fully evaluted number in primitive instruction\n {:?}", x)
+    };
+    let node = vm.alloc(value);
+    vm.push(node);
+}
+///Exucutes a unary primitive instruction taking a character
+fn primitive_char_unary<F>(vm: &VM, f: F) where F: FnOnce(char) -> Node_ {
+    let top = vm.pop();
+    let value = match *vm.get(top) {
+        Char(c) => f(c),
+        ref x => panic!("Expected fully evaluted character in primitive instruction\n {:?}", x)
+    };
+    let node = vm.alloc(value);
+    vm.push(node);
+}
+///Creates the node for `True` or `False`
+fn bool_node(b: bool) -> Node_ {
+    Constructor(if b { 0 } else { 1 }, Vec::new())
+}
+fn primitive<F>(vm: &VM, f: F) where F: FnOnce(isize, isize) -> isize {
+    primitive_int(vm, move |l, r| Int(f(l, r)))
+}
+///Exucutes an integer division or remainder which throws an `ArithException` if it fails
+fn checked_primitive<F>(vm: &VM, f: F) -> Result<(), Exception>
+    where F: FnOnce(isize, isize) -> Option<isize> {
+    let l = vm.pop();
+    let r = vm.pop();
+    let (lhs, rhs) = match (&*vm.get(l), &*vm.get(r)) {
+        (&Int(lhs), &Int(rhs)) => (lhs, rhs),
+        (lhs, rhs) => panic!("Expected fully evaluted numbers in primitive instruction\n LHS: {:?}\nRHS: {:?} ", lhs, rhs)
+    };
+    match f(lhs, rhs) {
+        Some(value) => {
+            let node = vm.alloc(Int(value));
+            vm.push(node);
+            Ok(())
+        }
+        None => Err(Exception::arith(vm, if rhs == 0 { DIVIDE_BY_ZERO } else { OVERFLOW }))
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum VMResult {
+    Int(isize),
+    Integer(Integer),
+    Double(f64),
+    Constructor(u16, Vec<VMResult>)
+}
+
+macro_rules! vm_error {
+    ($($post: ident ($typ: ty)),+) => {
+
+    #[derive(Debug)]
+    pub enum VMError {
+        Io(io::Error),
+        $($post($typ)),+
+    }
+
+    impl fmt::Display for VMError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match *self {
+                VMError::Io(ref e) => write!(f, "{}", e),
+                $(VMError::$post(ref e) => write!(f, "{}", e)),+
+            }
+        }
+    }
+
+    impl ToDiagnostics for VMError {
+        fn to_diagnostics(&self) -> Vec<Diagnostic> {
+            match *self {
+                VMError::Io(ref e) => vec![Diagnostic::error("E0003", e.to_string())],
+                $(VMError::$post(ref e) => e.to_diagnostics()),+
+            }
+        }
+    }
+
+    impl Error for VMError {
+        fn description(&self) -> &str {
+            match *self {
+                VMError::Io(ref e) => e.description(),
+                $(VMError::$post(ref e) => e.description()),+
+            }
+        }
+    }
+
+    impl From<io::Error> for VMError {
+        fn from(e: io::Error) -> Self { VMError::Io(e) }
+    }
+
+    $(impl From<$typ> for VMError {
+        fn from(e: $typ) -> Self { VMError::$post(e) }
+    })+
+    }
+}
+///An exception which was thrown but never caught while executing a program
+#[derive(PartialEq, Debug)]
+pub enum RuntimeError {
+    ErrorCall(String),
+    ArithException(String),
+    PatternMatchFail(String),
+    AsyncException(String),
+    ///A value depended on itself, holds the name of the supercombinator which was being evaluated
+    NonTermination(String),
+    IOException(String),
+    ///`exitWith` was called with the exit code, 0 for `ExitSuccess`
+    Exit(i32),
+    ///Every thread was blocked so the thread waiting on an `MVar` could never be woken
+    BlockedIndefinitelyOnMVar,
+    ///Every thread was blocked so the transaction which called `retry` could never be run again
+    BlockedIndefinitelyOnSTM
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_diagnostics(f, self)
+    }
+}
+
+impl ToDiagnostics for RuntimeError {
+    fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        let (code, exception, message) = match *self {
+            RuntimeError::ErrorCall(ref message) => ("E0301", "ErrorCall", message.clone()),
+            RuntimeError::ArithException(ref message) => ("E0302", "ArithException", message.clone()),
+            RuntimeError::PatternMatchFail(ref message) => ("E0303", "PatternMatchFail", message.clone()),
+            RuntimeError::AsyncException(ref message) => ("E0304", "AsyncException", message.clone()),
+            RuntimeError::NonTermination(ref name) => ("E0305", "NonTermination", format!("<<loop>> in `{}`", name)),
+            RuntimeError::IOException(ref message) => ("E0306", "IOException", message.clone()),
+            RuntimeError::Exit(0) => ("E0307", "ExitCode", "ExitSuccess".to_string()),
+            RuntimeError::Exit(code) => ("E0307", "ExitCode", format!("ExitFailure {}", code)),
+            RuntimeError::BlockedIndefinitelyOnMVar => ("E0308", "BlockedIndefinitelyOnMVar",
+                "thread blocked indefinitely in an MVar operation".to_string()),
+            RuntimeError::BlockedIndefinitelyOnSTM => ("E0309", "BlockedIndefinitelyOnSTM",
+                "thread blocked indefinitely in an STM transaction".to_string())
+        };
+        vec![Diagnostic::error(code, format!("uncaught exception: {}", message))
+            .with_note(format!("the exception has type `{}`", exception))]
+    }
+}
+
+impl Error for RuntimeError {
+    fn description(&self) -> &str { "uncaught exception" }
+}
+
+vm_error! {
+    ParseError(Errors<::parser::ParseError>),
+    RenamerError(::renamer::RenamerError),
+    TypeError(::typecheck::TypeError),
+    RuntimeError(RuntimeError),
+    LimitExceeded(LimitExceeded)
+}
+
+impl From<::parser::ParseError> for VMError {
+    fn from(e: ::parser::ParseError) -> Self { VMError::ParseError(Errors::from(e)) }
+}
+
+#[allow(dead_code)]
+fn compile_iter<T : Iterator<Item=char>>(iterator: T) -> Result<Assembly, VMError> {
+    compile_parser(Parser::new(iterator))
+}
+
+fn compile_parser<T : Iterator<Item=char>>(mut parser: Parser<T>) -> Result<Assembly, VMError> {
+    let module = try!(parser.module());
+    let mut module = try!(rename_module(module));
+    PrecedenceVisitor::new().visit_module(&mut module);
+    
+    let mut typer = TypeEnvironment::new();
+    try!(typer.typecheck_module(&mut module));
+    let core_module = do_lambda_lift(translate_module(module));
+    
+    let mut compiler = Compiler::new();
+    Ok(compiler.compile_module(&core_module))
+}
+
+///Compiles a single file
+pub fn compile_file(filename: &str) -> Result<Assembly, VMError> {
+    let path = &Path::new(filename);
+    let mut file = try!(File::open(path));
+    let mut contents = ::std::string::String::new();
+    try!(file.read_to_string(&mut contents));
+    let file = add_file(filename, &contents);
+    compile_parser(Parser::with_file(contents.chars(), file))
+}
+
+///Returns whether the type in question is an IO action
+pub fn is_io(typ: &Type<Name>) -> bool {
+    match *typ {
+        Type::Application(ref lhs, _) => 
+            match **lhs {
+                Type::Constructor(ref op) => op.name.as_ref() == "IO",
+                _ => false
+            },
+        _ => false
+    }
+}
+
+
+fn extract_result(vm: &VM, node: Node) -> Option<VMResult> {
+    let node = vm.get(node).clone();
+    match node {
+        Constructor(tag, fields) => {
+            let mut result = Vec::new();
+            for field in fields.iter() {
+                match extract_result(vm, *field) {
+                    Some(x) => result.push(x),
+                    None => return None
+                }
+            }
+            Some(VMResult::Constructor(tag, result))
+        }
+        Int(i) => Some(VMResult::Int(i)),
+        Integer(i) => Some(VMResult::Integer(i)),
+        Float(i) => Some(VMResult::Double(i)),
+        Indirection(node) => extract_result(vm, node),
+        x => {
+            println!("Can't extract result {:?}", x);
+            None
+        }
+    }
+}
+
+pub fn execute_main_string(module: &str) -> Result<Option<VMResult>, VMError> {
+    let assemblies = try!(compile_string(module));
+    execute_main_module_(assemblies, VMConfig::default())
+}
+
+///Takes a module with a main function and compiles it and all its imported modules
+///and then executes the main function
+pub fn execute_main_module(modulename: &str) -> Result<Option<VMResult>, VMError> {
+    let assemblies = try!(compile_module(modulename));
+    execute_main_module_(assemblies, VMConfig::default())
+}
+
+///Runs the main function of a module as a program in a VM configured by `config`.
+///The module is typechecked with `options`. The value of `main` is printed unless it is an IO action.
+///Returns the code which the process should exit with.
+pub fn run_program(modulename: &str, config: VMConfig, options: TypecheckOptions) -> Result<i32, VMError> {
+    let assemblies = try!(compile_module_with(modulename, options));
+    let mut vm = VM::with_config(config);
+    for assembly in assemblies.into_iter() {
+        vm.add_assembly(assembly);
+    }
+    let main = match vm.find_global("main") {
+        Some(main) => main,
+        None => {
+            println!("Error running module {}", modulename);
+            return Ok(1)
+        }
+    };
+    //A `main` which is not an IO action is printed using its `Show` instance if it has one
+    let shown = if is_io(&vm.global(main).typ.value) {
+        None
+    }
+    else {
+        compile_show_global(&vm, main, options)
+    };
+    let result = match shown {
+        Some(shown) => vm.evaluate_string(shown)
+            .map(|string| println!("{}", string)),
+        None => vm.run_main(main)
+            .map(|result| {
+                if !is_io(&vm.global(main).typ.value) {
+                    match extract_result(&vm, result) {
+                        Some(x) => println!("{:?}", x),
+                        None => println!("Error running module {}", modulename)
+                    }
+                }
+            })
+    };
+    match result {
+        Ok(()) => Ok(0),
+        Err(VMError::RuntimeError(RuntimeError::Exit(code))) => Ok(code),
+        Err(err) => Err(err)
+    }
+}
+
+///Compiles `show` applied to the global at `index`, returning `None` if it does not have a `Show` instance
+fn compile_show_global(vm: &VM, index: usize, options: TypecheckOptions) -> Option<Assembly> {
+    let identifier = ::module::TypedExpr::new(::module::Expr::Identifier(vm.global(index).name));
+    let assemblies: Vec<&Assembly> = vm.assembly.iter().collect();
+    compile_show_expr(&assemblies, identifier, options)
+}
+
+fn execute_main_module_(assemblies: Vec<Assembly>, config: VMConfig) -> Result<Option<VMResult>, VMError> {
+    let mut vm = VM::with_config(config);
+    for assembly in assemblies.into_iter() {
+        vm.add_assembly(assembly);
+    }
+    match vm.find_global("main") {
+        Some(main) => {
+            assert!(vm.global(main).arity == 0);
+            let result = try!(vm.run_main(main));
+            Ok(extract_result(&vm, result))
+        }
+        None => Ok(None)
+    }
+}
+
+//We mirror the naming scheme from Haskell here which is camelCase
+#[allow(non_snake_case)]
+mod primitive {
+
+    use std::io;
+    use std::fs::OpenOptions;
+    use std::io::BufReader;
+    use std::time::{Duration, Instant};
+    use vm::{VM, Node, Exception, IoHandle, ThreadState, OVERFLOW, DIVIDE_BY_ZERO};
+    use vm::Node_::{Application, Constructor, BuiltinFunction, Char, Int, Float, Indirection, MutVar, Array, IntArray, MVar, TVar};
+    use vm::Node_;
+    use integer::Integer;
+    use std::cmp::Ordering;
+
+    pub fn get_builtin(i: usize) -> Builtin {
+        match i {
+            0 => Builtin::Function(1, &[], error),
+            1 => Builtin::Function(2, &[0], seq),
+            2 => Builtin::Function(3, &[], io_bind),
+            3 => Builtin::Function(2, &[], io_return),
+            4 => Builtin::Function(2, &[0, 1], compare_tags),
+            5 => Builtin::Function(1, &[], throw),
+            6 => Builtin::Catch,
+            7 => Builtin::Function(2, &[0], evaluate),
+            8 => Builtin::Function(1, &[], pattern_match_fail),
+            9 => Builtin::Function(3, &[1], openFile),
+            10 => Builtin::Function(2, &[], hClose),
+            11 => Builtin::Function(2, &[], hFlush),
+            12 => Builtin::Function(3, &[], hPutStr),
+            13 => Builtin::Function(2, &[], hGetLine),
+            14 => Builtin::Function(2, &[], hGetContents),
+            15 => Builtin::Function(2, &[], hIsEOF),
+            16 => Builtin::Function(1, &[], getArgs),
+            17 => Builtin::Function(1, &[], getProgName),
+            18 => Builtin::Function(2, &[], lookupEnv),
+            19 => Builtin::Function(2, &[], newIORef),
+            20 => Builtin::Function(2, &[0], readIORef),
+            21 => Builtin::Function(3, &[0], writeIORef),
+            22 => Builtin::Function(3, &[0], newArray),
+            23 => Builtin::Function(3, &[0, 1], readArray),
+            24 => Builtin::Function(4, &[0, 1], writeArray),
+            25 => Builtin::Function(3, &[0, 1], newIntArray),
+            26 => Builtin::Function(3, &[0, 1], readIntArray),
+            27 => Builtin::Function(4, &[0, 1, 2], writeIntArray),
+            28 => Builtin::Function(1, &[], unsafePerformIO),
+            29 => Builtin::Function(2, &[], forkIO),
+            30 => Builtin::Function(1, &[], yield_),
+            31 => Builtin::Function(2, &[0], threadDelay),
+            32 => Builtin::Function(1, &[], myThreadId),
+            33 => Builtin::Function(3, &[0], throwTo),
+            34 => Builtin::Function(1, &[], newEmptyMVar),
+            TAKE_MVAR => Builtin::Function(2, &[0], takeMVar),
+            PUT_MVAR => Builtin::Function(3, &[0], putMVar),
+            READ_MVAR => Builtin::Function(2, &[0], readMVar),
+            38 => Builtin::Function(2, &[0], tryTakeMVar),
+            39 => Builtin::Function(3, &[0], tryPutMVar),
+            40 => Builtin::Function(2, &[], newTVar),
+            41 => Builtin::Function(2, &[0], readTVar),
+            42 => Builtin::Function(3, &[0], writeTVar),
+            43 => Builtin::Function(1, &[], retrySTM),
+            44 => Builtin::Atomically,
+            45 => Builtin::OrElse,
+            46 => Builtin::Function(1, &[0], intToInteger),
+            47 => Builtin::Function(1, &[0], integerToInt),
+            48 => Builtin::Function(1, &[0], integerToDouble),
+            49 => Builtin::Function(1, &[0], doubleToInteger),
+            50 => Builtin::Function(2, &[0, 1], integerAdd),
+            51 => Builtin::Function(2, &[0, 1], integerSubtract),
+            52 => Builtin::Function(2, &[0, 1], integerMultiply),
+            53 => Builtin::Function(2, &[0, 1], integerQuot),
+            54 => Builtin::Function(2, &[0, 1], integerRem),
+            55 => Builtin::Function(2, &[0, 1], integerDiv),
+            56 => Builtin::Function(2, &[0, 1], integerMod),
+            57 => Builtin::Function(2, &[0, 1], integerCompare),
+            58 => Builtin::Function(1, &[0], showInteger),
+            59 => Builtin::Function(2, &[0, 1], narrowInt),
+            60 => Builtin::Function(2, &[0, 1], narrowWord),
+            61 => Builtin::Function(2, &[0, 1], wordCompare),
+            62 => Builtin::Function(2, &[0, 1], wordQuot),
+            63 => Builtin::Function(2, &[0, 1], wordRem),
+            64 => Builtin::Function(1, &[0], wordToInteger),
+            65 => Builtin::Function(1, &[0], showDouble),
+            66 => Builtin::Function(1, &[0], showFloat),
+            67 => Builtin::Function(2, &[0, 1], rationalToDouble),
+            68 => Builtin::Function(1, &[0], decodeDouble),
+            69 => Builtin::Function(1, &[0], decodeFloat),
+            70 => Builtin::Function(2, &[0, 1], encodeDouble),
+            PASS => Builtin::Function(2, &[0], pass),
+            _ => panic!("undefined primitive")
+        }
+    }
+
+    ///The index of the builtin which `io_bind` uses to pass the result of an action on.
+    ///It is not one of the builtins which can be named in Haskell so it is placed after all of them
+    const PASS: usize = 71;
+    ///The builtins which block on an `MVar`, they are called again when the thread is woken
+    const TAKE_MVAR: usize = 35;
+    const PUT_MVAR: usize = 36;
+    const READ_MVAR: usize = 37;
+
+    ///A builtin function which is passed the index of the frame holding its arguments
+    pub type BuiltinFun = extern "Rust" fn (&VM, usize) -> Result<Node, Exception>;
+
+    pub enum Builtin {
+        ///A function with the given arity.
+        ///The arguments at the listed indexes are evaluated to WHNF by the VM before the function is called.
+        Function(usize, &'static [usize], BuiltinFun),
+        ///`primCatch` which needs the VM to install an exception handler
+        Catch,
+        ///`primAtomically` which runs a transaction and commits it
+        Atomically,
+        ///`primOrElse` which runs its second alternative if the first one calls `retry`
+        OrElse
+    }
+
+    impl Builtin {
+        pub fn arity(&self) -> usize {
+            match *self {
+                Builtin::Function(arity, _, _) => arity,
+                Builtin::Catch | Builtin::OrElse => 3,
+                Builtin::Atomically => 2
+            }
+        }
+    }
+
+    fn error(vm: &VM, args: usize) -> Result<Node, Exception> {
+        Err(Exception::error_call(vm, vm.stack_at(args)))
+    }
+    fn pattern_match_fail(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let message = try!(vm.deepseq(vm.stack_at(args)));
+        let message = get_string(vm, message);
+        Err(Exception::pattern_match_fail(vm, &message))
+    }
+    fn seq(vm: &VM, args: usize) -> Result<Node, Exception> {
+        Ok(vm.stack_at(args + 1))
+    }
+    fn io_bind(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //IO a -> (a -> IO b) -> IO b
+        //IO a = (RealWorld -> (a, RealWorld)
+        //((RealWorld -> (a, RealWorld)) -> (a -> RealWorld -> (b, RealWorld)) -> RealWorld -> (b, RealWorld)
+        //             0                                      1                        2
+        //(a, RealWorld)
+        vm.reserve(4);
+        let aw = vm.alloc(Application(vm.stack_at(args), vm.stack_at(args + 2)));
+        let p = vm.alloc(BuiltinFunction(PASS));
+        let pass_aw = vm.alloc(Application(p, aw));
+        Ok(vm.alloc(Application(pass_aw, vm.stack_at(args + 1))))
+    }
+    fn pass(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //(a, RealWorld) -> (a -> RealWorld -> (b, RealWorld)) -> (b, RealWorld)
+        let (a, rw) = match *vm.get(vm.stack_at(args)) {
+            Constructor(_, ref args) => (args[0], args[1]),
+            _ => panic!("pass exepected constructor")
+        };
+        vm.reserve(2);
+        let f = vm.alloc(Application(vm.stack_at(args + 1), a));
+        Ok(vm.alloc(Application(f, rw)))
+    }
+    fn io_return(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //a -> RealWorld -> (a, RealWorld)
+        Ok(vm.alloc(Constructor(0, vec!(vm.stack_at(args), vm.stack_at(args + 1)))))
+    }
+    fn throw(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //SomeException -> a
+        Err(Exception(vm.stack_at(args)))
+    }
+    fn evaluate(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //a -> RealWorld -> (a, RealWorld)
+        Ok(vm.alloc(Constructor(0, vec!(vm.stack_at(args), vm.stack_at(args + 1)))))
+    }
+    ///Returns the `(value, RealWorld)` pair of an IO action whose "RealWorld" argument is at `world`
+    fn io_result(vm: &VM, value: Node, world: usize) -> Node {
+        vm.alloc(Constructor(0, vec!(value, vm.stack_at(world))))
+    }
+    fn unit(vm: &VM, world: usize) -> Node {
+        let unit = vm.alloc(Constructor(0, vec!()));
+        io_result(vm, unit, world)
+    }
+    ///Runs `f` on the handle which the Haskell `Handle` at `handle` refers to.
+    ///Errors are thrown as an `IOException` which names the builtin which failed
+    fn with_handle<T, F>(vm: &VM, handle: usize, name: &str, f: F) -> Result<T, Exception>
+        where F: FnOnce(&mut IoHandle) -> io::Result<T> {
+        let handle = try!(vm.deepseq(vm.stack_at(handle)));
+        let index = match *vm.get(handle) {
+            Constructor(_, ref fields) => match *vm.get(fields[0]) {
+                Int(index) => index as usize,
+                _ => panic!("Expected Int in Handle")
+            },
+            _ => panic!("Expected Handle")
+        };
+        let result = match vm.handles.borrow_mut().get_mut(index) {
+            Some(handle) => f(handle),
+            None => Err(IoHandle::illegal("invalid handle"))
+        };
+        result.map_err(|err| Exception::io_error(vm, &err, &format!("{}: {}", name, err)))
+    }
+    fn openFile(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //FilePath -> IOMode -> RealWorld -> (Handle, RealWorld)
+        let path = try!(vm.deepseq(vm.stack_at(args)));
+        let path = get_string(vm, path);
+        let mode = match *vm.get(vm.stack_at(args + 1)) {
+            Constructor(tag, _) => tag,
+            _ => panic!("Expected IOMode")
+        };
+        let mut options = OpenOptions::new();
+        match mode {
+            0 => options.read(true),
+            1 => options.write(true).create(true).truncate(true),
+            2 => options.append(true).create(true),
+            _ => options.read(true).write(true).create(true)
+        };
+        let file = match options.open(&path) {
+            Ok(file) => file,
+            Err(err) => return Err(Exception::io_error(vm, &err, &format!("{}: openFile: {}", path, err)))
+        };
+        let index = {
+            let mut handles = vm.handles.borrow_mut();
+            handles.push(IoHandle::File { file: BufReader::new(file), readable: mode == 0 || mode == 3, writable: mode != 0 });
+            handles.len() - 1
+        };
+        let index = vm.alloc(Int(index as isize));
+        let handle = vm.alloc(Constructor(0, vec!(index)));
+        Ok(io_result(vm, handle, args + 2))
+    }
+    fn hClose(vm: &VM, args: usize) -> Result<Node, Exception> {
+        try!(with_handle(vm, args, "hClose", |handle| {
+            *handle = IoHandle::Closed;
+            Ok(())
+        }));
+        Ok(unit(vm, args + 1))
+    }
+    fn hFlush(vm: &VM, args: usize) -> Result<Node, Exception> {
+        try!(with_handle(vm, args, "hFlush", |handle| handle.flush()));
+        Ok(unit(vm, args + 1))
+    }
+    fn hPutStr(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let s = try!(vm.deepseq(vm.stack_at(args + 1)));
+        let s = get_string(vm, s);
+        try!(with_handle(vm, args, "hPutStr", |handle| handle.write(&s)));
+        Ok(unit(vm, args + 2))
+    }
+    fn hGetLine(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let line = try!(with_handle(vm, args, "hGetLine", |handle| {
+            match try!(handle.read_line()) {
+                Some(line) => Ok(line),
+                None => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "end of file"))
+            }
+        }));
+        let line = create_string(vm, &line);
+        Ok(io_result(vm, line, args + 1))
+    }
+    fn hGetContents(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let contents = try!(with_handle(vm, args, "hGetContents", |handle| handle.read_to_string()));
+        let contents = create_string(vm, &contents);
+        Ok(io_result(vm, contents, args + 1))
+    }
+    fn hIsEOF(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let eof = try!(with_handle(vm, args, "hIsEOF", |handle| handle.is_eof()));
+        //True is the first constructor of Bool
+        let eof = vm.alloc(Constructor(if eof { 0 } else { 1 }, vec!()));
+        Ok(io_result(vm, eof, args + 1))
+    }
+    fn getArgs(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //The list is not rooted while it is built so every cell is reserved up front
+        let cells = vm.config.args.iter()
+            .fold(2, |cells, arg| cells + 2 * arg.chars().count() + 2);
+        vm.reserve(cells);
+        let mut list = vm.alloc(Constructor(0, vec!()));
+        for arg in vm.config.args.iter().rev() {
+            let arg = create_string(vm, arg);
+            list = vm.alloc(Constructor(1, vec!(arg, list)));
+        }
+        Ok(io_result(vm, list, args))
+    }
+    fn getProgName(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let name = create_string(vm, &vm.config.prog_name);
+        Ok(io_result(vm, name, args))
+    }
+    fn lookupEnv(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let name = try!(vm.deepseq(vm.stack_at(args)));
+        let name = get_string(vm, name);
+        //Just is the first constructor of Maybe
+        let value = match ::std::env::var(&name) {
+            Ok(value) => {
+                let value = create_string(vm, &value);
+                vm.alloc(Constructor(0, vec!(value)))
+            }
+            Err(_) => vm.alloc(Constructor(1, vec!()))
+        };
+        Ok(io_result(vm, value, args + 1))
+    }
+    fn newIORef(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //a -> RealWorld -> (IORef a, RealWorld)
+        let reference = vm.alloc(MutVar(vm.stack_at(args)));
+        Ok(io_result(vm, reference, args + 1))
+    }
+    fn readIORef(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let value = match *vm.get(mutable(vm, args)) {
+            MutVar(value) => value,
+            _ => panic!("Expected IORef")
+        };
+        Ok(io_result(vm, value, args + 1))
+    }
+    fn writeIORef(vm: &VM, args: usize) -> Result<Node, Exception> {
+        vm.set(mutable(vm, args), MutVar(vm.stack_at(args + 1)));
+        Ok(unit(vm, args + 2))
+    }
+    fn newArray(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //Int -> a -> RealWorld -> (MutableArray a, RealWorld)
+        let size = try!(array_size(vm, args));
+        let array = vm.alloc(Array(vec![vm.stack_at(args + 1); size]));
+        Ok(io_result(vm, array, args + 2))
+    }
+    fn readArray(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let array = mutable(vm, args);
+        let index = try!(array_index(vm, array, args + 1));
+        let value = match *vm.get(array) {
+            Array(ref nodes) => nodes[index],
+            _ => panic!("Expected MutableArray")
+        };
+        Ok(io_result(vm, value, args + 2))
+    }
+    fn writeArray(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let array = mutable(vm, args);
+        let index = try!(array_index(vm, array, args + 1));
+        match *vm.get_mut(array) {
+            Array(ref mut nodes) => nodes[index] = vm.stack_at(args + 2),
+            _ => panic!("Expected MutableArray")
+        }
+        Ok(unit(vm, args + 3))
+    }
+    fn newIntArray(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //Int -> Int -> RealWorld -> (MutableIntArray, RealWorld)
+        let size = try!(array_size(vm, args));
+        let array = vm.alloc(IntArray(vec![int(vm, args + 1); size]));
+        Ok(io_result(vm, array, args + 2))
+    }
+    fn readIntArray(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let array = mutable(vm, args);
+        let index = try!(array_index(vm, array, args + 1));
+        let value = match *vm.get(array) {
+            IntArray(ref values) => values[index],
+            _ => panic!("Expected MutableIntArray")
+        };
+        let value = vm.alloc(Int(value));
+        Ok(io_result(vm, value, args + 2))
+    }
+    fn writeIntArray(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let array = mutable(vm, args);
+        let index = try!(array_index(vm, array, args + 1));
+        let value = int(vm, args + 2);
+        match *vm.get_mut(array) {
+            IntArray(ref mut values) => values[index] = value,
+            _ => panic!("Expected MutableIntArray")
+        }
+        Ok(unit(vm, args + 3))
+    }
+    fn unsafePerformIO(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //IO a -> a
+        //The "RealWorld" can be any value
+        let world = vm.alloc(Int(42));
+        let action = vm.alloc(Application(vm.stack_at(args), world));
+        let result = try!(vm.eval(action));
+        match *vm.get(result) {
+            Constructor(_, ref fields) => Ok(fields[0]),
+            _ => panic!("Expected (a, RealWorld)")
+        }
+    }
+    fn forkIO(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //IO () -> RealWorld -> (Int, RealWorld)
+        let id = vm.fork(vm.stack_at(args));
+        let id = vm.alloc(Int(id as isize));
+        Ok(io_result(vm, id, args + 1))
+    }
+    fn yield_(vm: &VM, args: usize) -> Result<Node, Exception> {
+        vm.yield_thread();
+        Ok(unit(vm, args))
+    }
+    fn threadDelay(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //Int -> RealWorld -> ((), RealWorld)
+        let micros = int(vm, args);
+        if micros <= 0 {
+            vm.yield_thread();
+        }
+        else {
+            let delay = Duration::from_micros(micros as u64);
+            if vm.can_switch() {
+                //The thread continues after the call once it is woken
+                try!(vm.wait(ThreadState::Sleeping(Instant::now() + delay)));
+            }
+            else {
+                ::std::thread::sleep(delay);
+            }
+        }
+        Ok(unit(vm, args + 1))
+    }
+    fn myThreadId(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let id = vm.alloc(Int(vm.thread_id() as isize));
+        Ok(io_result(vm, id, args))
+    }
+    fn throwTo(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //Int -> SomeException -> RealWorld -> ((), RealWorld)
+        try!(vm.throw_to(int(vm, args) as usize, vm.stack_at(args + 1)));
+        Ok(unit(vm, args + 2))
+    }
+    fn newEmptyMVar(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let mvar = vm.alloc(MVar(None));
+        Ok(io_result(vm, mvar, args))
+    }
+    fn takeMVar(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //MVar a -> RealWorld -> (a, RealWorld)
+        let mvar = mutable(vm, args);
+        match mvar_value(vm, mvar) {
+            Some(value) => {
+                vm.set(mvar, MVar(None));
+                Ok(io_result(vm, value, args + 1))
+            }
+            None => {
+                try!(vm.wait(ThreadState::Taking(mvar)));
+                Ok(retry(vm, TAKE_MVAR, args, 2))
+            }
+        }
+    }
+    fn putMVar(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //MVar a -> a -> RealWorld -> ((), RealWorld)
+        let mvar = mutable(vm, args);
+        match mvar_value(vm, mvar) {
+            Some(_) => {
+                try!(vm.wait(ThreadState::Putting(mvar)));
+                Ok(retry(vm, PUT_MVAR, args, 3))
+            }
+            None => {
+                vm.set(mvar, MVar(Some(vm.stack_at(args + 1))));
+                Ok(unit(vm, args + 2))
+            }
+        }
+    }
+    fn readMVar(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let mvar = mutable(vm, args);
+        match mvar_value(vm, mvar) {
+            Some(value) => Ok(io_result(vm, value, args + 1)),
+            None => {
+                try!(vm.wait(ThreadState::Taking(mvar)));
+                Ok(retry(vm, READ_MVAR, args, 2))
+            }
+        }
+    }
+    fn tryTakeMVar(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //MVar a -> RealWorld -> (Maybe a, RealWorld)
+        let mvar = mutable(vm, args);
+        let result = match mvar_value(vm, mvar) {
+            Some(value) => {
+                vm.set(mvar, MVar(None));
+                vm.alloc(Constructor(0, vec!(value)))
+            }
+            None => vm.alloc(Constructor(1, vec!()))
+        };
+        Ok(io_result(vm, result, args + 1))
+    }
+    fn tryPutMVar(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //MVar a -> a -> RealWorld -> (Bool, RealWorld)
+        let mvar = mutable(vm, args);
+        let result = match mvar_value(vm, mvar) {
+            Some(_) => vm.alloc(Constructor(1, vec!())),
+            None => {
+                vm.set(mvar, MVar(Some(vm.stack_at(args + 1))));
+                vm.alloc(Constructor(0, vec!()))
+            }
+        };
+        Ok(io_result(vm, result, args + 2))
+    }
+    fn newTVar(vm: &VM, args: usize) -> Result<Node, Exception> {
+        //a -> RealWorld -> (TVar a, RealWorld)
+        let tvar = vm.alloc(TVar(vm.stack_at(args), 0));
+        Ok(io_result(vm, tvar, args + 1))
+    }
+    fn readTVar(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let value = vm.read_tvar(mutable(vm, args));
+        Ok(io_result(vm, value, args + 1))
+    }
+    fn writeTVar(vm: &VM, args: usize) -> Result<Node, Exception> {
+        vm.write_tvar(mutable(vm, args), vm.stack_at(args + 1));
+        Ok(unit(vm, args + 2))
+    }
+    fn retrySTM(vm: &VM, _: usize) -> Result<Node, Exception> {
+        //The exception is only seen by `orElse` and `atomically` so its value is not used
+        vm.retrying.set(true);
+        Err(Exception(vm.alloc(Constructor(0, vec!()))))
+    }
+    fn mvar_value(vm: &VM, mvar: Node) -> Option<Node> {
+        match *vm.get(mvar) {
+            MVar(value) => value,
+            _ => panic!("Expected MVar")
+        }
+    }
+    ///Creates the call of the builtin `index` with the `arity` arguments at `args` so that the call is
+    ///made again when the thread which blocked in it runs next
+    fn retry(vm: &VM, index: usize, args: usize, arity: usize) -> Node {
+        vm.reserve(arity + 1);
+        let mut call = vm.alloc(BuiltinFunction(index));
+        for i in 0..arity {
+            call = vm.alloc(Application(call, vm.stack_at(args + i)));
+        }
+        call
+    }
+    ///Returns the mutable node which the evaluated argument at `arg` refers to
+    fn mutable(vm: &VM, arg: usize) -> Node {
+        let mut node = vm.stack_at(arg);
+        while let Indirection(next) = *vm.get(node) {
+            node = next;
+        }
+        node
+    }
+    ///Returns the evaluated `Int` argument at `arg`
+    fn int(vm: &VM, arg: usize) -> isize {
+        match *vm.get(mutable(vm, arg)) {
+            Int(i) => i,
+            _ => panic!("Expected Int")
+        }
+    }
+    fn array_size(vm: &VM, arg: usize) -> Result<usize, Exception> {
+        let size = int(vm, arg);
+        if size < 0 {
+            let message = create_string(vm, &format!("negative array size {}", size));
+            return Err(Exception::error_call(vm, message))
+        }
+        Ok(size as usize)
+    }
+    ///Checks that the `Int` argument at `arg` is an index into `array`
+    fn array_index(vm: &VM, array: Node, arg: usize) -> Result<usize, Exception> {
+        let len = match *vm.get(array) {
+            Array(ref nodes) => nodes.len(),
+            IntArray(ref values) => values.len(),
+            _ => panic!("Expected an array")
+        };
+        let index = int(vm, arg);
+        if index < 0 || index as usize >= len {
+            let message = create_string(vm, &format!("array index {} out of range for an array of {} elements", index, len));
+            return Err(Exception::error_call(vm, message))
+        }
+        Ok(index as usize)
+    }
+
+    ///Reads a fully evaluated string
+    pub fn get_string(vm: &VM, node: Node) -> String {
+        let mut buffer = String::new();
+        let mut node = node;
+        loop {
+            let (c, tail) = match *vm.get(node) {
+                Constructor(_, ref args) if args.len() == 2 => (args[0], args[1]),
+                Constructor(..) => break,
+                _ => panic!("Unevaluated list")
+            };
+            match *vm.get(c) {
+                Char(c) => buffer.push(c),
+                _ => panic!("Unevaluated char")
+            }
+            node = tail;
+        }
+        buffer
+    }
+    ///Allocates a string in the heap of the VM
+    pub fn create_string(vm: &VM, s: &str) -> Node {
+        let chars: Vec<char> = s.chars().collect();
+        //Each cons cell is only reachable from the next one until the whole list is built
+        vm.reserve(2 * chars.len() + 1);
+        let mut list = vm.alloc(Constructor(0, Vec::new()));
+        for &c in chars.iter().rev() {
+            let c = vm.alloc(Char(c));
+            list = vm.alloc(Constructor(1, vec!(c, list)));
+        }
+        list
+    }
+    ///Returns the evaluated `Integer` argument at `arg`
+    fn integer(vm: &VM, arg: usize) -> Integer {
+        match *vm.get(mutable(vm, arg)) {
+            Node_::Integer(ref i) => i.clone(),
+            _ => panic!("Expected Integer")
+        }
+    }
+    ///Allocates the `Ordering` constructor for `ordering`
+    fn ordering(vm: &VM, ordering: Ordering) -> Node {
+        let tag = match ordering {
+            Ordering::Less => 0,
+            Ordering::Equal => 1,
+            Ordering::Greater => 2
+        };
+        vm.alloc(Constructor(tag, Vec::new()))
+    }
+    ///Applies `f` to the two `Integer` arguments at `args`, throwing `DivideByZero` if `f` fails
+    fn integer_binop<F>(vm: &VM, args: usize, f: F) -> Result<Node, Exception>
+        where F: FnOnce(&Integer, &Integer) -> Option<Integer> {
+        let result = {
+            let (lhs, rhs) = (vm.get(mutable(vm, args)), vm.get(mutable(vm, args + 1)));
+            match (&*lhs, &*rhs) {
+                (&Node_::Integer(ref lhs), &Node_::Integer(ref rhs)) => f(lhs, rhs),
+                _ => panic!("Expected Integer")
+            }
+        };
+        match result {
+            Some(result) => Ok(vm.alloc(Node_::Integer(result))),
+            None => Err(Exception::arith(vm, DIVIDE_BY_ZERO))
+        }
+    }
+    fn intToInteger(vm: &VM, args: usize) -> Result<Node, Exception> {
+        Ok(vm.alloc(Node_::Integer(Integer::from_isize(int(vm, args)))))
+    }
+    fn integerToInt(vm: &VM, args: usize) -> Result<Node, Exception> {
+        Ok(vm.alloc(Int(integer(vm, args).to_isize_wrapping())))
+    }
+    fn integerToDouble(vm: &VM, args: usize) -> Result<Node, Exception> {
+        Ok(vm.alloc(Float(integer(vm, args).to_f64())))
+    }
+    fn doubleToInteger(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let value = match *vm.get(mutable(vm, args)) {
+            Float(f) => Integer::from_f64(f),
+            _ => panic!("Expected Double")
+        };
+        match value {
+            Some(value) => Ok(vm.alloc(Node_::Integer(value))),
+            None => Err(Exception::arith(vm, OVERFLOW))
+        }
+    }
+    fn integerAdd(vm: &VM, args: usize) -> Result<Node, Exception> {
+        integer_binop(vm, args, |l, r| Some(l + r))
+    }
+    fn integerSubtract(vm: &VM, args: usize) -> Result<Node, Exception> {
+        integer_binop(vm, args, |l, r| Some(l - r))
+    }
+    fn integerMultiply(vm: &VM, args: usize) -> Result<Node, Exception> {
+        integer_binop(vm, args, |l, r| Some(l * r))
+    }
+    fn integerQuot(vm: &VM, args: usize) -> Result<Node, Exception> {
+        integer_binop(vm, args, |l, r| l.quot_rem(r).map(|x| x.0))
+    }
+    fn integerRem(vm: &VM, args: usize) -> Result<Node, Exception> {
+        integer_binop(vm, args, |l, r| l.quot_rem(r).map(|x| x.1))
+    }
+    fn integerDiv(vm: &VM, args: usize) -> Result<Node, Exception> {
+        integer_binop(vm, args, |l, r| l.div_mod(r).map(|x| x.0))
+    }
+    fn integerMod(vm: &VM, args: usize) -> Result<Node, Exception> {
+        integer_binop(vm, args, |l, r| l.div_mod(r).map(|x| x.1))
+    }
+    fn integerCompare(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let result = integer(vm, args).cmp(&integer(vm, args + 1));
+        Ok(ordering(vm, result))
+    }
+    fn showInteger(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let s = integer(vm, args).to_string();
+        Ok(create_string(vm, &s))
+    }
+    ///Sign extends the lowest `bits` bits of an `Int`, the wrapping done by the `IntN` types
+    fn narrowInt(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let shift = 64 - int(vm, args) as u32;
+        Ok(vm.alloc(Int((int(vm, args + 1) as i64).wrapping_shl(shift).wrapping_shr(shift) as isize)))
+    }
+    ///Keeps the lowest `bits` bits of an `Int`, the wrapping done by the `WordN` types
+    fn narrowWord(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let shift = 64 - int(vm, args) as u32;
+        Ok(vm.alloc(Int((int(vm, args + 1) as u64).wrapping_shl(shift).wrapping_shr(shift) as isize)))
+    }
+    ///Compares two `Int`s as if they were unsigned
+    fn wordCompare(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let result = (int(vm, args) as u64).cmp(&(int(vm, args + 1) as u64));
+        Ok(ordering(vm, result))
+    }
+    fn word_binop<F>(vm: &VM, args: usize, f: F) -> Result<Node, Exception>
+        where F: FnOnce(u64, u64) -> Option<u64> {
+        match f(int(vm, args) as u64, int(vm, args + 1) as u64) {
+            Some(result) => Ok(vm.alloc(Int(result as isize))),
+            None => Err(Exception::arith(vm, DIVIDE_BY_ZERO))
+        }
+    }
+    fn wordQuot(vm: &VM, args: usize) -> Result<Node, Exception> {
+        word_binop(vm, args, |l, r| l.checked_div(r))
+    }
+    fn wordRem(vm: &VM, args: usize) -> Result<Node, Exception> {
+        word_binop(vm, args, |l, r| l.checked_rem(r))
+    }
+    fn wordToInteger(vm: &VM, args: usize) -> Result<Node, Exception> {
+        Ok(vm.alloc(Node_::Integer(Integer::from_unsigned(int(vm, args)))))
+    }
+    fn double(vm: &VM, args: usize) -> f64 {
+        match *vm.get(mutable(vm, args)) {
+            Float(f) => f,
+            _ => panic!("Expected Double")
+        }
+    }
+    fn showDouble(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let value = double(vm, args);
+        Ok(create_string(vm, &show_floating(value, &format!("{:e}", value))))
+    }
+    ///Shows a `Float`, which is stored as a `Double`, using only as many digits as a `Float` needs
+    fn showFloat(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let value = double(vm, args);
+        Ok(create_string(vm, &show_floating(value, &format!("{:e}", value as f32))))
+    }
+    fn rationalToDouble(vm: &VM, args: usize) -> Result<Node, Exception> {
+        Ok(vm.alloc(Float(Integer::ratio_to_f64(&integer(vm, args), &integer(vm, args + 1)))))
+    }
+    fn decodeDouble(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let bits = double(vm, args).to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as isize;
+        let fraction = bits & ((1 << 52) - 1);
+        let (mantissa, exponent) = if exponent == 0 { (fraction, -1074) } else { (fraction | (1 << 52), exponent - 1075) };
+        Ok(decoded(vm, bits >> 63 != 0, mantissa, exponent, 53))
+    }
+    ///Decodes a `Float`, which is stored as a `Double`, into a mantissa of 24 bits
+    fn decodeFloat(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let bits = (double(vm, args) as f32).to_bits() as u64;
+        let exponent = ((bits >> 23) & 0xff) as isize;
+        let fraction = bits & ((1 << 23) - 1);
+        let (mantissa, exponent) = if exponent == 0 { (fraction, -149) } else { (fraction | (1 << 23), exponent - 150) };
+        Ok(decoded(vm, bits >> 31 != 0, mantissa, exponent, 24))
+    }
+    ///Creates the `(Integer, Int)` result of `decodeFloat`, normalizing denormal numbers so that the
+    ///mantissa always has `digits` bits unless it is zero
+    fn decoded(vm: &VM, negative: bool, mut mantissa: u64, mut exponent: isize, digits: u32) -> Node {
+        if mantissa == 0 {
+            exponent = 0;
+        }
+        while mantissa != 0 && mantissa < 1 << (digits - 1) {
+            mantissa <<= 1;
+            exponent -= 1;
+        }
+        let mantissa = Integer::from_unsigned(mantissa as isize);
+        //The mantissa is not reachable from a root until the pair refers to it
+        vm.reserve(3);
+        let mantissa = vm.alloc(Node_::Integer(if negative { -&mantissa } else { mantissa }));
+        let exponent = vm.alloc(Int(exponent));
+        vm.alloc(Constructor(0, vec![mantissa, exponent]))
+    }
+    fn encodeDouble(vm: &VM, args: usize) -> Result<Node, Exception> {
+        let mantissa = integer(vm, args);
+        let exponent = int(vm, args + 1);
+        let one = Integer::from_isize(1);
+        let value = if exponent >= 0 {
+            Integer::ratio_to_f64(&mantissa.shift_left(exponent as usize), &one)
+        }
+        else {
+            Integer::ratio_to_f64(&mantissa, &one.shift_left((-exponent) as usize))
+        };
+        Ok(vm.alloc(Float(value)))
+    }
+    ///Formats a number the way `show` does in Haskell, given the shortest representation of the
+    ///number in scientific notation (as written by `{:e}`)
+    pub fn show_floating(value: f64, repr: &str) -> String {
+        if value.is_nan() {
+            return "NaN".to_string();
+        }
+        if value.is_infinite() {
+            return (if value < 0.0 { "-Infinity" } else { "Infinity" }).to_string();
+        }
+        let (sign, repr) = if repr.starts_with('-') { ("-", &repr[1..]) } else { ("", repr) };
+        let e = repr.find('e').expect("Expected an exponent");
+        let exponent: i32 = repr[e + 1..].parse().unwrap();
+        let digits: String = repr[..e].chars().filter(|c| *c != '.').collect();
+        let magnitude = value.abs();
+        let body = if magnitude == 0.0 {
+            "0.0".to_string()
+        }
+        else if magnitude >= 0.1 && magnitude < 10000000.0 {
+            if exponent < 0 {
+                format!("0.{}", digits)
+            }
+            else {
+                let point = exponent as usize + 1;
+                if digits.len() > point {
+                    format!("{}.{}", &digits[..point], &digits[point..])
+                }
+                else {
+                    format!("{}{}.0", digits, "0".repeat(point - digits.len()))
+                }
+            }
+        }
+        else {
+            let fraction = if digits.len() > 1 { &digits[1..] } else { "0" };
+            format!("{}.{}e{}", &digits[..1], fraction, exponent)
+        };
+        format!("{}{}", sign, body)
+    }
+    ///Compares the tags of two constructors, returning an Ordering
+    fn compare_tags(vm: &VM, args: usize) -> Result<Node, Exception> {
+        fn tag(vm: &VM, node: Node) -> Option<u16> {
+            match *vm.get(node) {
+                Constructor(tag, _) => Some(tag),
+                _ => None
+            }
+        }
+        let lhs = tag(vm, vm.stack_at(args));
+        let rhs = tag(vm, vm.stack_at(args + 1));
+        let result = match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => lhs.cmp(&rhs),
+            (_, _) => Ordering::Equal
+        };
+        Ok(ordering(vm, result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+use typecheck::TypeEnvironment;
+use compiler::{compile_with_type_env, compile_string, compile_string_with};
+use typecheck::TypecheckOptions;
+use vm::{VM, VMConfig, VMError, RuntimeError, LimitExceeded, Heap, compile_file, compile_iter, compile_show_global, execute_main_module, execute_main_string, extract_result, VMResult};
+use vm::Node_;
+use vm::VMResult::{Int, Double, Constructor};
+use interner::*;
+use std::time::Duration;
+
+fn execute_main<T : Iterator<Item=char>>(iterator: T) -> Option<VMResult> {
+    let mut vm = VM::new();
+    vm.add_assembly(compile_iter(iterator).unwrap());
+    let x = vm.assembly.iter().flat_map(|a| a.super_combinators.iter()).find(|sc| sc.name.name == intern("main"));
+    match x {
+        Some(sc) => {
+            assert!(sc.arity == 0);
+            let result = vm.evaluate(&*sc.instructions, sc.assembly_id).unwrap();
+            extract_result(&vm, result)
+        }
+        None => None
+    }
+}
+
+#[test]
+fn test_primitive()
+{
+    assert_eq!(execute_main("main = primIntAdd 10 5".chars()), Some(VMResult::Int(15)));
+    assert_eq!(execute_main("main = primIntSubtract 7 (primIntMultiply 2 3)".chars()), Some(VMResult::Int(1)));
+    assert_eq!(execute_main("main = primIntDivide 10 (primIntRemainder 6 4)".chars()), Some(VMResult::Int(5)));
+    assert_eq!(execute_main("main = primDoubleDivide 3. 2.".chars()), Some(VMResult::Double(1.5)));
+    let s = 
+r"data Bool = True | False
+main = primIntLT 1 2";
+    assert_eq!(execute_main(s.chars()), Some(VMResult::Constructor(0, Vec::new())));
+}
+
+#[test]
+fn test_function()
+{
+    let module = 
+r"mult2 x = primIntMultiply x 2
+
+main = mult2 10";
+    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(20)));
+
+    let module2 = 
+r"mult2 x = primIntMultiply x 2
+
+add x y = primIntAdd y x
+
+main = add 3 (mult2 10)";
+    assert_eq!(execute_main(module2.chars()), Some(VMResult::Int(23)));
+}
+#[test]
+fn test_case()
+{
+    let module = 
+r"mult2 x = primIntMultiply x 2
+
+main = case [mult2 123, 0] of
+    x:xs -> x
+    [] -> 10";
+    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(246)));
+}
+
+#[test]
+fn test_nested_case() {
+    let module = 
+r"mult2 x = primIntMultiply x 2
+
+main = case [mult2 123, 0] of
+    246:xs -> primIntAdd 0 246
+    [] -> 10";
+    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(246)));
+}
+
+#[test]
+fn test_nested_case2() {
+    let module = 
+r"mult2 x = primIntMultiply x 2
+
+main = case [mult2 123, 0] of
+    246:[] -> primIntAdd 0 246
+    x:xs -> 20
+    [] -> 10";
+    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(20)));
+}
+#[test]
+fn local_function() {
+    let module = 
+r"main =
+    let
+        f x y =
+            let
+                g x = primIntAdd x y
+            in g (primIntAdd 1 x)
+    in f (primIntAdd 2 0) (primIntAdd 3 0)";
+    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(6)));
+}
+
+#[test]
+fn local_function_free_variables_order() {
+    let module =
+r"test a b =
+    let f x = primIntAdd (primIntSubtract a b) x
+    in f 0
+main = test 10 3";
+    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(7)));
+}
+
+#[test]
+fn test_data_types()
+{
+    let module = 
+r"data Bool = True | False
+
+test = False
+
+main = case test of
+    False -> primIntAdd 0 0
+    True -> primIntAdd 1 0";
+    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(0)));
+}
+
+#[test]
+fn test_typeclasses_known_types()
+{
+    let module = 
+r"data Bool = True | False
+
+class Test a where
+    test :: a -> Int
+
+instance Test Int where
+    test x = x
+
+instance Test Bool where
+    test x = case x of
+        True -> 1
+        False -> 0
+
+
+main = primIntSubtract (test (primIntAdd 5 0)) (test True)";
+    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(4)));
+}
+
+#[test]
+fn test_typeclasses_unknown()
+{
+    let module = 
+r"data Bool = True | False
+
+class Test a where
+    test :: a -> Int
+
+instance Test Int where
+    test x = x
+
+instance Test Bool where
+    test x = case x of
+        True -> 1
+        False -> 0
+
+testAdd y = primIntAdd (test (primIntAdd 5 0)) (test y)
+
+main = testAdd True";
+    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(6)));
+}
+
+#[test]
+fn test_run_prelude() {
+    let prelude = compile_file("Prelude.hs").unwrap();
+    let assembly = {
+        let mut type_env = TypeEnvironment::new();
+
+        compile_with_type_env(&mut type_env, &[&prelude],
+r"add x y = primIntAdd x y
+main = foldl add 0 [1,2,3,4]").unwrap()
+    };
+
+    let mut vm = VM::new();
+    vm.add_assembly(prelude);
+    vm.add_assembly(assembly);
+    let x = vm.assembly.iter().flat_map(|a| a.super_combinators.iter()).find(|sc| sc.name.name == intern("main"));
+    let result = match x {
+        Some(sc) => {
+            assert!(sc.arity == 0);
+            let result = vm.evaluate(&*sc.instructions, sc.assembly_id).unwrap();
+            extract_result(&vm, result)
+        }
+        None => None
+    };
+    assert_eq!(result, Some(VMResult::Int(10)));
+}
+
+#[test]
+fn instance_super_class() {
+    let prelude = compile_file("Prelude.hs").unwrap();
+
+    let assembly = {
+        let mut type_env = TypeEnvironment::new();
+        compile_with_type_env(&mut type_env, &[&prelude], "main = [primIntAdd 0 1,2,3,4] == [1,2,3]").unwrap()
+    };
+
+    let mut vm = VM::new();
+    vm.add_assembly(prelude);
+    vm.add_assembly(assembly);
+    let x = vm.assembly.iter().flat_map(|a| a.super_combinators.iter()).find(|sc| sc.name.name == intern("main"));
+    let result = match x {
+        Some(sc) => {
+            assert!(sc.arity == 0);
+            let result = vm.evaluate(&*sc.instructions, sc.assembly_id).unwrap();
+            extract_result(&vm, result)
+        }
+        None => None
+    };
+    assert_eq!(result, Some(VMResult::Constructor(1, Vec::new())));
+}
+
+#[test]
+fn monad_do() {
+    let prelude = compile_file("Prelude.hs").unwrap();
+
+    let assembly = {
+        let mut type_env = TypeEnvironment::new();
+        compile_with_type_env(&mut type_env, &[&prelude],
+"
+test :: Maybe Int -> Maybe Int -> Maybe Int
+test x y = do
+    x1 <- x
+    y
+    return (x1 + 1)
+
+main = test (Just 4) (Just 6)").unwrap()
+    };
+
+    let mut vm = VM::new();
+    vm.add_assembly(prelude);
+    vm.add_assembly(assembly);
+    let x = vm.assembly.iter().flat_map(|a| a.super_combinators.iter()).find(|sc| sc.name.name == intern("main"));
+    let result = match x {
+        Some(sc) => {
+            assert!(sc.arity == 0);
+            let result = vm.evaluate(&*sc.instructions, sc.assembly_id).unwrap();
+            extract_result(&vm, result)
+        }
+        None => None
+    };
+    assert_eq!(result, Some(VMResult::Constructor(0, vec!(VMResult::Int(5)))));
+}
+
+#[test]
+fn import() {
+    let result = execute_main_module("Test")
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Int(6)));
+}
+
+#[test]
+fn pattern_bind() {
+    let result = execute_main_string(
+r"
+import Prelude
+
+test :: [Bool] -> Bool
+test (True:[]) = False
+test (True:y:ys) = y
+test [] = False
+
+main = test [True, True]
+")
+    .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, Vec::new())));
+}
+#[test]
+fn pattern_guards() {
+    let result = execute_main_string(
+r"
+import Prelude
+
+test :: Int -> [a] -> Int
+test 2 _ = 2
+test x []
+    | primIntLT x 0 = primIntSubtract 0 1
+    | primIntGT x 0 = 1
+test x _ = x
+
+main = (test 2 [], test 100 [], test 100 ['c'])
+
+")
+    .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, vec!(VMResult::Int(2), VMResult::Int(1), VMResult::Int(100)))));
+}
+
+#[test]
+fn pattern_guards_nested() {
+    let result = execute_main_string(
+r"
+import Prelude
+
+test :: Int -> [Int] -> Int
+test 2 _ = 2
+test x (0:[])
+    | primIntLT x 0 = primIntSubtract 0 1
+    | primIntGT x 0 = 1
+test x _ = x
+
+main = (test 2 [], test 100 [0], test 100 [0, 123])
+
+")
+    .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, vec!(VMResult::Int(2), VMResult::Int(1), VMResult::Int(100)))));
+}
+#[test]
+fn test_class_default_function()
+{
+    let module = 
+r"data Bool = True | False
+
+class Test a where
+    test :: a -> Int
+    test _ = 42
+    test2 :: Int
+
+instance Test Int where
+    test x = x
+    test2 = 0
+
+instance Test Bool where
+    test2 = 2
+
+main = (test True, test (1 :: Int))";
+    assert_eq!(execute_main(module.chars()), Some(VMResult::Constructor(0, vec![VMResult::Int(42), VMResult::Int(1)])));
+}
+
+#[test]
+fn use_super_class() {
+    let result = execute_main_string(
+r"
+import Prelude
+
+test x y = (x == y) || (x < y)
+main = (test (0 :: Int) 2) && not (test (1 :: Int) 0)")
+        .unwrap_or_else(|err| panic!("{:?}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, Vec::new())));
+}
+#[test]
+fn implement_class() {
+    let result = execute_main_string(
+r"
+import Prelude
+data AB = A | B
+
+instance Eq AB where
+    (==) A A = True
+    (==) B B = True
+    (==) _ _ = False
+
+test x y = x == y
+
+main = A == B && test A A")
+        .unwrap_or_else(|err| panic!("{:?}", err));
+    assert_eq!(result, Some(VMResult::Constructor(1, Vec::new())));
+}
+
+#[test]
+fn deriving_eq() {
+    let result = execute_main_string(
+r"
+import Prelude
+data Test = A Int | B
+    deriving(Eq)
+
+main = A 0 == A 2 || A 0 == B
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(1, Vec::new())));
+}
+#[test]
+fn deriving_ord() {
+    let result = execute_main_string(
+r"
+import Prelude
+data Test = A Int | B
+    deriving(Eq, Ord)
+
+main = compare (A 0) (A 2) == LT && compare B (A 123) == GT
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, Vec::new())));
+}
+
+#[test]
+fn instance_eq_list() {
+    let result = execute_main_string(
+r"
+import Prelude
+test x y = x == y
+main = test [1 :: Int] [3]
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(1, Vec::new())));
+}
+#[test]
+fn build_dictionary() {
+    //Test that the compiler can generate code to build a dictionary at runtime
+    let result = execute_main_string(
+r"
+import Prelude
+test :: Eq a => a -> a -> Bool
+test x y = [x] == [y]
+main = test [1 :: Int] [3]
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(1, Vec::new())));
+}
+
+#[test]
+fn if_else() {
+    let result = execute_main_string(
+r"
+import Prelude
+
+main = let
+        x = 123 :: Int
+    in if x < 0
+        then x
+        else 1
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Int(1)));
+}
+
+#[test]
+fn newtype() {
+    let result = execute_main_string(
+r"
+import Prelude
+newtype Even = Even Int
+
+makeEven :: Int -> Maybe Even
+makeEven i
+    | i `div` 2 /= (i - 1) `div` 2 = Just (Even i)
+    | otherwise = Nothing
+
+main = makeEven (100 * 3)
+").unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(result, Some(VMResult::Constructor(0, vec![VMResult::Int(300)])));
+}
+
+#[test]
+fn where_bindings() {
+    let result = execute_main_string(
+r"
+import Prelude
+
+main = case list of
+        [] -> 123
+        x:xs
+            | y < 10 -> 0
+            | otherwise -> y
+            where
+            y = x + 10
+    where
+        list = [1::Int]
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Int(11)));
+}
+
+
+fn runtime_error(module: &str) -> RuntimeError {
+    match execute_main_string(module) {
+        Err(VMError::RuntimeError(err)) => err,
+        Err(err) => panic!("{}", err),
+        Ok(result) => panic!("Expected an exception, got {:?}", result)
+    }
+}
+
+///Compiles `module` and evaluates `show main`
+fn show_main(module: &str) -> String {
+    let mut vm = VM::new();
+    for assembly in compile_string(module).unwrap_or_else(|err| panic!("{}", err)) {
+        vm.add_assembly(assembly);
+    }
+    let main = vm.find_global("main").expect("Expected main function");
+    let shown = compile_show_global(&vm, main, TypecheckOptions::default()).expect("Expected a Show instance for main");
+    vm.evaluate_string(shown).unwrap_or_else(|err| panic!("{}", err))
+}
+
+#[test]
+fn show_builtin_types() {
+    let show = |expr: &str| show_main(&format!("import Prelude\nmain = {}\n", expr));
+    assert_eq!(show("(1 :: Int, 0 - 2 :: Int, 2.5 :: Double, 'a', ())"), "(1,-2,2.5,'a',())");
+    assert_eq!(show("Just (0 - 3 :: Int)"), "Just (-3)");
+    assert_eq!(show("[Just 'a', Nothing]"), "[Just 'a',Nothing]");
+    assert_eq!(show("(Left 1 :: Either Int Bool, Right True :: Either Int Bool)"), "(Left 1,Right True)");
+    assert_eq!(show("Just (Left 2 :: Either Int Bool)"), "Just (Left 2)");
+    assert_eq!(show("[LT, EQ, GT]"), "[LT,EQ,GT]");
+    assert_eq!(show("(1, 2, 3, 4, 5, 6, 7)"), "(1,2,3,4,5,6,7)");
+    assert_eq!(show("([] :: [Int], [[1, 2], [3]])"), "([],[[1,2],[3]])");
+}
+
+#[test]
+fn show_escapes_characters() {
+    let show = |expr: &str| show_main(&format!("import Prelude\nmain = {}\n", expr));
+    assert_eq!(show(r#""tab\there \"quoted\" \\""#), r#""tab\there \"quoted\" \\""#);
+    assert_eq!(show(r#"['\'', '"', '\n']"#), r#""'\"\n""#);
+    assert_eq!(show(r#"('\'', '"', '\\')"#), r#"('\'','"','\\')"#);
+    assert_eq!(show(r#""'\0\a""#), r#""'\NUL\a""#);
+    assert_eq!(show("\"\u{e9}1\""), r#""\233\&1""#);
+}
+
+#[test]
+fn uncaught_error_call() {
+    let err = runtime_error(
+r#"
+import Prelude
+
+main :: Int
+main = error ("boom" ++ "!")
+"#);
+    assert_eq!(err, RuntimeError::ErrorCall("boom!".to_string()));
+    assert!(err.to_string().starts_with("error[E0301]: uncaught exception: boom!"), "{}", err);
+}
+
+#[test]
+fn divide_by_zero() {
+    let err = runtime_error(
+r"
+import Prelude
+
+main = 1 `div` (0 :: Int)
+");
+    assert_eq!(err, RuntimeError::ArithException("divide by zero".to_string()));
+    let err = runtime_error(
+r"
+import Prelude
+
+main = 1 `rem` (0 :: Int)
+");
+    assert_eq!(err, RuntimeError::ArithException("divide by zero".to_string()));
+}
+
+#[test]
+fn pattern_match_fail() {
+    let err = runtime_error(
+r"
+import Prelude
+
+f :: Maybe Int -> Int
+f (Just x) = x
+
+main = f Nothing
+");
+    assert_eq!(err, RuntimeError::PatternMatchFail("non-exhaustive patterns in case".to_string()));
+    let err = runtime_error(
+r"
+import Prelude
+
+f :: Int -> Int
+f x
+    | x > 10 = x
+
+main = f 3
+");
+    assert_eq!(err, RuntimeError::PatternMatchFail("non-exhaustive guards".to_string()));
+}
+
+#[test]
+fn catch_exceptions() {
+    let result = execute_main_string(
+r#"
+import Prelude
+
+handler :: ArithException -> IO Int
+handler e = case e of
+    DivideByZero -> return 10
+    Overflow -> return 20
+
+main :: IO Int
+main = do
+    x <- catch (evaluate (1 `div` (0 :: Int))) handler
+    y <- catch (evaluate (error "oops")) (\e -> case e of
+        ErrorCall msg -> return (length msg))
+    z <- catch (throw DivideByZero) (\e -> return (length (show (e :: SomeException))))
+    return (x + y + z)
+"#).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Int(10 + 4 + 14)));
+}
+
+#[test]
+fn catch_only_matching_exceptions() {
+    let err = runtime_error(
+r"
+import Prelude
+
+main :: IO Int
+main = catch (evaluate (1 `div` (0 :: Int))) (\e -> case e of
+    ErrorCall msg -> return 0)
+");
+    assert_eq!(err, RuntimeError::ArithException("divide by zero".to_string()));
+}
+
+#[test]
+fn bracket_releases_resource() {
+    let result = execute_main_string(
+r#"
+import Prelude
+
+main :: IO Int
+main = catch
+    (bracket (return 1) (\x -> putStrLn "released") (\x -> evaluate (x `div` (0 :: Int))))
+    (\e -> case e of
+        DivideByZero -> return 2
+        Overflow -> return 3)
+"#).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Int(2)));
+}
+
+#[test]
+fn heap_collect_compacts_live_cells() {
+    let mut heap = Heap::new(16);
+    let garbage = heap.alloc(Node_::Int(1));
+    let value = heap.alloc(Node_::Int(2));
+    let list = heap.alloc(Node_::Constructor(1, vec![value, value]));
+    heap.collect(&[&[list]]);
+    assert_eq!(heap.cells.len(), 2);
+    assert_eq!(heap.stats.freed, 1);
+    match *heap.get(list) {
+        Node_::Constructor(1, ref args) => assert_eq!(*args, vec![value, value]),
+        ref node => panic!("Unexpected {:?}", node)
+    }
+    match *heap.get(value) {
+        Node_::Int(2) => (),
+        ref node => panic!("Unexpected {:?}", node)
+    }
+    //The handle of the freed cell is reused
+    let reused = heap.alloc(Node_::Int(3));
+    assert_eq!(reused, garbage);
+    match *heap.get(reused) {
+        Node_::Int(3) => (),
+        ref node => panic!("Unexpected {:?}", node)
+    }
+}
+
+fn execute_with_config(config: VMConfig, module: &str) -> (Result<Option<VMResult>, VMError>, VM) {
+    let mut vm = VM::with_config(config);
+    for assembly in compile_string(module).unwrap_or_else(|err| panic!("{}", err)) {
+        vm.add_assembly(assembly);
+    }
+    let main = vm.find_global("main").unwrap();
+    let result = vm.run_main(main).map(|result| extract_result(&vm, result));
+    (result, vm)
+}
+
+fn execute_with_heap_size(heap_size: usize, module: &str) -> (Option<VMResult>, VM) {
+    let config = VMConfig { heap_size: heap_size, .. VMConfig::default() };
+    let (result, vm) = execute_with_config(config, module);
+    (result.unwrap_or_else(|err| panic!("{}", err)), vm)
+}
+
+///Runs `module` while collecting garbage before every allocation
+fn execute_under_gc_stress(module: &str) -> Option<VMResult> {
+    let config = VMConfig { gc_stress: true, .. VMConfig::default() };
+    let (result, _) = execute_with_config(config, module);
+    result.unwrap_or_else(|err| panic!("{}", err))
+}
+
+#[test]
+fn garbage_collection_keeps_reachable_nodes() {
+    let module =
+r"
+import Prelude
+
+upto :: Int -> Int -> [Int]
+upto n m = if n > m then [] else n : upto (n + 1) m
+
+main :: Int
+main = sum (map (\x -> x * 2) (upto 1 100)) + length (filter (\x -> x `rem` 3 == 0) (upto 1 100))
+";
+    let (expected, _) = execute_with_heap_size(super::DEFAULT_HEAP_SIZE, module);
+    let (result, vm) = execute_with_heap_size(32, module);
+    assert_eq!(expected, Some(Int(10100 + 33)));
+    assert_eq!(result, expected);
+    assert!(vm.gc_stats().collections > 0);
+    assert_eq!(execute_under_gc_stress(module), expected);
+
+    let (result, vm) = execute_with_heap_size(32,
+r"
+import Prelude
+
+upto :: Int -> Int -> [Int]
+upto n m = if n > m then [] else n : upto (n + 1) m
+
+main :: [Int]
+main = upto 1 2 ++ [sum (upto 1 100)]
+");
+    assert_eq!(result, Some(Constructor(1, vec![Int(1), Constructor(1, vec![Int(2),
+        Constructor(1, vec![Int(5050), Constructor(0, vec![])])])])));
+    assert!(vm.gc_stats().collections > 0);
+}
+
+#[test]
+fn garbage_collection_keeps_io_error_type() {
+    let result = execute_under_gc_stress(
+r#"
+import Prelude
+
+handler :: IOException -> IO Bool
+handler e = return (isDoesNotExistError e)
+
+main :: IO Bool
+main = catch (readFile "this/file/does/not/exist" >> return False) handler
+"#);
+    assert_eq!(result, Some(Constructor(0, vec![])));
+}
+
+#[test]
+fn garbage_collection_frees_unreachable_nodes() {
+    let (result, vm) = execute_with_heap_size(super::DEFAULT_HEAP_SIZE,
+r"
+import Prelude
+
+main :: Int
+main = case [1, 2, 3] of
+    a:b:c:_ -> a + b + c
+");
+    assert_eq!(result, Some(Int(6)));
+    //Nothing is reachable once main has returned
+    vm.collect_garbage();
+    let stats = vm.gc_stats();
+    assert_eq!(stats.live, 0);
+    assert_eq!(stats.freed, stats.allocated);
+}
+
+#[test]
+fn deep_evaluation_does_not_overflow() {
+    let result = execute_main_string(
+r"
+import Prelude
+
+upto :: Int -> Int -> [Int]
+upto n m = if n > m then [] else n : upto (n + 1) m
+
+count :: Int -> Int -> IO Int
+count acc n = if n == 0
+    then return acc
+    else evaluate (acc + n) >>= \acc2 -> count acc2 (n - 1)
+
+main :: IO Int
+main = do
+    x <- count 0 10000
+    return (x + sum (upto 1 10000) + length (upto 1 10000))
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(50005000 + 50005000 + 10000)));
+}
+
+fn execute_with_max_depth(max_depth: usize, module: &str) -> Result<Option<VMResult>, RuntimeError> {
+    let config = VMConfig { max_depth: max_depth, .. VMConfig::default() };
+    match execute_with_config(config, module).0 {
+        Ok(result) => Ok(result),
+        Err(VMError::RuntimeError(err)) => Err(err),
+        Err(err) => panic!("{}", err)
+    }
+}
+
+#[test]
+fn stack_overflow_exception() {
+    let err = execute_with_max_depth(1000,
+r"
+import Prelude
+
+f :: Int -> Int
+f n = 1 + f (n + 1)
+
+main = f 0
+").unwrap_err();
+    assert_eq!(err, RuntimeError::AsyncException("stack overflow".to_string()));
+    assert!(err.to_string().starts_with("error[E0304]: uncaught exception: stack overflow"), "{}", err);
+
+    let result = execute_with_max_depth(1000,
+r"
+import Prelude
+
+f :: Int -> Int
+f n = 1 + f (n + 1)
+
+main :: IO Int
+main = catch (evaluate (f 0)) (\e -> case e of
+    StackOverflow -> return 1
+    _ -> return 2)
+");
+    assert_eq!(result, Ok(Some(Int(1))));
+}
+
+#[test]
+fn tail_recursive_loop_runs_in_bounded_memory() {
+    let (result, vm) = execute_with_heap_size(1000,
+r"
+import Prelude
+
+loop :: Int -> Int -> Int
+loop acc n = if n == 0 then acc else seq acc (loop (acc + n) (n - 1))
+
+main :: Int
+main = loop 0 10000
+");
+    assert_eq!(result, Some(Int(50005000)));
+    let stats = vm.gc_stats();
+    assert!(stats.collections > 0);
+    assert!(stats.max_live < 1000, "{:?}", stats);
+}
+
+#[test]
+fn recursive_let() {
+    let result = execute_main_string(
+r"
+import Prelude
+
+main :: Int
+main =
+    let
+        ones = 1 : twos
+        twos = 2 : ones
+    in case ones of
+        x : xs -> case xs of
+            y : ys -> case ys of
+                z : _ -> x + y + z
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(4)));
+}
+
+#[test]
+fn self_dependent_value_throws_non_termination() {
+    let err = runtime_error(
+r"
+import Prelude
+
+main :: Int
+main = let x = x + 1 in x
+");
+    match err {
+        RuntimeError::NonTermination(_) => (),
+        _ => panic!("Expected NonTermination, got {:?}", err)
     }
-    fn seq<'a>(vm: &'a VM, stack: &[Node<'a>]) -> Node<'a> {
-        eval(vm, stack[0].clone());
-        stack[1].clone()
+    assert!(err.to_string().starts_with("error[E0305]: uncaught exception: <<loop>>"), "{}", err);
+
+    let result = execute_main_string(
+r"
+import Prelude
+
+handler :: NonTermination -> IO Int
+handler e = return 1
+
+main :: IO Int
+main = catch (evaluate (let x = x + 1 in x)) handler
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(1)));
+}
+
+#[test]
+fn thunk_is_restored_after_exception() {
+    //The thunk must throw the same exception again instead of being reported as a loop
+    let result = execute_main_string(
+r#"
+import Prelude
+
+handler :: ErrorCall -> IO Int
+handler e = case e of
+    ErrorCall msg -> return (length msg)
+
+main :: IO Int
+main = do
+    let x = (error "oops" :: Int)
+    a <- catch (evaluate (x + 1)) handler
+    b <- catch (evaluate (x + 2)) handler
+    return (a + b)
+"#).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(8)));
+}
+
+static INFINITE_LOOPS: &'static str =
+r"
+import Prelude
+
+loop :: Int -> Int
+loop n = loop (n + 1)
+
+grow :: Int -> [Int] -> Int
+grow n xs = grow (n + 1) (n : xs)
+
+handler :: SomeException -> IO Int
+handler e = return 0
+
+main :: IO Int
+main = catch (evaluate (loop 0)) handler
+
+heap :: Int
+heap = grow 0 []
+
+terminates :: Int
+terminates = 1 + 2
+";
+
+fn run_with_config(config: VMConfig, name: &str) -> Result<Option<VMResult>, VMError> {
+    let mut vm = VM::with_config(config);
+    for assembly in compile_string(INFINITE_LOOPS).unwrap_or_else(|err| panic!("{}", err)) {
+        vm.add_assembly(assembly);
     }
-    fn io_bind<'a>(_vm: &'a VM, stack: &[Node<'a>]) -> Node<'a> {
-        //IO a -> (a -> IO b) -> IO b
-        //IO a = (RealWorld -> (a, RealWorld)
-        //((RealWorld -> (a, RealWorld)) -> (a -> RealWorld -> (b, RealWorld)) -> RealWorld -> (b, RealWorld)
-        //             0                                      1                        2 
-        //(a, RealWorld)
-        let aw = Node::new(Application(stack[0].clone(), stack[2].clone()));
-        let p = Node::new(BuiltinFunction(2, pass));
-        Node::new(Application(Node::new(Application(p, aw)), stack[1].clone()))
+    let global = vm.find_global(name).unwrap();
+    vm.run_main(global).map(|result| extract_result(&vm, result))
+}
+
+#[test]
+fn reduction_limit_can_not_be_caught() {
+    let config = VMConfig { max_reductions: Some(10000), .. VMConfig::default() };
+    match run_with_config(config, "main") {
+        Err(VMError::LimitExceeded(LimitExceeded::Reductions(10000))) => (),
+        result => panic!("Expected the reduction limit to be exceeded, got {:?}", result)
     }
-    fn pass<'a>(vm: &'a VM, stack: &[Node<'a>]) -> Node<'a> {
-        //(a, RealWorld) -> (a -> RealWorld -> (b, RealWorld)) -> (b, RealWorld)
-        eval(vm, stack[0].clone());
-        let aw = stack[0].borrow();
-        let (a, rw) = match *aw {
-            Constructor(_, ref args) => (&args[0], &args[1]),
-            _ => panic!("pass exepected constructor")
-        };
-        Node::new(Application(Node::new(Application(stack[1].clone(), a.clone())), rw.clone()))
+}
+
+#[test]
+fn heap_and_time_limits() {
+    let config = VMConfig { max_heap_cells: Some(10000), .. VMConfig::default() };
+    match run_with_config(config, "heap") {
+        Err(VMError::LimitExceeded(LimitExceeded::HeapCells(10000))) => (),
+        result => panic!("Expected the heap limit to be exceeded, got {:?}", result)
     }
-    fn io_return<'a>(_vm: &'a VM, stack: &[Node<'a>]) -> Node<'a> {
-        //a -> RealWorld -> (a, RealWorld)
-        Node::new(Constructor(0, vec!(stack[0].clone(), stack[1].clone())))
-    }
-    fn readFile<'a>(vm: &'a VM, stack: &[Node<'a>]) -> Node<'a> {
-        let mut temp = Vec::new();
-        temp.push(stack[0].clone());
-        let node_filename = vm.deepseq(temp, 123);
-        let filename = get_string(&node_filename);
-        let mut file = match File::open(&filename) {
-            Ok(f) => f,
-            Err(err) => panic!("error: readFile -> {:?}", err)
-        };
-        let mut s = ::std::string::String::new();
-        let (begin, _end) = match file.read_to_string(&mut s) {
-            Ok(_) => create_string(&s),
-            Err(err) => panic!("error: readFile -> {:?}", err)
-        };
-        //Return (String, RealWorld)
-        Node::new(Constructor(0, vec!(begin, stack[1].clone())))
-    }
-
-    fn putStrLn<'a>(vm: &'a VM, stack: &[Node<'a>]) -> Node<'a> {
-        let mut temp = Vec::new();
-        temp.push(stack[0].clone());
-        let msg_node = vm.deepseq(temp, 123);
-        let msg = get_string(&msg_node);
-        println!("{:?}", msg);
-        Node::new(Constructor(0, vec!(Node::new(Constructor(0, vec!())), stack[1].clone())))
-    }
-    fn get_string<'a>(node: &Node_<'a>) -> String {
-        fn get_string_<'a>(buffer: &mut String, node: &Node_<'a>) {
-            match *node {
-                Constructor(_, ref args) => {
-                    if args.len() == 2 {
-                        match *args[0].borrow() {
-                            Char(c) => buffer.push(c),
-                            _ => panic!("Unevaluated char")
-                        }
-                        get_string_(buffer, &*args[1].borrow());
-                    }
-                }
-                _ => panic!("Unevaluated list")
-            }
-        }
-        let mut buffer = String::new();
-        get_string_(&mut buffer, node);
-        buffer
+    let config = VMConfig { max_time: Some(Duration::from_millis(50)), .. VMConfig::default() };
+    match run_with_config(config, "main") {
+        Err(VMError::LimitExceeded(LimitExceeded::Time(_))) => (),
+        result => panic!("Expected the time limit to be exceeded, got {:?}", result)
     }
-    fn create_string<'a>(s: &str) -> (Node<'a>, Node<'a>) {
-        let mut node = Node::new(Constructor(0, vec!()));
-        let first = node.clone();
-        for c in s.chars() {
-            let temp = match *node.borrow_mut() {
-                Constructor(ref mut tag, ref mut args) => {
-                    *tag = 1;
-                    args.push(Node::new(Char(c)));
-                    args.push(Node::new(Constructor(0, Vec::new())));
-                    args[1].clone()
-                }
-                _ => panic!()
-            };
-            node = temp;
-        }
-        (first, node)
+}
+
+#[test]
+fn vm_can_be_used_after_exceeding_a_limit() {
+    let mut vm = VM::with_config(VMConfig { max_reductions: Some(10000), .. VMConfig::default() });
+    for assembly in compile_string(INFINITE_LOOPS).unwrap_or_else(|err| panic!("{}", err)) {
+        vm.add_assembly(assembly);
     }
-    ///Compares the tags of two constructors, returning an Ordering
-    fn compare_tags<'a>(vm: &'a VM, stack: &[Node<'a>]) -> Node<'a> {
-        use std::cmp::Ordering;
-        assert_eq!(stack.len(), 2);
-        let lhs = eval(vm, stack[0].clone());
-        let rhs = eval(vm, stack[1].clone());
-        let tag = match (&*lhs.borrow(), &*rhs.borrow()) {
-            (&Constructor(lhs, _), &Constructor(rhs, _)) => match lhs.cmp(&rhs) {
-                Ordering::Less => 0,
-                Ordering::Equal => 1,
-                Ordering::Greater => 2
-            },
-            (_, _) => 1//EQ
-        };
-        Node::new(Constructor(tag, Vec::new()))
+    let main = vm.find_global("main").unwrap();
+    assert!(vm.run_main(main).is_err());
+    //The counted steps start from zero again in the next evaluation
+    let terminates = vm.find_global("terminates").unwrap();
+    let result = vm.run_main(terminates).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(extract_result(&vm, result), Some(Int(3)));
+}
+
+///Returns a path in the temporary directory as a Haskell string literal
+fn temp_file(name: &str) -> String {
+    let path = ::std::env::temp_dir().join(name);
+    format!("{:?}", path.to_str().unwrap())
+}
+
+#[test]
+fn write_append_and_read_file() {
+    let path = temp_file("vm_write_append_and_read_file.txt");
+    let result = execute_main_string(&format!(
+r#"
+import Prelude
+
+countLines :: Handle -> Int -> IO Int
+countLines h n = do
+    eof <- hIsEOF h
+    case eof of
+        True -> return n
+        False -> hGetLine h >> countLines h (n + 1)
+
+main :: IO Int
+main = do
+    writeFile {0} "first\nsecond\n"
+    appendFile {0} "third\n"
+    contents <- readFile {0}
+    h <- openFile {0} ReadMode
+    n <- countLines h 0
+    hClose h
+    return (length contents + 100 * n)
+"#, path)).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(19 + 300)));
+}
+
+#[test]
+fn io_errors_are_exceptions() {
+    let err = runtime_error(
+r#"
+import Prelude
+
+main :: IO [Char]
+main = readFile "this/file/does/not/exist"
+"#);
+    match err {
+        RuntimeError::IOException(ref message) => assert!(message.starts_with("this/file/does/not/exist: openFile: "), "{}", message),
+        _ => panic!("Expected IOException, got {:?}", err)
     }
+    assert!(err.to_string().starts_with("error[E0306]: uncaught exception: "), "{}", err);
+
+    let path = temp_file("vm_io_errors_are_exceptions.txt");
+    let result = execute_main_string(&format!(
+r#"
+import Prelude
+
+handler :: IOException -> IO Int
+handler e = return (if isDoesNotExistError e then 1 else if isEOFError e then 10 else 100)
+
+readTwice :: Handle -> IO Int
+readTwice h = hGetLine h >> hGetLine h >> return 0
+
+main :: IO Int
+main = do
+    a <- catch (readFile "this/file/does/not/exist" >> return 0) handler
+    writeFile {0} "one line\n"
+    h <- openFile {0} ReadMode
+    b <- catch (readTwice h) handler
+    hClose h
+    c <- catch (hGetLine h >> return 0) handler
+    return (a + b + c)
+"#, path)).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(1 + 10 + 100)));
 }
 
-#[cfg(test)]
-mod tests {
+#[test]
+fn program_arguments_and_environment() {
+    ::std::env::set_var("VM_PROGRAM_ARGUMENTS_TEST", "value");
+    let config = VMConfig {
+        args: vec!["first".to_string(), "second".to_string()],
+        prog_name: "Main".to_string(),
+        .. VMConfig::default()
+    };
+    let (result, _) = execute_with_config(config,
+r#"
+import Prelude
+import System.Environment
 
-use typecheck::TypeEnvironment;
-use compiler::{compile_with_type_env};
-use vm::{VM, compile_file, compile_iter, execute_main_module, execute_main_string, extract_result, VMResult};
-use vm::VMResult::{Int, Double, Constructor};
-use interner::*;
+found :: Maybe [Char] -> Int
+found var = case var of
+    Just value -> length value
+    Nothing -> 1000
 
-fn execute_main<T : Iterator<Item=char>>(iterator: T) -> Option<VMResult> {
-    let mut vm = VM::new();
-    vm.add_assembly(compile_iter(iterator).unwrap());
-    let x = vm.assembly.iter().flat_map(|a| a.super_combinators.iter()).find(|sc| sc.name.name == intern("main"));
-    match x {
-        Some(sc) => {
-            assert!(sc.arity == 0);
-            let result = vm.evaluate(&*sc.instructions, sc.assembly_id);
-            extract_result(result)
-        }
-        None => None
+main :: IO Int
+main = do
+    args <- getArgs
+    name <- getProgName
+    set <- lookupEnv "VM_PROGRAM_ARGUMENTS_TEST"
+    unset <- lookupEnv "VM_PROGRAM_ARGUMENTS_TEST_UNSET"
+    return (length args + 10 * length (args !! 1) + length name + 100 * found set + found unset)
+"#);
+    assert_eq!(result.unwrap_or_else(|err| panic!("{}", err)), Some(Int(2 + 10 * 6 + 4 + 100 * 5 + 1000)));
+}
+
+#[test]
+fn exit_codes() {
+    let exit = |code: &str| runtime_error(&format!(
+r"
+import Prelude
+import System.Exit
+
+main :: IO Int
+main = {} >> return 0
+", code));
+    assert_eq!(exit("exitWith (ExitFailure 3)"), RuntimeError::Exit(3));
+    assert_eq!(exit("exitFailure"), RuntimeError::Exit(1));
+    assert_eq!(exit("exitSuccess"), RuntimeError::Exit(0));
+    match exit("exitWith (ExitFailure 0)") {
+        RuntimeError::IOException(_) => (),
+        err => panic!("Expected IOException, got {:?}", err)
     }
+    assert_eq!(RuntimeError::Exit(3).to_string().lines().next(), Some("error[E0307]: uncaught exception: ExitFailure 3"));
 }
 
 #[test]
-fn test_primitive()
-{
-    assert_eq!(execute_main("main = primIntAdd 10 5".chars()), Some(VMResult::Int(15)));
-    assert_eq!(execute_main("main = primIntSubtract 7 (primIntMultiply 2 3)".chars()), Some(VMResult::Int(1)));
-    assert_eq!(execute_main("main = primIntDivide 10 (primIntRemainder 6 4)".chars()), Some(VMResult::Int(5)));
-    assert_eq!(execute_main("main = primDoubleDivide 3. 2.".chars()), Some(VMResult::Double(1.5)));
-    let s = 
-r"data Bool = True | False
-main = primIntLT 1 2";
-    assert_eq!(execute_main(s.chars()), Some(VMResult::Constructor(0, Vec::new())));
+fn exit_code_can_be_caught() {
+    let result = execute_main_string(
+r"
+import Prelude
+import System.Exit
+
+handler :: ExitCode -> IO Int
+handler e = return (case e of
+    ExitSuccess -> 0
+    ExitFailure code -> code)
+
+main :: IO Int
+main = catch (exitWith (ExitFailure 7) >> return 0) handler
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(7)));
 }
 
 #[test]
-fn test_function()
-{
-    let module = 
-r"mult2 x = primIntMultiply x 2
+fn ioref_and_st() {
+    let result = execute_main_string(
+r"
+import Prelude
+import Data.IORef
+import Control.Monad.ST
+import Data.STRef
 
-main = mult2 10";
-    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(20)));
+sumST :: [Int] -> Int
+sumST xs = runST (newSTRef 0 >>= \ref -> addAll ref xs >> readSTRef ref)
 
-    let module2 = 
-r"mult2 x = primIntMultiply x 2
+addAll :: STRef s Int -> [Int] -> ST s ()
+addAll ref xs = case xs of
+    [] -> return ()
+    y:ys -> modifySTRef' ref (\s -> s + y) >> addAll ref ys
 
-add x y = primIntAdd y x
+main :: IO Int
+main = do
+    ref <- newIORef (1 :: Int)
+    modifyIORef ref (\x -> x + 10)
+    modifyIORef' ref (\x -> x * 2)
+    old <- atomicModifyIORef ref (\x -> (x + 1, x))
+    new <- readIORef ref
+    return (old * 1000 + new * 10 + sumST [1, 2, 3])
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(22 * 1000 + 23 * 10 + 6)));
+}
 
-main = add 3 (mult2 10)";
-    assert_eq!(execute_main(module2.chars()), Some(VMResult::Int(23)));
+#[test]
+fn ioref_shared_through_thunk() {
+    let result = execute_main_string(
+r"
+import Prelude
+import Data.IORef
+
+main :: IO Int
+main = do
+    ref <- newIORef (0 :: Int)
+    let refs = [ref, ref]
+    writeIORef (head refs) 5
+    readIORef (refs !! 1)
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(5)));
 }
+
 #[test]
-fn test_case()
-{
-    let module = 
-r"mult2 x = primIntMultiply x 2
+fn mutable_arrays() {
+    let result = execute_main_string(
+r"
+import Prelude
+import Data.Array.IO
 
-main = case [mult2 123, 0] of
-    x:xs -> x
-    [] -> 10";
-    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(246)));
+main :: IO Int
+main = do
+    arr <- newArray (1, 5) (0 :: Int)
+    writeArray arr 2 10
+    modifyArray arr 2 (\x -> x + 1)
+    xs <- getElems arr
+    u <- newListUArray (0, 3) [1, 2, 3, 4]
+    modifyUArray u 3 (\x -> x * 10)
+    ys <- getUElems u
+    return (sum xs * 100 + sum ys)
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(11 * 100 + 46)));
+
+    let result = execute_main_string(
+r"
+import Prelude
+import Control.Monad.ST
+import Data.Array.ST
+
+sieve :: Int -> ST s Int
+sieve n = newUArray (2, n) 1 >>= \arr -> mark arr 2 n >> getUElems arr >>= \xs -> return (sum xs)
+
+mark :: STUArray s Int Int -> Int -> Int -> ST s ()
+mark arr i n = if i * i > n
+    then return ()
+    else readUArray arr i >>= \p -> (if p == 1 then clear arr (i * i) i n else return ()) >> mark arr (i + 1) n
+
+clear :: STUArray s Int Int -> Int -> Int -> Int -> ST s ()
+clear arr j step n = if j > n
+    then return ()
+    else writeUArray arr j 0 >> clear arr (j + step) step n
+
+main :: Int
+main = runST (sieve 100)
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(25)));
+
+    let err = runtime_error(
+r"
+import Prelude
+import Data.Array.IO
+
+main :: IO Int
+main = newArray (0, 2) (0 :: Int) >>= \arr -> readArray arr 3
+");
+    assert_eq!(err, RuntimeError::ErrorCall("array index 3 out of range (0,2)".to_string()));
 }
 
 #[test]
-fn test_nested_case() {
-    let module = 
-r"mult2 x = primIntMultiply x 2
+fn threads_and_channels() {
+    let result = execute_main_string(
+r"
+import Prelude
+import Control.Concurrent
+import Control.Concurrent.MVar
+import Control.Concurrent.Chan
 
-main = case [mult2 123, 0] of
-    246:xs -> primIntAdd 0 246
-    [] -> 10";
-    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(246)));
+producer :: Chan Int -> Int -> IO ()
+producer chan n = if n > 100 then return () else writeChan chan n >> producer chan (n + 1)
+
+consume :: Chan Int -> Int -> Int -> IO Int
+consume chan n acc = if n == 0 then return acc else readChan chan >>= \x -> consume chan (n - 1) (acc + x)
+
+threadNumber :: ThreadId -> Int
+threadNumber t = case t of
+    ThreadId n -> n
+
+main :: IO Int
+main = do
+    chan <- newChan
+    child <- newEmptyMVar
+    t <- forkIO (producer chan 1 >> myThreadId >>= putMVar child)
+    total <- consume chan 100 0
+    c <- takeMVar child
+    me <- myThreadId
+    return (total + 10000 * threadNumber c + 100000 * threadNumber t + 1000000 * threadNumber me)
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(5050 + 10000 + 100000)));
 }
 
 #[test]
-fn test_nested_case2() {
-    let module = 
-r"mult2 x = primIntMultiply x 2
+fn threads_are_preempted() {
+    let config = VMConfig { time_slice: 100, .. VMConfig::default() };
+    let (result, _) = execute_with_config(config,
+r"
+import Prelude
+import Data.IORef
+import Control.Concurrent
 
-main = case [mult2 123, 0] of
-    246:[] -> primIntAdd 0 246
-    x:xs -> 20
-    [] -> 10";
-    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(20)));
+spin :: IORef Int -> IO ()
+spin r = modifyIORef' r (\x -> x + 1) >> spin r
+
+wait :: IORef Int -> IO Int
+wait r = readIORef r >>= \n -> if n > 100 then return 1 else wait r
+
+main :: IO Int
+main = do
+    r <- newIORef (0 :: Int)
+    t <- forkIO (spin r)
+    n <- wait r
+    killThread t
+    return n
+");
+    assert_eq!(result.unwrap_or_else(|err| panic!("{}", err)), Some(Int(1)));
 }
+
 #[test]
-fn local_function() {
-    let module = 
-r"main =
-    let
-        f x y =
-            let
-                g x = primIntAdd x y
-            in g (primIntAdd 1 x)
-    in f (primIntAdd 2 0) (primIntAdd 3 0)";
-    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(6)));
+fn kill_thread_and_shared_thunks() {
+    let module =
+r"
+import Prelude
+import Control.Concurrent
+import Control.Concurrent.MVar
+
+count :: Int -> Int -> Int
+count acc n = if n == 0 then acc else count (acc + n) (n - 1)
+
+killed :: MVar Int -> AsyncException -> IO ()
+killed done e = case e of
+    ThreadKilled -> putMVar done 7
+    StackOverflow -> putMVar done 0
+
+waiter :: MVar Int -> MVar Int -> IO ()
+waiter never done = catch (takeMVar never >> return ()) (killed done)
+
+main :: IO Int
+main = do
+    never <- newEmptyMVar
+    done <- newEmptyMVar
+    w <- forkIO (waiter never done)
+    yield
+    killThread w
+    x <- takeMVar done
+    let shared = count 0 1000
+    result <- newEmptyMVar
+    forkIO (evaluate shared >>= putMVar result)
+    yield
+    z <- evaluate shared
+    y <- takeMVar result
+    return (x + z + y)
+";
+    //The exception which kills the thread must survive the collections made while switching to it
+    for &gc_stress in [false, true].iter() {
+        let config = VMConfig { time_slice: 50, gc_stress: gc_stress, .. VMConfig::default() };
+        let (result, _) = execute_with_config(config, module);
+        assert_eq!(result.unwrap_or_else(|err| panic!("{}", err)), Some(Int(7 + 500500 * 2)));
+    }
 }
 
 #[test]
-fn test_data_types()
-{
-    let module = 
-r"data Bool = True | False
+fn blocked_indefinitely_on_mvar() {
+    let err = runtime_error(
+r"
+import Prelude
+import Control.Concurrent
+import Control.Concurrent.MVar
 
-test = False
+main :: IO Int
+main = do
+    m <- newEmptyMVar
+    forkIO (takeMVar m >>= putMVar m)
+    takeMVar m
+");
+    assert_eq!(err, RuntimeError::BlockedIndefinitelyOnMVar);
+    assert!(err.to_string().starts_with("error[E0308]: uncaught exception: thread blocked indefinitely"), "{}", err);
+
+    let result = execute_main_string(
+r"
+import Prelude
+import Control.Concurrent.MVar
+
+handler :: BlockedIndefinitelyOnMVar -> IO Int
+handler e = return 2
+
+main :: IO Int
+main = newEmptyMVar >>= \m -> catch (takeMVar m) handler
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(2)));
+}
+
+
+#[test]
+fn stm_transactions_are_atomic() {
+    let config = VMConfig { time_slice: 20, .. VMConfig::default() };
+    let (result, _) = execute_with_config(config,
+r"
+import Prelude
+import Control.Concurrent
+import Control.Concurrent.MVar
+import Control.Concurrent.STM
+
+count :: Int -> Int -> Int
+count acc n = if n == 0 then acc else count (acc + 1) (n - 1)
+
+increment :: TVar Int -> TVar Int -> STM ()
+increment counter other = do
+    x <- readTVar counter
+    y <- readTVar other
+    writeTVar counter (count x 10 - 9)
+    writeTVar other (y - 1)
+
+worker :: TVar Int -> TVar Int -> Int -> MVar () -> IO ()
+worker counter other k done =
+    if k == 0 then putMVar done () else atomically (increment counter other) >> worker counter other (k - 1) done
+
+main :: IO Int
+main = do
+    counter <- newTVarIO 0
+    other <- newTVarIO 0
+    done <- newEmptyMVar
+    forkIO (worker counter other 100 done)
+    forkIO (worker counter other 100 done)
+    forkIO (worker counter other 100 done)
+    takeMVar done
+    takeMVar done
+    takeMVar done
+    x <- readTVarIO counter
+    y <- readTVarIO other
+    return (x + y * 1000)
+");
+    assert_eq!(result.unwrap_or_else(|err| panic!("{}", err)), Some(Int(300 - 300 * 1000)));
+}
+
+#[test]
+fn stm_retry_and_or_else() {
+    let result = execute_main_string(
+r"
+import Prelude
+import Control.Concurrent
+import Control.Concurrent.STM
+
+takeFrom :: TVar Int -> Int -> STM Int
+takeFrom v tag = readTVar v >>= \x -> check (x > 0) >> writeTVar v (x - 1) >> return tag
+
+main :: IO Int
+main = do
+    a <- newTVarIO 0
+    b <- newTVarIO 1
+    first <- atomically (takeFrom a 1 `orElse` takeFrom b 2)
+    forkIO (threadDelay 1000 >> atomically (writeTVar a 3))
+    second <- atomically (takeFrom a 10 `orElse` takeFrom b 20)
+    left <- readTVarIO a
+    return (first + second + 100 * left)
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(2 + 10 + 100 * 2)));
+
+    let err = runtime_error(
+r"
+import Prelude
+import Control.Concurrent.STM
+
+main :: IO Int
+main = newTVarIO 0 >>= \v -> atomically (readTVar v >>= \x -> check (x > 0) >> return x)
+");
+    assert_eq!(err, RuntimeError::BlockedIndefinitelyOnSTM);
+    assert!(err.to_string().starts_with("error[E0309]: uncaught exception: thread blocked indefinitely"), "{}", err);
+}
+
+#[test]
+fn integer_arithmetic() {
+    let result = execute_main_string(
+r"
+import Prelude
+
+fact :: Integer -> Integer
+fact 0 = 1
+fact n = n * fact (n - 1)
+
+main :: Integer
+main = div (fact 30) 123456789012345678901 - (0 - 7) `rem` 2 + toInteger (primIntAdd 9223372036854775807 1)
+").unwrap_or_else(|err| panic!("{}", err));
+    let expected = "2148548183815".parse::<::integer::Integer>().unwrap();
+    let expected = &(&expected + &::integer::Integer::from_isize(1)) + &::integer::Integer::from_isize(isize::min_value());
+    assert_eq!(result, Some(VMResult::Integer(expected)));
+
+    let err = runtime_error(
+r"
+import Prelude
 
-main = case test of
-    False -> primIntAdd 0 0
-    True -> primIntAdd 1 0";
-    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(0)));
+main :: Integer
+main = div 100000000000000000000 (100000000000000000000 - 100000000000000000000)
+");
+    assert_eq!(err, RuntimeError::ArithException("divide by zero".to_string()));
 }
 
 #[test]
-fn test_typeclasses_known_types()
-{
-    let module = 
-r"data Bool = True | False
+fn sized_integers_wrap() {
+    let result = execute_main_string(
+r"
+import Prelude
+import Data.Int
+import Data.Word
 
-class Test a where
-    test :: a -> Int
+a :: Int8
+a = 100 + 100
 
-instance Test Int where
-    test x = x
+b :: Word8
+b = 3 - 5
 
-instance Test Bool where
-    test x = case x of
-        True -> 1
-        False -> 0
+c :: Word64
+c = 0 - 1
 
+d :: Int32
+d = 2147483647 + 1
 
-main = primIntSubtract (test (primIntAdd 5 0)) (test True)";
-    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(4)));
+main :: Integer
+main = toInteger a + toInteger b + toInteger c + toInteger d + 7000000000000000000000000000000
+").unwrap_or_else(|err| panic!("{}", err));
+    let expected = "7000000000018446744071562068165".parse().unwrap();
+    assert_eq!(result, Some(VMResult::Integer(expected)));
 }
 
 #[test]
-fn test_typeclasses_unknown()
-{
-    let module = 
-r"data Bool = True | False
-
-class Test a where
-    test :: a -> Int
+fn numeric_literal_patterns() {
+    let result = execute_main_string(
+r"
+import Prelude
+import Data.Word
 
-instance Test Int where
-    test x = x
+isZero :: (Eq a, Num a) => a -> Int
+isZero 0 = 1
+isZero _ = 0
 
-instance Test Bool where
-    test x = case x of
-        True -> 1
-        False -> 0
+startsWithOne :: [Integer] -> Int
+startsWithOne (1:_) = 10
+startsWithOne _ = 0
 
-testAdd y = primIntAdd (test (primIntAdd 5 0)) (test y)
+byte :: Word8 -> Int
+byte 255 = 100
+byte _ = 0
 
-main = testAdd True";
-    assert_eq!(execute_main(module.chars()), Some(VMResult::Int(6)));
+main :: Int
+main = isZero (0 :: Integer) + isZero (0 :: Double) + isZero (3 :: Int) + startsWithOne [1, 2] + byte (0 - 1)
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(1 + 1 + 0 + 10 + 100)));
 }
 
 #[test]
-fn test_run_prelude() {
-    let prelude = compile_file("Prelude.hs").unwrap();
-    let assembly = {
-        let mut type_env = TypeEnvironment::new();
+fn floating_classes() {
+    let module =
+r"
+import Prelude
 
-        compile_with_type_env(&mut type_env, &[&prelude],
-r"add x y = primIntAdd x y
-main = foldl add 0 [1,2,3,4]").unwrap()
-    };
+hypotenuse :: Floating a => a -> a -> a
+hypotenuse x y = sqrt (x * x + y * y)
 
-    let mut vm = VM::new();
-    vm.add_assembly(prelude);
-    vm.add_assembly(assembly);
-    let x = vm.assembly.iter().flat_map(|a| a.super_combinators.iter()).find(|sc| sc.name.name == intern("main"));
-    let result = match x {
-        Some(sc) => {
-            assert!(sc.arity == 0);
-            let result = vm.evaluate(&*sc.instructions, sc.assembly_id);
-            extract_result(result)
-        }
-        None => None
-    };
-    assert_eq!(result, Some(VMResult::Int(10)));
+average :: [Int] -> Double
+average xs = fromIntegral (sum xs) / fromIntegral (length xs)
+
+main :: Double
+main = hypotenuse 3.0 4.0 + average [1, 2, 3, 4] + realToFrac (hypotenuse 6.0 (8.0 :: Float)) + logBase 2.0 1024.0
+";
+    let result = execute_main_string(module).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Double(5.0 + 2.5 + 10.0 + 10.0)));
+    assert_eq!(execute_under_gc_stress(module), result);
 }
 
 #[test]
-fn instance_super_class() {
-    let prelude = compile_file("Prelude.hs").unwrap();
-
-    let assembly = {
-        let mut type_env = TypeEnvironment::new();
-        compile_with_type_env(&mut type_env, &[&prelude], "main = [primIntAdd 0 1,2,3,4] == [1,2,3]").unwrap()
-    };
+fn decode_float_under_gc_stress() {
+    let result = execute_under_gc_stress(
+r"
+import Prelude
 
-    let mut vm = VM::new();
-    vm.add_assembly(prelude);
-    vm.add_assembly(assembly);
-    let x = vm.assembly.iter().flat_map(|a| a.super_combinators.iter()).find(|sc| sc.name.name == intern("main"));
-    let result = match x {
-        Some(sc) => {
-            assert!(sc.arity == 0);
-            let result = vm.evaluate(&*sc.instructions, sc.assembly_id);
-            extract_result(result)
-        }
-        None => None
-    };
-    assert_eq!(result, Some(VMResult::Constructor(1, Vec::new())));
+main :: ((Integer, Int), (Integer, Int))
+main = (decodeFloat (0.0 - 0.75 :: Double), decodeFloat (1.0 :: Float))
+");
+    let expected = VMResult::Constructor(0, vec![
+        VMResult::Constructor(0, vec![VMResult::Integer(::integer::Integer::from_isize(-(3 << 51))), Int(-53)]),
+        VMResult::Constructor(0, vec![VMResult::Integer(::integer::Integer::from_isize(1 << 23)), Int(-23)])
+    ]);
+    assert_eq!(result, Some(expected));
 }
 
 #[test]
-fn monad_do() {
-    let prelude = compile_file("Prelude.hs").unwrap();
+fn rounding() {
+    let result = execute_main_string(
+r"
+import Prelude
 
-    let assembly = {
-        let mut type_env = TypeEnvironment::new();
-        compile_with_type_env(&mut type_env, &[&prelude],
-"
-test :: Maybe Int -> Maybe Int -> Maybe Int
-test x y = do
-    x1 <- x
-    y
-    return (x1 + 1)
+main :: ((Int, Integer), (Int, Int))
+main = ((round (2.5 :: Double) + round (3.5 :: Double) + round (0.0 - 2.5 :: Double), truncate (0.0 - 2.7 :: Double)),
+        (floor (0.0 - 2.5 :: Double), ceiling (2.1 :: Float)))
+").unwrap_or_else(|err| panic!("{}", err));
+    let expected = VMResult::Constructor(0, vec![
+        VMResult::Constructor(0, vec![Int(2 + 4 - 2), VMResult::Integer(::integer::Integer::from_isize(-2))]),
+        VMResult::Constructor(0, vec![Int(-3), Int(3)])
+    ]);
+    assert_eq!(result, Some(expected));
 
-main = test (Just 4) (Just 6)").unwrap()
-    };
+    let result = execute_main_string(
+r"
+import Prelude
 
-    let mut vm = VM::new();
-    vm.add_assembly(prelude);
-    vm.add_assembly(assembly);
-    let x = vm.assembly.iter().flat_map(|a| a.super_combinators.iter()).find(|sc| sc.name.name == intern("main"));
-    let result = match x {
-        Some(sc) => {
-            assert!(sc.arity == 0);
-            let result = vm.evaluate(&*sc.instructions, sc.assembly_id);
-            extract_result(result)
-        }
-        None => None
-    };
-    assert_eq!(result, Some(VMResult::Constructor(0, vec!(VMResult::Int(5)))));
-}
+floorAndCeiling :: RealFrac a => a -> (Int, Int)
+floorAndCeiling x = (floor x, ceiling x)
 
-#[test]
-fn import() {
-    let result = execute_main_module("Test");
-    assert_eq!(result, Ok(Some(VMResult::Int(6))));
+main :: (Int, Int)
+main = floorAndCeiling (0.0 - 1.5 :: Float)
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, vec![Int(-2), Int(-1)])));
 }
 
 #[test]
-fn pattern_bind() {
+fn rational_arithmetic() {
     let result = execute_main_string(
 r"
 import Prelude
+import Data.Ratio
 
-test :: [Bool] -> Bool
-test (True:[]) = False
-test (True:y:ys) = y
-test [] = False
+mediant :: Integral a => Ratio a -> Ratio a -> Ratio a
+mediant x y =
+    let n = numerator x + numerator y
+        d = denominator x + denominator y
+    in n % d
 
-main = test [True, True]
-")
-    .unwrap_or_else(|err| panic!(err));
-    assert_eq!(result, Some(VMResult::Constructor(0, Vec::new())));
+main :: ((Bool, Bool), (Integer, Integer), Int)
+main = ((0.1 + 0.2 == (0.3 :: Rational), 1 % 3 < (1 % 2 :: Rational)),
+        (floor ((0 - 7) % 2 :: Rational), round (5 % 2 :: Rational)),
+        truncate (fromRational (mediant (1 % 3) (4 % 6)) * 10.0 :: Double))
+").unwrap_or_else(|err| panic!("{}", err));
+    let expected = VMResult::Constructor(0, vec![
+        VMResult::Constructor(0, vec![VMResult::Constructor(0, vec![]), VMResult::Constructor(0, vec![])]),
+        VMResult::Constructor(0, vec![VMResult::Integer(::integer::Integer::from_isize(-4)), VMResult::Integer(::integer::Integer::from_isize(2))]),
+        Int(5)
+    ]);
+    assert_eq!(result, Some(expected));
 }
+
 #[test]
-fn pattern_guards() {
+fn default_ambiguous_literals() {
     let result = execute_main_string(
 r"
 import Prelude
 
-test :: Int -> [a] -> Int
-test 2 _ = 2
-test x []
-    | primIntLT x 0 = primIntSubtract 0 1
-    | primIntGT x 0 = 1
-test x _ = x
-
-main = (test 2 [], test 100 [], test 100 ['c'])
+main :: (Int, Double)
+main = (length (show (2 ^ 70)), fromIntegral (length [1, 2, 3]) / 2)
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, vec![Int(22), VMResult::Double(1.5)])));
+}
 
-")
-    .unwrap_or_else(|err| panic!(err));
-    assert_eq!(result, Some(VMResult::Constructor(0, vec!(VMResult::Int(2), VMResult::Int(1), VMResult::Int(100)))));
+///Runs `main` returning its value and the number of reduction steps it took
+fn run_counting_reductions(module: &str, options: TypecheckOptions) -> (Option<VMResult>, usize) {
+    let mut vm = VM::new();
+    for assembly in compile_string_with(module, options).unwrap_or_else(|err| panic!("{}", err)) {
+        vm.add_assembly(assembly);
+    }
+    let main = vm.find_global("main").unwrap();
+    let result = vm.run_main(main).unwrap_or_else(|err| panic!("{}", err));
+    (extract_result(&vm, result), vm.steps.get())
 }
 
 #[test]
-fn pattern_guards_nested() {
-    let result = execute_main_string(
+fn monomorphism_restriction_shares_constant() {
+    let module =
 r"
 import Prelude
 
-test :: Int -> [Int] -> Int
-test 2 _ = 2
-test x (0:[])
-    | primIntLT x 0 = primIntSubtract 0 1
-    | primIntGT x 0 = 1
-test x _ = x
+count n = if n == 0 then 0 else 1 + count (n - 1)
 
-main = (test 2 [], test 100 [0], test 100 [0, 123])
+total = count 2000
 
-")
-    .unwrap_or_else(|err| panic!(err));
-    assert_eq!(result, Some(VMResult::Constructor(0, vec!(VMResult::Int(2), VMResult::Int(1), VMResult::Int(100)))));
+main :: Int
+main = total + total + total + total
+";
+    let (shared, shared_steps) = run_counting_reductions(module, TypecheckOptions::default());
+    let options = TypecheckOptions { monomorphism_restriction: false, .. TypecheckOptions::default() };
+    let (unshared, unshared_steps) = run_counting_reductions(module, options);
+    assert_eq!(shared, Some(Int(8000)));
+    assert_eq!(unshared, Some(Int(8000)));
+    //Without the restriction `total` takes a `Num` dictionary and is recomputed at each use
+    assert!(unshared_steps > 3 * shared_steps, "{} {}", unshared_steps, shared_steps);
 }
+
 #[test]
-fn test_class_default_function()
-{
-    let module = 
-r"data Bool = True | False
+fn applicative_monoid_and_monad_fail() {
+    let result = execute_main_string(
+r"
+import Prelude
 
-class Test a where
-    test :: a -> Int
-    test _ = 42
-    test2 :: Int
+pairs :: [(Int, Char)]
+pairs = do
+    x <- [1, 2, 3]
+    y <- ['a', 'b']
+    return (x, y)
 
-instance Test Int where
-    test x = x
-    test2 = 0
+heads :: [[Int]] -> [Int]
+heads xss = do
+    x:_ <- xss
+    return x
 
-instance Test Bool where
-    test2 = 2
+safeHead :: [Int] -> Maybe Int
+safeHead xs = do
+    x:_ <- Just xs
+    return x
 
-main = (test True, test (1 :: Int))";
-    assert_eq!(execute_main(module.chars()), Some(VMResult::Constructor(0, vec![VMResult::Int(42), VMResult::Int(1)])));
+main :: (Int, Int, Int, Int, Bool)
+main = (length pairs,
+        sum (heads [[1, 2], [], [3]]),
+        maybe 0 id (safeHead [] <|> Just 7),
+        length (mconcat [[1], [2, 3]] <> mempty :: [Int]),
+        (liftA2 (+) (Just 1) (Just 2) == Just (3 :: Int)) && (compare 1 2 <> GT == LT))
+").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, vec![Int(6), Int(4), Int(7), Int(3), VMResult::Constructor(0, vec![])])));
 }
 
 #[test]
-fn use_super_class() {
-    let result = execute_main_string(
+fn foldable_and_traversable() {
+    let module =
 r"
 import Prelude
 
-test x y = (x == y) || (x < y)
-main = (test (0 :: Int) 2) && not (test (1 :: Int) 0)")
-        .unwrap_or_else(|err| panic!("{:?}", err));
-    assert_eq!(result, Some(VMResult::Constructor(0, Vec::new())));
+half :: Int -> Maybe Int
+half x = if x `rem` 2 == 0 then Just (x `div` 2) else Nothing
+
+main :: (Int, Int, Bool, Int, Int)
+main = (sum (Just 3) + product [1, 2, 3, 4] + length (Right 'a' :: Either Int Char) + maximum (1, 5 :: Int),
+        foldr (\x acc -> x - acc) 0 [1, 2, 3] + foldl (\acc x -> acc - x) 0 [1, 2, 3] + minimum [4, 2, 8],
+        elem 3 [1, 2, 3] && not (null [1]) && null Nothing && length (toList (Just 'x')) == 1,
+        maybe 0 sum (traverse half [2, 4, 6]) + maybe 100 sum (mapM half [2, 3]) + maybe 0 length (sequence [Just 1, Just 2]),
+        length (concat (sequenceA [[1, 2], [3, 4, 5]])) + sum (fmap (\x -> x + 1) (10, 1)) + length (foldMap (\x -> [x, x]) (Just 'a')))
+";
+    let result = execute_main_string(module).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, vec![Int(33), Int(-2), VMResult::Constructor(0, vec![]), Int(108), Int(16)])));
+    //`traverse` and `foldMap` merge their own dictionaries into the class dictionary
+    assert_eq!(execute_under_gc_stress(module), result);
 }
+
 #[test]
-fn implement_class() {
-    let result = execute_main_string(
+fn foldable_polymorphic_reductions() {
+    let module =
 r"
 import Prelude
-data AB = A | B
 
-instance Eq AB where
-    (==) A A = True
-    (==) B B = True
-    (==) _ _ = False
+total :: Foldable t => t Int -> Int
+total xs = foldl' (+) 0 xs
 
-test x y = x == y
+listTotal :: Int -> [Int] -> Int
+listTotal acc xs = case xs of
+    y:ys -> let acc2 = acc + y in acc2 `seq` listTotal acc2 ys
+    [] -> acc
 
-main = A == B && test A A")
-        .unwrap_or_else(|err| panic!("{:?}", err));
-    assert_eq!(result, Some(VMResult::Constructor(1, Vec::new())));
+main :: Int
+main = ";
+    let (polymorphic, polymorphic_steps) =
+        run_counting_reductions(&format!("{}total (enumFromTo 1 2000)", module), TypecheckOptions::default());
+    let (direct, direct_steps) =
+        run_counting_reductions(&format!("{}listTotal 0 (enumFromTo 1 2000)", module), TypecheckOptions::default());
+    assert_eq!(polymorphic, Some(Int(2001000)));
+    assert_eq!(direct, Some(Int(2001000)));
+    //Going through the `Foldable` dictionary should only add a constant overhead per element
+    assert!(polymorphic_steps < 2 * direct_steps, "{} {}", polymorphic_steps, direct_steps);
 }
 
 #[test]
-fn deriving_eq() {
+fn library_modules() {
     let result = execute_main_string(
-r"
+r#"
 import Prelude
-data Test = A Int | B
-    deriving(Eq)
+import Data.List
+import Data.Char
+import Data.Maybe
+import Data.Either
 
-main = A 0 == A 2 || A 0 == B
-").unwrap_or_else(|err| panic!(err));
-    assert_eq!(result, Some(VMResult::Constructor(1, Vec::new())));
+main :: (Int, Bool, Int, Int, Int)
+main = (length (words "  the quick  brown fox ") + length (lines "a\nb\n") * 10,
+        sort [3, 1, 2] == [1, 2, 3] && nub [1, 1, 2, 1] == [1, 2] && isPrefixOf "ab" "abc" && intercalate ", " ["a", "b"] == "a, b"
+            && map toUpper "abc" == "ABC" && isDigit '7' && not (isSpace 'x') && chr (ord 'a' + 1) == 'b',
+        sum (zipWith (*) [1, 2, 3] [4, 5, 6]) + fromMaybe 0 (lookup 2 (zip [1, 2, 3] [10, 20, 30])),
+        sum (catMaybes [Just 1, Nothing, Just 2]) + sum (mapMaybe (\x -> if x > 1 then Just x else Nothing) [1, 2, 3])
+            + length (lefts [Left 'a', Right 1, Left 'b']) + sum (rights [Left 'a', Right 4]),
+        digitToInt 'f' + length (sortBy (\x y -> compare y x) "hello") + fromMaybe 0 (findIndex isUpper "abC"))
+"#).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, vec![Int(24), VMResult::Constructor(0, vec![]), Int(52), Int(14), Int(22)])));
 }
+
 #[test]
-fn deriving_ord() {
+fn data_map_and_set() {
     let result = execute_main_string(
-r"
+r#"
 import Prelude
-data Test = A Int | B
-    deriving(Eq, Ord)
+import qualified Data.Map as Map
+import qualified Data.Set as Set
 
-main = compare (A 0) (A 2) == LT && compare B (A 123) == GT
-").unwrap_or_else(|err| panic!(err));
-    assert_eq!(result, Some(VMResult::Constructor(0, Vec::new())));
+m :: Map.Map Int Char
+m = Map.fromList [(5, 'a'), (3, 'b'), (8, 'c'), (1, 'd')]
+
+main :: (Int, Bool, Int, Bool, Int)
+main = (Map.size m,
+        (Map.keys m == [1, 3, 5, 8]) && (Map.elems (Map.insert 3 'x' m) == "dxac") && (Map.lookup 4 m == Nothing) && Map.member 8 m
+            && (Map.elems (Map.alter (\_ -> Just 'z') 4 (Map.delete 5 m)) == "dbzc") && (Map.findWithDefault '?' 7 m == '?'),
+        Map.foldrWithKey (\k _ acc -> k + acc) 0 (Map.union m (Map.fromList [(2, 'e'), (5, 'f')])) + sum (fmap (\x -> x * 2) (Map.fromList [(1, 10), (2, 20)])),
+        (Map.fromList [(1, 'a'), (2, 'b')] == Map.insert 2 'b' (Map.singleton 1 'a')) && (compare (Set.fromList [1, 2]) (Set.fromList [1, 3]) == LT)
+            && (Set.toList (Set.union (Set.fromList [3, 1]) (Set.fromList [2, 3])) == [1, 2, 3]) && (show (Set.fromList [3, 1, 2]) == "fromList [1,2,3]"),
+        length (Set.fromList "mississippi") * 10 + length (Map.fromListWith (++) [(1, "a"), (2, "b"), (1, "c")]))
+"#).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, vec![Int(4), VMResult::Constructor(0, vec![]), Int(79), VMResult::Constructor(0, vec![]), Int(42)])));
 }
 
 #[test]
-fn instance_eq_list() {
-    let result = execute_main_string(
-r"
-import Prelude
-test x y = x == y
-main = test [1 :: Int] [3]
-").unwrap_or_else(|err| panic!(err));
-    assert_eq!(result, Some(VMResult::Constructor(1, Vec::new())));
+fn map_set_benchmark() {
+    let result = execute_main_module("tests/benchmarks/MapSet")
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, vec![Int(4000), Int(4000), Int(2500), Int(7752101), VMResult::Constructor(0, vec![])])));
 }
+
 #[test]
-fn build_dictionary() {
-    //Test that the compiler can generate code to build a dictionary at runtime
+fn read_builtin_types() {
     let result = execute_main_string(
-r"
+r#"
 import Prelude
-test :: Eq a => a -> a -> Bool
-test x y = [x] == [y]
-main = test [1 :: Int] [3]
-").unwrap_or_else(|err| panic!(err));
-    assert_eq!(result, Some(VMResult::Constructor(1, Vec::new())));
+
+main :: (Int, Double, Bool, Bool)
+main = (read " -42 " + read "(7)",
+        read "2.5e2" + read "-0.5" + read "3",
+        (read "123456789012345678901234567890" == (123456789012345678901234567890 :: Integer)) && (show (read "\"a\\nb\\\"c\\SOH\\65\"" :: [Char]) == "\"a\\nb\\\"c\\SOHA\"")
+            && (read "'\\n'" == '\n') && (read "['x', 'y']" == "xy") && (read "[1, 2,3]" == [1, 2, 3 :: Int]) && (read " [ ] " == ([] :: [Int])),
+        (show (read "(1,\"two\",'3')" :: (Int, [Char], Char)) == "(1,\"two\",'3')") && (read "Just (-5)" == Just (0 - 5 :: Int))
+            && (show (read " [Left 1, Right True]" :: [Either Int Bool]) == "[Left 1,Right True]")
+            && (show (read "(Nothing, [()], GT)" :: (Maybe Int, [()], Ordering)) == "(Nothing,[()],GT)"))
+"#).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, vec![Int(-35), VMResult::Double(252.5), VMResult::Constructor(0, vec![]), VMResult::Constructor(0, vec![])])));
 }
 
 #[test]
-fn if_else() {
+fn read_maybe_and_derived_read() {
     let result = execute_main_string(
-r"
+r#"
 import Prelude
+import Text.Read
 
-main = let
-        x = 123 :: Int
-    in if x < 0
-        then x
-        else 1
-").unwrap_or_else(|err| panic!(err));
-    assert_eq!(result, Some(VMResult::Int(1)));
+data Shape a = Circle a | Rect a a | Empty
+    deriving(Eq, Read)
+
+main :: (Bool, Bool, Bool)
+main = ((readMaybe "12x" == (Nothing :: Maybe Int)) && (readMaybe "  12 " == Just (12 :: Int))
+            && (show (readEither "1 2" :: Either [Char] Int) == "Left \"Prelude.read: no parse\""),
+        (read "[Rect 1 2, (Circle (-3)), Empty]" == [Rect 1 2, Circle (0 - 3), Empty :: Shape Int])
+            && (readMaybe "Rect 1" == (Nothing :: Maybe (Shape Int))) && (readMaybe "Just Circle 1" == (Nothing :: Maybe (Maybe (Shape Int)))),
+        read "Just (Circle 2.5)" == Just (Circle (2.5 :: Double)))
+"#).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(VMResult::Constructor(0, vec![VMResult::Constructor(0, vec![]), VMResult::Constructor(0, vec![]), VMResult::Constructor(0, vec![])])));
 }
 
 #[test]
-fn newtype() {
-    let result = execute_main_string(
-r"
+fn read_numbers_from_lines() {
+    let path = temp_file("vm_read_numbers_from_lines.txt");
+    let result = execute_main_string(&format!(
+r#"
 import Prelude
-newtype Even = Even Int
 
-makeEven :: Int -> Maybe Even
-makeEven i
-    | i `div` 2 /= (i - 1) `div` 2 = Just (Even i)
-    | otherwise = Nothing
+main :: IO Int
+main = do
+    writeFile {0} "12\n  30\n"
+    h <- openFile {0} ReadMode
+    x <- hGetLine h
+    y <- hGetLine h
+    hClose h
+    return (read x + read y)
+"#, path)).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Some(Int(42)));
+}
 
-main = makeEven (100 * 3)
-").unwrap_or_else(|err| panic!(err));
+#[test]
+fn read_without_parse_is_error() {
+    let err = runtime_error(
+r#"
+import Prelude
 
-    assert_eq!(result, Some(VMResult::Constructor(0, vec![VMResult::Int(300)])));
+main :: Int
+main = read "forty-two"
+"#);
+    assert_eq!(err, RuntimeError::ErrorCall("Prelude.read: no parse".to_string()));
 }
 
 #[test]
-fn where_bindings() {
-    let result = execute_main_string(
+fn chr_out_of_range() {
+    let err = runtime_error(
 r"
 import Prelude
+import Data.Char
 
-main = case list of
-        [] -> 123
-        x:xs
-            | y < 10 -> 0
-            | otherwise -> y
-            where
-            y = x + 10
-    where
-        list = [1::Int]
-").unwrap_or_else(|err| panic!(err));
-    assert_eq!(result, Some(VMResult::Int(11)));
+main :: Char
+main = chr (0 - 1)
+");
+    assert_eq!(err, RuntimeError::ErrorCall("Prelude.chr: bad argument: -1".to_string()));
+}
+
+#[test]
+fn show_floating() {
+    use vm::primitive::show_floating;
+    fn show(x: f64) -> String {
+        show_floating(x, &format!("{:e}", x))
+    }
+    assert_eq!(show(1.0), "1.0");
+    assert_eq!(show(0.1), "0.1");
+    assert_eq!(show(-2.5), "-2.5");
+    assert_eq!(show(1234567.0), "1234567.0");
+    assert_eq!(show(12345678.0), "1.2345678e7");
+    assert_eq!(show(0.01), "1.0e-2");
+    assert_eq!(show(0.0), "0.0");
+    assert_eq!(show(1.0 / 0.0), "Infinity");
+    assert_eq!(show_floating(0.1f32 as f64, &format!("{:e}", 0.1f32)), "0.1");
 }
 
 }
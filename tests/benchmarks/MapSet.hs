@@ -0,0 +1,30 @@
+module MapSet where
+
+import Prelude
+import Data.List (iterate, take, zip)
+import qualified Data.Map as Map
+import qualified Data.Set as Set
+
+randoms :: Int -> [Int]
+randoms n = take n (iterate (\x -> rem (x * 1103 + 12345) 10007) 1)
+
+table :: Map.Map Int Int
+table = Map.fromList (zip (randoms 4000) (enumFromTo 1 4000))
+
+counts :: Map.Map Int Int
+counts = foldl' (\m x -> Map.insertWith (+) (rem x 100) 1 m) Map.empty (randoms 4000)
+
+removed :: Map.Map Int Int
+removed = foldl' (\m x -> Map.delete x m) table (take 1500 (randoms 4000))
+
+altered :: Map.Map Int Int
+altered = foldl' (\m x -> Map.alter (\v -> maybe (Just 0) (\y -> Just (y * 2)) v) x m) removed (randoms 2000)
+
+digits :: Set.Set Int
+digits = Set.difference (Set.union (Set.fromList (map (\x -> rem x 500) (randoms 4000))) (Set.fromList (enumFromTo 400 700))) (Set.fromList (enumFromTo 0 99))
+
+main :: (Int, Int, Int, Int, Bool)
+main = (Map.size table, Map.foldr (+) 0 counts, Map.size removed, sum altered + Set.size digits,
+    Map.valid table && Map.valid counts && Map.valid removed && Map.valid altered && Set.valid digits
+        && (Map.foldr (+) 0 (fmap (\x -> x + 1) counts) == 4000 + Map.size counts)
+        && (Map.lookup (head (randoms 1)) removed == Nothing) && Set.member 650 digits && not (Set.member 50 digits))
@@ -0,0 +1,3 @@
+f x = x x
+
+main = f
@@ -0,0 +1 @@
+main = if 1 2 else 3
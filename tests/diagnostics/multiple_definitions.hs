@@ -0,0 +1,5 @@
+test x = x
+
+main = test 1
+
+test y = y
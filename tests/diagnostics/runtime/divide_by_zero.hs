@@ -0,0 +1,7 @@
+import Prelude
+
+average :: [Int] -> Int
+average xs = sum xs `div` length xs
+
+main :: Int
+main = average []
@@ -0,0 +1,4 @@
+import Prelude
+
+main :: Int
+main = head (filter (\x -> x > 3) [1, 2, 3])
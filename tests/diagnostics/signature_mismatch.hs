@@ -0,0 +1,4 @@
+test :: Int
+test = []
+
+main = test
@@ -0,0 +1,3 @@
+import Missing
+
+main = 1
@@ -0,0 +1,4 @@
+f :: Int a => a
+f = 1
+g :: (a b) => a
+g = 2
@@ -0,0 +1,3 @@
+class Eq a where
+    (==) :: a -> a ->
+    x = 
@@ -0,0 +1,2 @@
+main = do
+    x <- return 1
@@ -0,0 +1 @@
+main = case 1 of
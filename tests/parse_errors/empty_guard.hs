@@ -0,0 +1 @@
+f x | = 1
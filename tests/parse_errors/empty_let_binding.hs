@@ -0,0 +1 @@
+main = let x = in x
@@ -0,0 +1 @@
+infixl 10000000000000000000000 +++
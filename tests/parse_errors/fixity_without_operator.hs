@@ -0,0 +1 @@
+infixl 6
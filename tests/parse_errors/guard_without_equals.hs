@@ -0,0 +1 @@
+f x | x 1
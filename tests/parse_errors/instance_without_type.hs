@@ -0,0 +1 @@
+instance Eq where
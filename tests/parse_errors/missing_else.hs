@@ -0,0 +1 @@
+main = if 1 then 2
@@ -0,0 +1 @@
+module where
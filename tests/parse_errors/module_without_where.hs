@@ -0,0 +1,2 @@
+module M
+main = 1
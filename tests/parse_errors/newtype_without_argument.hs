@@ -0,0 +1 @@
+newtype N = N
@@ -0,0 +1,2 @@
+f (x = 1
+main = f 2